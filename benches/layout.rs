@@ -0,0 +1,28 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use wag::layout::{arrange, distribute, SizeLimit};
+
+fn limits(count: usize) -> Vec<SizeLimit> {
+    (0..count)
+        .map(|i| SizeLimit {
+            ratio: 1. + (i % 3) as f32,
+            min_size: (i % 5) as f32 * 10.,
+            max_size: if i % 4 == 0 { Some(40.) } else { None },
+        })
+        .collect()
+}
+
+fn bench_layout(c: &mut Criterion) {
+    for count in [4, 64, 1024] {
+        let limits = limits(count);
+        let target = count as f32 * 30.;
+        c.bench_function(&format!("distribute {count}"), |b| {
+            b.iter(|| distribute(black_box(&limits), black_box(target)))
+        });
+        c.bench_function(&format!("arrange {count}"), |b| {
+            b.iter(|| arrange(black_box(&limits), 0., black_box(target), 4.))
+        });
+    }
+}
+
+criterion_group!(benches, bench_layout);
+criterion_main!(benches);
@@ -0,0 +1,287 @@
+//!
+//! Gallery of the widgets, layouts and themes with the navigation sidebar.
+//!
+//! Run with `--smoke-test` to open every page and switch the themes automatically,
+//! then close the window. The process exits with non-zero code if anything failed,
+//! including the errors of the background tasks reported to the error handler,
+//! so the gallery can be used as the end-to-end test of the crate in CI.
+//!
+mod pages;
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_std::task::sleep;
+use futures::executor::{block_on, ThreadPool};
+use pages::Context;
+use wag::{
+    gui::{
+        detach, set_theme, spawn_window_event_receiver, Background, BackgroundParams, CellLimit,
+        LayerStack, LayerStackParams, Overlay, OverlayParams, Panel, Ribbon, RibbonOrientation,
+        RibbonOverflow, RibbonParams, Theme,
+    },
+    handle_err, set_error_handler,
+    window::{
+        initialize_window_thread,
+        native::{run_message_loop, Window},
+    },
+};
+use windows::{
+    Foundation::Numerics::Vector2,
+    Win32::{
+        Foundation::{LPARAM, WPARAM},
+        UI::WindowsAndMessaging::{PostMessageW, WM_CLOSE},
+    },
+    UI::Composition::Compositor,
+};
+
+// Time each page is shown in the smoke test
+const SMOKE_TEST_PAGE_TIME: Duration = Duration::from_millis(500);
+
+struct Page {
+    name: &'static str,
+    panel: Arc<dyn Panel>,
+}
+
+async fn show_page(content: &Ribbon, page: &Page) -> wag::Result<()> {
+    content.clear().await?;
+    // The panel is still attached to the cell it was removed from
+    detach(&*page.panel)?;
+    content
+        .insert_panel(0, page.panel.clone(), CellLimit::default())
+        .await
+}
+
+async fn smoke_test(content: Arc<Ribbon>, pages: Arc<Vec<Page>>) -> wag::Result<()> {
+    for theme in [Theme::light(), Theme::dark()] {
+        set_theme(theme);
+        for page in pages.iter() {
+            show_page(&content, page).await?;
+            sleep(SMOKE_TEST_PAGE_TIME).await;
+        }
+    }
+    Ok(())
+}
+
+fn main() -> wag::Result<()> {
+    let is_smoke_test = std::env::args().any(|arg| arg == "--smoke-test");
+
+    // Errors of the background tasks, the smoke test fails if there are any
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    if is_smoke_test {
+        let errors = errors.clone();
+        set_error_handler(move |e| {
+            eprintln!("error reported: {e}");
+            errors.lock().unwrap().push(e.to_string());
+        });
+    }
+
+    let _window_thread = initialize_window_thread()?;
+    let pool = ThreadPool::builder().pool_size(8).create()?;
+    let compositor = Compositor::new()?;
+    // The window content is the bottom layer, it's pushed when the pages are created
+    let layer_stack: Arc<LayerStack> = LayerStackParams::builder()
+        .compositor(compositor.clone())
+        .build()
+        .try_into()?;
+    let overlay: Arc<Overlay> = OverlayParams::builder()
+        .compositor(compositor.clone())
+        .build()
+        .try_into()?;
+    let ctx = Context {
+        compositor: compositor.clone(),
+        pool: pool.clone(),
+        layer_stack: layer_stack.clone(),
+        overlay: overlay.clone(),
+    };
+
+    let pages = Arc::new(vec![
+        Page {
+            name: "Buttons",
+            panel: pages::buttons(&ctx)?,
+        },
+        Page {
+            name: "Layout",
+            panel: pages::layout(&ctx)?,
+        },
+        Page {
+            name: "Scrolling",
+            panel: pages::scrolling(&ctx)?,
+        },
+        Page {
+            name: "Text",
+            panel: pages::text(&ctx)?,
+        },
+        Page {
+            name: "Progress",
+            panel: pages::progress(&ctx)?,
+        },
+        Page {
+            name: "Theme",
+            panel: pages::theme(&ctx)?,
+        },
+        Page {
+            name: "Dialog",
+            panel: pages::dialog(&ctx)?,
+        },
+        Page {
+            name: "Error dialog",
+            panel: pages::error_dialog(&ctx)?,
+        },
+        Page {
+            name: "Context menu",
+            panel: pages::context_menu(&ctx)?,
+        },
+        Page {
+            name: "Tooltip",
+            panel: pages::tooltip(&ctx)?,
+        },
+        Page {
+            name: "Rich text",
+            panel: pages::rich_text(&ctx)?,
+        },
+        Page {
+            name: "Surface",
+            panel: pages::surface(&ctx)?,
+        },
+        Page {
+            name: "Image",
+            panel: pages::image_background(&ctx)?,
+        },
+        Page {
+            name: "Backdrop",
+            panel: pages::backdrop(&ctx)?,
+        },
+        Page {
+            name: "Immediate",
+            panel: pages::immediate(&ctx)?,
+        },
+        Page {
+            name: "Transformed",
+            panel: pages::transformed(&ctx)?,
+        },
+        Page {
+            name: "Visibility",
+            panel: pages::visibility(&ctx)?,
+        },
+        Page {
+            name: "Enabled",
+            panel: pages::enabled(&ctx)?,
+        },
+        Page {
+            name: "Navigation",
+            panel: pages::nav_shell(&ctx)?,
+        },
+        Page {
+            name: "Fluent skins",
+            panel: pages::fluent_skins(&ctx)?,
+        },
+        Page {
+            name: "List view",
+            panel: pages::list_view(&ctx)?,
+        },
+    ]);
+
+    let content: Arc<Ribbon> = RibbonParams::builder()
+        .compositor(compositor.clone())
+        .orientation(RibbonOrientation::Stack)
+        .build()
+        .add_panel(pages[0].panel.clone(), CellLimit::default())?
+        .try_into()?;
+
+    let mut sidebar = RibbonParams::builder()
+        .compositor(compositor.clone())
+        .orientation(RibbonOrientation::Vertical)
+        .overflow(RibbonOverflow::Scroll)
+        .spacing(4.)
+        .build();
+    for index in 0..pages.len() {
+        let button = ctx.button(pages[index].name)?;
+        let content = Arc::downgrade(&content);
+        let pages = pages.clone();
+        let pool = pool.clone();
+        ctx.on_click(&button, move || {
+            let content = content.clone();
+            let pages = pages.clone();
+            pool.spawn_ok(handle_err(async move {
+                if let Some(content) = content.upgrade() {
+                    show_page(&content, &pages[index]).await?;
+                }
+                Ok(())
+            }));
+            Ok(())
+        });
+        sidebar = sidebar.add_panel(button, CellLimit::new(1., 50., Some(60.), None))?;
+    }
+    let sidebar: Arc<Ribbon> = sidebar.try_into()?;
+
+    let main_ribbon: Arc<Ribbon> = RibbonParams::builder()
+        .compositor(compositor.clone())
+        .orientation(RibbonOrientation::Horizontal)
+        .build()
+        .add_panel(sidebar, CellLimit::new(1., 160., Some(200.), None))?
+        .add_panel(content.clone(), CellLimit::new(4., 200., None, None))?
+        .try_into()?;
+
+    // Without the explicit color the background follows the theme
+    let background: Arc<Background> = BackgroundParams::builder()
+        .compositor(compositor.clone())
+        .round_corners(false)
+        .build()
+        .try_into()?;
+    let root: Arc<Ribbon> = RibbonParams::builder()
+        .compositor(compositor.clone())
+        .orientation(RibbonOrientation::Stack)
+        .build()
+        .add_panel(background, CellLimit::default())?
+        .add_panel(main_ribbon, CellLimit::default())?
+        .try_into()?;
+    // Nothing receives the events yet, so the layers are pushed in place
+    block_on(async {
+        layer_stack.push_panel(root).await?;
+        layer_stack.push_panel(overlay.clone()).await?;
+        // The clicks outside of the popups reach the content
+        layer_stack.set_pass_through(&*overlay, true).await;
+        wag::Result::Ok(())
+    })?;
+
+    let root_visual = compositor.CreateContainerVisual()?;
+    root_visual.SetSize(Vector2 { X: 1024., Y: 768. })?;
+    let channel = spawn_window_event_receiver(&pool, layer_stack, root_visual.clone())?;
+    let window = Window::new(compositor, "gallery", root_visual, channel);
+    let window = window.open()?;
+
+    let outcome = Arc::new(Mutex::new(None));
+    if is_smoke_test {
+        let outcome = outcome.clone();
+        let handle = window.handle();
+        pool.spawn_ok(async move {
+            let result = smoke_test(content, pages).await;
+            *outcome.lock().unwrap() = Some(result.map_err(|e| e.to_string()));
+            unsafe { PostMessageW(handle, WM_CLOSE, WPARAM::default(), LPARAM::default()) };
+        });
+    }
+    run_message_loop();
+
+    if is_smoke_test {
+        let errors = errors.lock().unwrap();
+        match outcome.lock().unwrap().take() {
+            Some(Ok(())) if !errors.is_empty() => {
+                eprintln!("smoke test failed: {} errors reported", errors.len());
+                std::process::exit(1);
+            }
+            Some(Ok(())) => println!("smoke test passed"),
+            Some(Err(e)) => {
+                eprintln!("smoke test failed: {e}");
+                std::process::exit(1);
+            }
+            None => {
+                eprintln!("smoke test interrupted: the window was closed");
+                std::process::exit(1);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,903 @@
+use std::{f32::consts::PI, sync::Arc, time::Duration};
+
+use async_event_streams::{spawn_event_pipe, EventSink, EventSource};
+use futures::{executor::ThreadPool, FutureExt, StreamExt};
+use wag::{
+    gui::{
+        follow_system_color_mode, set_theme,
+        skins::{
+            fluent::{FluentButtonSkin, FluentButtonSkinParams},
+            CheckBoxState, FluentCheckBoxSkin, FluentCheckBoxSkinParams, FluentSliderSkin,
+            FluentSliderSkinParams, SliderState,
+        },
+        AspectRatio, AspectRatioParams, BackdropMaterial, BackdropPanel, BackdropPanelParams,
+        Background, BackgroundParams, Bitmap, Border, BorderParams, Button, ButtonEvent,
+        ButtonParams, ButtonVisualState, CancellationToken, CellLimit, ContextMenu,
+        ContextMenuEvent, ContextMenuParams, Dialog, DialogParams, DialogResult, Enabled,
+        EnabledParams, ErrorDialog, ErrorDialogParams, HeaderFactory, Hyperlink, HyperlinkParams,
+        ImageBackground, ImageBackgroundParams, ImageSource, ImageStretch, ImmediatePanel,
+        ImmediatePanelParams, ItemFactory, LayerStack, ListView, ListViewParams, Margins,
+        MoreItemsLoader, NavShell, NavShellParams, Overlay, Padded, PaddedParams, Panel,
+        ProgressBar, ProgressBarParams, RepeatButton, RepeatButtonParams, Ribbon,
+        RibbonOrientation, RibbonOverflow, RibbonParams, RichText, RichTextParams,
+        SimpleButtonSkin, SimpleButtonSkinParams, Surface, SurfaceEvent, SurfaceParams, Text,
+        TextParams, TextRun, Theme, TooltipService, TooltipServiceParams, Transformed,
+        TransformedParams, Ui, Visibility, VisibilityParams,
+    },
+    handle_err, on_err,
+};
+use windows::{
+    Foundation::Numerics::Vector2,
+    Win32::Graphics::Direct2D::{
+        Common::{D2D1_COLOR_F, D2D_POINT_2F},
+        D2D1_ELLIPSE,
+    },
+    UI::{Colors, Composition::Compositor},
+};
+
+///
+/// Widgets shared by the pages
+///
+#[derive(Clone)]
+pub struct Context {
+    pub compositor: Compositor,
+    pub pool: ThreadPool,
+    /// Layers of the window for the dialogs
+    pub layer_stack: Arc<LayerStack>,
+    /// Topmost layer of the window for the popups, i.e. the context menus and the tooltips
+    pub overlay: Arc<Overlay>,
+}
+
+impl Context {
+    pub fn button(&self, text: &str) -> wag::Result<Arc<Button>> {
+        let skin: Arc<SimpleButtonSkin> = SimpleButtonSkinParams::builder()
+            .compositor(self.compositor.clone())
+            .text(text.to_owned())
+            .spawner(self.pool.clone())
+            .build()
+            .try_into()?;
+        ButtonParams::builder()
+            .compositor(self.compositor.clone())
+            .skin(skin)
+            .build()
+            .try_into()
+    }
+
+    pub fn text(&self, text: &str) -> wag::Result<Arc<Text>> {
+        TextParams::builder()
+            .compositor(self.compositor.clone())
+            .text(text.to_owned())
+            .spawner(self.pool.clone())
+            .build()
+            .try_into()
+    }
+
+    fn row(&self) -> RibbonParams {
+        RibbonParams::builder()
+            .compositor(self.compositor.clone())
+            .orientation(RibbonOrientation::Horizontal)
+            .spacing(16.)
+            .padding(Margins::uniform(16.))
+            .build()
+    }
+
+    fn column(&self) -> RibbonParams {
+        RibbonParams::builder()
+            .compositor(self.compositor.clone())
+            .orientation(RibbonOrientation::Vertical)
+            .spacing(8.)
+            .padding(Margins::uniform(16.))
+            .build()
+    }
+
+    ///
+    /// Run the handler on each click of the button
+    ///
+    pub fn on_click(
+        &self,
+        button: &Button,
+        handler: impl Fn() -> wag::Result<()> + Send + 'static,
+    ) {
+        let mut stream = EventSource::<ButtonEvent>::event_stream(button);
+        self.pool.spawn_ok(handle_err(async move {
+            while let Some(event) = stream.next().await {
+                if ButtonEvent::Release(true) == *event {
+                    handler()?;
+                }
+            }
+            Ok(())
+        }));
+    }
+}
+
+pub fn buttons(ctx: &Context) -> wag::Result<Arc<dyn Panel>> {
+    let button = ctx.button("Click me")?;
+    let clicks = ctx.text("Not clicked yet")?;
+    {
+        let clicks = Arc::downgrade(&clicks);
+        let mut stream = EventSource::<ButtonEvent>::event_stream(&*button);
+        ctx.pool.spawn_ok(handle_err(async move {
+            let mut count = 0;
+            while let Some(event) = stream.next().await {
+                match (&*event, clicks.upgrade()) {
+                    (ButtonEvent::Release(true), Some(clicks)) => {
+                        count += 1;
+                        clicks.set_text(format!("Clicked {count} times")).await;
+                    }
+                    (ButtonEvent::DoubleClick, Some(clicks)) => {
+                        clicks.set_text("Double click").await;
+                    }
+                    (_, None) => break,
+                    _ => (),
+                }
+            }
+            Ok(())
+        }));
+    }
+
+    let repeat_button: Arc<RepeatButton> = RepeatButtonParams::builder()
+        .spawner(ctx.pool.clone())
+        .button(ctx.button("Hold to count")?)
+        .build()
+        .into();
+    let counter = ctx.text("0")?;
+    {
+        let counter = Arc::downgrade(&counter);
+        let mut stream = EventSource::<ButtonEvent>::event_stream(&*repeat_button.button());
+        ctx.pool.spawn_ok(handle_err(async move {
+            let mut count = 0;
+            while let Some(event) = stream.next().await {
+                if ButtonEvent::Press == *event {
+                    if let Some(counter) = counter.upgrade() {
+                        count += 1;
+                        counter.set_text(count.to_string()).await;
+                    }
+                }
+            }
+            Ok(())
+        }));
+    }
+
+    let hyperlink: Arc<Hyperlink> = HyperlinkParams::builder()
+        .compositor(ctx.compositor.clone())
+        .spawner(ctx.pool.clone())
+        .text("Open windows-rs on GitHub")
+        .url("https://github.com/microsoft/windows-rs")
+        .build()
+        .try_into()?;
+
+    let row = CellLimit::new(1., 50., Some(80.), None);
+    let page: Arc<Ribbon> = ctx
+        .column()
+        .add_panel(button, row)?
+        .add_panel(clicks, row)?
+        .add_panel(repeat_button, row)?
+        .add_panel(counter, row)?
+        .add_panel(hyperlink, row)?
+        .try_into()?;
+    Ok(page)
+}
+
+pub fn layout(ctx: &Context) -> wag::Result<Arc<dyn Panel>> {
+    let background = |color| -> wag::Result<Arc<Background>> {
+        BackgroundParams::builder()
+            .compositor(ctx.compositor.clone())
+            .color(color)
+            .round_corners(true)
+            .build()
+            .try_into()
+    };
+
+    let padded: Arc<Padded> = PaddedParams::builder()
+        .compositor(ctx.compositor.clone())
+        .panel(background(Colors::Red()?)?)
+        .margins(Margins::uniform(12.))
+        .build()
+        .try_into()?;
+    let border: Arc<Border> = BorderParams::builder()
+        .compositor(ctx.compositor.clone())
+        .panel(padded)
+        .color(Colors::Gray()?)
+        .thickness(2.)
+        .corner_radius(8.)
+        .build()
+        .try_into()?;
+    let square: Arc<AspectRatio> = AspectRatioParams::builder()
+        .compositor(ctx.compositor.clone())
+        .panel(background(Colors::Green()?)?)
+        .build()
+        .try_into()?;
+    let limited: Arc<Ribbon> = ctx
+        .column()
+        .add_panel(
+            background(Colors::Blue()?)?,
+            CellLimit::new(1., 20., None, None),
+        )?
+        .add_panel(
+            background(Colors::SkyBlue()?)?,
+            CellLimit::new(2., 20., Some(100.), None),
+        )?
+        .add_panel(
+            background(Colors::Navy()?)?,
+            CellLimit::new(1., 50., None, Some(Vector2::new(0.5, 0.5))),
+        )?
+        .try_into()?;
+
+    let page: Arc<Ribbon> = ctx
+        .row()
+        .add_panel(border, CellLimit::default())?
+        .add_panel(square, CellLimit::default())?
+        .add_panel(limited, CellLimit::default())?
+        .try_into()?;
+    Ok(page)
+}
+
+pub fn scrolling(ctx: &Context) -> wag::Result<Arc<dyn Panel>> {
+    let mut params = RibbonParams::builder()
+        .compositor(ctx.compositor.clone())
+        .orientation(RibbonOrientation::Vertical)
+        .spacing(4.)
+        .padding(Margins::uniform(16.))
+        .overflow(RibbonOverflow::Scroll)
+        .build();
+    for i in 1..=30 {
+        params = params.add_panel(
+            ctx.button(&format!("Item {i}"))?,
+            CellLimit::new(1., 60., Some(60.), None),
+        )?;
+    }
+    let page: Arc<Ribbon> = params.try_into()?;
+    Ok(page)
+}
+
+const ITEM_TOTAL: usize = 10000;
+
+pub fn list_view(ctx: &Context) -> wag::Result<Arc<dyn Panel>> {
+    let item_ctx = ctx.clone();
+    // Slow item creation, like loading the image from disk
+    let item_factory: ItemFactory = Arc::new(move |index: usize, _token: CancellationToken| {
+        let ctx = item_ctx.clone();
+        async move {
+            async_std::task::sleep(Duration::from_millis(300)).await;
+            let item: Arc<dyn Panel> = ctx.text(&format!("Item {index}"))?;
+            wag::Result::Ok(item)
+        }
+        .boxed()
+    });
+    // Opaque header covering the rows scrolled under it
+    let header_ctx = ctx.clone();
+    let header_factory: HeaderFactory =
+        Arc::new(move |group: usize| -> wag::Result<Arc<dyn Panel>> {
+            let ctx = &header_ctx;
+            let background: Arc<Background> = BackgroundParams::builder()
+                .compositor(ctx.compositor.clone())
+                .round_corners(false)
+                .build()
+                .try_into()?;
+            let header: Arc<Ribbon> = RibbonParams::builder()
+                .compositor(ctx.compositor.clone())
+                .orientation(RibbonOrientation::Stack)
+                .build()
+                .add_panel(background, CellLimit::default())?
+                .add_panel(
+                    ctx.text(&format!("Items {}..{}", group * 100, group * 100 + 99))?,
+                    CellLimit::default(),
+                )?
+                .try_into()?;
+            Ok(header)
+        });
+    let compositor = ctx.compositor.clone();
+    let page: Arc<ListView> = ListViewParams::builder()
+        .compositor(ctx.compositor.clone())
+        .spawner(ctx.pool.clone())
+        .item_height(40.)
+        .item_count(200)
+        .item_factory(item_factory)
+        .groups((0..ITEM_TOTAL).step_by(100).collect())
+        .header_height(32.)
+        .header_factory(header_factory)
+        .loading_footer(ctx.text("Loading...")?)
+        .footer_height(40.)
+        .placeholder_factory(Arc::new(move || -> wag::Result<Arc<dyn Panel>> {
+            let placeholder: Arc<Background> = BackgroundParams::builder()
+                .compositor(compositor.clone())
+                .color(Colors::LightGray()?)
+                .round_corners(true)
+                .build()
+                .try_into()?;
+            Ok(placeholder)
+        }))
+        .build()
+        .try_into()?;
+    // Next pages are slow to come, like from the network
+    let loader: MoreItemsLoader = Arc::new(|offset: usize, count: usize| {
+        async move {
+            async_std::task::sleep(Duration::from_millis(500)).await;
+            wag::Result::Ok(count.min(ITEM_TOTAL - offset))
+        }
+        .boxed()
+    });
+    let list = page.clone();
+    ctx.pool.spawn_ok(handle_err(async move {
+        list.on_need_more_items(100, loader).await
+    }));
+    Ok(page)
+}
+
+pub fn text(ctx: &Context) -> wag::Result<Arc<dyn Panel>> {
+    let row = CellLimit::new(1., 40., None, None);
+    let page: Arc<Ribbon> = ctx
+        .column()
+        .add_panel(ctx.text("Text is laid out by DirectWrite")?, row)?
+        .add_panel(
+            ctx.text("Longer line of text to see how it fits when the window is resized")?,
+            CellLimit::new(2., 80., None, None),
+        )?
+        .add_panel(ctx.text("Юникод, 日本語, emoji 🎉")?, row)?
+        .try_into()?;
+    Ok(page)
+}
+
+pub fn progress(ctx: &Context) -> wag::Result<Arc<dyn Panel>> {
+    let progress_bar = |value, indeterminate| -> wag::Result<Arc<ProgressBar>> {
+        ProgressBarParams::builder()
+            .compositor(ctx.compositor.clone())
+            .color(Colors::MediumOrchid()?)
+            .track_color(Colors::LightGray()?)
+            .value(value)
+            .indeterminate(indeterminate)
+            .build()
+            .try_into()
+    };
+    let determinate = progress_bar(0.4, false)?;
+    let step = ctx.button("Step")?;
+    {
+        let determinate = Arc::downgrade(&determinate);
+        let pool = ctx.pool.clone();
+        ctx.on_click(&step, move || {
+            let determinate = determinate.clone();
+            pool.spawn_ok(handle_err(async move {
+                if let Some(determinate) = determinate.upgrade() {
+                    let value = determinate.value().await + 0.1;
+                    determinate
+                        .set_value(if value > 1. { 0. } else { value })
+                        .await?;
+                }
+                Ok(())
+            }));
+            Ok(())
+        });
+    }
+
+    let row = CellLimit::new(1., 10., Some(20.), None);
+    let page: Arc<Ribbon> = ctx
+        .column()
+        .add_panel(determinate, row)?
+        .add_panel(progress_bar(0., true)?, row)?
+        .add_panel(step, CellLimit::new(1., 50., Some(80.), None))?
+        .try_into()?;
+    Ok(page)
+}
+
+pub fn theme(ctx: &Context) -> wag::Result<Arc<dyn Panel>> {
+    let light = ctx.button("Light")?;
+    ctx.on_click(&light, || {
+        follow_system_color_mode(false);
+        set_theme(Theme::light());
+        Ok(())
+    });
+    let dark = ctx.button("Dark")?;
+    ctx.on_click(&dark, || {
+        follow_system_color_mode(false);
+        set_theme(Theme::dark());
+        Ok(())
+    });
+    let system = ctx.button("Follow system")?;
+    ctx.on_click(&system, || {
+        follow_system_color_mode(true);
+        Ok(())
+    });
+
+    let row = CellLimit::new(1., 50., Some(80.), None);
+    let page: Arc<Ribbon> = ctx
+        .column()
+        .add_panel(light, row)?
+        .add_panel(dark, row)?
+        .add_panel(system, row)?
+        .try_into()?;
+    Ok(page)
+}
+
+pub fn dialog(ctx: &Context) -> wag::Result<Arc<dyn Panel>> {
+    let ok = ctx.button("OK")?;
+    let cancel = ctx.button("Cancel")?;
+    let buttons: Arc<Ribbon> = ctx
+        .row()
+        .add_panel(ok.clone(), CellLimit::default())?
+        .add_panel(cancel.clone(), CellLimit::default())?
+        .try_into()?;
+    let background: Arc<Background> = BackgroundParams::builder()
+        .compositor(ctx.compositor.clone())
+        .round_corners(true)
+        .build()
+        .try_into()?;
+    let content: Arc<Ribbon> = ctx
+        .column()
+        .add_panel(
+            ctx.text("Modal dialog over the dimmed window")?,
+            CellLimit::default(),
+        )?
+        .add_panel(buttons, CellLimit::new(1., 50., Some(80.), None))?
+        .try_into()?;
+    let content: Arc<Ribbon> = RibbonParams::builder()
+        .compositor(ctx.compositor.clone())
+        .orientation(RibbonOrientation::Stack)
+        .build()
+        .add_panel(background, CellLimit::default())?
+        .add_panel(content, CellLimit::default())?
+        .try_into()?;
+    let dialog: Arc<Dialog> = DialogParams::builder()
+        .compositor(ctx.compositor.clone())
+        .layer_stack(ctx.layer_stack.clone())
+        .content(content)
+        .light_dismiss(true)
+        .build()
+        .try_into()?;
+    dialog.close_on_button(&ctx.pool, &ok, DialogResult::Ok)?;
+    dialog.close_on_button(&ctx.pool, &cancel, DialogResult::Cancel)?;
+
+    let show = ctx.button("Show dialog")?;
+    let result = ctx.text("Not shown yet")?;
+    {
+        let result = Arc::downgrade(&result);
+        let pool = ctx.pool.clone();
+        ctx.on_click(&show, move || {
+            let dialog = dialog.clone();
+            let result = result.clone();
+            pool.spawn_ok(handle_err(async move {
+                let outcome = dialog.show().await?;
+                if let Some(result) = result.upgrade() {
+                    result.set_text(format!("Closed with {outcome:?}")).await;
+                }
+                Ok(())
+            }));
+            Ok(())
+        });
+    }
+
+    let row = CellLimit::new(1., 50., Some(80.), None);
+    let page: Arc<Ribbon> = ctx
+        .column()
+        .add_panel(show, row)?
+        .add_panel(result, row)?
+        .try_into()?;
+    Ok(page)
+}
+
+pub fn error_dialog(ctx: &Context) -> wag::Result<Arc<dyn Panel>> {
+    let show = ctx.button("Report an error")?;
+    let context = ctx.clone();
+    ctx.on_click(&show, move || {
+        let dialog: Arc<ErrorDialog> = ErrorDialogParams::builder()
+            .compositor(context.compositor.clone())
+            .spawner(context.pool.clone())
+            .layer_stack(context.layer_stack.clone())
+            .message("Something went wrong")
+            .details("This is how the unexpected errors are reported to the user")
+            .build()
+            .try_into()?;
+        context
+            .pool
+            .spawn_ok(handle_err(async move { dialog.show().await }));
+        Ok(())
+    });
+    let page: Arc<Ribbon> = ctx
+        .column()
+        .add_panel(show, CellLimit::new(1., 50., Some(80.), None))?
+        .try_into()?;
+    Ok(page)
+}
+
+pub fn context_menu(ctx: &Context) -> wag::Result<Arc<dyn Panel>> {
+    let target: Arc<Border> = BorderParams::builder()
+        .compositor(ctx.compositor.clone())
+        .panel(ctx.text("Right click here")?)
+        .color(Colors::Gray()?)
+        .thickness(2.)
+        .corner_radius(8.)
+        .build()
+        .try_into()?;
+    let menu: Arc<ContextMenu> = ContextMenuParams::builder()
+        .compositor(ctx.compositor.clone())
+        .overlay(ctx.overlay.clone())
+        .panel(target)
+        .spawner(ctx.pool.clone())
+        .color(Colors::WhiteSmoke()?)
+        .build()
+        .add_item("cut", "Cut")
+        .add_item("copy", "Copy")
+        .add_item("paste", "Paste")
+        .try_into()?;
+    let chosen = ctx.text("Nothing chosen yet")?;
+    {
+        let chosen = Arc::downgrade(&chosen);
+        let mut stream = EventSource::<ContextMenuEvent>::event_stream(&*menu);
+        ctx.pool.spawn_ok(handle_err(async move {
+            while let Some(event) = stream.next().await {
+                let text = match &*event {
+                    ContextMenuEvent::Command(command) => format!("Chosen \"{command}\""),
+                    ContextMenuEvent::Dismissed => "Dismissed".to_owned(),
+                };
+                match chosen.upgrade() {
+                    Some(chosen) => chosen.set_text(text).await,
+                    None => break,
+                }
+            }
+            Ok(())
+        }));
+    }
+    let page: Arc<Ribbon> = ctx
+        .column()
+        .add_panel(menu, CellLimit::new(3., 100., None, None))?
+        .add_panel(chosen, CellLimit::new(1., 50., Some(80.), None))?
+        .try_into()?;
+    Ok(page)
+}
+
+pub fn tooltip(ctx: &Context) -> wag::Result<Arc<dyn Panel>> {
+    let service: TooltipService = TooltipServiceParams::builder()
+        .compositor(ctx.compositor.clone())
+        .overlay(ctx.overlay.clone())
+        .spawner(ctx.pool.clone())
+        .color(Colors::LightYellow()?)
+        .build()
+        .into();
+    let row = CellLimit::new(1., 50., Some(80.), None);
+    let page: Arc<Ribbon> = ctx
+        .column()
+        .add_panel(
+            service.attach(ctx.button("Hover me")?, "Tooltip of the button")?,
+            row,
+        )?
+        .add_panel(
+            service.attach(ctx.text("Hover this text too")?, "Tooltip of the text")?,
+            row,
+        )?
+        .try_into()?;
+    Ok(page)
+}
+
+pub fn rich_text(ctx: &Context) -> wag::Result<Arc<dyn Panel>> {
+    let rich_text: Arc<RichText> = RichTextParams::builder()
+        .compositor(ctx.compositor.clone())
+        .spawner(ctx.pool.clone())
+        .font_size(24.)
+        .build()
+        .add_run(TextRun::builder().text("Plain, ").build())
+        .add_run(TextRun::builder().text("bold, ").bold(true).build())
+        .add_run(TextRun::builder().text("italic, ").italic(true).build())
+        .add_run(
+            TextRun::builder()
+                .text("underlined, ")
+                .underline(true)
+                .build(),
+        )
+        .add_run(
+            TextRun::builder()
+                .text("colored")
+                .color(Colors::Crimson()?)
+                .build(),
+        )
+        .try_into()?;
+    let page: Arc<Ribbon> = ctx
+        .column()
+        .add_panel(rich_text, CellLimit::new(1., 80., None, None))?
+        .try_into()?;
+    Ok(page)
+}
+
+pub fn surface(ctx: &Context) -> wag::Result<Arc<dyn Panel>> {
+    let surface: Arc<Surface> = SurfaceParams::builder()
+        .compositor(ctx.compositor.clone())
+        .fit_surface_to_visual(true)
+        .build()
+        .try_into()?;
+    // The owner of the surface draws it whenever it's resized
+    {
+        let weak = Arc::downgrade(&surface);
+        let mut stream = EventSource::<SurfaceEvent>::event_stream(&*surface);
+        ctx.pool.spawn_ok(handle_err(async move {
+            while let Some(event) = stream.next().await {
+                let SurfaceEvent::Redraw(size) = *event;
+                let surface = match weak.upgrade() {
+                    Some(surface) => surface,
+                    None => break,
+                };
+                surface
+                    .draw(|context, origin| unsafe {
+                        context.Clear(Some(&D2D1_COLOR_F {
+                            r: 0.9,
+                            g: 0.95,
+                            b: 1.,
+                            a: 1.,
+                        }));
+                        let brush = context.CreateSolidColorBrush(
+                            &D2D1_COLOR_F {
+                                r: 0.2,
+                                g: 0.4,
+                                b: 0.8,
+                                a: 1.,
+                            },
+                            None,
+                        )?;
+                        let ellipse = D2D1_ELLIPSE {
+                            point: D2D_POINT_2F {
+                                x: origin.x as f32 + size.X / 2.,
+                                y: origin.y as f32 + size.Y / 2.,
+                            },
+                            radiusX: size.X / 3.,
+                            radiusY: size.Y / 3.,
+                        };
+                        context.FillEllipse(&ellipse, &brush);
+                        Ok(())
+                    })
+                    .await?;
+            }
+            Ok(())
+        }));
+    }
+    let page: Arc<Ribbon> = ctx
+        .column()
+        .add_panel(
+            ctx.text("Drawn with Direct2D")?,
+            CellLimit::new(1., 40., Some(40.), None),
+        )?
+        .add_panel(surface, CellLimit::new(4., 100., None, None))?
+        .try_into()?;
+    Ok(page)
+}
+
+pub fn image_background(ctx: &Context) -> wag::Result<Arc<dyn Panel>> {
+    // Checkerboard of 8x8 cells in premultiplied BGRA
+    const SIZE: u32 = 64;
+    let pixels = (0..SIZE * SIZE)
+        .flat_map(|i| {
+            let (x, y) = (i % SIZE, i / SIZE);
+            if (x / 8 + y / 8) % 2 == 0 {
+                [0xD0, 0x80, 0x30, 0xFF]
+            } else {
+                [0xF0, 0xF0, 0xF0, 0xFF]
+            }
+        })
+        .collect();
+    let bitmap = Bitmap::new(SIZE, SIZE, pixels)?;
+    let image = |stretch| -> wag::Result<Arc<ImageBackground>> {
+        ImageBackgroundParams::builder()
+            .compositor(ctx.compositor.clone())
+            .image(ImageSource::Bitmap(bitmap.clone()))
+            .stretch(stretch)
+            .corner_radius(8.)
+            .build()
+            .try_into()
+    };
+    let page: Arc<Ribbon> = ctx
+        .row()
+        .add_panel(image(ImageStretch::Uniform)?, CellLimit::default())?
+        .add_panel(image(ImageStretch::Tile)?, CellLimit::default())?
+        .try_into()?;
+    Ok(page)
+}
+
+pub fn backdrop(ctx: &Context) -> wag::Result<Arc<dyn Panel>> {
+    let backdrop = |material| -> wag::Result<Arc<Ribbon>> {
+        let backdrop: Arc<BackdropPanel> = BackdropPanelParams::builder()
+            .compositor(ctx.compositor.clone())
+            .material(material)
+            .build()
+            .try_into()?;
+        RibbonParams::builder()
+            .compositor(ctx.compositor.clone())
+            .orientation(RibbonOrientation::Stack)
+            .build()
+            .add_panel(backdrop, CellLimit::default())?
+            .add_panel(ctx.text(&format!("{material:?}"))?, CellLimit::default())?
+            .try_into()
+    };
+    let page: Arc<Ribbon> = ctx
+        .row()
+        .add_panel(backdrop(BackdropMaterial::Acrylic)?, CellLimit::default())?
+        .add_panel(backdrop(BackdropMaterial::Mica)?, CellLimit::default())?
+        .try_into()?;
+    Ok(page)
+}
+
+pub fn immediate(ctx: &Context) -> wag::Result<Arc<dyn Panel>> {
+    let accent = Colors::SteelBlue()?;
+    let mut frame = 0u32;
+    let panel: Arc<ImmediatePanel> = ImmediatePanelParams::builder()
+        .compositor(ctx.compositor.clone())
+        .spawner(ctx.pool.clone())
+        .frame(move |ui: &mut Ui| {
+            frame = frame.wrapping_add(1);
+            ui.heading("Immediate mode")?;
+            ui.label(format!("Frame {frame}"))?;
+            ui.separator();
+            ui.progress_bar((frame % 120) as f32 / 120., 200., accent);
+            ui.space(8.);
+            ui.horizontal(|ui| {
+                ui.rect(Vector2 { X: 16., Y: 16. }, accent);
+                ui.label("Square and the label in a row")?;
+                Ok(())
+            })?;
+            Ok(())
+        })
+        .build()
+        .try_into()?;
+    let page: Arc<Ribbon> = ctx
+        .column()
+        .add_panel(panel, CellLimit::default())?
+        .try_into()?;
+    Ok(page)
+}
+
+pub fn transformed(ctx: &Context) -> wag::Result<Arc<dyn Panel>> {
+    let transformed: Arc<Transformed> = TransformedParams::builder()
+        .compositor(ctx.compositor.clone())
+        .panel(ctx.button("Transformed button")?)
+        .rotation(PI / 12.)
+        .scale(Vector2 { X: 0.8, Y: 0.8 })
+        .build()
+        .try_into()?;
+    let rotate = ctx.button("Rotate")?;
+    {
+        let transformed = Arc::downgrade(&transformed);
+        let pool = ctx.pool.clone();
+        ctx.on_click(&rotate, move || {
+            let transformed = transformed.clone();
+            pool.spawn_ok(handle_err(async move {
+                if let Some(transformed) = transformed.upgrade() {
+                    let rotation = transformed.rotation().await + PI / 12.;
+                    transformed.set_rotation(rotation % (2. * PI)).await?;
+                }
+                Ok(())
+            }));
+            Ok(())
+        });
+    }
+    let page: Arc<Ribbon> = ctx
+        .column()
+        .add_panel(transformed, CellLimit::new(2., 100., None, None))?
+        .add_panel(rotate, CellLimit::new(1., 50., Some(80.), None))?
+        .try_into()?;
+    Ok(page)
+}
+
+pub fn visibility(ctx: &Context) -> wag::Result<Arc<dyn Panel>> {
+    let visibility: Arc<Visibility> = VisibilityParams::builder()
+        .panel(ctx.text("Now you see me")?)
+        .build()
+        .try_into()?;
+    let toggle = ctx.button("Show / hide")?;
+    {
+        let visibility = Arc::downgrade(&visibility);
+        let pool = ctx.pool.clone();
+        ctx.on_click(&toggle, move || {
+            let visibility = visibility.clone();
+            pool.spawn_ok(handle_err(async move {
+                if let Some(visibility) = visibility.upgrade() {
+                    let visible = visibility.is_visible().await;
+                    visibility.set_visible(!visible).await?;
+                }
+                Ok(())
+            }));
+            Ok(())
+        });
+    }
+    let row = CellLimit::new(1., 50., Some(80.), None);
+    let page: Arc<Ribbon> = ctx
+        .column()
+        .add_panel(toggle, row)?
+        .add_panel(visibility, row)?
+        .try_into()?;
+    Ok(page)
+}
+
+pub fn enabled(ctx: &Context) -> wag::Result<Arc<dyn Panel>> {
+    let enabled: Arc<Enabled> = EnabledParams::builder()
+        .compositor(ctx.compositor.clone())
+        .panel(ctx.button("Can be disabled")?)
+        .build()
+        .try_into()?;
+    let toggle = ctx.button("Enable / disable")?;
+    {
+        let enabled = Arc::downgrade(&enabled);
+        let pool = ctx.pool.clone();
+        ctx.on_click(&toggle, move || {
+            let enabled = enabled.clone();
+            pool.spawn_ok(handle_err(async move {
+                if let Some(enabled) = enabled.upgrade() {
+                    let is_enabled = enabled.is_enabled().await;
+                    enabled.set_enabled(!is_enabled).await?;
+                }
+                Ok(())
+            }));
+            Ok(())
+        });
+    }
+    let row = CellLimit::new(1., 50., Some(80.), None);
+    let page: Arc<Ribbon> = ctx
+        .column()
+        .add_panel(toggle, row)?
+        .add_panel(enabled, row)?
+        .try_into()?;
+    Ok(page)
+}
+
+pub fn nav_shell(ctx: &Context) -> wag::Result<Arc<dyn Panel>> {
+    let shell: Arc<NavShell> = NavShellParams::builder()
+        .compositor(ctx.compositor.clone())
+        .spawner(ctx.pool.clone())
+        .header(ctx.text("Navigation shell")?)
+        .build()
+        .add_item("Home", "H", ctx.text("Home page")?)
+        .add_item("Documents", "D", ctx.text("Documents page")?)
+        .add_item("Settings", "S", ctx.text("Settings page")?)
+        .try_into()?;
+    Ok(shell)
+}
+
+pub fn fluent_skins(ctx: &Context) -> wag::Result<Arc<dyn Panel>> {
+    let skin: Arc<FluentButtonSkin> = FluentButtonSkinParams::builder()
+        .compositor(ctx.compositor.clone())
+        .text("Fluent button".to_owned())
+        .spawner(ctx.pool.clone())
+        .build()
+        .try_into()?;
+    let button: Arc<Button> = ButtonParams::builder()
+        .compositor(ctx.compositor.clone())
+        .skin(skin.clone())
+        .build()
+        .try_into()?;
+    spawn_event_pipe(&ctx.pool, &*button, skin, on_err)?;
+    // No control drives these skins yet, so they show the fixed states
+    let check_box: Arc<FluentCheckBoxSkin> = FluentCheckBoxSkinParams::builder()
+        .compositor(ctx.compositor.clone())
+        .build()
+        .try_into()?;
+    let slider: Arc<FluentSliderSkin> = FluentSliderSkinParams::builder()
+        .compositor(ctx.compositor.clone())
+        .build()
+        .try_into()?;
+    {
+        let check_box = check_box.clone();
+        let slider = slider.clone();
+        ctx.pool.spawn_ok(handle_err(async move {
+            check_box
+                .on_event_owned(
+                    CheckBoxState {
+                        visual: ButtonVisualState::Normal,
+                        checked: true,
+                    },
+                    None,
+                )
+                .await?;
+            slider
+                .on_event_owned(
+                    SliderState {
+                        visual: ButtonVisualState::Normal,
+                        value: 0.6,
+                    },
+                    None,
+                )
+                .await
+        }));
+    }
+    let row = CellLimit::new(1., 40., Some(60.), None);
+    let page: Arc<Ribbon> = ctx
+        .column()
+        .add_panel(button, row)?
+        .add_panel(check_box, row)?
+        .add_panel(slider, row)?
+        .try_into()?;
+    Ok(page)
+}
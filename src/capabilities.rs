@@ -0,0 +1,131 @@
+//! Availability of the system features which depend on the Windows version, with fallbacks
+//! for the features used by the crate
+
+use windows::{
+    core::{s, w, HSTRING},
+    Foundation::Metadata::ApiInformation,
+    System::Profile::AnalyticsInfo,
+    Win32::System::LibraryLoader::{GetModuleHandleW, GetProcAddress},
+};
+
+use crate::{gui::BackdropMaterial, window::native::SystemBackdrop};
+
+/// Windows 10 1703, Creators Update
+const PER_MONITOR_V2_DPI_BUILD: u16 = 15063;
+/// Windows 11 21H2
+const MICA_BUILD: u16 = 22000;
+/// Windows 11 22H2, first build with `DWMWA_SYSTEMBACKDROP_TYPE`
+const SYSTEM_BACKDROP_BUILD: u16 = 22621;
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Default)]
+pub struct OsVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub build: u16,
+    pub revision: u16,
+}
+
+///
+/// Version of the running system. Unlike `GetVersionEx` it's not affected by the
+/// application manifest compatibility settings.
+///
+pub fn os_version() -> crate::Result<OsVersion> {
+    let version = AnalyticsInfo::VersionInfo()?.DeviceFamilyVersion()?;
+    // Four 16 bit parts packed into the decimal representation of u64
+    let version = version.to_string_lossy().parse::<u64>().unwrap_or_default();
+    Ok(OsVersion {
+        major: (version >> 48) as u16,
+        minor: (version >> 32) as u16,
+        build: (version >> 16) as u16,
+        revision: version as u16,
+    })
+}
+
+fn is_build_at_least(build: u16) -> bool {
+    os_version().map_or(false, |version| version.build >= build)
+}
+
+///
+/// Mica material, i.e. `Compositor::TryCreateBlurredWallpaperBackdropBrush`
+///
+pub fn is_mica_supported() -> bool {
+    is_build_at_least(MICA_BUILD)
+}
+
+///
+/// System drawn window backdrop set by `Window::set_system_backdrop`
+///
+pub fn is_system_backdrop_supported() -> bool {
+    is_build_at_least(SYSTEM_BACKDROP_BUILD)
+}
+
+///
+/// Undocumented `SetWindowCompositionAttribute` function of user32, used for acrylic
+/// window background on older systems
+///
+pub fn is_window_composition_attribute_supported() -> bool {
+    unsafe {
+        GetModuleHandleW(w!("user32.dll")).map_or(false, |user32| {
+            GetProcAddress(user32, s!("SetWindowCompositionAttribute")).is_some()
+        })
+    }
+}
+
+///
+/// Screen and window capture with `Windows.Graphics.Capture`
+///
+pub fn is_graphics_capture_supported() -> bool {
+    ApiInformation::IsTypePresent(&HSTRING::from(
+        "Windows.Graphics.Capture.GraphicsCaptureSession",
+    ))
+    .unwrap_or(false)
+}
+
+pub fn is_per_monitor_v2_dpi_supported() -> bool {
+    is_build_at_least(PER_MONITOR_V2_DPI_BUILD)
+}
+
+///
+/// Snapshot of all the detected capabilities, e.g. for diagnostics reports
+///
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct Capabilities {
+    pub os_version: OsVersion,
+    pub mica: bool,
+    pub system_backdrop: bool,
+    pub window_composition_attribute: bool,
+    pub graphics_capture: bool,
+    pub per_monitor_v2_dpi: bool,
+}
+
+pub fn capabilities() -> Capabilities {
+    Capabilities {
+        os_version: os_version().unwrap_or_default(),
+        mica: is_mica_supported(),
+        system_backdrop: is_system_backdrop_supported(),
+        window_composition_attribute: is_window_composition_attribute_supported(),
+        graphics_capture: is_graphics_capture_supported(),
+        per_monitor_v2_dpi: is_per_monitor_v2_dpi_supported(),
+    }
+}
+
+///
+/// The requested window backdrop if the system supports it, otherwise `SystemBackdrop::None`
+///
+pub fn system_backdrop_or_fallback(requested: SystemBackdrop) -> SystemBackdrop {
+    if is_system_backdrop_supported() {
+        requested
+    } else {
+        SystemBackdrop::None
+    }
+}
+
+///
+/// Mica falls back to acrylic on systems before Windows 11
+///
+pub fn backdrop_material_or_fallback(requested: BackdropMaterial) -> BackdropMaterial {
+    match requested {
+        BackdropMaterial::Mica if !is_mica_supported() => BackdropMaterial::Acrylic,
+        material => material,
+    }
+}
@@ -0,0 +1,136 @@
+use std::{borrow::Cow, time::Instant};
+
+use async_event_streams::{
+    EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
+};
+use async_event_streams_derive::EventSink;
+use async_std::sync::{Arc, RwLock};
+use async_trait::async_trait;
+use typed_builder::TypedBuilder;
+use windows::UI::Composition::Visual;
+
+use crate::gui::{Panel, PanelEvent};
+
+type EventFilter = Arc<dyn Fn(&PanelEvent) -> bool + Send + Sync>;
+
+///
+/// Transparent decorator which logs every event passing to the wrapped subtree
+/// to `tracing` (target "wag::events") with the time spent by the subtree handling it.
+///
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+pub struct EventLoggerPanel {
+    panel: Arc<dyn Panel>,
+    name: String,
+    filter: RwLock<Option<EventFilter>>,
+    panel_events: EventStreams<PanelEvent>,
+    id: Arc<()>,
+}
+
+#[derive(TypedBuilder)]
+pub struct EventLoggerPanelParams {
+    #[builder(setter(transform = |panel: impl Panel + 'static| Arc::new(panel) as Arc<dyn Panel>))]
+    panel: Arc<dyn Panel>,
+    #[builder(default, setter(into))]
+    name: String,
+}
+
+impl TryFrom<EventLoggerPanelParams> for EventLoggerPanel {
+    type Error = crate::Error;
+
+    fn try_from(value: EventLoggerPanelParams) -> crate::Result<Self> {
+        Ok(EventLoggerPanel {
+            panel: value.panel,
+            name: value.name,
+            filter: RwLock::new(None),
+            panel_events: EventStreams::new(),
+            id: Arc::new(()),
+        })
+    }
+}
+
+impl TryFrom<EventLoggerPanelParams> for Arc<EventLoggerPanel> {
+    type Error = crate::Error;
+
+    fn try_from(value: EventLoggerPanelParams) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
+
+impl EventLoggerPanel {
+    ///
+    /// Log only events for which the filter returns true,
+    /// e.g. `|e| matches!(e, PanelEvent::MouseInput { .. })`
+    ///
+    pub async fn set_filter(&self, filter: impl Fn(&PanelEvent) -> bool + Send + Sync + 'static) {
+        *self.filter.write().await = Some(Arc::new(filter));
+    }
+    pub async fn clear_filter(&self) {
+        *self.filter.write().await = None;
+    }
+    async fn is_logged(&self, event: &PanelEvent) -> bool {
+        match &*self.filter.read().await {
+            Some(filter) => filter(event),
+            None => true,
+        }
+    }
+}
+
+impl Panel for EventLoggerPanel {
+    fn outer_frame(&self) -> Visual {
+        self.panel.outer_frame()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+}
+
+impl EventSource<PanelEvent> for EventLoggerPanel {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for EventLoggerPanel {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        let logged = self.is_logged(event.as_ref()).await;
+        let start = Instant::now();
+        let result = self
+            .panel
+            .on_event_ref(event.as_ref(), source.clone())
+            .await;
+        if logged {
+            let elapsed_us = start.elapsed().as_micros() as u64;
+            match &result {
+                Ok(()) => tracing::debug!(
+                    target: "wag::events",
+                    name = %self.name,
+                    panel_id = self.panel.id(),
+                    elapsed_us,
+                    event = ?event.as_ref(),
+                    "event handled"
+                ),
+                Err(e) => tracing::warn!(
+                    target: "wag::events",
+                    name = %self.name,
+                    panel_id = self.panel.id(),
+                    elapsed_us,
+                    event = ?event.as_ref(),
+                    error = %e,
+                    "event failed"
+                ),
+            }
+        }
+        result?;
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
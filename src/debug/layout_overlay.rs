@@ -0,0 +1,169 @@
+use windows::{
+    core::InParam,
+    w,
+    Foundation::{
+        Numerics::{Matrix3x2, Vector2},
+        Size,
+    },
+    Graphics::{
+        DirectX::{DirectXAlphaMode, DirectXPixelFormat},
+        SizeInt32,
+    },
+    Win32::Graphics::{
+        Direct2D::{
+            Common::{D2D1_COLOR_F, D2D_RECT_F},
+            D2D1_BRUSH_PROPERTIES, D2D1_DRAW_TEXT_OPTIONS_NONE,
+        },
+        DirectWrite::{
+            DWRITE_FONT_STRETCH_NORMAL, DWRITE_FONT_STYLE_NORMAL, DWRITE_FONT_WEIGHT_NORMAL,
+            DWRITE_MEASURING_MODE_NATURAL,
+        },
+    },
+    UI::{
+        Color,
+        Composition::{CompositionDrawingSurface, CompositionStretch, Compositor, SpriteVisual},
+    },
+};
+
+use crate::{
+    gui::CellLimit,
+    window::{composition_graphics_device, draw, dwrite_factory, ToWide},
+};
+
+const fn tint(r: u8, g: u8, b: u8) -> Color {
+    Color {
+        A: 0x40,
+        R: r,
+        G: g,
+        B: b,
+    }
+}
+
+const TINTS: [Color; 6] = [
+    tint(0xFF, 0x00, 0x00),
+    tint(0x00, 0xC0, 0x00),
+    tint(0x00, 0x00, 0xFF),
+    tint(0xFF, 0xA0, 0x00),
+    tint(0x00, 0xC0, 0xC0),
+    tint(0xC0, 0x00, 0xC0),
+];
+
+///
+/// Translucent tint and label with computed rectangle and limits of a layout cell
+///
+#[derive(Clone)]
+pub(crate) struct CellOverlay {
+    visual: SpriteVisual,
+    label: SpriteVisual,
+    surface: CompositionDrawingSurface,
+}
+
+impl CellOverlay {
+    pub(crate) fn new(compositor: &Compositor, index: usize) -> crate::Result<Self> {
+        let visual = compositor.CreateSpriteVisual()?;
+        visual.SetBrush(&compositor.CreateColorBrushWithColor(TINTS[index % TINTS.len()])?)?;
+        let surface = composition_graphics_device(compositor)?.CreateDrawingSurface(
+            Size::default(),
+            DirectXPixelFormat::B8G8R8A8UIntNormalized,
+            DirectXAlphaMode::Premultiplied,
+        )?;
+        let brush = compositor.CreateSurfaceBrush()?;
+        brush.SetStretch(CompositionStretch::None)?;
+        brush.SetHorizontalAlignmentRatio(0.)?;
+        brush.SetVerticalAlignmentRatio(0.)?;
+        brush.SetSurface(&surface)?;
+        let label = compositor.CreateSpriteVisual()?;
+        label.SetBrush(&brush)?;
+        visual.Children()?.InsertAtTop(&label)?;
+        Ok(Self {
+            visual,
+            label,
+            surface,
+        })
+    }
+
+    pub(crate) fn visual(&self) -> &SpriteVisual {
+        &self.visual
+    }
+
+    pub(crate) fn update(
+        &self,
+        offset: Vector2,
+        size: Vector2,
+        limit: &CellLimit,
+    ) -> crate::Result<()> {
+        self.visual.SetSize(size)?;
+        self.label.SetSize(size)?;
+        if size.X < 1. || size.Y < 1. {
+            return Ok(());
+        }
+        let max_size = limit
+            .max_size
+            .map_or("none".to_owned(), |v| format!("{}", v));
+        let text = format!(
+            "{}, {} {}x{}\nratio {} min {} max {}\ncontent {}x{}",
+            offset.X,
+            offset.Y,
+            size.X,
+            size.Y,
+            limit.ratio,
+            limit.min_size,
+            max_size,
+            limit.content_ratio.X,
+            limit.content_ratio.Y
+        );
+        self.surface.Resize(SizeInt32 {
+            Width: size.X as i32,
+            Height: size.Y as i32,
+        })?;
+        draw(&self.surface, |context, point| {
+            let text_format = unsafe {
+                dwrite_factory()?.CreateTextFormat(
+                    w!("Consolas"),
+                    InParam::null(),
+                    DWRITE_FONT_WEIGHT_NORMAL,
+                    DWRITE_FONT_STYLE_NORMAL,
+                    DWRITE_FONT_STRETCH_NORMAL,
+                    12.,
+                    w!("en-US"),
+                )
+            }?;
+            let clearcolor = D2D1_COLOR_F {
+                r: 0.,
+                g: 0.,
+                b: 0.,
+                a: 0.,
+            };
+            let text_color = D2D1_COLOR_F {
+                r: 0.,
+                g: 0.,
+                b: 0.,
+                a: 1.,
+            };
+            let text_brush_properties = D2D1_BRUSH_PROPERTIES {
+                opacity: 1.,
+                transform: Matrix3x2::identity(),
+            };
+            unsafe { context.Clear(Some(&clearcolor)) };
+            let text_brush = unsafe {
+                context.CreateSolidColorBrush(&text_color, Some(&text_brush_properties))
+            }?;
+            unsafe {
+                context.DrawText(
+                    text.as_str().to_wide().as_wide(),
+                    &text_format,
+                    &D2D_RECT_F {
+                        left: point.x as f32,
+                        top: point.y as f32,
+                        right: point.x as f32 + size.X,
+                        bottom: point.y as f32 + size.Y,
+                    },
+                    &text_brush,
+                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    DWRITE_MEASURING_MODE_NATURAL,
+                )
+            };
+            Ok(())
+        })
+    }
+}
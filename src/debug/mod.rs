@@ -0,0 +1,11 @@
+mod event_logger;
+mod layout_overlay;
+mod stream_diagnostics;
+
+pub use event_logger::{EventLoggerPanel, EventLoggerPanelParams};
+pub(crate) use layout_overlay::CellOverlay;
+pub use stream_diagnostics::{
+    clear_stream_diagnostics_hook, set_slow_consumer_threshold, set_stream_diagnostics_hook,
+    slow_consumer_threshold, StreamDiagnostic, DEFAULT_EVENT_BUFFER_SIZE,
+};
+pub(crate) use stream_diagnostics::{report, timed, EventBuffer};
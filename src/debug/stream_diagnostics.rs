@@ -0,0 +1,146 @@
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+use async_event_streams::{EventBox, EventStreams};
+use async_std::sync::Arc;
+
+///
+/// Problem with delivering events to their consumers
+///
+#[derive(Clone, PartialEq, Debug)]
+pub enum StreamDiagnostic {
+    ///
+    /// Consumers of the stream took longer than the threshold to handle the event
+    ///
+    SlowConsumer { stream: String, elapsed: Duration },
+    ///
+    /// The stream buffer was full and the event was dropped
+    ///
+    Overflow { stream: String, dropped: u64 },
+}
+
+type Hook = Arc<dyn Fn(&StreamDiagnostic) + Send + Sync>;
+
+static HOOK: RwLock<Option<Hook>> = RwLock::new(None);
+// Microseconds
+static SLOW_CONSUMER_THRESHOLD: AtomicU64 = AtomicU64::new(100_000);
+
+///
+/// Receive the stream diagnostics instead of the default `tracing` warnings (target "wag::streams")
+///
+pub fn set_stream_diagnostics_hook(hook: impl Fn(&StreamDiagnostic) + Send + Sync + 'static) {
+    *HOOK.write().unwrap() = Some(Arc::new(hook));
+}
+
+pub fn clear_stream_diagnostics_hook() {
+    *HOOK.write().unwrap() = None;
+}
+
+pub fn slow_consumer_threshold() -> Duration {
+    Duration::from_micros(SLOW_CONSUMER_THRESHOLD.load(Ordering::Relaxed))
+}
+
+///
+/// Event handling time after which the consumers are reported as slow, 100ms by default
+///
+pub fn set_slow_consumer_threshold(threshold: Duration) {
+    SLOW_CONSUMER_THRESHOLD.store(threshold.as_micros() as u64, Ordering::Relaxed);
+}
+
+pub(crate) fn report(diagnostic: StreamDiagnostic) {
+    let hook = HOOK.read().unwrap().clone();
+    match hook {
+        Some(hook) => hook(&diagnostic),
+        None => tracing::warn!(target: "wag::streams", diagnostic = ?diagnostic, "event stream"),
+    }
+}
+
+///
+/// Run the event delivery and report it if it took too long
+///
+pub(crate) async fn timed<R>(stream: &str, delivery: impl Future<Output = R>) -> R {
+    let start = Instant::now();
+    let result = delivery.await;
+    let elapsed = start.elapsed();
+    if elapsed > slow_consumer_threshold() {
+        report(StreamDiagnostic::SlowConsumer {
+            stream: stream.to_owned(),
+            elapsed,
+        });
+    }
+    result
+}
+
+///
+/// Default number of the widget's events being delivered at once, see `EventBuffer`
+///
+pub const DEFAULT_EVENT_BUFFER_SIZE: usize = 64;
+
+///
+/// Bounds the events of the widget's stream which are sent but not yet handled
+/// by the consumers. The events sent above the bound are dropped and reported
+/// as `StreamDiagnostic::Overflow`, so the stalled consumer doesn't pile up
+/// the waiting senders.
+///
+pub(crate) struct EventBuffer {
+    stream: &'static str,
+    size: AtomicUsize,
+    pending: AtomicUsize,
+    dropped: AtomicU64,
+}
+
+// Counts the delivery as pending until it ends or is dropped
+struct PendingDelivery<'a>(&'a AtomicUsize);
+
+impl Drop for PendingDelivery<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+impl EventBuffer {
+    pub(crate) fn new(stream: &'static str, size: usize) -> Self {
+        Self {
+            stream,
+            size: AtomicUsize::new(size),
+            pending: AtomicUsize::new(0),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn size(&self) -> usize {
+        self.size.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_size(&self, size: usize) {
+        self.size.store(size, Ordering::Relaxed)
+    }
+
+    ///
+    /// Send the event to the streams unless the buffer is full, reporting the slow delivery
+    ///
+    pub(crate) async fn send<T: Send + Sync + 'static>(
+        &self,
+        streams: &EventStreams<T>,
+        event: T,
+        source: Option<Arc<EventBox>>,
+    ) {
+        if self.pending.fetch_add(1, Ordering::AcqRel) >= self.size() {
+            self.pending.fetch_sub(1, Ordering::AcqRel);
+            let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+            report(StreamDiagnostic::Overflow {
+                stream: self.stream.to_owned(),
+                dropped,
+            });
+            return;
+        }
+        let _pending = PendingDelivery(&self.pending);
+        timed(self.stream, streams.send_event(event, source)).await;
+    }
+}
@@ -1,3 +1,6 @@
+use std::sync::RwLock;
+
+use async_std::sync::Arc;
 use futures::{task::SpawnError, Future};
 use thiserror::Error;
 use windows::core;
@@ -6,6 +9,10 @@ use windows::core;
 pub enum Error {
     #[error("Bad element index")]
     BadIndex,
+    #[error("Pixel buffer size doesn't match the bitmap dimensions")]
+    BadBitmapSize,
+    #[error("Bad composition expression: {0}")]
+    BadExpression(String),
     #[error(transparent)]
     Spawn(SpawnError),
     #[error(transparent)]
@@ -34,9 +41,29 @@ impl From<std::io::Error> for Error {
     }
 }
 
-// Later this function will be able to call globally set user error handler
+type ErrorHandler = Arc<dyn Fn(Error) + Send + Sync>;
+
+static ERROR_HANDLER: RwLock<Option<ErrorHandler>> = RwLock::new(None);
+
+///
+/// Handle the errors passed to `on_err` and `handle_err` instead of the default panic,
+/// e.g. to log them or to show them to the user with `gui::set_error_dialog_handler`
+///
+pub fn set_error_handler(handler: impl Fn(Error) + Send + Sync + 'static) {
+    *ERROR_HANDLER.write().unwrap() = Some(Arc::new(handler));
+}
+
+pub fn clear_error_handler() {
+    *ERROR_HANDLER.write().unwrap() = None;
+}
+
 pub fn on_err(e: crate::Error) {
-    panic!("{}", e);
+    // The handler is called without the lock, so it may replace itself or fail again
+    let handler = ERROR_HANDLER.read().unwrap().clone();
+    match handler {
+        Some(handler) => handler(e),
+        None => panic!("{}", e),
+    }
 }
 
 pub fn handle_err(future: impl Future<Output = Result<()>>) -> impl Future<Output = ()> {
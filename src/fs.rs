@@ -0,0 +1,404 @@
+//! File system change notifications delivered as event streams
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::Arc,
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use async_event_streams::{EventSource, EventStream, EventStreams};
+use windows::{
+    core::PCWSTR,
+    Win32::{
+        Foundation::{CloseHandle, HANDLE, WAIT_OBJECT_0, WAIT_TIMEOUT},
+        Storage::FileSystem::{
+            CreateFileW, ReadDirectoryChangesW, FILE_ACTION, FILE_ACTION_ADDED,
+            FILE_ACTION_MODIFIED, FILE_ACTION_REMOVED, FILE_ACTION_RENAMED_NEW_NAME,
+            FILE_ACTION_RENAMED_OLD_NAME, FILE_FLAG_BACKUP_SEMANTICS, FILE_FLAG_OVERLAPPED,
+            FILE_LIST_DIRECTORY, FILE_NOTIFY_CHANGE_DIR_NAME, FILE_NOTIFY_CHANGE_FILE_NAME,
+            FILE_NOTIFY_CHANGE_LAST_WRITE, FILE_NOTIFY_CHANGE_SIZE, FILE_SHARE_DELETE,
+            FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING,
+        },
+        System::{
+            Threading::{CreateEventW, SetEvent, WaitForMultipleObjects},
+            IO::{CancelIoEx, GetOverlappedResult, OVERLAPPED},
+        },
+    },
+};
+
+use crate::{on_err, window::ToWide};
+
+/// Changes arriving within this interval after the first undelivered one are delivered together
+const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(100);
+const BUFFER_SIZE: usize = 64 * 1024;
+
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub enum FsChange {
+    Created(PathBuf),
+    Removed(PathBuf),
+    Modified(PathBuf),
+    Renamed { from: PathBuf, to: PathBuf },
+    /// Changes came faster than they were read and some of them are lost,
+    /// the watched tree should be scanned again
+    Overflow,
+}
+
+///
+/// Watches the directory tree on the background thread until dropped
+///
+pub struct FsWatcher {
+    fs_events: Arc<EventStreams<FsChange>>,
+    stop_event: HANDLE,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl EventSource<FsChange> for FsWatcher {
+    fn event_stream(&self) -> EventStream<FsChange> {
+        self.fs_events.create_event_stream()
+    }
+}
+
+impl Drop for FsWatcher {
+    fn drop(&mut self) {
+        unsafe { SetEvent(self.stop_event) };
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        unsafe { CloseHandle(self.stop_event) };
+    }
+}
+
+///
+/// Start watching for changes of files and directories under the path, including subdirectories
+///
+pub fn watch(path: impl AsRef<Path>) -> crate::Result<FsWatcher> {
+    let root = path.as_ref().to_path_buf();
+    let directory = unsafe {
+        CreateFileW(
+            root.to_wide().as_pcwstr(),
+            FILE_LIST_DIRECTORY,
+            FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+            None,
+            OPEN_EXISTING,
+            FILE_FLAG_BACKUP_SEMANTICS | FILE_FLAG_OVERLAPPED,
+            HANDLE::default(),
+        )
+    }?;
+    let stop_event = unsafe { CreateEventW(None, true, false, PCWSTR::null()) }?;
+    let fs_events = Arc::new(EventStreams::new());
+    let thread = {
+        let fs_events = fs_events.clone();
+        std::thread::spawn(move || {
+            let result = watch_directory(directory, &root, stop_event, &fs_events);
+            unsafe { CloseHandle(directory) };
+            result.unwrap_or_else(on_err)
+        })
+    };
+    Ok(FsWatcher {
+        fs_events,
+        stop_event,
+        thread: Some(thread),
+    })
+}
+
+fn watch_directory(
+    directory: HANDLE,
+    root: &Path,
+    stop_event: HANDLE,
+    fs_events: &EventStreams<FsChange>,
+) -> crate::Result<()> {
+    let io_event = unsafe { CreateEventW(None, true, false, PCWSTR::null()) }?;
+    let result = read_changes(directory, root, io_event, stop_event, fs_events);
+    unsafe { CloseHandle(io_event) };
+    result
+}
+
+fn read_changes(
+    directory: HANDLE,
+    root: &Path,
+    io_event: HANDLE,
+    stop_event: HANDLE,
+    fs_events: &EventStreams<FsChange>,
+) -> crate::Result<()> {
+    // Notification records are DWORD aligned
+    let mut buffer = vec![0u32; BUFFER_SIZE / 4];
+    let mut pending = PendingChanges::default();
+    let mut renamed_from = None;
+    loop {
+        let mut overlapped = OVERLAPPED {
+            hEvent: io_event,
+            ..Default::default()
+        };
+        unsafe {
+            ReadDirectoryChangesW(
+                directory,
+                buffer.as_mut_ptr() as _,
+                BUFFER_SIZE as u32,
+                true,
+                FILE_NOTIFY_CHANGE_FILE_NAME
+                    | FILE_NOTIFY_CHANGE_DIR_NAME
+                    | FILE_NOTIFY_CHANGE_SIZE
+                    | FILE_NOTIFY_CHANGE_LAST_WRITE,
+                None,
+                Some(&mut overlapped as *mut _),
+                None,
+            )
+        }
+        .ok()?;
+        loop {
+            // A steady stream of changes never lets the wait time out, so the window
+            // is counted from the first pending change and not from the last one
+            let timeout = match pending.since {
+                None => u32::MAX,
+                Some(since) => match DEBOUNCE_INTERVAL.checked_sub(since.elapsed()) {
+                    Some(remaining) => remaining.as_millis() as u32,
+                    None => 0,
+                },
+            };
+            let wait = unsafe { WaitForMultipleObjects(&[io_event, stop_event], false, timeout) };
+            if wait == WAIT_OBJECT_0 {
+                if pending.is_expired() {
+                    pending.flush(fs_events);
+                }
+                break;
+            } else if wait == WAIT_TIMEOUT {
+                pending.flush(fs_events);
+            } else {
+                // Stop requested: the pending read must complete before the buffer is released
+                let mut bytes = 0;
+                unsafe {
+                    CancelIoEx(directory, Some(&overlapped as *const _));
+                    GetOverlappedResult(directory, &overlapped, &mut bytes, true);
+                }
+                return Ok(());
+            }
+        }
+        let mut bytes = 0;
+        unsafe { GetOverlappedResult(directory, &overlapped, &mut bytes, false) }.ok()?;
+        if bytes == 0 {
+            // The buffer overflowed and the changes are lost, the rename can't be paired
+            renamed_from = None;
+            pending.push(FsChange::Overflow);
+            continue;
+        }
+        let data =
+            unsafe { std::slice::from_raw_parts(buffer.as_ptr() as *const u8, bytes as usize) };
+        parse_notifications(data, root, &mut renamed_from, &mut pending);
+    }
+}
+
+fn parse_notifications(
+    data: &[u8],
+    root: &Path,
+    renamed_from: &mut Option<PathBuf>,
+    pending: &mut PendingChanges,
+) {
+    let read_u32 = |offset: usize| {
+        u32::from_ne_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ])
+    };
+    let mut offset = 0;
+    while offset + 12 <= data.len() {
+        let next = read_u32(offset) as usize;
+        let action = FILE_ACTION(read_u32(offset + 4));
+        let name_length = read_u32(offset + 8) as usize;
+        // The truncated record is dropped instead of reading past the data
+        let name = match data.get(offset + 12..offset + 12 + name_length) {
+            Some(name) => name,
+            None => break,
+        };
+        let name: Vec<u16> = name
+            .chunks_exact(2)
+            .map(|c| u16::from_ne_bytes([c[0], c[1]]))
+            .collect();
+        let path = root.join(String::from_utf16_lossy(&name));
+        let change = match action {
+            FILE_ACTION_ADDED => Some(FsChange::Created(path)),
+            FILE_ACTION_REMOVED => Some(FsChange::Removed(path)),
+            FILE_ACTION_MODIFIED => Some(FsChange::Modified(path)),
+            FILE_ACTION_RENAMED_OLD_NAME => {
+                *renamed_from = Some(path);
+                None
+            }
+            FILE_ACTION_RENAMED_NEW_NAME => renamed_from
+                .take()
+                .map(|from| FsChange::Renamed { from, to: path }),
+            _ => None,
+        };
+        if let Some(change) = change {
+            pending.push(change);
+        }
+        if next == 0 {
+            break;
+        }
+        offset += next;
+    }
+}
+
+///
+/// Changes collected for the next delivery in the order of arrival
+///
+#[derive(Default)]
+struct PendingChanges {
+    changes: Vec<FsChange>,
+    seen: HashSet<FsChange>,
+    since: Option<Instant>,
+}
+
+impl PendingChanges {
+    // Writes usually produce several identical notifications, keep only the first one
+    fn push(&mut self, change: FsChange) {
+        if self.seen.insert(change.clone()) {
+            self.since.get_or_insert_with(Instant::now);
+            self.changes.push(change);
+        }
+    }
+
+    fn is_expired(&self) -> bool {
+        self.since
+            .map_or(false, |since| since.elapsed() >= DEBOUNCE_INTERVAL)
+    }
+
+    fn flush(&mut self, fs_events: &EventStreams<FsChange>) {
+        self.seen.clear();
+        self.since = None;
+        for change in self.changes.drain(..) {
+            fs_events.post_event(change, None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // FILE_NOTIFY_INFORMATION record, `last` sets the zero offset of the next one
+    fn record(action: FILE_ACTION, name: &str, last: bool) -> Vec<u8> {
+        let name: Vec<u8> = name.encode_utf16().flat_map(u16::to_ne_bytes).collect();
+        // Records are DWORD aligned
+        let size = (12 + name.len() + 3) / 4 * 4;
+        let mut record = Vec::with_capacity(size);
+        record.extend((if last { 0 } else { size as u32 }).to_ne_bytes());
+        record.extend(action.0.to_ne_bytes());
+        record.extend((name.len() as u32).to_ne_bytes());
+        record.extend(name);
+        record.resize(size, 0);
+        record
+    }
+
+    fn parse(records: &[(FILE_ACTION, &str)]) -> Vec<FsChange> {
+        let data: Vec<u8> = records
+            .iter()
+            .enumerate()
+            .flat_map(|(i, (action, name))| record(*action, name, i + 1 == records.len()))
+            .collect();
+        let mut pending = PendingChanges::default();
+        parse_notifications(&data, Path::new("root"), &mut None, &mut pending);
+        pending.changes
+    }
+
+    #[test]
+    fn actions_are_parsed() {
+        let changes = parse(&[
+            (FILE_ACTION_ADDED, "a.txt"),
+            (FILE_ACTION_MODIFIED, "dir\\b.txt"),
+            (FILE_ACTION_REMOVED, "c"),
+        ]);
+        assert_eq!(
+            changes,
+            vec![
+                FsChange::Created(PathBuf::from("root").join("a.txt")),
+                FsChange::Modified(PathBuf::from("root").join("dir\\b.txt")),
+                FsChange::Removed(PathBuf::from("root").join("c")),
+            ]
+        );
+    }
+
+    #[test]
+    fn rename_is_paired() {
+        let changes = parse(&[
+            (FILE_ACTION_RENAMED_OLD_NAME, "old.txt"),
+            (FILE_ACTION_RENAMED_NEW_NAME, "новый 📄.txt"),
+        ]);
+        assert_eq!(
+            changes,
+            vec![FsChange::Renamed {
+                from: PathBuf::from("root").join("old.txt"),
+                to: PathBuf::from("root").join("новый 📄.txt"),
+            }]
+        );
+    }
+
+    #[test]
+    fn rename_is_paired_across_reads() {
+        let mut renamed_from = None;
+        let mut pending = PendingChanges::default();
+        let root = Path::new("root");
+        let old = record(FILE_ACTION_RENAMED_OLD_NAME, "old", true);
+        let new = record(FILE_ACTION_RENAMED_NEW_NAME, "new", true);
+        parse_notifications(&old, root, &mut renamed_from, &mut pending);
+        assert!(pending.changes.is_empty());
+        parse_notifications(&new, root, &mut renamed_from, &mut pending);
+        assert_eq!(
+            pending.changes,
+            vec![FsChange::Renamed {
+                from: root.join("old"),
+                to: root.join("new"),
+            }]
+        );
+    }
+
+    #[test]
+    fn new_name_without_old_is_ignored() {
+        assert!(parse(&[(FILE_ACTION_RENAMED_NEW_NAME, "new")]).is_empty());
+    }
+
+    #[test]
+    fn duplicates_are_merged() {
+        let changes = parse(&[
+            (FILE_ACTION_MODIFIED, "a"),
+            (FILE_ACTION_MODIFIED, "a"),
+            (FILE_ACTION_MODIFIED, "b"),
+        ]);
+        assert_eq!(
+            changes,
+            vec![
+                FsChange::Modified(PathBuf::from("root").join("a")),
+                FsChange::Modified(PathBuf::from("root").join("b")),
+            ]
+        );
+    }
+
+    #[test]
+    fn debounce_window_starts_at_first_change() {
+        let mut pending = PendingChanges::default();
+        assert!(!pending.is_expired());
+        pending.push(FsChange::Modified(PathBuf::from("a")));
+        let since = pending.since;
+        assert!(since.is_some());
+        pending.push(FsChange::Modified(PathBuf::from("b")));
+        assert_eq!(pending.since, since);
+        pending.since = Some(Instant::now() - DEBOUNCE_INTERVAL);
+        assert!(pending.is_expired());
+    }
+
+    #[test]
+    fn truncated_data_is_ignored() {
+        let mut data = record(FILE_ACTION_ADDED, "a", false);
+        data.extend(&record(FILE_ACTION_ADDED, "long name", true)[..16]);
+        let mut pending = PendingChanges::default();
+        parse_notifications(&data, Path::new("root"), &mut None, &mut pending);
+        assert_eq!(
+            pending.changes,
+            vec![FsChange::Created(PathBuf::from("root").join("a"))]
+        );
+        parse_notifications(&[1, 2, 3], Path::new("root"), &mut None, &mut pending);
+        assert_eq!(pending.changes.len(), 1);
+    }
+}
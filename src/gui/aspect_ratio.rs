@@ -0,0 +1,204 @@
+use std::borrow::Cow;
+
+use async_event_streams::{
+    EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
+};
+use async_event_streams_derive::EventSink;
+use async_std::sync::{Arc, RwLock};
+use async_trait::async_trait;
+use typed_builder::TypedBuilder;
+use windows::{
+    Foundation::Numerics::{Vector2, Vector3},
+    UI::Composition::{Compositor, ContainerVisual, Visual},
+};
+
+use super::{attach, is_point_in_box, Panel, PanelEvent};
+
+struct Core {
+    ratio: f32,
+    size: Vector2,
+    child_offset: Vector2,
+    child_size: Vector2,
+    mouse_pos: Option<Vector2>,
+}
+
+impl Core {
+    fn layout(&mut self) {
+        let ratio = if self.ratio > 0. { self.ratio } else { 1. };
+        let width = self.size.X.min(self.size.Y * ratio);
+        self.child_size = Vector2 {
+            X: width,
+            Y: width / ratio,
+        };
+        self.child_offset = Vector2 {
+            X: (self.size.X - self.child_size.X) / 2.,
+            Y: (self.size.Y - self.child_size.Y) / 2.,
+        };
+    }
+}
+
+///
+/// Decorator which keeps the wrapped panel at fixed width:height ratio
+/// centered in the available space
+///
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+pub struct AspectRatio {
+    container: ContainerVisual,
+    panel: Arc<dyn Panel>,
+    core: RwLock<Core>,
+    panel_events: EventStreams<PanelEvent>,
+    id: Arc<()>,
+}
+
+#[derive(TypedBuilder)]
+pub struct AspectRatioParams {
+    compositor: Compositor,
+    #[builder(setter(transform = |panel: impl Panel + 'static| Arc::new(panel) as Arc<dyn Panel>))]
+    panel: Arc<dyn Panel>,
+    /// Width divided by height
+    #[builder(default = 1.)]
+    ratio: f32,
+}
+
+impl TryFrom<AspectRatioParams> for AspectRatio {
+    type Error = crate::Error;
+
+    fn try_from(value: AspectRatioParams) -> crate::Result<Self> {
+        let container = value.compositor.CreateContainerVisual()?;
+        attach(&container, &*value.panel)?;
+        Ok(AspectRatio {
+            container,
+            panel: value.panel,
+            core: RwLock::new(Core {
+                ratio: value.ratio,
+                size: Vector2::default(),
+                child_offset: Vector2::default(),
+                child_size: Vector2::default(),
+                mouse_pos: None,
+            }),
+            panel_events: EventStreams::new(),
+            id: Arc::new(()),
+        })
+    }
+}
+
+impl TryFrom<AspectRatioParams> for Arc<AspectRatio> {
+    type Error = crate::Error;
+
+    fn try_from(value: AspectRatioParams) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
+
+impl AspectRatio {
+    pub async fn ratio(&self) -> f32 {
+        self.core.read().await.ratio
+    }
+
+    pub async fn set_ratio(&self, ratio: f32) -> crate::Result<()> {
+        let size = {
+            let mut core = self.core.write().await;
+            core.ratio = ratio;
+            core.size
+        };
+        self.resize(size, None).await
+    }
+
+    async fn resize(&self, size: Vector2, source: Option<Arc<EventBox>>) -> crate::Result<()> {
+        let (offset, child_size) = {
+            let mut core = self.core.write().await;
+            core.size = size;
+            core.layout();
+            (core.child_offset, core.child_size)
+        };
+        self.container.SetSize(size)?;
+        let frame = self.panel.outer_frame();
+        frame.SetOffset(Vector3 {
+            X: offset.X,
+            Y: offset.Y,
+            Z: 0.,
+        })?;
+        frame.SetSize(child_size)?;
+        self.panel
+            .on_event_owned(PanelEvent::Resized(child_size), source)
+            .await
+    }
+
+    async fn translate_cursor_moved(
+        &self,
+        mouse_pos: Vector2,
+        seq: u64,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        let offset = {
+            let mut core = self.core.write().await;
+            core.mouse_pos = Some(mouse_pos);
+            core.child_offset
+        };
+        let mouse_pos = Vector2 {
+            X: mouse_pos.X - offset.X,
+            Y: mouse_pos.Y - offset.Y,
+        };
+        self.panel
+            .on_event_owned(PanelEvent::CursorMoved(mouse_pos, seq), source)
+            .await
+    }
+
+    async fn is_in_child(&self) -> bool {
+        let core = self.core.read().await;
+        core.mouse_pos.map_or(false, |mouse_pos| {
+            is_point_in_box(mouse_pos, core.child_offset, core.child_size)
+        })
+    }
+}
+
+impl Panel for AspectRatio {
+    fn outer_frame(&self) -> Visual {
+        self.container.clone().into()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+}
+
+impl EventSource<PanelEvent> for AspectRatio {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for AspectRatio {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        match event.as_ref() {
+            PanelEvent::Resized(size) => self.resize(*size, source.clone()).await?,
+            PanelEvent::CursorMoved(mouse_pos, seq) => {
+                self.translate_cursor_moved(*mouse_pos, *seq, source.clone())
+                    .await?
+            }
+            PanelEvent::MouseInput { in_slot, .. }
+            | PanelEvent::MouseDoubleClick { in_slot, .. }
+            | PanelEvent::DragDrop { in_slot, .. } => {
+                let in_slot = *in_slot && self.is_in_child().await;
+                self.panel
+                    .on_event_owned(event.with_in_slot(in_slot), source.clone())
+                    .await?
+            }
+            _ => {
+                self.panel
+                    .on_event_ref(event.as_ref(), source.clone())
+                    .await?
+            }
+        }
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+use std::borrow::Cow;
+
+use async_event_streams::{
+    EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
+};
+use async_event_streams_derive::{self, EventSink};
+use async_std::sync::{Arc, RwLock};
+use async_trait::async_trait;
+use typed_builder::TypedBuilder;
+use windows::UI::{
+    Color,
+    Composition::{CompositionBrush, Compositor, ContainerVisual, SpriteVisual, Visual},
+};
+
+use crate::capabilities::backdrop_material_or_fallback;
+
+use super::{Panel, PanelEvent};
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum BackdropMaterial {
+    ///
+    /// Blurred content behind the window. Requires `Window::set_host_backdrop(true)`.
+    ///
+    #[default]
+    Acrylic,
+    ///
+    /// Blurred desktop wallpaper, available since Windows 11. Acrylic is used on older systems.
+    ///
+    Mica,
+}
+
+struct Core {
+    material: BackdropMaterial,
+    tint_color: Color,
+}
+
+///
+/// Translucent system material under the tint color. If the material is not supported
+/// by the system, only the tint color is shown.
+///
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+pub struct BackdropPanel {
+    compositor: Compositor,
+    container: ContainerVisual,
+    backdrop: SpriteVisual,
+    tint: SpriteVisual,
+    core: RwLock<Core>,
+    panel_events: EventStreams<PanelEvent>,
+    id: Arc<()>,
+}
+
+#[derive(TypedBuilder)]
+pub struct BackdropPanelParams {
+    compositor: Compositor,
+    #[builder(default)]
+    material: BackdropMaterial,
+    #[builder(default = Color { A: 0x40, R: 0xF3, G: 0xF3, B: 0xF3 })]
+    tint_color: Color,
+}
+
+fn create_material_brush(
+    compositor: &Compositor,
+    material: BackdropMaterial,
+) -> Option<CompositionBrush> {
+    let brush = match backdrop_material_or_fallback(material) {
+        BackdropMaterial::Acrylic => compositor.CreateHostBackdropBrush(),
+        BackdropMaterial::Mica => compositor.TryCreateBlurredWallpaperBackdropBrush(),
+    };
+    brush.ok().map(|brush| brush.into())
+}
+
+impl TryFrom<BackdropPanelParams> for BackdropPanel {
+    type Error = crate::Error;
+
+    fn try_from(value: BackdropPanelParams) -> crate::Result<Self> {
+        let compositor = value.compositor;
+        let container = compositor.CreateContainerVisual()?;
+        let backdrop = compositor.CreateSpriteVisual()?;
+        backdrop.SetBrush(create_material_brush(&compositor, value.material).as_ref())?;
+        let tint = compositor.CreateSpriteVisual()?;
+        tint.SetBrush(&compositor.CreateColorBrushWithColor(value.tint_color)?)?;
+        container.Children()?.InsertAtTop(&backdrop)?;
+        container.Children()?.InsertAtTop(&tint)?;
+        Ok(BackdropPanel {
+            compositor,
+            container,
+            backdrop,
+            tint,
+            core: RwLock::new(Core {
+                material: value.material,
+                tint_color: value.tint_color,
+            }),
+            panel_events: EventStreams::new(),
+            id: Arc::new(()),
+        })
+    }
+}
+
+impl TryFrom<BackdropPanelParams> for Arc<BackdropPanel> {
+    type Error = crate::Error;
+
+    fn try_from(value: BackdropPanelParams) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
+
+impl BackdropPanel {
+    pub async fn material(&self) -> BackdropMaterial {
+        self.core.read().await.material
+    }
+    pub async fn set_material(&self, material: BackdropMaterial) -> crate::Result<()> {
+        self.core.write().await.material = material;
+        self.backdrop
+            .SetBrush(create_material_brush(&self.compositor, material).as_ref())?;
+        Ok(())
+    }
+    pub async fn tint_color(&self) -> Color {
+        self.core.read().await.tint_color
+    }
+    pub async fn set_tint_color(&self, tint_color: Color) -> crate::Result<()> {
+        self.core.write().await.tint_color = tint_color;
+        self.tint
+            .SetBrush(&self.compositor.CreateColorBrushWithColor(tint_color)?)?;
+        Ok(())
+    }
+}
+
+impl Panel for BackdropPanel {
+    fn outer_frame(&self) -> Visual {
+        self.container.clone().into()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+}
+
+impl EventSource<PanelEvent> for BackdropPanel {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for BackdropPanel {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        if let PanelEvent::Resized(size) = event.as_ref() {
+            self.container.SetSize(*size)?;
+            self.backdrop.SetSize(*size)?;
+            self.tint.SetSize(*size)?;
+        }
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
@@ -12,75 +12,108 @@ use windows::{
     Foundation::Numerics::Vector2,
     UI::{
         Color,
-        Composition::{CompositionShape, Compositor, ContainerVisual, ShapeVisual, Visual},
+        Composition::{
+            CompositionRoundedRectangleGeometry, CompositionSpriteShape, Compositor,
+            ContainerVisual, ShapeVisual, Visual,
+        },
     },
 };
 
-use super::{Panel, PanelEvent};
+use super::{
+    brush_cache::shared_color_brush,
+    is_translated_point_in_rounded_box,
+    theme::{Theme, ThemeRef},
+    Panel, PanelEvent,
+};
 
 struct Core {
     round_corners: bool,
+    corner_radius: Option<f32>,
     color: Color,
+    // The color is taken from the theme, not set explicitly
+    themed_color: bool,
+    theme: ThemeRef,
+    stroke_color: Color,
+    stroke_thickness: f32,
     compositor: Compositor,
     container: ShapeVisual,
+    geometry: CompositionRoundedRectangleGeometry,
+    shape: CompositionSpriteShape,
+    // Size the geometry was built for, `None` before the first resize
+    size: Option<Vector2>,
 }
 
 impl Core {
-    fn create_background_shape(
-        compositor: &Compositor,
-        size: Vector2,
-        round_corners: bool,
-        color: Color,
-    ) -> crate::Result<CompositionShape> {
-        let container_shape = compositor.CreateContainerShape()?;
-        let rect_geometry = compositor.CreateRoundedRectangleGeometry()?;
-        rect_geometry.SetSize(size)?;
-        if round_corners {
-            let size = rect_geometry.Size()?;
-            let radius = std::cmp::min(FloatOrd(size.X), FloatOrd(size.Y)).0 / 20.;
-            rect_geometry.SetCornerRadius(Vector2 {
-                X: radius,
-                Y: radius,
-            })?;
-        } else {
-            rect_geometry.SetCornerRadius(Vector2 { X: 0., Y: 0. })?;
+    fn corner_radius(&self, size: Vector2) -> f32 {
+        match self.corner_radius {
+            Some(radius) => radius,
+            None if self.round_corners => self
+                .theme
+                .get()
+                .corner_radius
+                .unwrap_or_else(|| std::cmp::min(FloatOrd(size.X), FloatOrd(size.Y)).0 / 20.),
+            None => 0.,
         }
-        let brush = compositor.CreateColorBrushWithColor(color)?;
-        let rect = compositor.CreateSpriteShapeWithGeometry(&rect_geometry)?;
-        rect.SetFillBrush(&brush)?;
-        rect.SetOffset(Vector2 { X: 0., Y: 0. })?;
-        container_shape.Shapes()?.Append(&rect)?;
-        let shape = container_shape.into();
-        Ok(shape)
     }
     fn redraw(&self) -> crate::Result<()> {
-        self.container.Shapes()?.Clear()?;
-        self.container
-            .Shapes()?
-            .Append(&Self::create_background_shape(
-                &self.compositor,
-                self.container.Size()?,
-                self.round_corners,
-                self.color,
-            )?)?;
+        let size = self.container.Size()?;
+        // The stroke is centered on the geometry edge, so the geometry is shrunk
+        // by half of the thickness to keep the stroke inside the panel
+        let inset = self.stroke_thickness / 2.;
+        self.geometry.SetOffset(Vector2 { X: inset, Y: inset })?;
+        self.geometry.SetSize(Vector2 {
+            X: (size.X - self.stroke_thickness).max(0.),
+            Y: (size.Y - self.stroke_thickness).max(0.),
+        })?;
+        let radius = self.corner_radius(size);
+        self.geometry.SetCornerRadius(Vector2 {
+            X: radius,
+            Y: radius,
+        })?;
         Ok(())
     }
     fn resize(&mut self, size: Vector2) -> crate::Result<()> {
+        if self.size == Some(size) {
+            return Ok(());
+        }
+        self.size = Some(size);
         self.container.SetSize(size)?;
         self.redraw()?;
         Ok(())
     }
     fn set_color(&mut self, color: Color) -> crate::Result<()> {
+        if self.color == color {
+            return Ok(());
+        }
         self.color = color;
-        self.redraw()?;
+        self.shape
+            .SetFillBrush(&shared_color_brush(&self.compositor, color)?)?;
         Ok(())
     }
+    fn restyle(&mut self, theme: &Arc<Theme>) -> crate::Result<()> {
+        if self.theme.update(theme) {
+            if self.themed_color {
+                self.set_color(theme.background_color)?;
+            }
+            self.redraw()?;
+        }
+        Ok(())
+    }
+    fn set_stroke(&mut self, stroke_color: Color, stroke_thickness: f32) -> crate::Result<()> {
+        self.stroke_color = stroke_color;
+        self.stroke_thickness = stroke_thickness;
+        self.shape
+            .SetStrokeBrush(&shared_color_brush(&self.compositor, stroke_color)?)?;
+        self.shape.SetStrokeThickness(stroke_thickness)?;
+        self.redraw()
+    }
 }
 
 #[derive(EventSink)]
 #[event_sink(event=PanelEvent)]
 pub struct Background {
     container: ContainerVisual,
+    geometry: CompositionRoundedRectangleGeometry,
     core: RwLock<Core>,
     panel_events: EventStreams<PanelEvent>,
     id: Arc<()>,
@@ -89,23 +122,56 @@ pub struct Background {
 #[derive(TypedBuilder)]
 pub struct BackgroundParams {
     round_corners: bool,
-    color: Color,
+    /// Background color of the theme by default
+    #[builder(default, setter(strip_option))]
+    color: Option<Color>,
     compositor: Compositor,
+    /// Theme to use instead of the global one
+    #[builder(default, setter(into))]
+    theme: Option<Arc<Theme>>,
+    /// Explicit corner radius, overrides `round_corners`
+    #[builder(default, setter(strip_option))]
+    corner_radius: Option<f32>,
+    #[builder(default = Color { A: 0, R: 0, G: 0, B: 0 })]
+    stroke_color: Color,
+    #[builder(default)]
+    stroke_thickness: f32,
 }
 
 impl TryFrom<BackgroundParams> for Background {
     type Error = crate::Error;
 
     fn try_from(value: BackgroundParams) -> crate::Result<Self> {
-        let container = value.compositor.CreateShapeVisual()?;
-        let core = RwLock::new(Core {
+        let compositor = value.compositor;
+        let container = compositor.CreateShapeVisual()?;
+        // The shape tree is built once, later changes only update its properties
+        let geometry = compositor.CreateRoundedRectangleGeometry()?;
+        let shape = compositor.CreateSpriteShapeWithGeometry(&geometry)?;
+        container.Shapes()?.Append(&shape)?;
+        let theme = ThemeRef::new(value.theme);
+        let color = value.color.unwrap_or(theme.get().background_color);
+        let mut core = Core {
             round_corners: value.round_corners,
-            color: value.color,
-            compositor: value.compositor,
+            corner_radius: value.corner_radius,
+            color,
+            themed_color: value.color.is_none(),
+            theme,
+            stroke_color: value.stroke_color,
+            stroke_thickness: value.stroke_thickness,
+            compositor,
             container: container.clone(),
-        });
+            geometry,
+            shape,
+            size: None,
+        };
+        core.shape
+            .SetFillBrush(&shared_color_brush(&core.compositor, color)?)?;
+        core.set_stroke(value.stroke_color, value.stroke_thickness)?;
+        let geometry = core.geometry.clone();
+        let core = RwLock::new(core);
         Ok(Background {
             container: container.into(),
+            geometry,
             core,
             panel_events: EventStreams::new(),
             id: Arc::new(()),
@@ -126,8 +192,36 @@ impl Background {
         self.core.read().await.color
     }
     pub async fn set_color(&self, color: Color) -> crate::Result<()> {
-        self.core.write().await.set_color(color)?;
-        Ok(())
+        let mut core = self.core.write().await;
+        core.themed_color = false;
+        core.set_color(color)
+    }
+    pub async fn corner_radius(&self) -> Option<f32> {
+        self.core.read().await.corner_radius
+    }
+    ///
+    /// Set the explicit corner radius, `None` returns to the one defined by `round_corners`
+    ///
+    pub async fn set_corner_radius(&self, corner_radius: Option<f32>) -> crate::Result<()> {
+        let mut core = self.core.write().await;
+        core.corner_radius = corner_radius;
+        core.redraw()
+    }
+    pub async fn stroke_color(&self) -> Color {
+        self.core.read().await.stroke_color
+    }
+    pub async fn set_stroke_color(&self, stroke_color: Color) -> crate::Result<()> {
+        let mut core = self.core.write().await;
+        let stroke_thickness = core.stroke_thickness;
+        core.set_stroke(stroke_color, stroke_thickness)
+    }
+    pub async fn stroke_thickness(&self) -> f32 {
+        self.core.read().await.stroke_thickness
+    }
+    pub async fn set_stroke_thickness(&self, stroke_thickness: f32) -> crate::Result<()> {
+        let mut core = self.core.write().await;
+        let stroke_color = core.stroke_color;
+        core.set_stroke(stroke_color, stroke_thickness)
     }
 }
 
@@ -139,6 +233,13 @@ impl Panel for Background {
     fn id(&self) -> usize {
         Arc::as_ptr(&self.id) as usize
     }
+    // Clicks outside of the rounded corners go to the panels below
+    fn hit_test(&self, point: Vector2) -> bool {
+        match (self.container.Size(), self.geometry.CornerRadius()) {
+            (Ok(size), Ok(radius)) => is_translated_point_in_rounded_box(point, size, radius.X),
+            _ => false,
+        }
+    }
 }
 
 impl EventSource<PanelEvent> for Background {
@@ -155,8 +256,10 @@ impl EventSinkExt<PanelEvent> for Background {
         event: Cow<'a, PanelEvent>,
         source: Option<Arc<EventBox>>,
     ) -> crate::Result<()> {
-        if let PanelEvent::Resized(size) = event.as_ref() {
-            self.core.write().await.resize(*size)?;
+        match event.as_ref() {
+            PanelEvent::Resized(size) => self.core.write().await.resize(*size)?,
+            PanelEvent::ThemeChanged(theme) => self.core.write().await.restyle(theme)?,
+            _ => (),
         }
         self.panel_events
             .send_event(event.into_owned(), source)
@@ -0,0 +1,181 @@
+use std::borrow::Cow;
+
+use async_event_streams::{
+    EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
+};
+use async_event_streams_derive::EventSink;
+use async_std::sync::{Arc, RwLock};
+use async_trait::async_trait;
+use typed_builder::TypedBuilder;
+use windows::{
+    Foundation::Numerics::Vector2,
+    UI::{
+        Color,
+        Composition::{
+            CompositionColorBrush, CompositionRoundedRectangleGeometry, CompositionSpriteShape,
+            Compositor, ContainerVisual, ShapeVisual, Visual,
+        },
+    },
+};
+
+use super::{attach, Margins, Padded, PaddedParams, Panel, PanelEvent};
+
+struct Core {
+    size: Vector2,
+    thickness: f32,
+    corner_radius: f32,
+}
+
+///
+/// Decorator drawing the stroked rectangle around the wrapped panel.
+/// The panel is inset by the stroke thickness.
+///
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+pub struct Border {
+    container: ContainerVisual,
+    shape: ShapeVisual,
+    geometry: CompositionRoundedRectangleGeometry,
+    stroke: CompositionSpriteShape,
+    brush: CompositionColorBrush,
+    padded: Padded,
+    core: RwLock<Core>,
+    panel_events: EventStreams<PanelEvent>,
+    id: Arc<()>,
+}
+
+#[derive(TypedBuilder)]
+pub struct BorderParams {
+    compositor: Compositor,
+    #[builder(setter(transform = |panel: impl Panel + 'static| Arc::new(panel) as Arc<dyn Panel>))]
+    panel: Arc<dyn Panel>,
+    color: Color,
+    #[builder(default = 1.)]
+    thickness: f32,
+    #[builder(default)]
+    corner_radius: f32,
+}
+
+impl TryFrom<BorderParams> for Border {
+    type Error = crate::Error;
+
+    fn try_from(value: BorderParams) -> crate::Result<Self> {
+        let compositor = value.compositor;
+        let container = compositor.CreateContainerVisual()?;
+        let shape = compositor.CreateShapeVisual()?;
+        let geometry = compositor.CreateRoundedRectangleGeometry()?;
+        let stroke = compositor.CreateSpriteShapeWithGeometry(&geometry)?;
+        let brush = compositor.CreateColorBrushWithColor(value.color)?;
+        stroke.SetStrokeBrush(&brush)?;
+        shape.Shapes()?.Append(&stroke)?;
+        container.Children()?.InsertAtTop(&shape)?;
+        let padded: Padded = PaddedParams::builder()
+            .compositor(compositor)
+            .panel(value.panel)
+            .margins(Margins::uniform(value.thickness))
+            .build()
+            .try_into()?;
+        attach(&container, &padded)?;
+        Ok(Border {
+            container,
+            shape,
+            geometry,
+            stroke,
+            brush,
+            padded,
+            core: RwLock::new(Core {
+                size: Vector2::default(),
+                thickness: value.thickness,
+                corner_radius: value.corner_radius,
+            }),
+            panel_events: EventStreams::new(),
+            id: Arc::new(()),
+        })
+    }
+}
+
+impl TryFrom<BorderParams> for Arc<Border> {
+    type Error = crate::Error;
+
+    fn try_from(value: BorderParams) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
+
+impl Border {
+    fn redraw(&self, core: &Core) -> crate::Result<()> {
+        self.shape.SetSize(core.size)?;
+        // Stroke is centered on the geometry outline, so the outline is inset by half of thickness
+        let half = core.thickness / 2.;
+        self.geometry.SetOffset(Vector2 { X: half, Y: half })?;
+        self.geometry.SetSize(Vector2 {
+            X: (core.size.X - core.thickness).max(0.),
+            Y: (core.size.Y - core.thickness).max(0.),
+        })?;
+        self.geometry.SetCornerRadius(Vector2 {
+            X: core.corner_radius,
+            Y: core.corner_radius,
+        })?;
+        self.stroke.SetStrokeThickness(core.thickness)?;
+        Ok(())
+    }
+
+    pub fn set_color(&self, color: Color) -> crate::Result<()> {
+        self.brush.SetColor(color)?;
+        Ok(())
+    }
+
+    pub async fn set_thickness(&self, thickness: f32) -> crate::Result<()> {
+        {
+            let mut core = self.core.write().await;
+            core.thickness = thickness;
+            self.redraw(&core)?;
+        }
+        self.padded.set_margins(Margins::uniform(thickness)).await
+    }
+
+    pub async fn set_corner_radius(&self, corner_radius: f32) -> crate::Result<()> {
+        let mut core = self.core.write().await;
+        core.corner_radius = corner_radius;
+        self.redraw(&core)
+    }
+}
+
+impl Panel for Border {
+    fn outer_frame(&self) -> Visual {
+        self.container.clone().into()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+}
+
+impl EventSource<PanelEvent> for Border {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for Border {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        if let PanelEvent::Resized(size) = event.as_ref() {
+            self.container.SetSize(*size)?;
+            let mut core = self.core.write().await;
+            core.size = *size;
+            self.redraw(&core)?;
+        }
+        self.padded
+            .on_event_ref(event.as_ref(), source.clone())
+            .await?;
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use windows::UI::{
+    Color,
+    Composition::{CompositionColorBrush, Compositor},
+};
+
+// Colors are rarely animated through the brushes, but if they are, the cache
+// is dropped when it grows too large. Brushes in use stay alive in their shapes.
+const MAX_CACHED_BRUSHES: usize = 256;
+
+static BRUSH_CACHE: Mutex<Vec<(Compositor, HashMap<u32, CompositionColorBrush>)>> =
+    Mutex::new(Vec::new());
+
+fn color_key(color: Color) -> u32 {
+    u32::from_be_bytes([color.A, color.R, color.G, color.B])
+}
+
+///
+/// Solid color brush shared by all the panels of the compositor. The returned brush
+/// must not be modified.
+///
+pub(crate) fn shared_color_brush(
+    compositor: &Compositor,
+    color: Color,
+) -> crate::Result<CompositionColorBrush> {
+    let mut cache = BRUSH_CACHE.lock().unwrap();
+    let index = match cache.iter().position(|(c, _)| c == compositor) {
+        Some(index) => index,
+        None => {
+            cache.push((compositor.clone(), HashMap::new()));
+            cache.len() - 1
+        }
+    };
+    let brushes = &mut cache[index].1;
+    if let Some(brush) = brushes.get(&color_key(color)) {
+        return Ok(brush.clone());
+    }
+    if brushes.len() >= MAX_CACHED_BRUSHES {
+        brushes.clear();
+    }
+    let brush = compositor.CreateColorBrushWithColor(color)?;
+    brushes.insert(color_key(color), brush.clone());
+    Ok(brush)
+}
+
+///
+/// Release the shared brushes of the compositor, e.g. before closing it
+///
+pub fn clear_brush_cache(compositor: &Compositor) {
+    BRUSH_CACHE.lock().unwrap().retain(|(c, _)| c != compositor);
+}
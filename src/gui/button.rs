@@ -1,7 +1,15 @@
 use std::borrow::Cow;
 
-use super::{attach, Text, TextParams};
+use super::skins::{ControlSkin, SkinTransition, TransitionToken};
+use super::{
+    attach, detach,
+    enabled::{fade, EnabledOpacity},
+    theme::{theme, Theme, ThemeRef},
+    touch::is_point_in_hit_area,
+    EventOrder, Margins, Text, TextParams,
+};
 use super::{Background, BackgroundParams, LayerStack, LayerStackParams, Panel, PanelEvent};
+use crate::debug::{EventBuffer, DEFAULT_EVENT_BUFFER_SIZE};
 use async_event_streams::{
     EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
 };
@@ -11,23 +19,60 @@ use async_std::sync::RwLock;
 use async_trait::async_trait;
 use futures::task::Spawn;
 use typed_builder::TypedBuilder;
+use windows::Foundation::Numerics::Vector2;
 use windows::UI::Composition::Visual;
 use windows::UI::{
-    Color, Colors,
+    Color,
     Composition::{Compositor, ContainerVisual},
 };
 use winit::event::{ElementState, MouseButton};
 
+const BUTTON_EVENTS: &str = "button events";
+
 #[derive(PartialEq, Clone, Debug)]
 pub enum ButtonEvent {
     Press,
     Release(bool),
+    /// Second click of the left button, sent after its `Press`
+    DoubleClick,
 }
 
+///
+/// Visual state of the button passed to its skin on each change
+///
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum ButtonVisualState {
+    #[default]
+    Normal,
+    Hover,
+    Pressed,
+    Disabled,
+    Focused,
+}
+
+///
+/// Change of the visual state published for the skins animating it
+///
+pub type ButtonTransition = SkinTransition<ButtonVisualState>;
+
 struct Core {
     skin: Arc<dyn ButtonSkin>,
     pressed: bool,
+    hover: bool,
+    // The disabled button is faded like the panels in `Enabled`
+    enabled: EnabledOpacity,
+    focused: bool,
+    size: Vector2,
+    hit_slop: Margins,
+    state: ButtonVisualState,
+    compositor: Compositor,
+    visual: Visual,
+    token: TransitionToken,
+    transition_events: Arc<EventStreams<ButtonTransition>>,
     button_events: Arc<EventStreams<ButtonEvent>>,
+    button_events_buffer: EventBuffer,
+    // Stale pointer events must not revert the newer press or hover state
+    event_order: EventOrder,
 }
 
 #[derive(EventSink)]
@@ -37,6 +82,7 @@ pub struct Button {
     core: RwLock<Core>,
     panel_events: EventStreams<PanelEvent>,
     button_events: Arc<EventStreams<ButtonEvent>>,
+    transition_events: Arc<EventStreams<ButtonTransition>>,
     id: Arc<()>,
 }
 
@@ -45,6 +91,16 @@ pub struct ButtonParams {
     compositor: Compositor,
     #[builder(setter(transform = |skin: impl ButtonSkin + 'static | Arc::new(skin) as Arc<dyn ButtonSkin>))]
     skin: Arc<dyn ButtonSkin>,
+    /// Extension of the hit test area around the button
+    #[builder(default)]
+    hit_slop: Margins,
+    /// Button events being delivered at once, the following ones are dropped
+    /// and reported as `StreamDiagnostic::Overflow` until the consumers catch up
+    #[builder(default = DEFAULT_EVENT_BUFFER_SIZE)]
+    event_buffer_size: usize,
+    /// Theme to use instead of the global one
+    #[builder(default, setter(into))]
+    theme: Option<Arc<Theme>>,
 }
 
 impl TryFrom<ButtonParams> for Button {
@@ -55,16 +111,30 @@ impl TryFrom<ButtonParams> for Button {
         let skin = value.skin;
         attach(&container, &*skin)?;
         let button_events = Arc::new(EventStreams::new());
+        let transition_events = Arc::new(EventStreams::new());
         let core = RwLock::new(Core {
             skin,
             pressed: false,
+            hover: false,
+            enabled: EnabledOpacity::new(true, None, value.theme),
+            focused: false,
+            size: Vector2::default(),
+            hit_slop: value.hit_slop,
+            state: ButtonVisualState::Normal,
+            compositor: value.compositor,
+            visual: container.clone().into(),
+            token: TransitionToken::default(),
+            transition_events: transition_events.clone(),
             button_events: button_events.clone(),
+            button_events_buffer: EventBuffer::new(BUTTON_EVENTS, value.event_buffer_size),
+            event_order: EventOrder::default(),
         });
         Ok(Button {
             container,
             core,
             panel_events: EventStreams::new(),
             button_events,
+            transition_events,
             id: Arc::new(()),
         })
     }
@@ -79,20 +149,68 @@ impl TryFrom<ButtonParams> for Arc<Button> {
 }
 
 impl Core {
+    // The theme of the button gives the minimal touch target
+    fn is_point_in_hit_area(&self, point: Vector2) -> bool {
+        is_point_in_hit_area(
+            point,
+            self.size,
+            &self.hit_slop,
+            self.enabled.theme().min_touch_target,
+        )
+    }
+    fn visual_state(&self) -> ButtonVisualState {
+        if !self.enabled.is_enabled() {
+            ButtonVisualState::Disabled
+        } else if self.pressed {
+            ButtonVisualState::Pressed
+        } else if self.hover {
+            ButtonVisualState::Hover
+        } else if self.focused {
+            ButtonVisualState::Focused
+        } else {
+            ButtonVisualState::Normal
+        }
+    }
+    async fn update_state(&mut self, source: Option<Arc<EventBox>>) -> crate::Result<()> {
+        let state = self.visual_state();
+        if state != self.state {
+            let from = self.state;
+            self.state = state;
+            self.token.cancel();
+            self.token = TransitionToken::default();
+            self.skin.on_event_ref(&state, source.clone()).await?;
+            // Posted to not wait for the animating skin while the button is locked
+            self.transition_events.post_event(
+                SkinTransition {
+                    from,
+                    to: state,
+                    compositor: self.compositor.clone(),
+                    visual: self.visual.clone(),
+                    token: self.token.clone(),
+                },
+                source,
+            );
+        }
+        Ok(())
+    }
     async fn press(&mut self, source: Option<Arc<EventBox>>) -> crate::Result<()> {
         self.pressed = true;
-        let event = ButtonEvent::Press;
-        self.skin.on_event_ref(&event, source.clone()).await?;
-        self.button_events.send_event(event, source).await;
+        self.update_state(source.clone()).await?;
+        self.send_button_event(ButtonEvent::Press, source).await;
         Ok(())
     }
     async fn release(&mut self, in_slot: bool, source: Option<Arc<EventBox>>) -> crate::Result<()> {
         self.pressed = false;
-        let event = ButtonEvent::Release(in_slot);
-        self.skin.on_event_ref(&event, source.clone()).await?;
-        self.button_events.send_event(event, source).await;
+        self.update_state(source.clone()).await?;
+        self.send_button_event(ButtonEvent::Release(in_slot), source)
+            .await;
         Ok(())
     }
+    async fn send_button_event(&self, event: ButtonEvent, source: Option<Arc<EventBox>>) {
+        self.button_events_buffer
+            .send(&self.button_events, event, source)
+            .await
+    }
     fn is_pressed(&self) -> bool {
         self.pressed
     }
@@ -101,12 +219,102 @@ impl Core {
     }
 }
 
+impl Button {
+    pub async fn visual_state(&self) -> ButtonVisualState {
+        self.core.read().await.state
+    }
+
+    pub async fn is_enabled(&self) -> bool {
+        self.core.read().await.enabled.is_enabled()
+    }
+
+    ///
+    /// Disabled button ignores mouse input and is faded to `Theme::disabled_opacity`.
+    /// Disabling the pressed button releases it outside of the slot.
+    ///
+    pub async fn set_enabled(&self, enabled: bool) -> crate::Result<()> {
+        let mut core = self.core.write().await;
+        if let Some(opacity) = core.enabled.set_enabled(enabled) {
+            fade(&core.visual, opacity)?;
+        }
+        if !enabled && core.is_pressed() {
+            core.release(false, None).await
+        } else {
+            core.update_state(None).await
+        }
+    }
+
+    pub async fn set_focused(&self, focused: bool) -> crate::Result<()> {
+        let mut core = self.core.write().await;
+        core.focused = focused;
+        core.update_state(None).await
+    }
+
+    pub async fn event_buffer_size(&self) -> usize {
+        self.core.read().await.button_events_buffer.size()
+    }
+
+    pub async fn set_event_buffer_size(&self, size: usize) {
+        self.core.read().await.button_events_buffer.set_size(size)
+    }
+
+    pub async fn hit_slop(&self) -> Margins {
+        self.core.read().await.hit_slop
+    }
+
+    pub async fn set_hit_slop(&self, hit_slop: Margins) {
+        self.core.write().await.hit_slop = hit_slop;
+    }
+
+    ///
+    /// Replace the skin. The new skin receives the current size and visual state.
+    ///
+    pub async fn set_skin(&self, skin: impl ButtonSkin + 'static) -> crate::Result<()> {
+        let skin = Arc::new(skin) as Arc<dyn ButtonSkin>;
+        let mut core = self.core.write().await;
+        detach(&*core.skin)?;
+        attach(&self.container, &*skin)?;
+        skin.on_event_owned(PanelEvent::Resized(core.size), None)
+            .await?;
+        skin.on_event_ref(&core.state, None).await?;
+        core.token.cancel();
+        core.token = TransitionToken::default();
+        core.skin = skin;
+        Ok(())
+    }
+
+    pub async fn is_pressed(&self) -> bool {
+        self.core.read().await.is_pressed()
+    }
+
+    ///
+    /// Send one more `Press` if the button is held with the cursor over it.
+    /// Returns false when the button is released.
+    ///
+    pub(crate) async fn repeat_press(&self, source: Option<Arc<EventBox>>) -> bool {
+        let core = self.core.read().await;
+        if !core.is_pressed() {
+            return false;
+        }
+        if core.enabled.is_enabled() && core.hover {
+            core.send_button_event(ButtonEvent::Press, source).await;
+        }
+        true
+    }
+}
+
 impl EventSource<ButtonEvent> for Button {
     fn event_stream(&self) -> EventStream<ButtonEvent> {
         self.button_events.create_event_stream()
     }
 }
 
+impl EventSource<ButtonTransition> for Button {
+    fn event_stream(&self) -> EventStream<ButtonTransition> {
+        self.transition_events.create_event_stream()
+    }
+}
+
 impl EventSource<PanelEvent> for Button {
     fn event_stream(&self) -> EventStream<PanelEvent> {
         self.panel_events.create_event_stream()
@@ -121,20 +329,45 @@ impl EventSinkExt<PanelEvent> for Button {
         event: Cow<'a, PanelEvent>,
         source: Option<Arc<EventBox>>,
     ) -> crate::Result<()> {
+        if !self.core.write().await.event_order.accept(event.as_ref()) {
+            return Ok(());
+        }
         let skin = self.core.read().await.skin_panel();
         skin.on_event_ref(event.as_ref(), source.clone()).await?;
         self.panel_events
             .send_event(event.clone().into_owned(), source.clone())
             .await;
         match event.as_ref() {
+            PanelEvent::Resized(size) => self.core.write().await.size = *size,
+            PanelEvent::ThemeChanged(theme) => {
+                let mut core = self.core.write().await;
+                if let Some(opacity) = core.enabled.update_theme(theme) {
+                    core.visual.SetOpacity(opacity)?;
+                }
+            }
+            PanelEvent::CursorMoved(point, _) => {
+                let mut core = self.core.write().await;
+                core.hover = core.is_point_in_hit_area(*point);
+                core.update_state(source.clone()).await?;
+            }
             PanelEvent::MouseInput {
                 in_slot,
                 state,
                 button,
+                ..
             } => {
-                if *button == MouseButton::Left {
+                if !*in_slot {
+                    // The button is covered, e.g. by a dialog: the cursor doesn't hover it
+                    let mut core = self.core.write().await;
+                    if core.hover {
+                        core.hover = false;
+                        core.update_state(source.clone()).await?;
+                    }
+                }
+                if *button == MouseButton::Left && self.core.read().await.enabled.is_enabled() {
+                    let in_slot = *in_slot;
                     if *state == ElementState::Pressed {
-                        if *in_slot {
+                        if in_slot {
                             self.core.write().await.press(source.clone()).await?;
                         }
                     } else if *state == ElementState::Released {
@@ -142,12 +375,24 @@ impl EventSinkExt<PanelEvent> for Button {
                             self.core
                                 .write()
                                 .await
-                                .release(*in_slot, source.clone())
+                                .release(in_slot, source.clone())
                                 .await?;
                         }
                     }
                 }
             }
+            PanelEvent::MouseDoubleClick {
+                in_slot,
+                button: MouseButton::Left,
+                count: 2,
+                ..
+            } => {
+                let core = self.core.read().await;
+                if core.enabled.is_enabled() && *in_slot {
+                    core.send_button_event(ButtonEvent::DoubleClick, source.clone())
+                        .await;
+                }
+            }
             _ => {}
         };
         Ok(())
@@ -161,42 +406,88 @@ impl Panel for Button {
     fn id(&self) -> usize {
         Arc::as_ptr(&self.id) as usize
     }
+    // The hit slop extends the area where the parents pass the input with `in_slot` set
+    fn hit_test(&self, point: Vector2) -> bool {
+        match self.core.try_read() {
+            Some(core) => core.is_point_in_hit_area(point),
+            None => self
+                .container
+                .Size()
+                .map(|size| {
+                    is_point_in_hit_area(point, size, &Margins::default(), theme().min_touch_target)
+                })
+                .unwrap_or(false),
+        }
+    }
 }
 
-pub trait ButtonSkin: Panel + EventSink<ButtonEvent, Error = crate::Error> {}
-impl<T: Panel + EventSink<ButtonEvent, Error = crate::Error>> ButtonSkin for T {}
+pub trait ButtonSkin: ControlSkin<ButtonVisualState> {}
+impl<T: ControlSkin<ButtonVisualState>> ButtonSkin for T {}
 
 #[derive(EventSink)]
 #[event_sink(event=PanelEvent)]
-#[event_sink(event=ButtonEvent)]
+#[event_sink(event=ButtonVisualState)]
 pub struct SimpleButtonSkin {
     layer_stack: LayerStack,
     text: Arc<Text>,
     background: Arc<Background>,
+    core: RwLock<SimpleButtonSkinCore>,
     panel_events: EventStreams<PanelEvent>,
 }
 
+struct SimpleButtonSkinCore {
+    color: Option<Color>,
+    state: ButtonVisualState,
+    theme: ThemeRef,
+}
+
+impl SimpleButtonSkinCore {
+    fn state_color(&self) -> Color {
+        let theme = self.theme.get();
+        match self.state {
+            // The disabled button keeps its color, the button fades it
+            ButtonVisualState::Normal
+            | ButtonVisualState::Focused
+            | ButtonVisualState::Disabled => self.color.unwrap_or(theme.accent_color),
+            ButtonVisualState::Hover => theme.accent_hover_color,
+            ButtonVisualState::Pressed => theme.accent_pressed_color,
+        }
+    }
+}
+
 #[derive(TypedBuilder)]
 pub struct SimpleButtonSkinParams<T: Spawn> {
     compositor: Compositor,
     text: String,
-    color: Color,
+    /// Color of the button in normal state, the accent color of the theme by default
+    #[builder(default, setter(strip_option))]
+    color: Option<Color>,
     spawner: T,
+    /// Theme to use instead of the global one
+    #[builder(default, setter(into))]
+    theme: Option<Arc<Theme>>,
 }
 
 impl<T: Spawn> TryFrom<SimpleButtonSkinParams<T>> for SimpleButtonSkin {
     type Error = crate::Error;
     fn try_from(value: SimpleButtonSkinParams<T>) -> crate::Result<Self> {
+        let core = SimpleButtonSkinCore {
+            color: value.color,
+            state: ButtonVisualState::Normal,
+            theme: ThemeRef::new(value.theme.clone()),
+        };
         let background: Arc<Background> = BackgroundParams::builder()
-            .color(value.color)
+            .color(core.state_color())
             .round_corners(true)
             .compositor(value.compositor.clone())
+            .theme(value.theme.clone())
             .build()
             .try_into()?;
         let text: Arc<Text> = TextParams::builder()
             .compositor(value.compositor.clone())
             .text(value.text)
             .spawner(value.spawner)
+            .theme(value.theme)
             .build()
             .try_into()?;
         let layer_stack = LayerStackParams::builder()
@@ -209,6 +500,7 @@ impl<T: Spawn> TryFrom<SimpleButtonSkinParams<T>> for SimpleButtonSkin {
             layer_stack,
             background,
             text,
+            core: RwLock::new(core),
             panel_events: EventStreams::new(),
         })
     }
@@ -222,19 +514,26 @@ impl<T: Spawn> TryFrom<SimpleButtonSkinParams<T>> for Arc<SimpleButtonSkin> {
     }
 }
 
+impl SimpleButtonSkin {
+    pub async fn set_text(&self, text: impl Into<String>) {
+        self.text.set_text(text).await
+    }
+}
+
 #[async_trait]
-impl EventSinkExt<ButtonEvent> for SimpleButtonSkin {
+impl EventSinkExt<ButtonVisualState> for SimpleButtonSkin {
     type Error = crate::Error;
     async fn on_event<'a>(
         &'a self,
-        event: Cow<'a, ButtonEvent>,
+        event: Cow<'a, ButtonVisualState>,
         _: Option<Arc<EventBox>>,
     ) -> crate::Result<()> {
-        match event.as_ref() {
-            ButtonEvent::Press => self.background.set_color(Colors::DarkMagenta()?).await?,
-            ButtonEvent::Release(_) => self.background.set_color(Colors::Magenta()?).await?,
-        }
-        Ok(())
+        let color = {
+            let mut core = self.core.write().await;
+            core.state = *event.as_ref();
+            core.state_color()
+        };
+        self.background.set_color(color).await
     }
 }
 
@@ -246,6 +545,15 @@ impl EventSinkExt<PanelEvent> for SimpleButtonSkin {
         event: Cow<'a, PanelEvent>,
         source: Option<Arc<EventBox>>,
     ) -> crate::Result<()> {
+        if let PanelEvent::ThemeChanged(theme) = event.as_ref() {
+            let color = {
+                let mut core = self.core.write().await;
+                core.theme.update(theme).then(|| core.state_color())
+            };
+            if let Some(color) = color {
+                self.background.set_color(color).await?;
+            }
+        }
         self.layer_stack.on_event(event, source).await
     }
 }
@@ -0,0 +1,84 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+};
+
+use async_std::sync::Arc;
+use windows::UI::Composition::Visual;
+
+use super::{mouse_capture::root_visual, Panel};
+
+// Token of the running `Resized` pass of each window, by the window's root visual
+static RESIZE_CANCELLATIONS: Mutex<Vec<(Visual, CancellationToken)>> = Mutex::new(Vec::new());
+
+///
+/// Flag checked by the long-running work to stop when its result is not needed anymore
+///
+#[derive(Clone, Default, Debug)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Release)
+    }
+}
+
+///
+/// Issues the tokens for the repeated work where only the latest run matters,
+/// e.g. resize propagation: starting the new run cancels the previous one
+///
+#[derive(Default, Debug)]
+pub struct CancellationSource(Mutex<CancellationToken>);
+
+impl CancellationSource {
+    pub fn renew(&self) -> CancellationToken {
+        let mut current = self.0.lock().unwrap();
+        current.cancel();
+        *current = CancellationToken::default();
+        current.clone()
+    }
+    pub fn cancel(&self) {
+        self.0.lock().unwrap().cancel()
+    }
+}
+
+// The window receives the new size: cancel the running pass and start the new one
+pub(crate) fn renew_resize_cancellation(root: &Visual) -> CancellationToken {
+    let mut cancellations = RESIZE_CANCELLATIONS.lock().unwrap();
+    let token = CancellationToken::default();
+    match cancellations.iter_mut().find(|(r, _)| r == root) {
+        Some((_, current)) => {
+            current.cancel();
+            *current = token.clone();
+        }
+        None => cancellations.push((root.clone(), token.clone())),
+    }
+    token
+}
+
+// The window's event receiver has ended
+pub(crate) fn remove_resize_cancellation(root: &Visual) {
+    RESIZE_CANCELLATIONS
+        .lock()
+        .unwrap()
+        .retain(|(r, _)| r != root);
+}
+
+///
+/// Token of the `Resized` pass running in the panel's window. It's cancelled as soon
+/// as the window receives the newer size, so the panel handling `Resized` in `on_event`
+/// checks it to stop laying out its children for the stale size.
+///
+pub fn resize_cancellation_token<T: Panel + ?Sized>(panel: &T) -> CancellationToken {
+    let root = root_visual(panel);
+    RESIZE_CANCELLATIONS
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|(r, _)| *r == root)
+        .map(|(_, token)| token.clone())
+        .unwrap_or_default()
+}
@@ -0,0 +1,274 @@
+use std::borrow::Cow;
+
+use async_event_streams::{
+    EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
+};
+use async_event_streams_derive::EventSink;
+use async_std::sync::{Arc, RwLock};
+use async_trait::async_trait;
+use futures::{
+    task::{Spawn, SpawnExt},
+    StreamExt,
+};
+use typed_builder::TypedBuilder;
+use windows::{
+    Foundation::{Numerics::Vector2, Rect},
+    UI::{
+        Color,
+        Composition::{Compositor, Visual},
+    },
+};
+use winit::event::{ElementState, MouseButton};
+
+use crate::handle_err;
+
+use super::{
+    visual_offset, Button, ButtonEvent, ButtonParams, CellLimit, Overlay, Panel, PanelEvent,
+    PopupPlacement, Ribbon, RibbonOrientation, RibbonParams, SimpleButtonSkin,
+    SimpleButtonSkinParams,
+};
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum ContextMenuEvent {
+    Command(String),
+    Dismissed,
+}
+
+struct Core {
+    mouse_pos: Option<Vector2>,
+}
+
+///
+/// Decorator which opens the menu in the overlay on right click inside the wrapped panel
+///
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+pub struct ContextMenu {
+    panel: Arc<dyn Panel>,
+    overlay: Arc<Overlay>,
+    menu: Arc<Ribbon>,
+    menu_size: Vector2,
+    placement: PopupPlacement,
+    core: RwLock<Core>,
+    panel_events: EventStreams<PanelEvent>,
+    context_menu_events: Arc<EventStreams<ContextMenuEvent>>,
+    id: Arc<()>,
+}
+
+#[derive(TypedBuilder)]
+pub struct ContextMenuParams<T: Spawn> {
+    compositor: Compositor,
+    overlay: Arc<Overlay>,
+    #[builder(setter(transform = |panel: impl Panel + 'static| Arc::new(panel) as Arc<dyn Panel>))]
+    panel: Arc<dyn Panel>,
+    spawner: T,
+    color: Color,
+    #[builder(default = Vector2 { X: 160., Y: 32. })]
+    item_size: Vector2,
+    #[builder(default)]
+    items: Vec<(String, String)>,
+    /// Placement of the menu relative to the clicked point, flipped and shifted
+    /// to stay inside the overlay
+    #[builder(default)]
+    placement: PopupPlacement,
+}
+
+impl<T: Spawn> ContextMenuParams<T> {
+    pub fn add_item(mut self, command: impl Into<String>, text: impl Into<String>) -> Self {
+        self.items.push((command.into(), text.into()));
+        self
+    }
+}
+
+impl<T: Spawn + Clone> TryFrom<ContextMenuParams<T>> for ContextMenu {
+    type Error = crate::Error;
+
+    fn try_from(value: ContextMenuParams<T>) -> crate::Result<Self> {
+        let context_menu_events = Arc::new(EventStreams::new());
+        let mut menu_params = RibbonParams::builder()
+            .compositor(value.compositor.clone())
+            .orientation(RibbonOrientation::Vertical)
+            .build();
+        let mut buttons = Vec::new();
+        for (command, text) in &value.items {
+            let skin: SimpleButtonSkin = SimpleButtonSkinParams::builder()
+                .compositor(value.compositor.clone())
+                .text(text.clone())
+                .color(value.color)
+                .spawner(value.spawner.clone())
+                .build()
+                .try_into()?;
+            let button: Arc<Button> = ButtonParams::builder()
+                .compositor(value.compositor.clone())
+                .skin(skin)
+                .build()
+                .try_into()?;
+            menu_params = menu_params.add_panel(button.clone(), CellLimit::default())?;
+            buttons.push((command.clone(), button));
+        }
+        let menu: Arc<Ribbon> = menu_params.try_into()?;
+
+        for (command, button) in buttons {
+            let mut stream = EventSource::<ButtonEvent>::event_stream(&*button);
+            let overlay = Arc::downgrade(&value.overlay);
+            let menu_id = menu.id();
+            let context_menu_events = Arc::downgrade(&context_menu_events);
+            value.spawner.spawn(handle_err(async move {
+                while let Some(event) = stream.next().await {
+                    if *event == ButtonEvent::Release(true) {
+                        if let (Some(overlay), Some(context_menu_events)) =
+                            (overlay.upgrade(), context_menu_events.upgrade())
+                        {
+                            overlay.hide(menu_id).await?;
+                            context_menu_events
+                                .send_event(ContextMenuEvent::Command(command.clone()), None)
+                                .await;
+                        }
+                    }
+                }
+                Ok(())
+            }))?;
+        }
+
+        // Light dismiss: close the menu when mouse is pressed outside of it
+        let mut stream = EventSource::<PanelEvent>::event_stream(&*menu);
+        let overlay = Arc::downgrade(&value.overlay);
+        let menu_id = menu.id();
+        let weak_context_menu_events = Arc::downgrade(&context_menu_events);
+        value.spawner.spawn(handle_err(async move {
+            while let Some(event) = stream.next().await {
+                if let PanelEvent::MouseInput {
+                    in_slot: false,
+                    state: ElementState::Pressed,
+                    ..
+                } = *event
+                {
+                    if let (Some(overlay), Some(context_menu_events)) =
+                        (overlay.upgrade(), weak_context_menu_events.upgrade())
+                    {
+                        if overlay.is_shown(menu_id).await {
+                            overlay.hide(menu_id).await?;
+                            context_menu_events
+                                .send_event(ContextMenuEvent::Dismissed, None)
+                                .await;
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }))?;
+
+        let menu_size = Vector2 {
+            X: value.item_size.X,
+            Y: value.item_size.Y * value.items.len() as f32,
+        };
+        Ok(ContextMenu {
+            panel: value.panel,
+            overlay: value.overlay,
+            menu,
+            menu_size,
+            placement: value.placement,
+            core: RwLock::new(Core { mouse_pos: None }),
+            panel_events: EventStreams::new(),
+            context_menu_events,
+            id: Arc::new(()),
+        })
+    }
+}
+
+impl<T: Spawn + Clone> TryFrom<ContextMenuParams<T>> for Arc<ContextMenu> {
+    type Error = crate::Error;
+
+    fn try_from(value: ContextMenuParams<T>) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
+
+impl ContextMenu {
+    ///
+    /// Open the menu at the point in coordinates of the wrapped panel
+    ///
+    pub async fn open(&self, point: Vector2) -> crate::Result<()> {
+        let origin = visual_offset(&self.panel.outer_frame())?;
+        let overlay_origin = visual_offset(&self.overlay.outer_frame())?;
+        let area = self.overlay.size()?;
+        let position = self.placement.place(
+            Rect {
+                X: origin.X - overlay_origin.X + point.X,
+                Y: origin.Y - overlay_origin.Y + point.Y,
+                Width: 0.,
+                Height: 0.,
+            },
+            self.menu_size,
+            Rect {
+                X: 0.,
+                Y: 0.,
+                Width: area.X,
+                Height: area.Y,
+            },
+        );
+        self.overlay
+            .show(self.menu.clone(), position, self.menu_size)
+            .await
+    }
+
+    pub async fn close(&self) -> crate::Result<()> {
+        self.overlay.hide(self.menu.id()).await
+    }
+}
+
+impl Panel for ContextMenu {
+    fn outer_frame(&self) -> Visual {
+        self.panel.outer_frame()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+}
+
+impl EventSource<PanelEvent> for ContextMenu {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+impl EventSource<ContextMenuEvent> for ContextMenu {
+    fn event_stream(&self) -> EventStream<ContextMenuEvent> {
+        self.context_menu_events.create_event_stream()
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for ContextMenu {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        match event.as_ref() {
+            PanelEvent::CursorMoved(mouse_pos, _) => {
+                self.core.write().await.mouse_pos = Some(*mouse_pos)
+            }
+            PanelEvent::MouseInput {
+                in_slot: true,
+                state: ElementState::Released,
+                button: MouseButton::Right,
+                ..
+            } => {
+                let mouse_pos = self.core.read().await.mouse_pos;
+                if let Some(mouse_pos) = mouse_pos {
+                    self.open(mouse_pos).await?;
+                }
+            }
+            _ => (),
+        }
+        self.panel
+            .on_event_ref(event.as_ref(), source.clone())
+            .await?;
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
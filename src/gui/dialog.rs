@@ -0,0 +1,170 @@
+use async_event_streams::EventSource;
+use async_std::sync::{Arc, RwLock};
+use futures::{
+    channel::oneshot,
+    future::{self, Either},
+    task::{Spawn, SpawnExt},
+    StreamExt,
+};
+use typed_builder::TypedBuilder;
+use windows::{
+    Foundation::Numerics::Vector2,
+    UI::{Color, Composition::Compositor},
+};
+
+use crate::handle_err;
+
+use super::{
+    Background, BackgroundParams, Button, ButtonEvent, CellLimit, LayerStack, LayerStackEvent,
+    Panel, Ribbon, RibbonOrientation, RibbonParams,
+};
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum DialogResult {
+    Ok,
+    Cancel,
+    Custom(String),
+}
+
+///
+/// Modal layer with the content centered over the dimmed background.
+/// While the dialog is shown the lower layers of the `LayerStack` don't receive mouse input.
+///
+pub struct Dialog {
+    layer_stack: Arc<LayerStack>,
+    layer: Arc<Ribbon>,
+    content: Arc<dyn Panel>,
+    light_dismiss: bool,
+    result_sender: RwLock<Option<oneshot::Sender<DialogResult>>>,
+}
+
+#[derive(TypedBuilder)]
+pub struct DialogParams {
+    compositor: Compositor,
+    layer_stack: Arc<LayerStack>,
+    #[builder(setter(transform = |panel: impl Panel + 'static| Arc::new(panel) as Arc<dyn Panel>))]
+    content: Arc<dyn Panel>,
+    /// Size of the content relative to the layer size
+    #[builder(default = Vector2 { X: 0.5, Y: 0.5 })]
+    content_ratio: Vector2,
+    #[builder(default = Color { A: 0x80, R: 0, G: 0, B: 0 })]
+    dim_color: Color,
+    /// Close the dialog with `DialogResult::Cancel` on click outside of the content
+    #[builder(default)]
+    light_dismiss: bool,
+}
+
+impl TryFrom<DialogParams> for Dialog {
+    type Error = crate::Error;
+
+    fn try_from(value: DialogParams) -> crate::Result<Self> {
+        let dim: Background = BackgroundParams::builder()
+            .compositor(value.compositor.clone())
+            .color(value.dim_color)
+            .round_corners(false)
+            .build()
+            .try_into()?;
+        let layer: Arc<Ribbon> = RibbonParams::builder()
+            .compositor(value.compositor)
+            .orientation(RibbonOrientation::Stack)
+            .build()
+            .add_panel(Arc::new(dim), CellLimit::default())?
+            .add_panel(
+                value.content.clone(),
+                CellLimit::new(1., 0., None, Some(value.content_ratio)),
+            )?
+            .try_into()?;
+        Ok(Dialog {
+            layer_stack: value.layer_stack,
+            layer,
+            content: value.content,
+            light_dismiss: value.light_dismiss,
+            result_sender: RwLock::new(None),
+        })
+    }
+}
+
+impl TryFrom<DialogParams> for Arc<Dialog> {
+    type Error = crate::Error;
+
+    fn try_from(value: DialogParams) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
+
+impl Dialog {
+    ///
+    /// Push the dialog layer and wait until the dialog is closed. If the dialog is shown
+    /// again before closing, the layer stays in place, the pending call returns
+    /// `DialogResult::Cancel` and the new one waits for the result instead.
+    ///
+    pub async fn show(&self) -> crate::Result<DialogResult> {
+        let (sender, receiver) = oneshot::channel();
+        let shown = self.result_sender.write().await.replace(sender).is_some();
+        let mut layer_stack_events =
+            EventSource::<LayerStackEvent>::event_stream(&*self.layer_stack);
+        if !shown {
+            self.layer_stack
+                .push_modal_panel_with_content(self.layer.clone(), &*self.content)
+                .await?;
+        }
+        let layer_id = self.layer.id();
+        let light_dismiss = self.light_dismiss;
+        let dismissed = Box::pin(async move {
+            while let Some(event) = layer_stack_events.next().await {
+                if light_dismiss && *event == (LayerStackEvent::ModalClickOutside { layer_id }) {
+                    return;
+                }
+            }
+            future::pending::<()>().await
+        });
+        let result = match future::select(receiver, dismissed).await {
+            Either::Left((Ok(result), _)) => result,
+            // The sender is replaced by the newer `show` call which removes the layer itself
+            Either::Left((Err(_), _)) => return Ok(DialogResult::Cancel),
+            Either::Right(_) => {
+                self.result_sender.write().await.take();
+                DialogResult::Cancel
+            }
+        };
+        self.layer_stack.remove_panel(&*self.layer).await?;
+        Ok(result)
+    }
+
+    ///
+    /// Close the dialog resolving the pending `show` call with the result
+    ///
+    pub async fn close(&self, result: DialogResult) {
+        if let Some(sender) = self.result_sender.write().await.take() {
+            let _ = sender.send(result);
+        }
+    }
+
+    pub async fn is_shown(&self) -> bool {
+        self.result_sender.read().await.is_some()
+    }
+
+    ///
+    /// Close the dialog with given result when the button is clicked
+    ///
+    pub fn close_on_button(
+        self: &Arc<Self>,
+        spawner: &impl Spawn,
+        button: &Button,
+        result: DialogResult,
+    ) -> crate::Result<()> {
+        let mut stream = EventSource::<ButtonEvent>::event_stream(button);
+        let dialog = Arc::downgrade(self);
+        spawner.spawn(handle_err(async move {
+            while let Some(event) = stream.next().await {
+                if *event == ButtonEvent::Release(true) {
+                    if let Some(dialog) = dialog.upgrade() {
+                        dialog.close(result.clone()).await;
+                    }
+                }
+            }
+            Ok(())
+        }))?;
+        Ok(())
+    }
+}
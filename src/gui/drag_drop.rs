@@ -0,0 +1,395 @@
+use std::{
+    any::Any,
+    borrow::Cow,
+    fmt::{self, Debug, Formatter},
+    sync::Mutex,
+};
+
+use async_event_streams::{
+    EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
+};
+use async_event_streams_derive::EventSink;
+use async_std::sync::{Arc, RwLock};
+use async_trait::async_trait;
+use typed_builder::TypedBuilder;
+use windows::{
+    Foundation::Numerics::{Vector2, Vector3},
+    UI::Composition::{Compositor, ContainerVisual, Visual},
+};
+use winit::event::{ElementState, MouseButton};
+
+use super::{attach, Panel, PanelEvent};
+
+// Position of the drag ghost relative to the cursor
+const GHOST_OFFSET: Vector2 = Vector2 { X: 8., Y: 8. };
+const GHOST_OPACITY: f32 = 0.7;
+
+static CURRENT_DRAG: Mutex<Option<DragPayload>> = Mutex::new(None);
+
+///
+/// Data carried by the drag, the drop targets check its type with `get`
+///
+#[derive(Clone)]
+pub struct DragPayload(Arc<dyn Any + Send + Sync>);
+
+impl DragPayload {
+    pub fn new<T: Any + Send + Sync>(value: T) -> Self {
+        Self(Arc::new(value))
+    }
+    pub fn get<T: Any>(&self) -> Option<&T> {
+        self.0.downcast_ref()
+    }
+}
+
+impl Debug for DragPayload {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("DragPayload")
+    }
+}
+
+///
+/// Payload of the drag in progress
+///
+pub fn current_drag() -> Option<DragPayload> {
+    CURRENT_DRAG.lock().unwrap().clone()
+}
+
+fn set_current_drag(payload: Option<DragPayload>) {
+    *CURRENT_DRAG.lock().unwrap() = payload;
+}
+
+///
+/// Drag events of the `DropTarget`, positions are in the target's coordinates
+///
+#[derive(Clone, Debug)]
+pub enum DragEvent {
+    Enter(Vector2, DragPayload),
+    Over(Vector2, DragPayload),
+    Leave,
+    Drop(Vector2, DragPayload),
+}
+
+struct Ghost {
+    panel: Arc<dyn Panel>,
+    container: ContainerVisual,
+}
+
+struct DragLayerCore {
+    ghost: Option<Ghost>,
+    mouse_pos: Option<Vector2>,
+}
+
+impl DragLayerCore {
+    fn move_ghost(&self) -> crate::Result<()> {
+        if let (Some(ghost), Some(mouse_pos)) = (&self.ghost, self.mouse_pos) {
+            ghost.container.SetOffset(Vector3 {
+                X: mouse_pos.X + GHOST_OFFSET.X,
+                Y: mouse_pos.Y + GHOST_OFFSET.Y,
+                Z: 0.,
+            })?;
+        }
+        Ok(())
+    }
+}
+
+///
+/// Top layer of the window which draws the ghost of the dragged item under the cursor.
+/// It's never hit by the mouse, so the input goes to the layers below. The drag ends
+/// when the mouse button is released, the `DropTarget` under the cursor receives the drop.
+///
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+pub struct DragLayer {
+    compositor: Compositor,
+    container: ContainerVisual,
+    core: RwLock<DragLayerCore>,
+    panel_events: EventStreams<PanelEvent>,
+    id: Arc<()>,
+}
+
+#[derive(TypedBuilder)]
+pub struct DragLayerParams {
+    compositor: Compositor,
+}
+
+impl TryFrom<DragLayerParams> for DragLayer {
+    type Error = crate::Error;
+
+    fn try_from(value: DragLayerParams) -> crate::Result<Self> {
+        let container = value.compositor.CreateContainerVisual()?;
+        Ok(DragLayer {
+            compositor: value.compositor,
+            container,
+            core: RwLock::new(DragLayerCore {
+                ghost: None,
+                mouse_pos: None,
+            }),
+            panel_events: EventStreams::new(),
+            id: Arc::new(()),
+        })
+    }
+}
+
+impl TryFrom<DragLayerParams> for Arc<DragLayer> {
+    type Error = crate::Error;
+
+    fn try_from(value: DragLayerParams) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
+
+impl DragLayer {
+    ///
+    /// Start dragging the payload with the `ghost` panel of given size following the cursor.
+    /// Usually called by the source panel when the cursor moves away from the press point
+    /// with the button held. The source shouldn't capture the mouse, otherwise
+    /// the drop targets don't see the cursor.
+    ///
+    pub async fn start_drag(
+        &self,
+        payload: DragPayload,
+        ghost: Arc<dyn Panel>,
+        size: Vector2,
+    ) -> crate::Result<()> {
+        self.end_drag().await?;
+        let container = self.compositor.CreateContainerVisual()?;
+        container.SetSize(size)?;
+        container.SetOpacity(GHOST_OPACITY)?;
+        attach(&container, &*ghost)?;
+        self.container.Children()?.InsertAtTop(&container)?;
+        {
+            let mut core = self.core.write().await;
+            core.ghost = Some(Ghost {
+                panel: ghost.clone(),
+                container,
+            });
+            core.move_ghost()?;
+        }
+        set_current_drag(Some(payload));
+        ghost.on_event_owned(PanelEvent::Resized(size), None).await
+    }
+
+    pub async fn is_dragging(&self) -> bool {
+        self.core.read().await.ghost.is_some()
+    }
+
+    ///
+    /// Stop the drag without the drop
+    ///
+    pub async fn cancel_drag(&self) -> crate::Result<()> {
+        self.end_drag().await
+    }
+
+    async fn end_drag(&self) -> crate::Result<()> {
+        if let Some(ghost) = self.core.write().await.ghost.take() {
+            set_current_drag(None);
+            self.container.Children()?.Remove(&ghost.container)?;
+        }
+        Ok(())
+    }
+}
+
+impl Panel for DragLayer {
+    fn outer_frame(&self) -> Visual {
+        self.container.clone().into()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+    fn hit_test(&self, _: Vector2) -> bool {
+        false
+    }
+}
+
+impl EventSource<PanelEvent> for DragLayer {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for DragLayer {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        match event.as_ref() {
+            PanelEvent::Resized(size) => self.container.SetSize(*size)?,
+            PanelEvent::CursorMoved(mouse_pos, _) => {
+                let mut core = self.core.write().await;
+                core.mouse_pos = Some(*mouse_pos);
+                core.move_ghost()?;
+            }
+            PanelEvent::MouseInput {
+                state: ElementState::Released,
+                button: MouseButton::Left,
+                ..
+            } => self.end_drag().await?,
+            PanelEvent::ThemeChanged(_) => {
+                let ghost = self
+                    .core
+                    .read()
+                    .await
+                    .ghost
+                    .as_ref()
+                    .map(|ghost| ghost.panel.clone());
+                if let Some(ghost) = ghost {
+                    ghost.on_event_ref(event.as_ref(), source.clone()).await?;
+                }
+            }
+            _ => (),
+        }
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
+
+struct DropTargetCore {
+    // Payload of the drag hovering the target
+    hovered: Option<DragPayload>,
+    mouse_pos: Option<Vector2>,
+}
+
+///
+/// Decorator which reports the drags over the wrapped panel as `DragEvent`
+///
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+pub struct DropTarget {
+    panel: Arc<dyn Panel>,
+    core: RwLock<DropTargetCore>,
+    panel_events: EventStreams<PanelEvent>,
+    drag_events: EventStreams<DragEvent>,
+    id: Arc<()>,
+}
+
+#[derive(TypedBuilder)]
+pub struct DropTargetParams {
+    #[builder(setter(transform = |panel: impl Panel + 'static| Arc::new(panel) as Arc<dyn Panel>))]
+    panel: Arc<dyn Panel>,
+}
+
+impl From<DropTargetParams> for DropTarget {
+    fn from(value: DropTargetParams) -> Self {
+        DropTarget {
+            panel: value.panel,
+            core: RwLock::new(DropTargetCore {
+                hovered: None,
+                mouse_pos: None,
+            }),
+            panel_events: EventStreams::new(),
+            drag_events: EventStreams::new(),
+            id: Arc::new(()),
+        }
+    }
+}
+
+impl From<DropTargetParams> for Arc<DropTarget> {
+    fn from(value: DropTargetParams) -> Self {
+        Arc::new(value.into())
+    }
+}
+
+impl DropTarget {
+    async fn cursor_moved(
+        &self,
+        mouse_pos: Vector2,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        let drag_event = {
+            let mut core = self.core.write().await;
+            core.mouse_pos = Some(mouse_pos);
+            let drag = current_drag().filter(|_| self.panel.hit_test(mouse_pos));
+            match (drag, core.hovered.is_some()) {
+                (Some(payload), false) => {
+                    core.hovered = Some(payload.clone());
+                    Some(DragEvent::Enter(mouse_pos, payload))
+                }
+                (Some(payload), true) => Some(DragEvent::Over(mouse_pos, payload)),
+                (None, true) => {
+                    core.hovered = None;
+                    Some(DragEvent::Leave)
+                }
+                (None, false) => None,
+            }
+        };
+        if let Some(drag_event) = drag_event {
+            self.drag_events.send_event(drag_event, source).await;
+        }
+        Ok(())
+    }
+
+    async fn released(&self, in_slot: bool, source: Option<Arc<EventBox>>) {
+        let drag_event = {
+            let mut core = self.core.write().await;
+            match (core.hovered.take(), core.mouse_pos) {
+                (Some(payload), Some(mouse_pos)) if in_slot => {
+                    Some(DragEvent::Drop(mouse_pos, payload))
+                }
+                (Some(_), _) => Some(DragEvent::Leave),
+                _ => None,
+            }
+        };
+        if let Some(drag_event) = drag_event {
+            self.drag_events.send_event(drag_event, source).await;
+        }
+    }
+}
+
+impl Panel for DropTarget {
+    fn outer_frame(&self) -> Visual {
+        self.panel.outer_frame()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+    fn hit_test(&self, point: Vector2) -> bool {
+        self.panel.hit_test(point)
+    }
+}
+
+impl EventSource<PanelEvent> for DropTarget {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+impl EventSource<DragEvent> for DropTarget {
+    fn event_stream(&self) -> EventStream<DragEvent> {
+        self.drag_events.create_event_stream()
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for DropTarget {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        match event.as_ref() {
+            PanelEvent::CursorMoved(mouse_pos, _) => {
+                self.cursor_moved(*mouse_pos, source.clone()).await?
+            }
+            PanelEvent::MouseInput {
+                in_slot,
+                state: ElementState::Released,
+                button: MouseButton::Left,
+                ..
+            } => self.released(*in_slot, source.clone()).await,
+            _ => (),
+        }
+        self.panel
+            .on_event_ref(event.as_ref(), source.clone())
+            .await?;
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
@@ -0,0 +1,307 @@
+//! Easing functions shared by composition animations and animations updated from code.
+//! Formulas of `ease` match the corresponding system composition easing functions.
+
+use std::f32::consts::PI;
+
+use windows::{
+    Foundation::Numerics::Vector2,
+    UI::Composition::{CompositionEasingFunction, CompositionEasingFunctionMode, Compositor},
+};
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum EasingMode {
+    /// Effect is applied at the start of the animation
+    In,
+    /// Effect is applied at the end of the animation
+    #[default]
+    Out,
+    InOut,
+}
+
+impl From<EasingMode> for CompositionEasingFunctionMode {
+    fn from(mode: EasingMode) -> Self {
+        match mode {
+            EasingMode::In => CompositionEasingFunctionMode::In,
+            EasingMode::Out => CompositionEasingFunctionMode::Out,
+            EasingMode::InOut => CompositionEasingFunctionMode::InOut,
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub enum Easing {
+    #[default]
+    Linear,
+    Quad(EasingMode),
+    Cubic(EasingMode),
+    Elastic {
+        mode: EasingMode,
+        oscillations: u32,
+        /// Stiffness of the spring, larger values make oscillations decay faster
+        springiness: f32,
+    },
+    Bounce {
+        mode: EasingMode,
+        bounces: u32,
+        /// Ratio between the heights of two successive bounces, greater than 1
+        bounciness: f32,
+    },
+    /// Curve from (0, 0) to (1, 1) with two control points, as in CSS `cubic-bezier`
+    CubicBezier(Vector2, Vector2),
+}
+
+impl Easing {
+    pub fn elastic(mode: EasingMode) -> Self {
+        Easing::Elastic {
+            mode,
+            oscillations: 3,
+            springiness: 3.,
+        }
+    }
+
+    pub fn bounce(mode: EasingMode) -> Self {
+        Easing::Bounce {
+            mode,
+            bounces: 3,
+            bounciness: 2.,
+        }
+    }
+
+    ///
+    /// Eased progress for the linear progress `t` from 0 to 1. The result is 0 at start
+    /// and 1 at end, but may go out of this range in between.
+    ///
+    pub fn ease(&self, t: f32) -> f32 {
+        let t = t.clamp(0., 1.);
+        match *self {
+            Easing::Linear => t,
+            Easing::Quad(mode) => apply_mode(mode, t, |t| t * t),
+            Easing::Cubic(mode) => apply_mode(mode, t, |t| t * t * t),
+            Easing::Elastic {
+                mode,
+                oscillations,
+                springiness,
+            } => apply_mode(mode, t, |t| elastic_in(t, oscillations, springiness)),
+            Easing::Bounce {
+                mode,
+                bounces,
+                bounciness,
+            } => apply_mode(mode, t, |t| bounce_in(t, bounces, bounciness)),
+            Easing::CubicBezier(p1, p2) => cubic_bezier(t, p1, p2),
+        }
+    }
+
+    ///
+    /// Value between `from` and `to` at the linear progress `t`
+    ///
+    pub fn interpolate(&self, from: f32, to: f32, t: f32) -> f32 {
+        from + (to - from) * self.ease(t)
+    }
+
+    ///
+    /// Equivalent easing function for keyframes of composition animations
+    ///
+    pub fn composition_function(
+        &self,
+        compositor: &Compositor,
+    ) -> crate::Result<CompositionEasingFunction> {
+        let function: CompositionEasingFunction = match *self {
+            Easing::Linear => compositor.CreateLinearEasingFunction()?.into(),
+            Easing::Quad(mode) => {
+                CompositionEasingFunction::CreatePowerEasingFunction(compositor, mode.into(), 2.)?
+                    .into()
+            }
+            Easing::Cubic(mode) => {
+                CompositionEasingFunction::CreatePowerEasingFunction(compositor, mode.into(), 3.)?
+                    .into()
+            }
+            Easing::Elastic {
+                mode,
+                oscillations,
+                springiness,
+            } => CompositionEasingFunction::CreateElasticEasingFunction(
+                compositor,
+                mode.into(),
+                oscillations as i32,
+                springiness,
+            )?
+            .into(),
+            Easing::Bounce {
+                mode,
+                bounces,
+                bounciness,
+            } => CompositionEasingFunction::CreateBounceEasingFunction(
+                compositor,
+                mode.into(),
+                bounces as i32,
+                bounciness,
+            )?
+            .into(),
+            Easing::CubicBezier(p1, p2) => {
+                compositor.CreateCubicBezierEasingFunction(p1, p2)?.into()
+            }
+        };
+        Ok(function)
+    }
+}
+
+// Builds out and in-out variants from the "in" curve
+fn apply_mode(mode: EasingMode, t: f32, ease_in: impl Fn(f32) -> f32) -> f32 {
+    match mode {
+        EasingMode::In => ease_in(t),
+        EasingMode::Out => 1. - ease_in(1. - t),
+        EasingMode::InOut => {
+            if t < 0.5 {
+                ease_in(t * 2.) / 2.
+            } else {
+                1. - ease_in((1. - t) * 2.) / 2.
+            }
+        }
+    }
+}
+
+fn elastic_in(t: f32, oscillations: u32, springiness: f32) -> f32 {
+    let envelope = if springiness.abs() < f32::EPSILON {
+        t
+    } else {
+        ((springiness * t).exp() - 1.) / (springiness.exp() - 1.)
+    };
+    envelope * ((2. * PI * oscillations as f32 + PI / 2.) * t).sin()
+}
+
+fn bounce_in(t: f32, bounces: u32, bounciness: f32) -> f32 {
+    // Bounciness of 1 gives infinite sum of bounce durations
+    let bounciness = bounciness.max(1.001);
+    let bounces = bounces as f32;
+    let pow = bounciness.powf(bounces);
+    let one_minus_bounciness = 1. - bounciness;
+    // Bounce durations form the geometric progression, the last half-bounce is the fall
+    let sum_of_units = (1. - pow) / one_minus_bounciness + pow * 0.5;
+    let unit_at_t = t * sum_of_units;
+    let bounce_at_t = ((-unit_at_t * one_minus_bounciness + 1.).ln() / bounciness.ln()).floor();
+    let start = (1. - bounciness.powf(bounce_at_t)) / one_minus_bounciness;
+    let end = (1. - bounciness.powf(bounce_at_t + 1.)) / one_minus_bounciness;
+    let start_time = start / sum_of_units;
+    let end_time = end / sum_of_units;
+    let mid_time = (start_time + end_time) / 2.;
+    let time_relative_to_peak = t - mid_time;
+    let radius = mid_time - start_time;
+    let amplitude = (1. / bounciness).powf(bounces - bounce_at_t);
+    -amplitude / (radius * radius)
+        * (time_relative_to_peak - radius)
+        * (time_relative_to_peak + radius)
+}
+
+fn cubic_bezier(t: f32, p1: Vector2, p2: Vector2) -> f32 {
+    let bezier = |s: f32, a: f32, b: f32| {
+        let r = 1. - s;
+        3. * r * r * s * a + 3. * r * s * s * b + s * s * s
+    };
+    // X of the curve is monotonic for control points inside the unit square,
+    // so the curve parameter for the given time is found by bisection
+    let (mut low, mut high) = (0., 1.);
+    let mut s = t;
+    for _ in 0..32 {
+        let x = bezier(s, p1.X, p2.X);
+        if (x - t).abs() < 1e-6 {
+            break;
+        }
+        if x < t {
+            low = s;
+        } else {
+            high = s;
+        }
+        s = (low + high) / 2.;
+    }
+    bezier(s, p1.Y, p2.Y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-4,
+            "{actual} is not near {expected}"
+        );
+    }
+
+    fn all() -> Vec<Easing> {
+        let mut all = vec![
+            Easing::Linear,
+            Easing::CubicBezier(Vector2 { X: 0.25, Y: 0.1 }, Vector2 { X: 0.25, Y: 1. }),
+        ];
+        for mode in [EasingMode::In, EasingMode::Out, EasingMode::InOut] {
+            all.extend([
+                Easing::Quad(mode),
+                Easing::Cubic(mode),
+                Easing::elastic(mode),
+                Easing::bounce(mode),
+            ]);
+        }
+        all
+    }
+
+    #[test]
+    fn starts_at_zero_and_ends_at_one() {
+        for easing in all() {
+            assert_near(easing.ease(0.), 0.);
+            assert_near(easing.ease(1.), 1.);
+        }
+    }
+
+    #[test]
+    fn progress_is_clamped() {
+        for easing in all() {
+            assert_near(easing.ease(-1.), 0.);
+            assert_near(easing.ease(2.), 1.);
+        }
+    }
+
+    #[test]
+    fn power_modes() {
+        assert_near(Easing::Quad(EasingMode::In).ease(0.5), 0.25);
+        assert_near(Easing::Quad(EasingMode::Out).ease(0.5), 0.75);
+        assert_near(Easing::Quad(EasingMode::InOut).ease(0.25), 0.125);
+        assert_near(Easing::Quad(EasingMode::InOut).ease(0.75), 0.875);
+        assert_near(Easing::Cubic(EasingMode::In).ease(0.5), 0.125);
+        assert_near(Easing::Cubic(EasingMode::InOut).ease(0.5), 0.5);
+    }
+
+    #[test]
+    fn bounce_stays_in_range() {
+        for mode in [EasingMode::In, EasingMode::Out, EasingMode::InOut] {
+            for i in 0..=100 {
+                let value = Easing::bounce(mode).ease(i as f32 / 100.);
+                assert!((-1e-4..=1. + 1e-4).contains(&value), "{value}");
+            }
+        }
+    }
+
+    #[test]
+    fn elastic_overshoots() {
+        let max = (0..=100)
+            .map(|i| Easing::elastic(EasingMode::Out).ease(i as f32 / 100.))
+            .fold(f32::MIN, f32::max);
+        assert!(max > 1.);
+    }
+
+    #[test]
+    fn cubic_bezier_with_diagonal_points_is_linear() {
+        let easing =
+            Easing::CubicBezier(Vector2 { X: 0.25, Y: 0.25 }, Vector2 { X: 0.75, Y: 0.75 });
+        for t in [0.1, 0.3, 0.5, 0.9] {
+            assert_near(easing.ease(t), t);
+        }
+    }
+
+    #[test]
+    fn interpolate_between_values() {
+        assert_near(Easing::Linear.interpolate(10., 20., 0.25), 12.5);
+        assert_near(
+            Easing::Quad(EasingMode::In).interpolate(20., 10., 0.5),
+            17.5,
+        );
+    }
+}
@@ -0,0 +1,242 @@
+use std::{borrow::Cow, time::Duration};
+
+use async_event_streams::{
+    EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
+};
+use async_event_streams_derive::EventSink;
+use async_std::sync::{Arc, RwLock};
+use async_trait::async_trait;
+use typed_builder::TypedBuilder;
+use windows::{
+    h,
+    Foundation::Numerics::Vector2,
+    UI::Composition::{Compositor, ContainerVisual, Visual},
+};
+
+use super::{
+    attach,
+    easing::{Easing, EasingMode},
+    theme::{Theme, ThemeRef},
+    Panel, PanelEvent,
+};
+
+const FADE_DURATION: Duration = Duration::from_millis(150);
+
+///
+/// Enabled state and opacity of the panel, shared by `Enabled` and the controls
+/// which disable themselves, e.g. `Button`
+///
+pub(crate) struct EnabledOpacity {
+    enabled: bool,
+    // Opacity set explicitly instead of the theme's one
+    disabled_opacity: Option<f32>,
+    theme: ThemeRef,
+}
+
+impl EnabledOpacity {
+    pub(crate) fn new(
+        enabled: bool,
+        disabled_opacity: Option<f32>,
+        theme: Option<Arc<Theme>>,
+    ) -> Self {
+        Self {
+            enabled,
+            disabled_opacity,
+            theme: ThemeRef::new(theme),
+        }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub(crate) fn theme(&self) -> &Theme {
+        self.theme.get()
+    }
+
+    pub(crate) fn opacity(&self) -> f32 {
+        if self.enabled {
+            1.
+        } else {
+            self.disabled_opacity
+                .unwrap_or(self.theme.get().disabled_opacity)
+        }
+    }
+
+    // Returns the new opacity to fade to if the state is changed
+    pub(crate) fn set_enabled(&mut self, enabled: bool) -> Option<f32> {
+        if self.enabled == enabled {
+            return None;
+        }
+        self.enabled = enabled;
+        Some(self.opacity())
+    }
+
+    pub(crate) fn set_disabled_opacity(&mut self, opacity: Option<f32>) -> f32 {
+        self.disabled_opacity = opacity;
+        self.opacity()
+    }
+
+    // Returns the new opacity if the theme is changed
+    pub(crate) fn update_theme(&mut self, theme: &Arc<Theme>) -> Option<f32> {
+        self.theme.update(theme).then(|| self.opacity())
+    }
+}
+
+///
+/// Animate the opacity of the visual from the current value
+///
+pub(crate) fn fade(visual: &Visual, opacity: f32) -> crate::Result<()> {
+    let old_opacity = visual.Opacity()?;
+    visual.SetOpacity(opacity)?;
+    let compositor = visual.Compositor()?;
+    let easing = Easing::Quad(EasingMode::Out).composition_function(&compositor)?;
+    let animation = compositor.CreateScalarKeyFrameAnimation()?;
+    animation.InsertKeyFrame(0., old_opacity)?;
+    animation.InsertKeyFrameWithEasingFunction(1., opacity, &easing)?;
+    animation.SetDuration(FADE_DURATION.into())?;
+    visual.StartAnimation(h!("Opacity"), &animation)?;
+    Ok(())
+}
+
+///
+/// Decorator which disables the wrapped panel: fades it to the disabled opacity
+/// and blocks its input. Mouse button events still reach the panel with `in_slot`
+/// set to false, so the panel pressed at the moment of disabling is released
+/// without the click.
+///
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+pub struct Enabled {
+    // Own visual of the decorator, the opacity of the panel's frame is left to the panel
+    container: ContainerVisual,
+    panel: Arc<dyn Panel>,
+    core: RwLock<EnabledOpacity>,
+    panel_events: EventStreams<PanelEvent>,
+    id: Arc<()>,
+}
+
+#[derive(TypedBuilder)]
+pub struct EnabledParams {
+    compositor: Compositor,
+    #[builder(setter(transform = |panel: impl Panel + 'static| Arc::new(panel) as Arc<dyn Panel>))]
+    panel: Arc<dyn Panel>,
+    #[builder(default = true)]
+    enabled: bool,
+    /// Opacity of the disabled panel, `Theme::disabled_opacity` by default
+    #[builder(default, setter(strip_option))]
+    disabled_opacity: Option<f32>,
+    /// Theme to use instead of the global one
+    #[builder(default, setter(into))]
+    theme: Option<Arc<Theme>>,
+}
+
+impl TryFrom<EnabledParams> for Enabled {
+    type Error = crate::Error;
+
+    fn try_from(value: EnabledParams) -> crate::Result<Self> {
+        let core = EnabledOpacity::new(value.enabled, value.disabled_opacity, value.theme);
+        let container = value.compositor.CreateContainerVisual()?;
+        container.SetOpacity(core.opacity())?;
+        attach(&container, &*value.panel)?;
+        Ok(Enabled {
+            container,
+            panel: value.panel,
+            core: RwLock::new(core),
+            panel_events: EventStreams::new(),
+            id: Arc::new(()),
+        })
+    }
+}
+
+impl TryFrom<EnabledParams> for Arc<Enabled> {
+    type Error = crate::Error;
+
+    fn try_from(value: EnabledParams) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
+
+impl Enabled {
+    pub async fn is_enabled(&self) -> bool {
+        self.core.read().await.is_enabled()
+    }
+
+    pub async fn set_enabled(&self, enabled: bool) -> crate::Result<()> {
+        let opacity = self.core.write().await.set_enabled(enabled);
+        match opacity {
+            Some(opacity) => fade(&self.container.clone().into(), opacity),
+            None => Ok(()),
+        }
+    }
+
+    pub async fn set_disabled_opacity(&self, opacity: Option<f32>) -> crate::Result<()> {
+        let opacity = self.core.write().await.set_disabled_opacity(opacity);
+        fade(&self.container.clone().into(), opacity)
+    }
+}
+
+impl Panel for Enabled {
+    fn outer_frame(&self) -> Visual {
+        self.container.clone().into()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+    fn hit_test(&self, point: Vector2) -> bool {
+        self.panel.hit_test(point)
+    }
+}
+
+impl EventSource<PanelEvent> for Enabled {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for Enabled {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        match event.as_ref() {
+            PanelEvent::ThemeChanged(theme) => {
+                let opacity = self.core.write().await.update_theme(theme);
+                if let Some(opacity) = opacity {
+                    self.container.SetOpacity(opacity)?;
+                }
+            }
+            PanelEvent::Resized(size) => self.container.SetSize(*size)?,
+            _ => {}
+        }
+        let enabled = self.core.read().await.is_enabled();
+        match event.as_ref() {
+            PanelEvent::MouseInput { .. }
+            | PanelEvent::MouseDoubleClick { .. }
+            | PanelEvent::DragDrop { .. }
+                if !enabled =>
+            {
+                self.panel
+                    .on_event_owned(event.with_in_slot(false), source.clone())
+                    .await?
+            }
+            PanelEvent::CursorMoved(..)
+            | PanelEvent::MouseWheel { .. }
+            | PanelEvent::KeyboardInput(..)
+            | PanelEvent::ReceivedCharacter(..)
+                if !enabled => {}
+            _ => {
+                self.panel
+                    .on_event_ref(event.as_ref(), source.clone())
+                    .await?
+            }
+        }
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
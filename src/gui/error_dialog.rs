@@ -0,0 +1,356 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use async_event_streams::EventSource;
+use async_std::sync::Arc;
+use futures::{
+    task::{Spawn, SpawnExt},
+    StreamExt,
+};
+use typed_builder::TypedBuilder;
+use windows::{
+    System::DispatcherQueueHandler,
+    UI::{Color, Composition::Compositor},
+};
+
+use crate::{capabilities::capabilities, set_error_handler, window::set_clipboard_text};
+
+use super::{
+    Background, BackgroundParams, Button, ButtonEvent, ButtonParams, CellLimit, Dialog,
+    DialogParams, DialogResult, LayerStack, Margins, Padded, PaddedParams, Ribbon,
+    RibbonOrientation, RibbonParams, RichText, RichTextParams, SimpleButtonSkin,
+    SimpleButtonSkinParams, TextRun,
+};
+
+///
+/// Dialog reporting the error to the user. Besides closing it allows to copy the error
+/// details to the clipboard and to save the diagnostics report to a file.
+///
+pub struct ErrorDialog {
+    dialog: Arc<Dialog>,
+    report: String,
+}
+
+#[derive(TypedBuilder)]
+pub struct ErrorDialogParams<T: Spawn + Clone> {
+    compositor: Compositor,
+    spawner: T,
+    layer_stack: Arc<LayerStack>,
+    /// Short description of what went wrong
+    #[builder(setter(into))]
+    message: String,
+    /// Technical details, e.g. the error chain
+    #[builder(default, setter(into))]
+    details: String,
+    /// Directory for the saved reports, the system temporary directory by default
+    #[builder(default = std::env::temp_dir(), setter(into))]
+    report_dir: PathBuf,
+    #[builder(default = Color { A: 0xFF, R: 0xF3, G: 0xF3, B: 0xF3 })]
+    color: Color,
+}
+
+fn create_button<T: Spawn + Clone>(
+    compositor: &Compositor,
+    spawner: &T,
+    text: &str,
+    color: Color,
+) -> crate::Result<Arc<Button>> {
+    let skin: SimpleButtonSkin = SimpleButtonSkinParams::builder()
+        .compositor(compositor.clone())
+        .text(text.to_owned())
+        .color(color)
+        .spawner(spawner.clone())
+        .build()
+        .try_into()?;
+    ButtonParams::builder()
+        .compositor(compositor.clone())
+        .skin(skin)
+        .build()
+        .try_into()
+}
+
+// Run the dialog action on the button click without closing the dialog, its outcome
+// is shown in the status line
+fn on_button<T: Spawn>(
+    spawner: &T,
+    button: &Button,
+    status: Arc<RichText>,
+    mut action: impl FnMut() -> String + Send + 'static,
+) -> crate::Result<()> {
+    let mut stream = EventSource::<ButtonEvent>::event_stream(button);
+    spawner.spawn(async move {
+        while let Some(event) = stream.next().await {
+            if *event == ButtonEvent::Release(true) {
+                let text = action();
+                status
+                    .set_runs(vec![TextRun::builder().text(text).build()])
+                    .await;
+            }
+        }
+    })?;
+    Ok(())
+}
+
+impl<T: Spawn + Clone> TryFrom<ErrorDialogParams<T>> for ErrorDialog {
+    type Error = crate::Error;
+
+    fn try_from(value: ErrorDialogParams<T>) -> crate::Result<Self> {
+        let compositor = value.compositor;
+        let message: Arc<RichText> = RichTextParams::builder()
+            .compositor(compositor.clone())
+            .spawner(value.spawner.clone())
+            .font_size(20.)
+            .build()
+            .add_run(
+                TextRun::builder()
+                    .text(value.message.clone())
+                    .bold(true)
+                    .build(),
+            )
+            .try_into()?;
+        let details: Arc<RichText> = RichTextParams::builder()
+            .compositor(compositor.clone())
+            .spawner(value.spawner.clone())
+            .font_size(14.)
+            .build()
+            .add_run(TextRun::builder().text(value.details.clone()).build())
+            .try_into()?;
+        let button_color = Color {
+            A: 0xFF,
+            R: 0xD0,
+            G: 0xD0,
+            B: 0xD0,
+        };
+        let copy_button = create_button(&compositor, &value.spawner, "Copy", button_color)?;
+        let save_button = create_button(&compositor, &value.spawner, "Save", button_color)?;
+        let close_button = create_button(&compositor, &value.spawner, "Close", button_color)?;
+        // Result of the copy and save actions
+        let status: Arc<RichText> = RichTextParams::builder()
+            .compositor(compositor.clone())
+            .spawner(value.spawner.clone())
+            .font_size(12.)
+            .build()
+            .try_into()?;
+        let buttons: Ribbon = RibbonParams::builder()
+            .compositor(compositor.clone())
+            .orientation(RibbonOrientation::Horizontal)
+            .build()
+            .add_panel(copy_button.clone(), CellLimit::default())?
+            .add_panel(save_button.clone(), CellLimit::default())?
+            .add_panel(close_button.clone(), CellLimit::default())?
+            .try_into()?;
+        let rows: Ribbon = RibbonParams::builder()
+            .compositor(compositor.clone())
+            .orientation(RibbonOrientation::Vertical)
+            .build()
+            .add_panel(message, CellLimit::new(1., 30., Some(60.), None))?
+            .add_panel(details, CellLimit::new(4., 0., None, None))?
+            .add_panel(status.clone(), CellLimit::new(1., 20., Some(30.), None))?
+            .add_panel(Arc::new(buttons), CellLimit::new(1., 40., Some(50.), None))?
+            .try_into()?;
+        let padded: Padded = PaddedParams::builder()
+            .panel(rows)
+            .margins(Margins::uniform(16.))
+            .compositor(compositor.clone())
+            .build()
+            .try_into()?;
+        let background: Background = BackgroundParams::builder()
+            .compositor(compositor.clone())
+            .color(value.color)
+            .round_corners(true)
+            .build()
+            .try_into()?;
+        let content: Ribbon = RibbonParams::builder()
+            .compositor(compositor.clone())
+            .orientation(RibbonOrientation::Stack)
+            .build()
+            .add_panel(Arc::new(background), CellLimit::default())?
+            .add_panel(Arc::new(padded), CellLimit::default())?
+            .try_into()?;
+        let dialog: Arc<Dialog> = DialogParams::builder()
+            .compositor(compositor)
+            .layer_stack(value.layer_stack)
+            .content(content)
+            .build()
+            .try_into()?;
+        dialog.close_on_button(&value.spawner, &close_button, DialogResult::Ok)?;
+        let report = diagnostics_report(&value.message, &value.details);
+        let copied = report.clone();
+        on_button(
+            &value.spawner,
+            &copy_button,
+            status.clone(),
+            move || match set_clipboard_text(&copied) {
+                Ok(()) => "Details are copied to the clipboard".to_owned(),
+                Err(e) => format!("Failed to copy the details: {e}"),
+            },
+        )?;
+        let (saved, report_dir) = (report.clone(), value.report_dir);
+        on_button(
+            &value.spawner,
+            &save_button,
+            status,
+            move || match write_report(&report_dir, &saved) {
+                Ok(path) => format!("Report is saved to {}", path.display()),
+                Err(e) => format!("Failed to save the report: {e}"),
+            },
+        )?;
+        Ok(ErrorDialog { dialog, report })
+    }
+}
+
+impl<T: Spawn + Clone> TryFrom<ErrorDialogParams<T>> for Arc<ErrorDialog> {
+    type Error = crate::Error;
+
+    fn try_from(value: ErrorDialogParams<T>) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
+
+impl ErrorDialog {
+    ///
+    /// Show the dialog until it's closed. Copy and save actions keep the dialog open
+    /// and report their outcome, e.g. the path of the saved file, under the details.
+    ///
+    pub async fn show(&self) -> crate::Result<()> {
+        self.dialog.show().await?;
+        Ok(())
+    }
+
+    pub async fn close(&self) {
+        self.dialog.close(DialogResult::Cancel).await
+    }
+
+    pub fn report(&self) -> &str {
+        &self.report
+    }
+
+    ///
+    /// Write the diagnostics report to a new file in the directory and return its path
+    ///
+    pub fn save_report(&self, dir: impl AsRef<Path>) -> crate::Result<PathBuf> {
+        write_report(dir.as_ref(), &self.report)
+    }
+}
+
+fn write_report(dir: &Path, report: &str) -> crate::Result<PathBuf> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let path = dir.join(format!(
+        "wag-report-{}-{}.txt",
+        std::process::id(),
+        timestamp
+    ));
+    std::fs::write(&path, report)?;
+    Ok(path)
+}
+
+///
+/// Plain text report with the error and the information about the application and the system
+///
+pub fn diagnostics_report(message: &str, details: &str) -> String {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let executable = std::env::current_exe()
+        .map(|path| path.display().to_string())
+        .unwrap_or_default();
+    format!(
+        "Error: {}\n\n{}\n\nExecutable: {}\nProcess id: {}\nwag version: {}\nSystem: {} {}\n{:?}\nUnix time: {}\n",
+        message,
+        details,
+        executable,
+        std::process::id(),
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        capabilities(),
+        timestamp
+    )
+}
+
+///
+/// Show the errors passed to `on_err` and `handle_err` in the `ErrorDialog` over the layer stack
+/// of the window. The dialog is created on the window thread through the compositor's
+/// dispatcher queue. The errors which happen while the dialog is shown or after the layer
+/// stack is dropped are only logged.
+///
+pub fn set_error_dialog_handler<T: Spawn + Clone + Send + Sync + 'static>(
+    compositor: Compositor,
+    spawner: T,
+    layer_stack: &Arc<LayerStack>,
+) {
+    let layer_stack = Arc::downgrade(layer_stack);
+    let showing = Arc::new(AtomicBool::new(false));
+    set_error_handler(move |error| {
+        let layer_stack = match layer_stack.upgrade() {
+            Some(layer_stack) if !showing.swap(true, Ordering::AcqRel) => layer_stack,
+            _ => {
+                tracing::error!(target: "wag::errors", %error, "unreported error");
+                return;
+            }
+        };
+        let message = error.to_string();
+        let mut pending = Some((layer_stack, message.clone(), format!("{error:?}")));
+        let dialog_compositor = compositor.clone();
+        let dialog_spawner = spawner.clone();
+        let dialog_showing = showing.clone();
+        let handler = DispatcherQueueHandler::new(move || {
+            if let Some((layer_stack, message, details)) = pending.take() {
+                let shown = show_error_dialog(
+                    &dialog_compositor,
+                    &dialog_spawner,
+                    layer_stack,
+                    &message,
+                    details,
+                    dialog_showing.clone(),
+                );
+                if let Err(e) = shown {
+                    dialog_showing.store(false, Ordering::Release);
+                    tracing::error!(target: "wag::errors", error = %message, dialog_error = %e, "failed to show the error dialog");
+                }
+            }
+            Ok(())
+        });
+        let enqueued = compositor
+            .DispatcherQueue()
+            .and_then(|queue| queue.TryEnqueue(&handler));
+        if !matches!(enqueued, Ok(true)) {
+            showing.store(false, Ordering::Release);
+            tracing::error!(target: "wag::errors", error = %message, "failed to show the error dialog: the window thread is not running");
+        }
+    });
+}
+
+// Runs on the window thread, the spawned task waits until the dialog is closed
+fn show_error_dialog<T: Spawn + Clone + Send + 'static>(
+    compositor: &Compositor,
+    spawner: &T,
+    layer_stack: Arc<LayerStack>,
+    message: &str,
+    details: String,
+    showing: Arc<AtomicBool>,
+) -> crate::Result<()> {
+    let dialog: ErrorDialog = ErrorDialogParams::builder()
+        .compositor(compositor.clone())
+        .spawner(spawner.clone())
+        .layer_stack(layer_stack)
+        .message(message)
+        .details(details)
+        .build()
+        .try_into()?;
+    let message = message.to_owned();
+    spawner.spawn(async move {
+        if let Err(e) = dialog.show().await {
+            tracing::error!(target: "wag::errors", error = %message, dialog_error = %e, "failed to show the error dialog");
+        }
+        showing.store(false, Ordering::Release);
+    })?;
+    Ok(())
+}
@@ -0,0 +1,279 @@
+use std::{
+    ops::{Add, Div, Mul, Neg, Sub},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use windows::{core::HSTRING, UI::Composition::CompositionObject};
+
+static NEXT_REFERENCE: AtomicU64 = AtomicU64::new(0);
+
+///
+/// Expression of the composition `ExpressionAnimation` built from the properties
+/// of the composition objects, e.g. the ribbon's scroll offset. The compositor
+/// reevaluates it on each frame, so the animated property follows the referenced ones
+/// without the event round trip. E.g. the parallax header is
+/// `(-ribbon.scroll_offset_expression() * 0.5).start(&header.outer_frame(), "Offset.Y")`.
+/// The invalid parts, e.g. the NaN constant, are reported by `start`.
+///
+#[derive(Clone, Debug)]
+pub struct Expression {
+    text: String,
+    references: Vec<(String, CompositionObject)>,
+    // First problem found while building the expression
+    error: Option<String>,
+}
+
+// Property path like "Offset.Y"
+fn is_property_path(path: &str) -> bool {
+    path.split('.').all(|name| {
+        let mut chars = name.chars();
+        chars
+            .next()
+            .map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+impl Expression {
+    pub fn constant(value: f32) -> Self {
+        if !value.is_finite() {
+            return Self {
+                text: "0".to_owned(),
+                references: Vec::new(),
+                error: Some(format!("constant {value} is not finite")),
+            };
+        }
+        // Display never uses the exponent notation, which the expression syntax lacks
+        let text = if value < 0. {
+            format!("({value})")
+        } else {
+            format!("{value}")
+        };
+        Self {
+            text,
+            references: Vec::new(),
+            error: None,
+        }
+    }
+    ///
+    /// Property of the composition object, e.g. "Opacity", "Size.X" or the name
+    /// inserted into the `CompositionPropertySet`
+    ///
+    pub fn property(object: impl Into<CompositionObject>, name: &'static str) -> Self {
+        let reference = format!("r{}", NEXT_REFERENCE.fetch_add(1, Ordering::Relaxed));
+        Self {
+            text: format!("{reference}.{name}"),
+            references: vec![(reference, object.into())],
+            error: (!is_property_path(name)).then(|| format!("bad property name {name:?}")),
+        }
+    }
+    pub fn vector2(x: impl Into<Expression>, y: impl Into<Expression>) -> Self {
+        Self::call("Vector2", [x.into(), y.into()])
+    }
+    pub fn vector3(
+        x: impl Into<Expression>,
+        y: impl Into<Expression>,
+        z: impl Into<Expression>,
+    ) -> Self {
+        Self::call("Vector3", [x.into(), y.into(), z.into()])
+    }
+    pub fn abs(self) -> Self {
+        Self::call("Abs", [self])
+    }
+    pub fn min(self, other: impl Into<Expression>) -> Self {
+        Self::call("Min", [self, other.into()])
+    }
+    pub fn max(self, other: impl Into<Expression>) -> Self {
+        Self::call("Max", [self, other.into()])
+    }
+    pub fn clamp(self, min: impl Into<Expression>, max: impl Into<Expression>) -> Self {
+        Self::call("Clamp", [self, min.into(), max.into()])
+    }
+    ///
+    /// Linear interpolation from `self` to `to`, `progress` 0 gives `self`
+    ///
+    pub fn lerp(self, to: impl Into<Expression>, progress: impl Into<Expression>) -> Self {
+        Self::call("Lerp", [self, to.into(), progress.into()])
+    }
+    ///
+    /// Expression string as passed to the compositor
+    ///
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+    ///
+    /// Check that the expression has no invalid parts, e.g. the infinite constants
+    ///
+    pub fn validate(&self) -> crate::Result<()> {
+        match &self.error {
+            Some(error) => Err(crate::Error::BadExpression(error.clone())),
+            None => Ok(()),
+        }
+    }
+    ///
+    /// Animate the target's property with the expression until `StopAnimation`
+    /// is called for it or another animation is started
+    ///
+    pub fn start(
+        &self,
+        target: impl Into<CompositionObject>,
+        property: &'static str,
+    ) -> crate::Result<()> {
+        self.validate()?;
+        if !is_property_path(property) {
+            return Err(crate::Error::BadExpression(format!(
+                "bad target property name {property:?}"
+            )));
+        }
+        let target = target.into();
+        let animation = target
+            .Compositor()?
+            .CreateExpressionAnimationWithExpression(&HSTRING::from(&self.text))?;
+        for (name, object) in &self.references {
+            animation.SetReferenceParameter(&HSTRING::from(name), object)?;
+        }
+        target.StartAnimation(&HSTRING::from(property), &animation)?;
+        Ok(())
+    }
+
+    fn call<const N: usize>(function: &str, args: [Expression; N]) -> Self {
+        let mut references = Vec::new();
+        let mut texts = Vec::new();
+        let mut error = None;
+        for arg in args {
+            texts.push(arg.text);
+            references.extend(arg.references);
+            error = error.or(arg.error);
+        }
+        Self {
+            text: format!("{function}({})", texts.join(", ")),
+            references,
+            error,
+        }
+    }
+
+    fn binary(mut self, operator: &str, other: Expression) -> Self {
+        self.references.extend(other.references);
+        Self {
+            text: format!("({} {operator} {})", self.text, other.text),
+            references: self.references,
+            error: self.error.or(other.error),
+        }
+    }
+}
+
+impl From<f32> for Expression {
+    fn from(value: f32) -> Self {
+        Self::constant(value)
+    }
+}
+
+impl<T: Into<Expression>> Add<T> for Expression {
+    type Output = Expression;
+    fn add(self, rhs: T) -> Expression {
+        self.binary("+", rhs.into())
+    }
+}
+
+impl<T: Into<Expression>> Sub<T> for Expression {
+    type Output = Expression;
+    fn sub(self, rhs: T) -> Expression {
+        self.binary("-", rhs.into())
+    }
+}
+
+impl<T: Into<Expression>> Mul<T> for Expression {
+    type Output = Expression;
+    fn mul(self, rhs: T) -> Expression {
+        self.binary("*", rhs.into())
+    }
+}
+
+impl<T: Into<Expression>> Div<T> for Expression {
+    type Output = Expression;
+    fn div(self, rhs: T) -> Expression {
+        self.binary("/", rhs.into())
+    }
+}
+
+impl Neg for Expression {
+    type Output = Expression;
+    fn neg(self) -> Expression {
+        Self {
+            text: format!("(-{})", self.text),
+            references: self.references,
+            error: self.error,
+        }
+    }
+}
+
+impl Sub<Expression> for f32 {
+    type Output = Expression;
+    fn sub(self, rhs: Expression) -> Expression {
+        Expression::constant(self) - rhs
+    }
+}
+
+impl Add<Expression> for f32 {
+    type Output = Expression;
+    fn add(self, rhs: Expression) -> Expression {
+        Expression::constant(self) + rhs
+    }
+}
+
+impl Mul<Expression> for f32 {
+    type Output = Expression;
+    fn mul(self, rhs: Expression) -> Expression {
+        Expression::constant(self) * rhs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constants_without_exponent() {
+        assert_eq!(Expression::constant(1.5).text(), "1.5");
+        assert_eq!(Expression::constant(-2.).text(), "(-2)");
+        assert_eq!(Expression::constant(1e-7).text(), "0.0000001");
+        assert_eq!(Expression::constant(1e10).text(), "10000000000");
+    }
+
+    #[test]
+    fn operators_and_functions() {
+        let e = (Expression::constant(1.) + 2.) * 3. - 4. / Expression::constant(5.);
+        assert_eq!(e.text(), "(((1 + 2) * 3) - (4 / 5))");
+        let e = (-Expression::constant(1.)).abs().clamp(0., 1.);
+        assert_eq!(e.text(), "Clamp(Abs((-1)), 0, 1)");
+        let e = Expression::vector2(1., 2.).lerp(Expression::vector2(3., 4.), 0.5);
+        assert_eq!(e.text(), "Lerp(Vector2(1, 2), Vector2(3, 4), 0.5)");
+        let e = Expression::constant(1.).min(2.).max(0.);
+        assert_eq!(e.text(), "Max(Min(1, 2), 0)");
+        assert_eq!(Expression::vector3(1., 2., 3.).text(), "Vector3(1, 2, 3)");
+    }
+
+    #[test]
+    fn not_finite_constants_are_invalid() {
+        for value in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            assert!(Expression::constant(value).validate().is_err());
+            // The error survives the composition
+            let e = (Expression::constant(1.) + value).max(2.) * 3.;
+            assert!(e.validate().is_err());
+            assert!((-Expression::constant(value)).validate().is_err());
+        }
+        assert!((Expression::constant(1.) + 2.).validate().is_ok());
+    }
+
+    #[test]
+    fn property_paths() {
+        assert!(is_property_path("Opacity"));
+        assert!(is_property_path("Offset.Y"));
+        assert!(is_property_path("_scroll1.X"));
+        assert!(!is_property_path(""));
+        assert!(!is_property_path("Offset."));
+        assert!(!is_property_path("1Offset"));
+        assert!(!is_property_path("Offset.Y + 1"));
+        assert!(!is_property_path("Размер"));
+    }
+}
@@ -0,0 +1,139 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_event_streams::{EventSource, EventStream, EventStreams};
+use typed_builder::TypedBuilder;
+use windows::{
+    Foundation::{EventRegistrationToken, TypedEventHandler},
+    System::{DispatcherQueue, DispatcherQueueTimer},
+};
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct FrameTick {
+    /// Time passed since the previous tick
+    pub delta: Duration,
+    /// Time passed since the ticker was started
+    pub elapsed: Duration,
+}
+
+struct Clock {
+    started: Instant,
+    last_tick: Instant,
+}
+
+///
+/// Source of the per-frame tick events for animations updated from code. Ticks are produced
+/// by the dispatcher queue timer on the window thread, so nothing runs while the ticker is stopped.
+///
+pub struct FrameTicker {
+    timer: DispatcherQueueTimer,
+    token: EventRegistrationToken,
+    clock: Arc<Mutex<Clock>>,
+    tick_events: Arc<EventStreams<FrameTick>>,
+}
+
+#[derive(TypedBuilder)]
+pub struct FrameTickerParams {
+    /// Dispatcher queue of the thread running the ticker, the current thread's one if not set
+    #[builder(default, setter(strip_option))]
+    dispatcher_queue: Option<DispatcherQueue>,
+    #[builder(default = Duration::from_millis(16))]
+    interval: Duration,
+    #[builder(default = true)]
+    start: bool,
+}
+
+impl TryFrom<FrameTickerParams> for FrameTicker {
+    type Error = crate::Error;
+
+    fn try_from(value: FrameTickerParams) -> crate::Result<Self> {
+        let dispatcher_queue = match value.dispatcher_queue {
+            Some(dispatcher_queue) => dispatcher_queue,
+            None => DispatcherQueue::GetForCurrentThread()?,
+        };
+        let timer = dispatcher_queue.CreateTimer()?;
+        timer.SetInterval(value.interval.into())?;
+        timer.SetIsRepeating(true)?;
+        let now = Instant::now();
+        let clock = Arc::new(Mutex::new(Clock {
+            started: now,
+            last_tick: now,
+        }));
+        let tick_events = Arc::new(EventStreams::new());
+        let token = {
+            let clock = clock.clone();
+            let tick_events = tick_events.clone();
+            timer.Tick(&TypedEventHandler::new(move |_, _| {
+                let now = Instant::now();
+                let mut clock = clock.lock().unwrap();
+                let tick = FrameTick {
+                    delta: now - clock.last_tick,
+                    elapsed: now - clock.started,
+                };
+                clock.last_tick = now;
+                // Ticks not handled yet are outdated by the new one
+                tick_events.clear();
+                tick_events.post_event(tick, None);
+                Ok(())
+            }))?
+        };
+        if value.start {
+            timer.Start()?;
+        }
+        Ok(FrameTicker {
+            timer,
+            token,
+            clock,
+            tick_events,
+        })
+    }
+}
+
+impl TryFrom<FrameTickerParams> for Arc<FrameTicker> {
+    type Error = crate::Error;
+
+    fn try_from(value: FrameTickerParams) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
+
+impl FrameTicker {
+    ///
+    /// Start or resume ticking. The first tick after resume doesn't include the paused time
+    /// in its `delta`, but `elapsed` counts from the first start.
+    ///
+    pub fn start(&self) -> crate::Result<()> {
+        if !self.timer.IsRunning()? {
+            self.clock.lock().unwrap().last_tick = Instant::now();
+            self.timer.Start()?;
+        }
+        Ok(())
+    }
+    pub fn stop(&self) -> crate::Result<()> {
+        Ok(self.timer.Stop()?)
+    }
+    pub fn is_running(&self) -> crate::Result<bool> {
+        Ok(self.timer.IsRunning()?)
+    }
+    pub fn interval(&self) -> crate::Result<Duration> {
+        Ok(self.timer.Interval()?.into())
+    }
+    pub fn set_interval(&self, interval: Duration) -> crate::Result<()> {
+        Ok(self.timer.SetInterval(interval.into())?)
+    }
+}
+
+impl Drop for FrameTicker {
+    fn drop(&mut self) {
+        let _ = self.timer.Stop();
+        let _ = self.timer.RemoveTick(self.token);
+    }
+}
+
+impl EventSource<FrameTick> for FrameTicker {
+    fn event_stream(&self) -> EventStream<FrameTick> {
+        self.tick_events.create_event_stream()
+    }
+}
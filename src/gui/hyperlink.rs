@@ -0,0 +1,245 @@
+use std::borrow::Cow;
+
+use async_event_streams::{
+    EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
+};
+use async_event_streams_derive::{self, EventSink};
+use async_std::sync::{Arc, RwLock};
+use async_trait::async_trait;
+use futures::task::Spawn;
+use typed_builder::TypedBuilder;
+use windows::{
+    core::PCWSTR,
+    w,
+    Foundation::Numerics::Vector2,
+    Win32::{Foundation::HWND, UI::Shell::ShellExecuteW, UI::WindowsAndMessaging::SW_SHOWNORMAL},
+    UI::{
+        Color,
+        Composition::{Compositor, Visual},
+    },
+};
+use winit::{
+    event::{ElementState, MouseButton},
+    window::CursorIcon,
+};
+
+use crate::window::{set_cursor, ToWide};
+
+use super::{is_translated_point_in_box, Panel, PanelEvent, RichText, RichTextParams, TextRun};
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum HyperlinkEvent {
+    Activated,
+}
+
+struct Core {
+    text: String,
+    color: Color,
+    hover_color: Color,
+    url: Option<String>,
+    hover: bool,
+    pressed: bool,
+    size: Vector2,
+}
+
+impl Core {
+    fn run(&self) -> TextRun {
+        TextRun::builder()
+            .text(self.text.clone())
+            .color(if self.hover {
+                self.hover_color
+            } else {
+                self.color
+            })
+            .underline(true)
+            .build()
+    }
+}
+
+///
+/// Underlined text which sends `HyperlinkEvent::Activated` on click and opens
+/// the url in the default browser if it's provided
+///
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+pub struct Hyperlink {
+    text: RichText,
+    core: RwLock<Core>,
+    panel_events: EventStreams<PanelEvent>,
+    hyperlink_events: EventStreams<HyperlinkEvent>,
+    id: Arc<()>,
+}
+
+#[derive(TypedBuilder)]
+pub struct HyperlinkParams<T: Spawn> {
+    compositor: Compositor,
+    spawner: T,
+    #[builder(setter(into))]
+    text: String,
+    #[builder(default, setter(strip_option, into))]
+    url: Option<String>,
+    #[builder(default = Color { A: 0xFF, R: 0x00, G: 0x5F, B: 0xB8 })]
+    color: Color,
+    #[builder(default = Color { A: 0xFF, R: 0x00, G: 0x3E, B: 0x92 })]
+    hover_color: Color,
+}
+
+impl<T: Spawn> TryFrom<HyperlinkParams<T>> for Hyperlink {
+    type Error = crate::Error;
+
+    fn try_from(value: HyperlinkParams<T>) -> crate::Result<Self> {
+        let core = Core {
+            text: value.text,
+            color: value.color,
+            hover_color: value.hover_color,
+            url: value.url,
+            hover: false,
+            pressed: false,
+            size: Vector2::default(),
+        };
+        let text = RichTextParams::builder()
+            .compositor(value.compositor)
+            .spawner(value.spawner)
+            .build()
+            .add_run(core.run())
+            .try_into()?;
+        Ok(Hyperlink {
+            text,
+            core: RwLock::new(core),
+            panel_events: EventStreams::new(),
+            hyperlink_events: EventStreams::new(),
+            id: Arc::new(()),
+        })
+    }
+}
+
+impl<T: Spawn> TryFrom<HyperlinkParams<T>> for Arc<Hyperlink> {
+    type Error = crate::Error;
+
+    fn try_from(value: HyperlinkParams<T>) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
+
+fn open_url(url: &str) -> crate::Result<()> {
+    let url = url.to_wide();
+    let result = unsafe {
+        ShellExecuteW(
+            HWND::default(),
+            w!("open"),
+            url.as_pcwstr(),
+            PCWSTR::null(),
+            PCWSTR::null(),
+            SW_SHOWNORMAL,
+        )
+    };
+    // Values not greater than 32 are error codes
+    if result.0 <= 32 {
+        return Err(windows::core::Error::from_win32().into());
+    }
+    Ok(())
+}
+
+impl Hyperlink {
+    pub async fn url(&self) -> Option<String> {
+        self.core.read().await.url.clone()
+    }
+
+    pub async fn set_url(&self, url: Option<String>) {
+        self.core.write().await.url = url;
+    }
+
+    pub async fn set_text(&self, text: impl Into<String>) {
+        let mut core = self.core.write().await;
+        core.text = text.into();
+        self.text.set_runs(vec![core.run()]).await;
+    }
+
+    async fn set_hover(&self, hover: bool) -> crate::Result<()> {
+        let mut core = self.core.write().await;
+        if core.hover != hover {
+            core.hover = hover;
+            self.text.set_runs(vec![core.run()]).await;
+            set_cursor(if hover {
+                CursorIcon::Hand
+            } else {
+                CursorIcon::Default
+            })?;
+        }
+        Ok(())
+    }
+
+    async fn activate(&self, source: Option<Arc<EventBox>>) -> crate::Result<()> {
+        if let Some(url) = self.url().await {
+            open_url(&url)?;
+        }
+        self.hyperlink_events
+            .send_event(HyperlinkEvent::Activated, source)
+            .await;
+        Ok(())
+    }
+}
+
+impl EventSource<HyperlinkEvent> for Hyperlink {
+    fn event_stream(&self) -> EventStream<HyperlinkEvent> {
+        self.hyperlink_events.create_event_stream()
+    }
+}
+
+impl EventSource<PanelEvent> for Hyperlink {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for Hyperlink {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        self.text
+            .on_event_ref(event.as_ref(), source.clone())
+            .await?;
+        match event.as_ref() {
+            PanelEvent::Resized(size) => self.core.write().await.size = *size,
+            PanelEvent::CursorMoved(point, _) => {
+                let size = self.core.read().await.size;
+                self.set_hover(is_translated_point_in_box(*point, size))
+                    .await?;
+            }
+            PanelEvent::MouseInput {
+                in_slot,
+                state,
+                button: MouseButton::Left,
+                ..
+            } => {
+                let hover = self.core.read().await.hover;
+                if *state == ElementState::Pressed {
+                    self.core.write().await.pressed = *in_slot && hover;
+                } else {
+                    let pressed = std::mem::take(&mut self.core.write().await.pressed);
+                    if pressed && *in_slot && hover {
+                        self.activate(source.clone()).await?;
+                    }
+                }
+            }
+            _ => {}
+        }
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
+
+impl Panel for Hyperlink {
+    fn outer_frame(&self) -> Visual {
+        self.text.outer_frame()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+}
@@ -0,0 +1,470 @@
+use std::{borrow::Cow, path::PathBuf};
+
+use async_event_streams::{
+    EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
+};
+use async_event_streams_derive::{self, EventSink};
+use async_std::sync::{Arc, RwLock};
+use async_trait::async_trait;
+use typed_builder::TypedBuilder;
+use windows::{
+    core::InParam,
+    Foundation::{
+        Numerics::{Matrix3x2, Vector2},
+        Size,
+    },
+    Graphics::DirectX::{DirectXAlphaMode, DirectXPixelFormat},
+    Win32::{
+        Foundation::POINT,
+        Graphics::{
+            Direct2D::{
+                Common::{
+                    D2D1_ALPHA_MODE_PREMULTIPLIED, D2D1_COLOR_F, D2D1_PIXEL_FORMAT, D2D_RECT_F,
+                    D2D_SIZE_U,
+                },
+                ID2D1DeviceContext, D2D1_BITMAP_BRUSH_PROPERTIES,
+                D2D1_BITMAP_INTERPOLATION_MODE_LINEAR, D2D1_BITMAP_PROPERTIES,
+                D2D1_BRUSH_PROPERTIES, D2D1_EXTEND_MODE_WRAP,
+            },
+            Dxgi::Common::DXGI_FORMAT_B8G8R8A8_UNORM,
+            Imaging::{
+                CLSID_WICImagingFactory, GUID_WICPixelFormat32bppPBGRA, IWICImagingFactory,
+                WICBitmapDitherTypeNone, WICBitmapPaletteTypeMedianCut,
+                WICDecodeMetadataCacheOnDemand,
+            },
+        },
+        System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER},
+    },
+    UI::Composition::{
+        CompositionDrawingSurface, CompositionStretch, CompositionSurfaceBrush, Compositor,
+        ContainerVisual, ShapeVisual, Visual,
+    },
+};
+
+use crate::window::{
+    clipboard_data, create_composition_graphics_device, draw, set_clipboard_data, ClipboardFormat,
+};
+
+use super::{Panel, PanelEvent};
+
+// Size of BITMAPINFOHEADER
+const DIB_HEADER_SIZE: usize = 40;
+
+// Prepend the BMP file header to the clipboard DIB, so WIC decodes any of its formats
+fn dib_to_bmp_file(dib: &[u8]) -> crate::Result<Vec<u8>> {
+    if dib.len() < DIB_HEADER_SIZE {
+        return Err(crate::Error::BadBitmapSize);
+    }
+    let u16_at = |offset: usize| u16::from_le_bytes([dib[offset], dib[offset + 1]]) as u32;
+    let u32_at = |offset: usize| u32::from_le_bytes(dib[offset..offset + 4].try_into().unwrap());
+    let (header_size, bit_count, compression, colors_used) =
+        (u32_at(0), u16_at(14), u32_at(16), u32_at(32));
+    let colors = match bit_count {
+        1 | 4 | 8 if colors_used == 0 => 1 << bit_count,
+        _ => colors_used,
+    };
+    // The color masks follow the short header of the BI_BITFIELDS bitmap
+    let masks = if header_size == DIB_HEADER_SIZE as u32 && compression == 3 {
+        12
+    } else {
+        0
+    };
+    let offset = 14 + header_size + masks + colors * 4;
+    let mut file = Vec::with_capacity(14 + dib.len());
+    file.extend(b"BM");
+    file.extend(((14 + dib.len()) as u32).to_le_bytes());
+    file.extend(0u32.to_le_bytes());
+    file.extend(offset.to_le_bytes());
+    file.extend_from_slice(dib);
+    Ok(file)
+}
+
+// Length of the pixel data, `None` if it overflows or the row is too long for the u32 stride
+pub(crate) fn pixels_len(width: u32, height: u32) -> Option<usize> {
+    width.checked_mul(4)?;
+    (width as usize)
+        .checked_mul(height as usize)?
+        .checked_mul(4)
+}
+
+#[derive(Clone, Debug)]
+pub enum ImageSource {
+    File(PathBuf),
+    /// Encoded image file content, e.g. PNG or JPEG
+    Memory(Vec<u8>),
+    Bitmap(Bitmap),
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum ImageStretch {
+    None,
+    #[default]
+    Fill,
+    Uniform,
+    UniformToFill,
+    /// Repeat the image in original size over the whole panel
+    Tile,
+}
+
+impl ImageStretch {
+    fn composition_stretch(&self) -> CompositionStretch {
+        match self {
+            ImageStretch::None | ImageStretch::Tile => CompositionStretch::None,
+            ImageStretch::Fill => CompositionStretch::Fill,
+            ImageStretch::Uniform => CompositionStretch::Uniform,
+            ImageStretch::UniformToFill => CompositionStretch::UniformToFill,
+        }
+    }
+}
+
+///
+/// Decoded image in 32 bit premultiplied BGRA format, rows are top to bottom without padding
+///
+#[derive(Clone, Debug)]
+pub struct Bitmap {
+    width: u32,
+    height: u32,
+    pixels: Arc<Vec<u8>>,
+}
+
+impl Bitmap {
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> crate::Result<Self> {
+        if pixels_len(width, height) != Some(pixels.len()) {
+            return Err(crate::Error::BadBitmapSize);
+        }
+        Ok(Self {
+            width,
+            height,
+            pixels: Arc::new(pixels),
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    fn load(source: ImageSource) -> crate::Result<Self> {
+        match source {
+            ImageSource::File(path) => Self::decode(&std::fs::read(path)?),
+            ImageSource::Memory(data) => Self::decode(&data),
+            ImageSource::Bitmap(bitmap) => Ok(bitmap),
+        }
+    }
+
+    ///
+    /// Decode the image file content with any format supported by WIC
+    ///
+    pub fn decode(data: &[u8]) -> crate::Result<Self> {
+        let factory: IWICImagingFactory = unsafe {
+            CoCreateInstance(
+                &CLSID_WICImagingFactory,
+                InParam::null(),
+                CLSCTX_INPROC_SERVER,
+            )
+        }?;
+        let stream = unsafe { factory.CreateStream() }?;
+        unsafe { stream.InitializeFromMemory(data) }?;
+        let decoder = unsafe {
+            factory.CreateDecoderFromStream(
+                &stream,
+                std::ptr::null(),
+                WICDecodeMetadataCacheOnDemand,
+            )
+        }?;
+        let frame = unsafe { decoder.GetFrame(0) }?;
+        let converter = unsafe { factory.CreateFormatConverter() }?;
+        unsafe {
+            converter.Initialize(
+                &frame,
+                &GUID_WICPixelFormat32bppPBGRA,
+                WICBitmapDitherTypeNone,
+                InParam::null(),
+                0.,
+                WICBitmapPaletteTypeMedianCut,
+            )
+        }?;
+        let (mut width, mut height) = (0, 0);
+        unsafe { converter.GetSize(&mut width, &mut height) }?;
+        let len = pixels_len(width, height).ok_or(crate::Error::BadBitmapSize)?;
+        let mut pixels = vec![0; len];
+        unsafe { converter.CopyPixels(std::ptr::null(), width * 4, &mut pixels) }?;
+        Self::new(width, height, pixels)
+    }
+
+    ///
+    /// Replace the clipboard content with the bitmap as the device independent bitmap
+    ///
+    pub fn to_clipboard(&self) -> crate::Result<()> {
+        set_clipboard_data(&[(ClipboardFormat::Dib, &self.to_dib())])
+    }
+
+    ///
+    /// Image from the clipboard, `None` if it has no image. PNG is preferred
+    /// over DIB as it keeps the transparency.
+    ///
+    pub fn from_clipboard() -> crate::Result<Option<Self>> {
+        if let Some(png) = clipboard_data(ClipboardFormat::Png)? {
+            return Self::decode(&png).map(Some);
+        }
+        match clipboard_data(ClipboardFormat::Dib)? {
+            Some(dib) => Self::decode(&dib_to_bmp_file(&dib)?).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    // BITMAPINFOHEADER followed by the 32 bit rows from bottom to top
+    fn to_dib(&self) -> Vec<u8> {
+        let row = self.width as usize * 4;
+        let mut dib = Vec::with_capacity(DIB_HEADER_SIZE + self.pixels.len());
+        dib.extend((DIB_HEADER_SIZE as u32).to_le_bytes());
+        dib.extend((self.width as i32).to_le_bytes());
+        dib.extend((self.height as i32).to_le_bytes());
+        dib.extend(1u16.to_le_bytes()); // planes
+        dib.extend(32u16.to_le_bytes()); // bits per pixel
+        dib.extend(0u32.to_le_bytes()); // BI_RGB
+                                        // Zero size is allowed for the uncompressed image too large for the field
+        dib.extend(u32::try_from(self.pixels.len()).unwrap_or(0).to_le_bytes());
+        dib.extend([0; 16]); // resolution and palette
+        if row > 0 {
+            for line in self.pixels.chunks_exact(row).rev() {
+                dib.extend_from_slice(line);
+            }
+        }
+        dib
+    }
+
+    fn draw(&self, context: ID2D1DeviceContext, point: POINT, size: Vector2) -> crate::Result<()> {
+        let bitmap_properties = D2D1_BITMAP_PROPERTIES {
+            pixelFormat: D2D1_PIXEL_FORMAT {
+                format: DXGI_FORMAT_B8G8R8A8_UNORM,
+                alphaMode: D2D1_ALPHA_MODE_PREMULTIPLIED,
+            },
+            dpiX: 96.,
+            dpiY: 96.,
+        };
+        let bitmap = unsafe {
+            context.CreateBitmap(
+                D2D_SIZE_U {
+                    width: self.width,
+                    height: self.height,
+                },
+                Some(self.pixels.as_ptr() as *const _),
+                self.width * 4,
+                &bitmap_properties,
+            )
+        }?;
+        let bitmap_brush_properties = D2D1_BITMAP_BRUSH_PROPERTIES {
+            extendModeX: D2D1_EXTEND_MODE_WRAP,
+            extendModeY: D2D1_EXTEND_MODE_WRAP,
+            interpolationMode: D2D1_BITMAP_INTERPOLATION_MODE_LINEAR,
+        };
+        let brush_properties = D2D1_BRUSH_PROPERTIES {
+            opacity: 1.,
+            transform: Matrix3x2::translation(point.x as f32, point.y as f32),
+        };
+        let brush = unsafe {
+            context.CreateBitmapBrush(
+                &bitmap,
+                Some(&bitmap_brush_properties),
+                Some(&brush_properties),
+            )
+        }?;
+        let clearcolor = D2D1_COLOR_F {
+            r: 0.,
+            g: 0.,
+            b: 0.,
+            a: 0.,
+        };
+        unsafe {
+            context.Clear(Some(&clearcolor));
+            context.FillRectangle(
+                &D2D_RECT_F {
+                    left: point.x as f32,
+                    top: point.y as f32,
+                    right: point.x as f32 + size.X,
+                    bottom: point.y as f32 + size.Y,
+                },
+                &brush,
+            );
+        }
+        Ok(())
+    }
+}
+
+struct Core {
+    compositor: Compositor,
+    container: ShapeVisual,
+    surface: CompositionDrawingSurface,
+    brush: CompositionSurfaceBrush,
+    image: Bitmap,
+    stretch: ImageStretch,
+    corner_radius: f32,
+}
+
+impl Core {
+    fn redraw(&self) -> crate::Result<()> {
+        let size = self.container.Size()?;
+        // Tiled image is drawn over the whole panel, otherwise the surface keeps
+        // the original image size and the brush scales it
+        let surface_size = if self.stretch == ImageStretch::Tile {
+            size
+        } else {
+            Vector2 {
+                X: self.image.width as f32,
+                Y: self.image.height as f32,
+            }
+        };
+        self.surface.Resize(windows::Graphics::SizeInt32 {
+            Width: surface_size.X as i32,
+            Height: surface_size.Y as i32,
+        })?;
+        if surface_size.X > 0. && surface_size.Y > 0. {
+            draw(&self.surface, |context, point| {
+                self.image.draw(context, point, surface_size)
+            })?;
+        }
+        self.brush.SetStretch(self.stretch.composition_stretch())?;
+
+        let rect_geometry = self.compositor.CreateRoundedRectangleGeometry()?;
+        rect_geometry.SetSize(size)?;
+        rect_geometry.SetCornerRadius(Vector2 {
+            X: self.corner_radius,
+            Y: self.corner_radius,
+        })?;
+        let rect = self
+            .compositor
+            .CreateSpriteShapeWithGeometry(&rect_geometry)?;
+        rect.SetFillBrush(&self.brush)?;
+        self.container.Shapes()?.Clear()?;
+        self.container.Shapes()?.Append(&rect)?;
+        Ok(())
+    }
+}
+
+///
+/// Rounded rectangle filled with the bitmap loaded from the file or memory buffer
+///
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+pub struct ImageBackground {
+    container: ContainerVisual,
+    core: RwLock<Core>,
+    panel_events: EventStreams<PanelEvent>,
+    id: Arc<()>,
+}
+
+#[derive(TypedBuilder)]
+pub struct ImageBackgroundParams {
+    compositor: Compositor,
+    image: ImageSource,
+    #[builder(default)]
+    stretch: ImageStretch,
+    #[builder(default)]
+    corner_radius: f32,
+}
+
+impl TryFrom<ImageBackgroundParams> for ImageBackground {
+    type Error = crate::Error;
+
+    fn try_from(value: ImageBackgroundParams) -> crate::Result<Self> {
+        let container = value.compositor.CreateShapeVisual()?;
+        let surface = create_composition_graphics_device(&value.compositor)?.CreateDrawingSurface(
+            Size::default(),
+            DirectXPixelFormat::B8G8R8A8UIntNormalized,
+            DirectXAlphaMode::Premultiplied,
+        )?;
+        let brush = value.compositor.CreateSurfaceBrushWithSurface(&surface)?;
+        brush.SetHorizontalAlignmentRatio(0.)?;
+        brush.SetVerticalAlignmentRatio(0.)?;
+        let core = RwLock::new(Core {
+            compositor: value.compositor,
+            container: container.clone(),
+            surface,
+            brush,
+            image: Bitmap::load(value.image)?,
+            stretch: value.stretch,
+            corner_radius: value.corner_radius,
+        });
+        Ok(ImageBackground {
+            container: container.into(),
+            core,
+            panel_events: EventStreams::new(),
+            id: Arc::new(()),
+        })
+    }
+}
+
+impl TryFrom<ImageBackgroundParams> for Arc<ImageBackground> {
+    type Error = crate::Error;
+
+    fn try_from(value: ImageBackgroundParams) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
+
+impl ImageBackground {
+    pub async fn set_image(&self, image: ImageSource) -> crate::Result<()> {
+        let mut core = self.core.write().await;
+        core.image = Bitmap::load(image)?;
+        core.redraw()
+    }
+    pub async fn stretch(&self) -> ImageStretch {
+        self.core.read().await.stretch
+    }
+    pub async fn set_stretch(&self, stretch: ImageStretch) -> crate::Result<()> {
+        let mut core = self.core.write().await;
+        core.stretch = stretch;
+        core.redraw()
+    }
+    pub async fn corner_radius(&self) -> f32 {
+        self.core.read().await.corner_radius
+    }
+    pub async fn set_corner_radius(&self, corner_radius: f32) -> crate::Result<()> {
+        let mut core = self.core.write().await;
+        core.corner_radius = corner_radius;
+        core.redraw()
+    }
+}
+
+impl Panel for ImageBackground {
+    fn outer_frame(&self) -> Visual {
+        self.container.clone().into()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+}
+
+impl EventSource<PanelEvent> for ImageBackground {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for ImageBackground {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        if let PanelEvent::Resized(size) = event.as_ref() {
+            let core = self.core.write().await;
+            core.container.SetSize(*size)?;
+            core.redraw()?;
+        }
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
@@ -0,0 +1,529 @@
+use std::{
+    borrow::Cow,
+    sync::{Arc, Weak},
+    time::Duration,
+};
+
+use async_event_streams::{
+    spawn_event_pipe, EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
+};
+use async_event_streams_derive::EventSink;
+use async_std::{sync::RwLock, task::sleep};
+use async_trait::async_trait;
+use futures::task::{Spawn, SpawnExt};
+use typed_builder::TypedBuilder;
+use windows::{
+    core::InParam,
+    Foundation::{Numerics::Vector2, Rect},
+    Graphics::SizeInt32,
+    Win32::{
+        Foundation::POINT,
+        Graphics::{
+            Direct2D::{
+                Common::{D2D1_COLOR_F, D2D_RECT_F},
+                ID2D1DeviceContext, D2D1_DRAW_TEXT_OPTIONS_NONE,
+            },
+            DirectWrite::{
+                IDWriteTextFormat, DWRITE_FONT_STRETCH_NORMAL, DWRITE_FONT_STYLE_NORMAL,
+                DWRITE_FONT_WEIGHT_NORMAL, DWRITE_MEASURING_MODE_NATURAL,
+            },
+        },
+    },
+    UI::{
+        Color,
+        Composition::{Compositor, Visual},
+    },
+};
+
+use crate::{
+    handle_err, on_err,
+    window::{dwrite_factory, ToWide},
+};
+
+use super::{surface::SurfaceEvent, Panel, PanelEvent, Surface, SurfaceFormat, SurfaceParams};
+
+#[derive(Clone, PartialEq, Debug)]
+enum Shape {
+    Text {
+        rect: Rect,
+        text: String,
+        font_size: f32,
+        color: Color,
+    },
+    Rect {
+        rect: Rect,
+        color: Color,
+    },
+}
+
+///
+/// Drawing and layout context passed to the `ImmediatePanel` frame callback. Elements are
+/// placed from top to bottom, or from left to right inside `horizontal`.
+///
+pub struct Ui {
+    shapes: Vec<Shape>,
+    size: Vector2,
+    cursor: Vector2,
+    horizontal: bool,
+    // Height of the current horizontal row
+    row_height: f32,
+    font_size: f32,
+    text_color: Color,
+    spacing: f32,
+}
+
+impl Ui {
+    ///
+    /// Size of the panel area not yet occupied by elements
+    ///
+    pub fn available_size(&self) -> Vector2 {
+        Vector2 {
+            X: (self.size.X - self.cursor.X).max(0.),
+            Y: (self.size.Y - self.cursor.Y).max(0.),
+        }
+    }
+    pub fn set_font_size(&mut self, font_size: f32) {
+        self.font_size = font_size;
+    }
+    pub fn set_text_color(&mut self, text_color: Color) {
+        self.text_color = text_color;
+    }
+    pub fn set_spacing(&mut self, spacing: f32) {
+        self.spacing = spacing;
+    }
+
+    ///
+    /// Reserve the space of given size at the current position
+    ///
+    pub fn allocate(&mut self, size: Vector2) -> Rect {
+        let rect = Rect {
+            X: self.cursor.X,
+            Y: self.cursor.Y,
+            Width: size.X,
+            Height: size.Y,
+        };
+        if self.horizontal {
+            self.cursor.X += size.X + self.spacing;
+            self.row_height = self.row_height.max(size.Y);
+        } else {
+            self.cursor.Y += size.Y + self.spacing;
+        }
+        rect
+    }
+
+    pub fn label(&mut self, text: impl Into<String>) -> crate::Result<Rect> {
+        self.text(text.into(), self.font_size, self.text_color)
+    }
+    pub fn colored_label(&mut self, color: Color, text: impl Into<String>) -> crate::Result<Rect> {
+        self.text(text.into(), self.font_size, color)
+    }
+    pub fn heading(&mut self, text: impl Into<String>) -> crate::Result<Rect> {
+        self.text(text.into(), self.font_size * 1.5, self.text_color)
+    }
+
+    fn text(&mut self, text: String, font_size: f32, color: Color) -> crate::Result<Rect> {
+        let max_width = if self.horizontal {
+            f32::MAX
+        } else {
+            self.available_size().X
+        };
+        let size = measure(&text, font_size, max_width)?;
+        let rect = self.allocate(size);
+        self.shapes.push(Shape::Text {
+            rect,
+            text,
+            font_size,
+            color,
+        });
+        Ok(rect)
+    }
+
+    pub fn rect(&mut self, size: Vector2, color: Color) -> Rect {
+        let rect = self.allocate(size);
+        self.shapes.push(Shape::Rect { rect, color });
+        rect
+    }
+
+    ///
+    /// Bar of the font height filled according to `fraction` from 0 to 1
+    ///
+    pub fn progress_bar(&mut self, fraction: f32, width: f32, color: Color) -> Rect {
+        let rect = self.allocate(Vector2 {
+            X: width,
+            Y: self.font_size,
+        });
+        self.shapes.push(Shape::Rect {
+            rect,
+            color: Color { A: 0x40, ..color },
+        });
+        self.shapes.push(Shape::Rect {
+            rect: Rect {
+                Width: width * fraction.clamp(0., 1.),
+                ..rect
+            },
+            color,
+        });
+        rect
+    }
+
+    pub fn separator(&mut self) -> Rect {
+        let size = if self.horizontal {
+            Vector2 {
+                X: 1.,
+                Y: self.row_height,
+            }
+        } else {
+            Vector2 {
+                X: self.available_size().X,
+                Y: 1.,
+            }
+        };
+        self.rect(
+            size,
+            Color {
+                A: 0x40,
+                ..self.text_color
+            },
+        )
+    }
+
+    pub fn space(&mut self, amount: f32) {
+        if self.horizontal {
+            self.cursor.X += amount;
+        } else {
+            self.cursor.Y += amount;
+        }
+    }
+
+    ///
+    /// Place the elements added by `f` in a row
+    ///
+    pub fn horizontal<F: FnOnce(&mut Ui) -> crate::Result<()>>(
+        &mut self,
+        f: F,
+    ) -> crate::Result<Rect> {
+        if self.horizontal {
+            f(self)?;
+            return Ok(Rect::default());
+        }
+        let origin = self.cursor;
+        self.horizontal = true;
+        self.row_height = 0.;
+        let result = f(self);
+        let rect = Rect {
+            X: origin.X,
+            Y: origin.Y,
+            Width: self.cursor.X - origin.X,
+            Height: self.row_height,
+        };
+        self.horizontal = false;
+        self.cursor = Vector2 {
+            X: origin.X,
+            Y: origin.Y + self.row_height + self.spacing,
+        };
+        result.map(|_| rect)
+    }
+}
+
+fn create_text_format(font_size: f32) -> crate::Result<IDWriteTextFormat> {
+    let font_family = "Segoe UI".to_wide();
+    let locale = "en-US".to_wide();
+    Ok(unsafe {
+        dwrite_factory()?.CreateTextFormat(
+            font_family.as_pcwstr(),
+            InParam::null(),
+            DWRITE_FONT_WEIGHT_NORMAL,
+            DWRITE_FONT_STYLE_NORMAL,
+            DWRITE_FONT_STRETCH_NORMAL,
+            font_size,
+            locale.as_pcwstr(),
+        )
+    }?)
+}
+
+fn measure(text: &str, font_size: f32, max_width: f32) -> crate::Result<Vector2> {
+    let text: Vec<u16> = text.encode_utf16().collect();
+    let text_format = create_text_format(font_size)?;
+    let text_layout = unsafe {
+        dwrite_factory()?.CreateTextLayout(text.as_slice(), &text_format, max_width, f32::MAX)
+    }?;
+    let metrics = unsafe { text_layout.GetMetrics() }?;
+    Ok(Vector2 {
+        X: metrics.widthIncludingTrailingWhitespace.ceil(),
+        Y: metrics.height.ceil(),
+    })
+}
+
+fn to_d2d_color(color: Color) -> D2D1_COLOR_F {
+    D2D1_COLOR_F {
+        r: color.R as f32 / 255.,
+        g: color.G as f32 / 255.,
+        b: color.B as f32 / 255.,
+        a: color.A as f32 / 255.,
+    }
+}
+
+fn to_d2d_rect(rect: &Rect, point: POINT) -> D2D_RECT_F {
+    D2D_RECT_F {
+        left: point.x as f32 + rect.X,
+        top: point.y as f32 + rect.Y,
+        right: point.x as f32 + rect.X + rect.Width,
+        bottom: point.y as f32 + rect.Y + rect.Height,
+    }
+}
+
+fn draw_shapes(
+    context: ID2D1DeviceContext,
+    point: POINT,
+    shapes: &[Shape],
+    background_color: Color,
+) -> crate::Result<()> {
+    unsafe { context.Clear(Some(&to_d2d_color(background_color))) };
+    for shape in shapes {
+        match shape {
+            Shape::Text {
+                rect,
+                text,
+                font_size,
+                color,
+            } => {
+                let text_format = create_text_format(*font_size)?;
+                let brush = unsafe { context.CreateSolidColorBrush(&to_d2d_color(*color), None) }?;
+                let text: Vec<u16> = text.encode_utf16().collect();
+                unsafe {
+                    context.DrawText(
+                        text.as_slice(),
+                        &text_format,
+                        &to_d2d_rect(rect, point),
+                        &brush,
+                        D2D1_DRAW_TEXT_OPTIONS_NONE,
+                        DWRITE_MEASURING_MODE_NATURAL,
+                    )
+                };
+            }
+            Shape::Rect { rect, color } => {
+                let brush = unsafe { context.CreateSolidColorBrush(&to_d2d_color(*color), None) }?;
+                unsafe { context.FillRectangle(&to_d2d_rect(rect, point), &brush) };
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(EventSink)]
+#[event_sink(event=SurfaceEvent)]
+struct Core {
+    surface: Arc<Surface>,
+    size: Vector2,
+    // Shapes currently shown on the surface
+    front: Vec<Shape>,
+    // Shapes of the previous frame, their buffer is reused by the next one
+    back: Vec<Shape>,
+    frame_interval: Duration,
+    font_size: f32,
+    text_color: Color,
+    background_color: Color,
+}
+
+impl Core {
+    fn begin_frame(&mut self) -> Ui {
+        let mut shapes = std::mem::take(&mut self.back);
+        shapes.clear();
+        Ui {
+            shapes,
+            size: self.size,
+            cursor: Vector2::default(),
+            horizontal: false,
+            row_height: 0.,
+            font_size: self.font_size,
+            text_color: self.text_color,
+            spacing: (self.font_size / 4.).ceil(),
+        }
+    }
+
+    // Returns true if the frame differs from the shown one
+    fn end_frame(&mut self, ui: Ui) -> bool {
+        self.back = ui.shapes;
+        if self.back == self.front {
+            false
+        } else {
+            std::mem::swap(&mut self.back, &mut self.front);
+            true
+        }
+    }
+}
+
+async fn redraw(size: Vector2, core: &Core) -> crate::Result<()> {
+    let new_surface_size = SizeInt32 {
+        Width: size.X as i32,
+        Height: size.Y as i32,
+    };
+    core.surface.resize_surface(new_surface_size).await?;
+    core.surface
+        .draw(|context, point| draw_shapes(context, point, &core.front, core.background_color))
+        .await
+}
+
+#[async_trait]
+impl EventSinkExt<SurfaceEvent> for Core {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, SurfaceEvent>,
+        _: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        match event.as_ref() {
+            SurfaceEvent::Redraw(size) => redraw(*size, self).await?,
+        }
+        Ok(())
+    }
+}
+
+// Runs until the panel is dropped, which is detected by its `id`
+async fn run_frames<F>(
+    core: Arc<RwLock<Core>>,
+    panel_id: Weak<()>,
+    mut frame: F,
+) -> crate::Result<()>
+where
+    F: FnMut(&mut Ui) -> crate::Result<()>,
+{
+    while panel_id.strong_count() > 0 {
+        let frame_interval = core.read().await.frame_interval;
+        sleep(frame_interval).await;
+        let surface = {
+            let mut core = core.write().await;
+            let mut ui = core.begin_frame();
+            frame(&mut ui)?;
+            core.end_frame(ui).then(|| core.surface.clone())
+        };
+        if let Some(surface) = surface {
+            surface.request_redraw().await;
+        }
+    }
+    Ok(())
+}
+
+///
+/// Panel redrawn by the user callback on each frame tick, for quick debug views inside
+/// the application. The callback describes the whole content with the `Ui` context; the
+/// surface is redrawn only when the result differs from the previous frame.
+///
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+pub struct ImmediatePanel {
+    surface: Arc<Surface>,
+    core: Arc<RwLock<Core>>,
+    panel_events: EventStreams<PanelEvent>,
+    id: Arc<()>,
+}
+
+impl ImmediatePanel {
+    pub async fn frame_interval(&self) -> Duration {
+        self.core.read().await.frame_interval
+    }
+    pub async fn set_frame_interval(&self, frame_interval: Duration) {
+        self.core.write().await.frame_interval = frame_interval;
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for ImmediatePanel {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        if let PanelEvent::Resized(size) = event.as_ref() {
+            self.core.write().await.size = *size;
+        }
+        self.surface
+            .on_event_ref(event.as_ref(), source.clone())
+            .await?;
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
+
+impl EventSource<PanelEvent> for ImmediatePanel {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+impl Panel for ImmediatePanel {
+    fn outer_frame(&self) -> Visual {
+        self.surface.outer_frame()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+}
+
+#[derive(TypedBuilder)]
+pub struct ImmediatePanelParams<T: Spawn, F: FnMut(&mut Ui) -> crate::Result<()> + Send + 'static> {
+    compositor: Compositor,
+    spawner: T,
+    /// Called on each frame tick to describe the panel content
+    frame: F,
+    #[builder(default = Duration::from_millis(16))]
+    frame_interval: Duration,
+    #[builder(default = 16.)]
+    font_size: f32,
+    #[builder(default = Color { A: 0xFF, R: 0, G: 0, B: 0 })]
+    text_color: Color,
+    #[builder(default = Color { A: 0, R: 0, G: 0, B: 0 })]
+    background_color: Color,
+    #[builder(default)]
+    format: SurfaceFormat,
+}
+
+impl<T: Spawn, F: FnMut(&mut Ui) -> crate::Result<()> + Send + 'static>
+    TryFrom<ImmediatePanelParams<T, F>> for ImmediatePanel
+{
+    type Error = crate::Error;
+
+    fn try_from(value: ImmediatePanelParams<T, F>) -> crate::Result<Self> {
+        let surface: Arc<Surface> = SurfaceParams::builder()
+            .compositor(value.compositor)
+            .format(value.format)
+            .build()
+            .try_into()?;
+        let core = Arc::new(RwLock::new(Core {
+            surface: surface.clone(),
+            size: Vector2::default(),
+            front: Vec::new(),
+            back: Vec::new(),
+            frame_interval: value.frame_interval,
+            font_size: value.font_size,
+            text_color: value.text_color,
+            background_color: value.background_color,
+        }));
+        let id = Arc::new(());
+        spawn_event_pipe(&value.spawner, &surface, core.clone(), on_err)?;
+        value.spawner.spawn(handle_err(run_frames(
+            core.clone(),
+            Arc::downgrade(&id),
+            value.frame,
+        )))?;
+        Ok(ImmediatePanel {
+            surface,
+            core,
+            panel_events: EventStreams::new(),
+            id,
+        })
+    }
+}
+
+impl<T: Spawn, F: FnMut(&mut Ui) -> crate::Result<()> + Send + 'static>
+    TryFrom<ImmediatePanelParams<T, F>> for Arc<ImmediatePanel>
+{
+    type Error = crate::Error;
+
+    fn try_from(value: ImmediatePanelParams<T, F>) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
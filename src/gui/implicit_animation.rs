@@ -0,0 +1,129 @@
+use std::time::Duration;
+
+use windows::{
+    h,
+    Foundation::{
+        Numerics::{Vector2, Vector3},
+        TypedEventHandler,
+    },
+    UI::Composition::{CompositionBatchTypes, Visual},
+};
+
+use crate::on_err;
+
+use super::easing::{Easing, EasingMode};
+
+///
+/// Animation played by the container when it adds or removes the panel
+///
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum ImplicitAnimation {
+    /// Panel appears and disappears instantly
+    #[default]
+    None,
+    Fade(Duration),
+    /// Fade combined with the movement from (on show) or to (on hide) the position shifted by `offset`
+    Slide {
+        duration: Duration,
+        offset: Vector2,
+    },
+}
+
+impl ImplicitAnimation {
+    fn parameters(&self) -> Option<(Duration, Vector2)> {
+        match *self {
+            ImplicitAnimation::None => None,
+            ImplicitAnimation::Fade(duration) => Some((duration, Vector2::default())),
+            ImplicitAnimation::Slide { duration, offset } => Some((duration, offset)),
+        }
+    }
+
+    pub(crate) fn show(&self, visual: &Visual) -> crate::Result<()> {
+        if let Some((duration, offset)) = self.parameters() {
+            let position = visual.Offset()?;
+            start_animations(visual, duration, offset, position, true)?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Play the animation and call `on_hidden` when it ends, e.g. to remove the visual.
+    /// If it returns true, the visual's opacity and offset are restored, so it can be
+    /// shown again. False means the visual was shown again while hiding and is left as is.
+    ///
+    pub(crate) fn hide<F: FnOnce() -> crate::Result<bool> + Send + 'static>(
+        &self,
+        visual: &Visual,
+        on_hidden: F,
+    ) -> crate::Result<()> {
+        let (duration, offset) = match self.parameters() {
+            Some(parameters) => parameters,
+            None => return on_hidden().map(|_| ()),
+        };
+        let position = visual.Offset()?;
+        let batch = visual
+            .Compositor()?
+            .CreateScopedBatch(CompositionBatchTypes::Animation)?;
+        start_animations(visual, duration, offset, position, false)?;
+        batch.End()?;
+        let visual = visual.clone();
+        let mut on_hidden = Some(on_hidden);
+        batch.Completed(&TypedEventHandler::new(move |_, _| {
+            if let Some(on_hidden) = on_hidden.take() {
+                if !on_hidden().unwrap_or_else(|e| {
+                    on_err(e);
+                    true
+                }) {
+                    return Ok(());
+                }
+                visual.StopAnimation(h!("Opacity"))?;
+                visual.StopAnimation(h!("Offset"))?;
+                visual.SetOpacity(1.)?;
+                visual.SetOffset(position)?;
+            }
+            Ok(())
+        }))?;
+        Ok(())
+    }
+}
+
+fn start_animations(
+    visual: &Visual,
+    duration: Duration,
+    offset: Vector2,
+    position: Vector3,
+    show: bool,
+) -> crate::Result<()> {
+    let compositor = visual.Compositor()?;
+    // Appearing panel decelerates into place, disappearing one accelerates away
+    let easing = if show {
+        Easing::Cubic(EasingMode::Out)
+    } else {
+        Easing::Cubic(EasingMode::In)
+    }
+    .composition_function(&compositor)?;
+    let (from, to) = if show { (0., 1.) } else { (1., 0.) };
+    let opacity = compositor.CreateScalarKeyFrameAnimation()?;
+    opacity.InsertKeyFrame(0., from)?;
+    opacity.InsertKeyFrameWithEasingFunction(1., to, &easing)?;
+    opacity.SetDuration(duration.into())?;
+    visual.StartAnimation(h!("Opacity"), &opacity)?;
+    if offset != Vector2::default() {
+        let shifted = Vector3 {
+            X: position.X + offset.X,
+            Y: position.Y + offset.Y,
+            Z: position.Z,
+        };
+        let (from, to) = if show {
+            (shifted, position)
+        } else {
+            (position, shifted)
+        };
+        let movement = compositor.CreateVector3KeyFrameAnimation()?;
+        movement.InsertKeyFrame(0., from)?;
+        movement.InsertKeyFrameWithEasingFunction(1., to, &easing)?;
+        movement.SetDuration(duration.into())?;
+        visual.StartAnimation(h!("Offset"), &movement)?;
+    }
+    Ok(())
+}
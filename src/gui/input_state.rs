@@ -0,0 +1,97 @@
+use std::collections::HashSet;
+
+use async_std::sync::RwLock;
+use windows::Foundation::Numerics::Vector2;
+use winit::event::{ElementState, MouseButton, VirtualKeyCode, WindowEvent};
+
+use super::IntoVector2;
+
+#[derive(Clone, Default, Debug)]
+pub struct KeyboardState {
+    pressed: HashSet<VirtualKeyCode>,
+}
+
+impl KeyboardState {
+    pub fn is_down(&self, key: VirtualKeyCode) -> bool {
+        self.pressed.contains(&key)
+    }
+    pub fn pressed_keys(&self) -> impl Iterator<Item = &VirtualKeyCode> {
+        self.pressed.iter()
+    }
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct MouseState {
+    position: Option<Vector2>,
+    pressed: HashSet<MouseButton>,
+}
+
+impl MouseState {
+    ///
+    /// Cursor position in window coordinates, none until the cursor enters the window
+    ///
+    pub fn position(&self) -> Option<Vector2> {
+        self.position
+    }
+    pub fn is_down(&self, button: MouseButton) -> bool {
+        self.pressed.contains(&button)
+    }
+}
+
+///
+/// Current keyboard and mouse state updated by the window event receiver.
+/// Allows to poll the input e.g. on each frame instead of accumulating events.
+///
+#[derive(Default)]
+pub struct InputState {
+    keyboard: RwLock<KeyboardState>,
+    mouse: RwLock<MouseState>,
+}
+
+impl InputState {
+    pub async fn keyboard(&self) -> KeyboardState {
+        self.keyboard.read().await.clone()
+    }
+
+    pub async fn mouse(&self) -> MouseState {
+        self.mouse.read().await.clone()
+    }
+
+    pub async fn is_key_down(&self, key: VirtualKeyCode) -> bool {
+        self.keyboard.read().await.is_down(key)
+    }
+
+    pub async fn is_button_down(&self, button: MouseButton) -> bool {
+        self.mouse.read().await.is_down(button)
+    }
+
+    pub(crate) async fn update(&self, event: &WindowEvent<'static>) {
+        match event {
+            WindowEvent::KeyboardInput { input, .. } => {
+                if let Some(key) = input.virtual_keycode {
+                    let mut keyboard = self.keyboard.write().await;
+                    match input.state {
+                        ElementState::Pressed => keyboard.pressed.insert(key),
+                        ElementState::Released => keyboard.pressed.remove(&key),
+                    };
+                }
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.mouse.write().await.position = Some(position.into_vector2())
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                let mut mouse = self.mouse.write().await;
+                match state {
+                    ElementState::Pressed => mouse.pressed.insert(*button),
+                    ElementState::Released => mouse.pressed.remove(button),
+                };
+            }
+            // Key and button releases are not delivered to the window without focus
+            WindowEvent::Focused(false) => {
+                self.keyboard.write().await.pressed.clear();
+                self.mouse.write().await.pressed.clear();
+            }
+            _ => (),
+        }
+    }
+}
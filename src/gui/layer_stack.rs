@@ -1,19 +1,58 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, sync::Mutex};
 
 use async_event_streams_derive::EventSink;
 use async_std::sync::{Arc, RwLock};
 
-use super::{attach, detach, Panel, PanelEvent};
+use super::{
+    attach, is_translated_point_in_box,
+    mouse_capture::{window_mouse_captures, CaptureRoute},
+    resize_cancellation_token, visual_offset, CancellationSource, ImplicitAnimation, Panel,
+    PanelEvent,
+};
 use async_event_streams::{
     EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
 };
 use async_trait::async_trait;
 
 use typed_builder::TypedBuilder;
-use windows::UI::Composition::{Compositor, ContainerVisual, Visual};
+use windows::{
+    Foundation::Numerics::Vector2,
+    UI::Composition::{Compositor, ContainerVisual, Visual},
+};
+use winit::event::ElementState;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum LayerStackEvent {
+    ///
+    /// Mouse button was pressed outside of the content of the topmost modal layer,
+    /// e.g. to close it (light dismiss)
+    ///
+    ModalClickOutside { layer_id: usize },
+}
+
+struct ModalLayer {
+    id: usize,
+    // Visual of the layer's content, clicks outside of it are reported
+    content: Option<Visual>,
+}
 
 struct Core {
     layers: Vec<Arc<dyn Panel>>,
+    // Input goes only to the topmost of modal layers
+    modal_layers: Vec<ModalLayer>,
+    // Layers which don't stop mouse input to the layers below
+    pass_through_layers: Vec<usize>,
+    mouse_pos: Option<Vector2>,
+    show_animation: ImplicitAnimation,
+    hide_animation: ImplicitAnimation,
+}
+
+impl Core {
+    fn top_modal_layer(&self) -> Option<(Arc<dyn Panel>, Option<Visual>)> {
+        let modal_layer = self.modal_layers.last()?;
+        let layer = self.layers.iter().find(|v| v.id() == modal_layer.id)?;
+        Some((layer.clone(), modal_layer.content.clone()))
+    }
 }
 
 #[derive(EventSink)]
@@ -21,7 +60,13 @@ struct Core {
 pub struct LayerStack {
     container: ContainerVisual,
     core: RwLock<Core>,
+    // Newer resize propagation makes the older one obsolete
+    resize_cancellation: CancellationSource,
+    capture_route: CaptureRoute,
+    // Removed layers which stay in the visual tree until their hide animation ends
+    hiding: Arc<Mutex<Vec<usize>>>,
     panel_events: EventStreams<PanelEvent>,
+    layer_stack_events: EventStreams<LayerStackEvent>,
     id: Arc<()>,
 }
 
@@ -30,20 +75,169 @@ impl LayerStack {
         self.core.read().await.layers.clone()
     }
 
-    pub async fn push_panel(&mut self, panel: Arc<dyn Panel>) -> crate::Result<()> {
-        attach(&self.container, &*panel)?;
-        self.core.write().await.layers.push(panel);
+    // The layer pushed again while hiding is detached now and not by the hide animation
+    fn cancel_hiding(&self, panel: &dyn Panel) -> crate::Result<()> {
+        let mut hiding = self.hiding.lock().unwrap();
+        if let Some(index) = hiding.iter().position(|id| *id == panel.id()) {
+            hiding.remove(index);
+            self.container.Children()?.Remove(&panel.outer_frame())?;
+        }
         Ok(())
     }
 
-    pub async fn remove_panel(&mut self, panel: impl Panel) -> crate::Result<()> {
+    pub async fn push_panel(&self, panel: Arc<dyn Panel>) -> crate::Result<()> {
+        self.cancel_hiding(&*panel)?;
+        attach(&self.container, &*panel)?;
+        let show_animation = {
+            let mut core = self.core.write().await;
+            core.layers.push(panel.clone());
+            core.show_animation
+        };
+        panel
+            .on_event_owned(PanelEvent::Resized(self.container.Size()?), None)
+            .await?;
+        show_animation.show(&panel.outer_frame())
+    }
+
+    ///
+    /// Push the layer which receives all mouse and keyboard input until it's removed
+    ///
+    pub async fn push_modal_panel(&self, panel: Arc<dyn Panel>) -> crate::Result<()> {
+        self.core.write().await.modal_layers.push(ModalLayer {
+            id: panel.id(),
+            content: None,
+        });
+        self.push_panel(panel).await
+    }
+    ///
+    /// Same as `push_modal_panel`, and also sends `LayerStackEvent::ModalClickOutside`
+    /// when the mouse is pressed outside of the `content` inside the layer
+    ///
+    pub async fn push_modal_panel_with_content<T: Panel + ?Sized>(
+        &self,
+        panel: Arc<dyn Panel>,
+        content: &T,
+    ) -> crate::Result<()> {
+        self.core.write().await.modal_layers.push(ModalLayer {
+            id: panel.id(),
+            content: Some(content.outer_frame()),
+        });
+        self.push_panel(panel).await
+    }
+
+    pub async fn remove_panel<T: Panel + ?Sized>(&self, panel: &T) -> crate::Result<()> {
         let mut core = self.core.write().await;
         if let Some(index) = core.layers.iter().position(|v| v.id() == panel.id()) {
-            detach(&panel)?;
+            // Removed layer stops receiving events immediately, but stays visible while hiding
+            let container = self.container.clone();
+            let visual = panel.outer_frame();
+            let id = panel.id();
+            let hiding = self.hiding.clone();
+            hiding.lock().unwrap().push(id);
+            core.hide_animation.hide(&panel.outer_frame(), move || {
+                let mut hiding = hiding.lock().unwrap();
+                match hiding.iter().position(|v| *v == id) {
+                    Some(index) => {
+                        hiding.remove(index);
+                        container.Children()?.Remove(&visual)?;
+                        Ok(true)
+                    }
+                    None => Ok(false),
+                }
+            })?;
             core.layers.remove(index);
         }
+        core.modal_layers.retain(|layer| layer.id != panel.id());
+        core.pass_through_layers.retain(|id| *id != panel.id());
         Ok(())
     }
+    ///
+    /// Let the mouse input through the layer: it still receives the mouse button events,
+    /// but the layers below get them too with `in_slot` set, so clicks on its
+    /// transparent regions reach the content beneath
+    ///
+    pub async fn set_pass_through<T: Panel + ?Sized>(&self, panel: &T, pass_through: bool) {
+        let mut core = self.core.write().await;
+        core.pass_through_layers.retain(|id| *id != panel.id());
+        if pass_through {
+            core.pass_through_layers.push(panel.id());
+        }
+    }
+    pub async fn is_pass_through<T: Panel + ?Sized>(&self, panel: &T) -> bool {
+        self.core
+            .read()
+            .await
+            .pass_through_layers
+            .contains(&panel.id())
+    }
+    ///
+    /// Insert the layer at the position in the z-order, 0 is the bottom
+    ///
+    pub async fn insert_at(&self, index: usize, panel: Arc<dyn Panel>) -> crate::Result<()> {
+        let show_animation = {
+            let mut core = self.core.write().await;
+            if index > core.layers.len() {
+                return Err(crate::Error::BadIndex);
+            }
+            self.cancel_hiding(&*panel)?;
+            core.layers.insert(index, panel.clone());
+            self.place_layer(&core.layers, index)?;
+            core.show_animation
+        };
+        panel
+            .on_event_owned(PanelEvent::Resized(self.container.Size()?), None)
+            .await?;
+        show_animation.show(&panel.outer_frame())
+    }
+    ///
+    /// Move the layer above all the others, e.g. to raise the floating window on click
+    ///
+    pub async fn bring_to_front<T: Panel + ?Sized>(&self, panel: &T) -> crate::Result<()> {
+        let mut core = self.core.write().await;
+        let top = core.layers.len().saturating_sub(1);
+        self.move_layer(&mut core.layers, panel.id(), top)
+    }
+    ///
+    /// Move the layer below all the others
+    ///
+    pub async fn send_to_back<T: Panel + ?Sized>(&self, panel: &T) -> crate::Result<()> {
+        let mut core = self.core.write().await;
+        self.move_layer(&mut core.layers, panel.id(), 0)
+    }
+    fn move_layer(
+        &self,
+        layers: &mut Vec<Arc<dyn Panel>>,
+        id: usize,
+        index: usize,
+    ) -> crate::Result<()> {
+        let from = layers
+            .iter()
+            .position(|v| v.id() == id)
+            .ok_or(crate::Error::BadIndex)?;
+        let layer = layers.remove(from);
+        self.container.Children()?.Remove(&layer.outer_frame())?;
+        layers.insert(index, layer);
+        self.place_layer(layers, index)
+    }
+    ///
+    /// Insert the layer's visual keeping the children order equal to the layers order
+    ///
+    fn place_layer(&self, layers: &[Arc<dyn Panel>], index: usize) -> crate::Result<()> {
+        let children = self.container.Children()?;
+        match layers.get(index + 1) {
+            Some(above) => {
+                children.InsertBelow(&layers[index].outer_frame(), &above.outer_frame())?
+            }
+            None => children.InsertAtTop(&layers[index].outer_frame())?,
+        }
+        Ok(())
+    }
+    pub async fn set_show_animation(&self, animation: ImplicitAnimation) {
+        self.core.write().await.show_animation = animation;
+    }
+    pub async fn set_hide_animation(&self, animation: ImplicitAnimation) {
+        self.core.write().await.hide_animation = animation;
+    }
     async fn translate_event_to_all_layers(
         &self,
         event: &PanelEvent,
@@ -55,27 +249,130 @@ impl LayerStack {
         }
         Ok(())
     }
-    async fn translate_event_to_top_layer(
+    // Mouse button events go to all layers, `in_slot` is kept only for the layers from
+    // the top down to the first layer which is hit by `Panel::hit_test` and is not pass-through
+    async fn translate_mouse_input(
         &self,
         event: &PanelEvent,
+        in_slot: bool,
         source: Option<Arc<EventBox>>,
     ) -> crate::Result<()> {
-        if let Some(item) = self.layers().await.first_mut() {
-            item.on_event_ref(event, source).await?;
+        let (layers, pass_through_layers) = {
+            let core = self.core.read().await;
+            (core.layers.clone(), core.pass_through_layers.clone())
+        };
+        let mut covered = false;
+        for layer in layers.iter().rev() {
+            if !self.capture_route.accepts(self, layer.id()) {
+                continue;
+            }
+            let frame = layer.outer_frame();
+            let hit = frame.IsVisible()?
+                && match self.mouse_pos_in(&frame).await? {
+                    Some(point) => layer.hit_test(point),
+                    None => false,
+                };
+            let had_captures = window_mouse_captures(self);
+            layer
+                .on_event_owned(
+                    event.with_in_slot(in_slot && hit && !covered),
+                    source.clone(),
+                )
+                .await?;
+            self.capture_route.update(self, &had_captures, layer.id());
+            covered |= hit && !pass_through_layers.contains(&layer.id());
         }
         Ok(())
     }
+    // Mouse position in coordinates of the visual
+    async fn mouse_pos_in(&self, visual: &Visual) -> crate::Result<Option<Vector2>> {
+        let mouse_pos = match self.core.read().await.mouse_pos {
+            Some(mouse_pos) => mouse_pos,
+            None => return Ok(None),
+        };
+        let offset = visual_offset(visual)?;
+        let origin = visual_offset(&self.container.clone().into())?;
+        Ok(Some(Vector2 {
+            X: mouse_pos.X - (offset.X - origin.X),
+            Y: mouse_pos.Y - (offset.Y - origin.Y),
+        }))
+    }
+    async fn is_mouse_in(&self, visual: &Visual) -> crate::Result<bool> {
+        Ok(match self.mouse_pos_in(visual).await? {
+            Some(point) => is_translated_point_in_box(point, visual.Size()?),
+            None => false,
+        })
+    }
     async fn translate_event(
         &self,
         event: &PanelEvent,
         source: Option<Arc<EventBox>>,
     ) -> crate::Result<()> {
+        if let PanelEvent::CursorMoved(mouse_pos, _) = event {
+            self.core.write().await.mouse_pos = Some(*mouse_pos);
+        }
+        let modal_layer = self.core.read().await.top_modal_layer();
+        if let Some((modal_layer, content)) = modal_layer {
+            match event {
+                PanelEvent::MouseInput {
+                    state: ElementState::Pressed,
+                    ..
+                } => {
+                    if let Some(content) = content {
+                        if !self.is_mouse_in(&content).await? {
+                            self.layer_stack_events
+                                .send_event(
+                                    LayerStackEvent::ModalClickOutside {
+                                        layer_id: modal_layer.id(),
+                                    },
+                                    source.clone(),
+                                )
+                                .await;
+                        }
+                    }
+                    return modal_layer.on_event_ref(event, source).await;
+                }
+                PanelEvent::CursorMoved(..)
+                | PanelEvent::MouseInput { .. }
+                | PanelEvent::MouseDoubleClick { .. }
+                | PanelEvent::DragDrop { .. }
+                | PanelEvent::MouseWheel { .. }
+                | PanelEvent::KeyboardInput(..)
+                | PanelEvent::ReceivedCharacter(..) => {
+                    return modal_layer.on_event_ref(event, source).await;
+                }
+                _ => (),
+            }
+        }
         match event {
             PanelEvent::Resized(size) => {
                 self.container.SetSize(*size)?;
-                self.translate_event_to_all_layers(event, source).await
+                let token = self.resize_cancellation.renew();
+                let window_token = resize_cancellation_token(self);
+                for item in self.layers().await {
+                    if token.is_cancelled() || window_token.is_cancelled() {
+                        break;
+                    }
+                    item.on_event_ref(event, source.clone()).await?;
+                }
+                Ok(())
+            }
+            PanelEvent::CursorMoved(..) => {
+                for layer in self.layers().await {
+                    if self.capture_route.accepts(self, layer.id()) {
+                        // The layer may capture the mouse on move, e.g. the scrolling drag
+                        let had_captures = window_mouse_captures(self);
+                        layer.on_event_ref(event, source.clone()).await?;
+                        self.capture_route.update(self, &had_captures, layer.id());
+                    }
+                }
+                Ok(())
+            }
+            PanelEvent::MouseInput { in_slot, .. }
+            | PanelEvent::MouseDoubleClick { in_slot, .. }
+            | PanelEvent::DragDrop { in_slot, .. } => {
+                self.translate_mouse_input(event, *in_slot, source).await
             }
-            PanelEvent::MouseInput { .. } => self.translate_event_to_top_layer(event, source).await,
             _ => self.translate_event_to_all_layers(event, source).await,
         }
     }
@@ -102,12 +399,28 @@ pub struct LayerStackParams {
     compositor: Compositor,
     #[builder(default)]
     layers: Vec<Arc<dyn Panel>>,
+    #[builder(default, setter(skip))]
+    pass_through_layers: Vec<usize>,
+    /// Animation of the layers pushed after the stack is created
+    #[builder(default)]
+    show_animation: ImplicitAnimation,
+    /// Animation of the removed layers
+    #[builder(default)]
+    hide_animation: ImplicitAnimation,
 }
 impl LayerStackParams {
     pub fn push_panel(mut self, panel: Arc<dyn Panel>) -> Self {
         self.layers.push(panel);
         self
     }
+    ///
+    /// Push the layer which lets the mouse input through, see `LayerStack::set_pass_through`
+    ///
+    pub fn push_pass_through_panel(mut self, panel: Arc<dyn Panel>) -> Self {
+        self.pass_through_layers.push(panel.id());
+        self.layers.push(panel);
+        self
+    }
 }
 
 impl TryFrom<LayerStackParams> for LayerStack {
@@ -119,12 +432,23 @@ impl TryFrom<LayerStackParams> for LayerStack {
         for layer in &mut layers {
             attach(&container, &**layer)?;
         }
-        let core = RwLock::new(Core { layers });
+        let core = RwLock::new(Core {
+            layers,
+            modal_layers: Vec::new(),
+            pass_through_layers: value.pass_through_layers,
+            mouse_pos: None,
+            show_animation: value.show_animation,
+            hide_animation: value.hide_animation,
+        });
         // container.SetComment(HSTRING::from("LAYER_STACK"))?;
         Ok(LayerStack {
             container,
             core,
+            resize_cancellation: CancellationSource::default(),
+            capture_route: CaptureRoute::default(),
+            hiding: Arc::new(Mutex::new(Vec::new())),
             panel_events: EventStreams::new(),
+            layer_stack_events: EventStreams::new(),
             id: Arc::new(()),
         })
     }
@@ -153,6 +477,12 @@ impl EventSource<PanelEvent> for LayerStack {
     }
 }
 
+impl EventSource<LayerStackEvent> for LayerStack {
+    fn event_stream(&self) -> EventStream<LayerStackEvent> {
+        self.layer_stack_events.create_event_stream()
+    }
+}
+
 #[async_trait]
 impl EventSinkExt<PanelEvent> for LayerStack {
     type Error = crate::Error;
@@ -0,0 +1,890 @@
+use std::{borrow::Cow, ops::Range};
+
+use async_event_streams::{
+    EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
+};
+use async_event_streams_derive::EventSink;
+use async_std::sync::{Arc, RwLock, Weak};
+use async_trait::async_trait;
+use futures::{
+    future::BoxFuture,
+    task::{Spawn, SpawnExt},
+    FutureExt,
+};
+use typed_builder::TypedBuilder;
+use windows::{
+    Foundation::Numerics::{Vector2, Vector3},
+    UI::Composition::{Compositor, ContainerVisual, Visual},
+};
+
+use crate::handle_err;
+
+use super::{attach, detach, is_point_in_box, CancellationToken, Panel, PanelEvent};
+
+// Distance scrolled by one line of the mouse wheel
+const SCROLL_LINE: f32 = 48.;
+
+///
+/// Creates the panel of the item by its index, e.g. loading the image from disk.
+/// The token is cancelled when the row is scrolled out of view before the panel is ready,
+/// the result of the cancelled factory is dropped.
+///
+pub type ItemFactory = Arc<
+    dyn Fn(usize, CancellationToken) -> BoxFuture<'static, crate::Result<Arc<dyn Panel>>>
+        + Send
+        + Sync,
+>;
+
+///
+/// Creates the lightweight panel shown in the row until its item is ready
+///
+pub type PlaceholderFactory = Arc<dyn Fn() -> crate::Result<Arc<dyn Panel>> + Send + Sync>;
+
+///
+/// Creates the header panel of the group by its index
+///
+pub type HeaderFactory = Arc<dyn Fn(usize) -> crate::Result<Arc<dyn Panel>> + Send + Sync>;
+
+///
+/// Loads more items when the list is scrolled near its end, e.g. the next page from
+/// disk or network. Receives the number of the items in the list and the number of the items
+/// requested, returns the number of the items made available to the item factory.
+/// Zero means the end of the data.
+///
+pub type MoreItemsLoader =
+    Arc<dyn Fn(usize, usize) -> BoxFuture<'static, crate::Result<usize>> + Send + Sync>;
+
+struct Row {
+    index: usize,
+    panel: Arc<dyn Panel>,
+    is_placeholder: bool,
+    token: CancellationToken,
+}
+
+struct Header {
+    group: usize,
+    panel: Arc<dyn Panel>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum LoadingState {
+    Idle,
+    Loading,
+    Failed,
+    Finished,
+}
+
+struct MoreItems {
+    loader: MoreItemsLoader,
+    count: usize,
+    list: Weak<ListView>,
+}
+
+struct Core {
+    item_count: usize,
+    item_height: f32,
+    header_height: f32,
+    // Indexes of the first items of the groups, ascending
+    groups: Vec<usize>,
+    scroll_offset: f32,
+    size: Vector2,
+    mouse_pos: Option<Vector2>,
+    rows: Vec<Row>,
+    headers: Vec<Header>,
+    more_items: Option<MoreItems>,
+    loading: LoadingState,
+    // Shown below the items while more items are loading or after the loading failed
+    loading_footer: Option<Arc<dyn Panel>>,
+    footer_height: f32,
+    // Placeholders of the rows scrolled out of view, reused for the rows coming into view
+    free_placeholders: Vec<Arc<dyn Panel>>,
+}
+
+impl Core {
+    // Groups starting past the items are shown when the items are loaded
+    fn group_count(&self) -> usize {
+        self.groups
+            .partition_point(|&first| first < self.item_count)
+    }
+    fn items_height(&self) -> f32 {
+        self.item_count as f32 * self.item_height + self.group_count() as f32 * self.header_height
+    }
+    fn is_footer_shown(&self) -> bool {
+        self.loading_footer.is_some()
+            && matches!(self.loading, LoadingState::Loading | LoadingState::Failed)
+    }
+    fn content_height(&self) -> f32 {
+        if self.is_footer_shown() {
+            self.items_height() + self.footer_height
+        } else {
+            self.items_height()
+        }
+    }
+    // The loading starts when less than a viewport of the items remains below the viewport
+    fn needs_more_items(&self) -> bool {
+        self.more_items.is_some()
+            && self.loading == LoadingState::Idle
+            && self.scroll_offset + 2. * self.size.Y >= self.items_height()
+    }
+    fn max_scroll_offset(&self) -> f32 {
+        (self.content_height() - self.size.Y).max(0.)
+    }
+    fn header_position(&self, group: usize) -> f32 {
+        self.groups[group] as f32 * self.item_height + group as f32 * self.header_height
+    }
+    fn item_position(&self, index: usize) -> f32 {
+        let headers = self.groups.partition_point(|&first| first <= index);
+        index as f32 * self.item_height + headers as f32 * self.header_height
+    }
+    // Number of the group headers starting at or above the position
+    fn headers_above(&self, position: f32) -> usize {
+        (0..self.group_count())
+            .take_while(|&group| self.header_position(group) <= position)
+            .count()
+    }
+    // Index of the item at the position, the item below when the position is on the header
+    fn item_at(&self, position: f32) -> usize {
+        let (first, top) = match self.headers_above(position) {
+            0 => (0, 0.),
+            headers => (
+                self.groups[headers - 1],
+                self.header_position(headers - 1) + self.header_height,
+            ),
+        };
+        first + ((position - top).max(0.) / self.item_height).floor() as usize
+    }
+    fn visible_range(&self) -> Range<usize> {
+        if self.item_height <= 0. {
+            return 0..0;
+        }
+        let first = self.item_at(self.scroll_offset);
+        let last = self.item_at(self.scroll_offset + self.size.Y) + 1;
+        first.min(self.item_count)..last.min(self.item_count)
+    }
+    // Groups shown in the viewport, including the group scrolled under its sticky header
+    fn visible_groups(&self) -> Range<usize> {
+        let first = self.headers_above(self.scroll_offset);
+        let last = (0..self.group_count())
+            .take_while(|&group| self.header_position(group) < self.scroll_offset + self.size.Y)
+            .count();
+        first.saturating_sub(1)..last
+    }
+    fn row_offset(&self, index: usize) -> Vector2 {
+        Vector2 {
+            X: 0.,
+            Y: self.item_position(index) - self.scroll_offset,
+        }
+    }
+    fn footer_offset(&self) -> Vector2 {
+        Vector2 {
+            X: 0.,
+            Y: self.items_height() - self.scroll_offset,
+        }
+    }
+    // The header sticks to the top of the viewport while its group is scrolled
+    // and is pushed out by the header of the next group
+    fn header_offset(&self, group: usize) -> Vector2 {
+        let position = self.header_position(group);
+        let end = if group + 1 < self.group_count() {
+            self.header_position(group + 1)
+        } else {
+            self.items_height()
+        };
+        let sticky = self
+            .scroll_offset
+            .min(end - self.header_height)
+            .max(position);
+        Vector2 {
+            X: 0.,
+            Y: sticky - self.scroll_offset,
+        }
+    }
+}
+
+///
+/// Vertical list of the items of the same height which creates the panels only for
+/// the visible rows. The row shows the placeholder at once and gets the item's panel
+/// when its async factory resolves; the placeholders are recycled and the pending
+/// factories are cancelled when the rows are scrolled out of view.
+/// The items may be split into groups, the header of the group sticks to the top
+/// of the list while the group is scrolled. More items may be loaded on demand
+/// when the list is scrolled near its end, see `on_need_more_items`.
+///
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+pub struct ListView {
+    container: ContainerVisual,
+    items: ContainerVisual,
+    headers: ContainerVisual,
+    item_factory: ItemFactory,
+    placeholder_factory: PlaceholderFactory,
+    header_factory: Option<HeaderFactory>,
+    spawner: Arc<dyn Spawn + Send + Sync>,
+    core: Arc<RwLock<Core>>,
+    panel_events: EventStreams<PanelEvent>,
+    id: Arc<()>,
+}
+
+#[derive(TypedBuilder)]
+pub struct ListViewParams<T: Spawn + Send + Sync + 'static> {
+    compositor: Compositor,
+    spawner: T,
+    item_height: f32,
+    #[builder(default)]
+    item_count: usize,
+    item_factory: ItemFactory,
+    placeholder_factory: PlaceholderFactory,
+    #[builder(default)]
+    groups: Vec<usize>,
+    #[builder(default)]
+    header_height: f32,
+    #[builder(default, setter(strip_option))]
+    header_factory: Option<HeaderFactory>,
+    /// Panel shown below the items while more items are loading, e.g. with the retry button
+    /// calling `load_more_items` after the failure
+    #[builder(default, setter(strip_option))]
+    loading_footer: Option<Arc<dyn Panel>>,
+    #[builder(default)]
+    footer_height: f32,
+}
+
+impl<T: Spawn + Send + Sync + 'static> TryFrom<ListViewParams<T>> for ListView {
+    type Error = crate::Error;
+
+    fn try_from(value: ListViewParams<T>) -> crate::Result<Self> {
+        let container = value.compositor.CreateContainerVisual()?;
+        container.SetClip(&value.compositor.CreateInsetClip()?)?;
+        // The headers are kept above the items scrolled under them
+        let items = value.compositor.CreateContainerVisual()?;
+        let headers = value.compositor.CreateContainerVisual()?;
+        container.Children()?.InsertAtTop(&items)?;
+        container.Children()?.InsertAtTop(&headers)?;
+        let mut groups = value.groups;
+        groups.sort_unstable();
+        groups.dedup();
+        Ok(ListView {
+            container,
+            items,
+            headers,
+            item_factory: value.item_factory,
+            placeholder_factory: value.placeholder_factory,
+            header_factory: value.header_factory,
+            spawner: Arc::new(value.spawner),
+            core: Arc::new(RwLock::new(Core {
+                item_count: value.item_count,
+                item_height: value.item_height,
+                header_height: value.header_height,
+                groups,
+                scroll_offset: 0.,
+                size: Vector2::default(),
+                mouse_pos: None,
+                rows: Vec::new(),
+                headers: Vec::new(),
+                more_items: None,
+                loading: LoadingState::Idle,
+                loading_footer: value.loading_footer,
+                footer_height: value.footer_height,
+                free_placeholders: Vec::new(),
+            })),
+            panel_events: EventStreams::new(),
+            id: Arc::new(()),
+        })
+    }
+}
+
+impl<T: Spawn + Send + Sync + 'static> TryFrom<ListViewParams<T>> for Arc<ListView> {
+    type Error = crate::Error;
+
+    fn try_from(value: ListViewParams<T>) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
+
+impl ListView {
+    pub async fn item_count(&self) -> usize {
+        self.core.read().await.item_count
+    }
+
+    ///
+    /// Change the number of the items. The rows of the remaining items are kept,
+    /// use `reload` to recreate them.
+    ///
+    pub async fn set_item_count(&self, item_count: usize) -> crate::Result<()> {
+        self.core.write().await.item_count = item_count;
+        self.update_rows(false, None).await
+    }
+
+    ///
+    /// Recreate the panels of the visible rows, e.g. when the items are changed
+    ///
+    pub async fn reload(&self) -> crate::Result<()> {
+        {
+            let mut core = self.core.write().await;
+            for row in std::mem::take(&mut core.rows) {
+                recycle_row(&mut core, row)?;
+            }
+        }
+        self.update_rows(false, None).await
+    }
+
+    pub async fn groups(&self) -> Vec<usize> {
+        self.core.read().await.groups.clone()
+    }
+
+    ///
+    /// Split the items into groups by the indexes of their first items
+    ///
+    pub async fn set_groups(&self, mut groups: Vec<usize>) -> crate::Result<()> {
+        groups.sort_unstable();
+        groups.dedup();
+        {
+            let mut core = self.core.write().await;
+            for header in std::mem::take(&mut core.headers) {
+                detach(&*header.panel)?;
+            }
+            core.groups = groups;
+        }
+        self.update_rows(false, None).await
+    }
+
+    ///
+    /// Call the loader when the list is scrolled near its end. The loading footer is shown
+    /// while the loader runs and is kept when it fails, then `load_more_items` retries.
+    ///
+    pub async fn on_need_more_items(
+        self: &Arc<Self>,
+        count: usize,
+        loader: MoreItemsLoader,
+    ) -> crate::Result<()> {
+        {
+            let mut core = self.core.write().await;
+            core.more_items = Some(MoreItems {
+                loader,
+                count,
+                list: Arc::downgrade(self),
+            });
+            reset_loading(&mut core)?;
+        }
+        self.update_rows(false, None).await
+    }
+
+    ///
+    /// Retry the failed loading or resume the loading finished by the end of the data
+    ///
+    pub async fn load_more_items(&self) -> crate::Result<()> {
+        {
+            let mut core = self.core.write().await;
+            reset_loading(&mut core)?;
+        }
+        self.update_rows(false, None).await
+    }
+
+    pub async fn scroll_offset(&self) -> f32 {
+        self.core.read().await.scroll_offset
+    }
+
+    ///
+    /// Scroll the list, the offset is limited by the height of the items exceeding the list
+    ///
+    pub async fn set_scroll_offset(&self, offset: f32) -> crate::Result<()> {
+        {
+            let mut core = self.core.write().await;
+            core.scroll_offset = offset.clamp(0., core.max_scroll_offset());
+        }
+        self.update_rows(false, None).await
+    }
+
+    // Recycle the rows scrolled out of view, create the rows scrolled into view
+    // and place them all, then the same for the group headers.
+    // The resized panels are notified after the lock is released.
+    async fn update_rows(&self, resized: bool, source: Option<Arc<EventBox>>) -> crate::Result<()> {
+        let mut notify = Vec::new();
+        {
+            let mut core = self.core.write().await;
+            core.scroll_offset = core.scroll_offset.clamp(0., core.max_scroll_offset());
+            let range = core.visible_range();
+            let (visible, hidden) = std::mem::take(&mut core.rows)
+                .into_iter()
+                .partition::<Vec<_>, _>(|row| range.contains(&row.index));
+            core.rows = visible;
+            for row in hidden {
+                recycle_row(&mut core, row)?;
+            }
+            for index in range {
+                if core.rows.iter().any(|row| row.index == index) {
+                    continue;
+                }
+                let row = self.create_row(&mut core, index)?;
+                notify.push((row.panel.clone(), core.item_height));
+                core.rows.push(row);
+            }
+            let size = Vector2 {
+                X: core.size.X,
+                Y: core.item_height,
+            };
+            for row in &core.rows {
+                let offset = core.row_offset(row.index);
+                let frame = row.panel.outer_frame();
+                frame.SetOffset(Vector3 {
+                    X: offset.X,
+                    Y: offset.Y,
+                    Z: 0.,
+                })?;
+                frame.SetSize(size)?;
+                if resized && !notify.iter().any(|(p, _)| p.id() == row.panel.id()) {
+                    notify.push((row.panel.clone(), core.item_height));
+                }
+            }
+            self.update_headers(&mut core, resized, &mut notify)?;
+            self.update_footer(&mut core, resized, &mut notify)?;
+        }
+        let width = self.core.read().await.size.X;
+        for (panel, height) in notify {
+            let size = Vector2 {
+                X: width,
+                Y: height,
+            };
+            panel
+                .on_event_owned(PanelEvent::Resized(size), source.clone())
+                .await?;
+        }
+        Ok(())
+    }
+
+    fn update_headers(
+        &self,
+        core: &mut Core,
+        resized: bool,
+        notify: &mut Vec<(Arc<dyn Panel>, f32)>,
+    ) -> crate::Result<()> {
+        let header_factory = match &self.header_factory {
+            Some(header_factory) => header_factory,
+            None => return Ok(()),
+        };
+        let range = core.visible_groups();
+        let (visible, hidden) = std::mem::take(&mut core.headers)
+            .into_iter()
+            .partition::<Vec<_>, _>(|header| range.contains(&header.group));
+        core.headers = visible;
+        for header in hidden {
+            detach(&*header.panel)?;
+        }
+        for group in range {
+            if core.headers.iter().any(|header| header.group == group) {
+                continue;
+            }
+            let panel = header_factory(group)?;
+            attach(&self.headers, &*panel)?;
+            notify.push((panel.clone(), core.header_height));
+            core.headers.push(Header { group, panel });
+        }
+        let size = Vector2 {
+            X: core.size.X,
+            Y: core.header_height,
+        };
+        for header in &core.headers {
+            let offset = core.header_offset(header.group);
+            let frame = header.panel.outer_frame();
+            frame.SetOffset(Vector3 {
+                X: offset.X,
+                Y: offset.Y,
+                Z: 0.,
+            })?;
+            frame.SetSize(size)?;
+            if resized && !notify.iter().any(|(p, _)| p.id() == header.panel.id()) {
+                notify.push((header.panel.clone(), core.header_height));
+            }
+        }
+        Ok(())
+    }
+
+    // Start loading more items if the end of the list is near and place the loading footer
+    fn update_footer(
+        &self,
+        core: &mut Core,
+        resized: bool,
+        notify: &mut Vec<(Arc<dyn Panel>, f32)>,
+    ) -> crate::Result<()> {
+        if core.needs_more_items() {
+            if let Some(more_items) = &core.more_items {
+                let load = (more_items.loader)(core.item_count, more_items.count);
+                let list = more_items.list.clone();
+                self.spawner.spawn(handle_err(async move {
+                    let loaded = load.await;
+                    match list.upgrade() {
+                        Some(list) => list.finish_loading(loaded).await,
+                        None => Ok(()),
+                    }
+                }))?;
+            }
+            core.loading = LoadingState::Loading;
+            if let Some(footer) = &core.loading_footer {
+                attach(&self.items, &**footer)?;
+                notify.push((footer.clone(), core.footer_height));
+            }
+        } else if resized && core.is_footer_shown() {
+            if let Some(footer) = &core.loading_footer {
+                notify.push((footer.clone(), core.footer_height));
+            }
+        }
+        if let (Some(footer), true) = (&core.loading_footer, core.is_footer_shown()) {
+            let offset = core.footer_offset();
+            let frame = footer.outer_frame();
+            frame.SetOffset(Vector3 {
+                X: offset.X,
+                Y: offset.Y,
+                Z: 0.,
+            })?;
+            frame.SetSize(Vector2 {
+                X: core.size.X,
+                Y: core.footer_height,
+            })?;
+        }
+        Ok(())
+    }
+
+    // Boxed to break the cycle of the update_rows and finish_loading futures
+    fn finish_loading(&self, loaded: crate::Result<usize>) -> BoxFuture<'_, crate::Result<()>> {
+        async move {
+            {
+                let mut core = self.core.write().await;
+                match loaded {
+                    Ok(0) => core.loading = LoadingState::Finished,
+                    Ok(count) => {
+                        core.item_count += count;
+                        core.loading = LoadingState::Idle;
+                    }
+                    // The footer is kept until the retry
+                    Err(e) => {
+                        core.loading = LoadingState::Failed;
+                        return Err(e);
+                    }
+                }
+                if let Some(footer) = &core.loading_footer {
+                    detach(&**footer)?;
+                }
+            }
+            self.update_rows(false, None).await
+        }
+        .boxed()
+    }
+
+    fn create_row(&self, core: &mut Core, index: usize) -> crate::Result<Row> {
+        let panel = match core.free_placeholders.pop() {
+            Some(panel) => panel,
+            None => (self.placeholder_factory)()?,
+        };
+        attach(&self.items, &*panel)?;
+        let token = CancellationToken::default();
+        let item = (self.item_factory)(index, token.clone());
+        let core = Arc::downgrade(&self.core);
+        let container = self.items.clone();
+        let row_token = token.clone();
+        self.spawner.spawn(handle_err(async move {
+            let item = item.await?;
+            match core.upgrade() {
+                Some(core) if !row_token.is_cancelled() => {
+                    show_item(&core, &container, index, item).await
+                }
+                _ => Ok(()),
+            }
+        }))?;
+        Ok(Row {
+            index,
+            panel,
+            is_placeholder: true,
+            token,
+        })
+    }
+
+    async fn resize(&self, size: Vector2, source: Option<Arc<EventBox>>) -> crate::Result<()> {
+        self.container.SetSize(size)?;
+        self.items.SetSize(size)?;
+        self.headers.SetSize(size)?;
+        self.core.write().await.size = size;
+        self.update_rows(true, source).await
+    }
+
+    async fn translate_cursor_moved(
+        &self,
+        mouse_pos: Vector2,
+        seq: u64,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        let panels = {
+            let mut core = self.core.write().await;
+            core.mouse_pos = Some(mouse_pos);
+            child_panels(&core)
+        };
+        for (offset, _, panel) in panels {
+            let mouse_pos = Vector2 {
+                X: mouse_pos.X - offset.X,
+                Y: mouse_pos.Y - offset.Y,
+            };
+            panel
+                .on_event_owned(PanelEvent::CursorMoved(mouse_pos, seq), source.clone())
+                .await?;
+        }
+        Ok(())
+    }
+
+    // Mouse buttons reach the panel under the cursor in slot, the other panels out of slot.
+    // The sticky header covers the row scrolled under it.
+    async fn translate_slot_event(
+        &self,
+        event: &PanelEvent,
+        in_slot: bool,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        let (panels, mouse_pos) = {
+            let core = self.core.read().await;
+            (child_panels(&core), core.mouse_pos)
+        };
+        let mut hit = false;
+        for (offset, size, panel) in panels {
+            let in_panel = !hit
+                && mouse_pos.map_or(false, |mouse_pos| is_point_in_box(mouse_pos, offset, size));
+            hit |= in_panel;
+            panel
+                .on_event_owned(event.with_in_slot(in_slot && in_panel), source.clone())
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn wheel_scroll(&self, event: &PanelEvent, delta: Vector2) -> crate::Result<()> {
+        let offset = {
+            let core = self.core.read().await;
+            let offset =
+                (core.scroll_offset - delta.Y * SCROLL_LINE).clamp(0., core.max_scroll_offset());
+            // At the end of the range the wheel scrolls the enclosing panel
+            if offset == core.scroll_offset {
+                return Ok(());
+            }
+            offset
+        };
+        event.set_handled();
+        self.set_scroll_offset(offset).await
+    }
+
+    async fn translate_panel_event_default(
+        &self,
+        event: &PanelEvent,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        let panels = child_panels(&*self.core.read().await);
+        for (_, _, panel) in panels {
+            panel.on_event_ref(event, source.clone()).await?;
+        }
+        Ok(())
+    }
+}
+
+// Offsets, sizes and panels of the headers and the rows, the topmost first
+fn child_panels(core: &Core) -> Vec<(Vector2, Vector2, Arc<dyn Panel>)> {
+    let header_size = Vector2 {
+        X: core.size.X,
+        Y: core.header_height,
+    };
+    let row_size = Vector2 {
+        X: core.size.X,
+        Y: core.item_height,
+    };
+    let headers = core.headers.iter().map(|header| {
+        (
+            core.header_offset(header.group),
+            header_size,
+            header.panel.clone(),
+        )
+    });
+    let rows = core
+        .rows
+        .iter()
+        .map(|row| (core.row_offset(row.index), row_size, row.panel.clone()));
+    let footer_size = Vector2 {
+        X: core.size.X,
+        Y: core.footer_height,
+    };
+    let footer = core
+        .loading_footer
+        .iter()
+        .filter(|_| core.is_footer_shown())
+        .map(|footer| (core.footer_offset(), footer_size, footer.clone()));
+    headers.chain(rows).chain(footer).collect()
+}
+
+// Allow the next loading unless it's in progress, the footer is shown again when it starts
+fn reset_loading(core: &mut Core) -> crate::Result<()> {
+    if core.loading != LoadingState::Loading {
+        core.loading = LoadingState::Idle;
+        if let Some(footer) = &core.loading_footer {
+            detach(&**footer)?;
+        }
+    }
+    Ok(())
+}
+
+// Cancel the pending factory of the row scrolled out of view and keep its placeholder
+fn recycle_row(core: &mut Core, row: Row) -> crate::Result<()> {
+    row.token.cancel();
+    detach(&*row.panel)?;
+    if row.is_placeholder {
+        core.free_placeholders.push(row.panel);
+    }
+    Ok(())
+}
+
+// Replace the placeholder of the row with the item's panel if the row is still in view
+async fn show_item(
+    core: &RwLock<Core>,
+    container: &ContainerVisual,
+    index: usize,
+    item: Arc<dyn Panel>,
+) -> crate::Result<()> {
+    let size = {
+        let mut core = core.write().await;
+        let offset = core.row_offset(index);
+        let size = Vector2 {
+            X: core.size.X,
+            Y: core.item_height,
+        };
+        let row = match core
+            .rows
+            .iter_mut()
+            .find(|row| row.index == index && row.is_placeholder && !row.token.is_cancelled())
+        {
+            Some(row) => row,
+            None => return Ok(()),
+        };
+        let placeholder = std::mem::replace(&mut row.panel, item.clone());
+        row.is_placeholder = false;
+        detach(&*placeholder)?;
+        core.free_placeholders.push(placeholder);
+        attach(container, &*item)?;
+        let frame = item.outer_frame();
+        frame.SetOffset(Vector3 {
+            X: offset.X,
+            Y: offset.Y,
+            Z: 0.,
+        })?;
+        frame.SetSize(size)?;
+        size
+    };
+    item.on_event_owned(PanelEvent::Resized(size), None).await
+}
+
+impl Panel for ListView {
+    fn outer_frame(&self) -> Visual {
+        self.container.clone().into()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+}
+
+impl EventSource<PanelEvent> for ListView {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for ListView {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        match event.as_ref() {
+            PanelEvent::Resized(size) => self.resize(*size, source.clone()).await?,
+            PanelEvent::CursorMoved(mouse_pos, seq) => {
+                self.translate_cursor_moved(*mouse_pos, *seq, source.clone())
+                    .await?
+            }
+            PanelEvent::MouseInput { in_slot, .. }
+            | PanelEvent::MouseDoubleClick { in_slot, .. }
+            | PanelEvent::DragDrop { in_slot, .. } => {
+                self.translate_slot_event(event.as_ref(), *in_slot, source.clone())
+                    .await?
+            }
+            PanelEvent::MouseWheel { delta, .. } => {
+                self.translate_panel_event_default(event.as_ref(), source.clone())
+                    .await?;
+                // The nested scrolling panel has already scrolled
+                if !event.is_handled() {
+                    self.wheel_scroll(event.as_ref(), *delta).await?
+                }
+            }
+            _ => {
+                self.translate_panel_event_default(event.as_ref(), source.clone())
+                    .await?
+            }
+        }
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(groups: Vec<usize>, scroll_offset: f32) -> Core {
+        Core {
+            item_count: 10,
+            item_height: 10.,
+            header_height: 5.,
+            groups,
+            scroll_offset,
+            size: Vector2 { X: 100., Y: 30. },
+            mouse_pos: None,
+            rows: Vec::new(),
+            headers: Vec::new(),
+            more_items: None,
+            loading: LoadingState::Idle,
+            loading_footer: None,
+            footer_height: 0.,
+            free_placeholders: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn items_are_placed_below_group_headers() {
+        let core = layout(vec![0, 4], 0.);
+        assert_eq!(core.content_height(), 110.);
+        assert_eq!(core.item_position(0), 5.);
+        assert_eq!(core.item_position(3), 35.);
+        assert_eq!(core.header_position(1), 45.);
+        assert_eq!(core.item_position(4), 50.);
+        assert_eq!(core.item_at(42.), 3);
+        assert_eq!(core.item_at(47.), 4);
+        assert_eq!(core.visible_range(), 0..3);
+        assert_eq!(core.visible_groups(), 0..1);
+    }
+
+    #[test]
+    fn header_sticks_until_pushed_by_next_header() {
+        let core = layout(vec![0, 4], 20.);
+        assert_eq!(core.visible_groups(), 0..2);
+        assert_eq!(core.header_offset(0).Y, 0.);
+        let core = layout(vec![0, 4], 42.);
+        assert_eq!(core.header_offset(0).Y, -2.);
+        assert_eq!(core.header_offset(1).Y, 3.);
+        let core = layout(vec![0, 4], 60.);
+        assert_eq!(core.visible_groups(), 1..2);
+        assert_eq!(core.header_offset(1).Y, 0.);
+    }
+
+    #[test]
+    fn groups_past_the_items_are_not_shown() {
+        let mut core = layout(vec![0, 4, 10], 0.);
+        assert_eq!(core.group_count(), 2);
+        assert_eq!(core.content_height(), 110.);
+        core.item_count = 12;
+        assert_eq!(core.group_count(), 3);
+        assert_eq!(core.content_height(), 135.);
+    }
+}
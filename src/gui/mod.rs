@@ -1,22 +1,112 @@
+mod aspect_ratio;
+mod backdrop;
 mod background;
+mod border;
+mod brush_cache;
 mod button;
+mod cancellation;
+mod context_menu;
+mod dialog;
+mod drag_drop;
+pub mod easing;
+mod enabled;
+mod error_dialog;
+mod expression;
+mod frame_ticker;
+mod hyperlink;
+mod image_background;
+mod immediate;
+mod implicit_animation;
+mod input_state;
 mod layer_stack;
+mod list_view;
+mod mouse_capture;
+mod nav_shell;
+mod overlay;
+mod padded;
 mod panel;
+mod popup_placement;
+mod progress_bar;
+mod repeat_button;
+mod responsive;
 mod ribbon;
+mod rich_text;
+mod selection_model;
+mod settings;
+pub mod skins;
 mod surface;
 mod text;
+mod theme;
+mod tooltip;
+mod touch;
+mod transformed;
+mod visibility;
+mod window_manager;
+mod window_supervisor;
 
+pub use aspect_ratio::{AspectRatio, AspectRatioParams};
+pub use backdrop::{BackdropMaterial, BackdropPanel, BackdropPanelParams};
 pub use background::{Background, BackgroundParams};
+pub use border::{Border, BorderParams};
+pub use brush_cache::clear_brush_cache;
 pub use button::{
-    Button, ButtonEvent, ButtonParams, ButtonSkin, SimpleButtonSkin, SimpleButtonSkinParams,
+    Button, ButtonEvent, ButtonParams, ButtonSkin, ButtonTransition, ButtonVisualState,
+    SimpleButtonSkin, SimpleButtonSkinParams,
 };
-pub use layer_stack::{LayerStack, LayerStackParams};
-pub use panel::{attach, detach, spawn_window_event_receiver, Panel, PanelEvent};
-pub use ribbon::{CellLimit, Ribbon, RibbonOrientation, RibbonParams};
-pub use surface::{Surface, SurfaceParams};
-pub use text::{Text, TextParams};
+pub use cancellation::{resize_cancellation_token, CancellationSource, CancellationToken};
+pub use context_menu::{ContextMenu, ContextMenuEvent, ContextMenuParams};
+pub use dialog::{Dialog, DialogParams, DialogResult};
+pub use drag_drop::{
+    current_drag, DragEvent, DragLayer, DragLayerParams, DragPayload, DropTarget, DropTargetParams,
+};
+pub use enabled::{Enabled, EnabledParams};
+pub use error_dialog::{
+    diagnostics_report, set_error_dialog_handler, ErrorDialog, ErrorDialogParams,
+};
+pub use expression::Expression;
+pub use frame_ticker::{FrameTick, FrameTicker, FrameTickerParams};
+pub use hyperlink::{Hyperlink, HyperlinkEvent, HyperlinkParams};
+pub(crate) use image_background::pixels_len;
+pub use image_background::{
+    Bitmap, ImageBackground, ImageBackgroundParams, ImageSource, ImageStretch,
+};
+pub use immediate::{ImmediatePanel, ImmediatePanelParams, Ui};
+pub use implicit_animation::ImplicitAnimation;
+pub use input_state::{InputState, KeyboardState, MouseState};
+pub use layer_stack::{LayerStack, LayerStackEvent, LayerStackParams};
+pub use list_view::{
+    HeaderFactory, ItemFactory, ListView, ListViewParams, MoreItemsLoader, PlaceholderFactory,
+};
+pub use mouse_capture::{capture_mouse, has_mouse_capture, mouse_capture, release_mouse_capture};
+pub use nav_shell::{NavShell, NavShellEvent, NavShellParams};
+pub use overlay::{Overlay, OverlayParams};
+pub use padded::{Margins, Padded, PaddedParams};
+pub use panel::{
+    attach, detach, screen_bounds, spawn_window_event_receiver,
+    spawn_window_event_receiver_with_input_state, EventHandled, EventOrder, Panel, PanelEvent,
+};
+pub use popup_placement::{PopupDirection, PopupPlacement};
+pub use progress_bar::{ProgressBar, ProgressBarParams};
+pub use repeat_button::{RepeatButton, RepeatButtonParams};
+pub use responsive::{BreakpointAxis, ResponsivePanel, ResponsivePanelParams, SizeClass};
+pub use ribbon::{
+    CellAlignment, CellLimit, Ribbon, RibbonMutation, RibbonOrientation, RibbonOverflow,
+    RibbonParams,
+};
+pub use rich_text::{RichText, RichTextParams, TextRun};
+pub use selection_model::{SelectionEvent, SelectionMode, SelectionModel};
+pub use settings::{SettingsGroup, SettingsGroupParams, SettingsRow, SettingsRowParams};
+pub use surface::{Surface, SurfaceEvent, SurfaceFormat, SurfaceParams};
+pub use text::{measure_text, Text, TextParams};
+pub use theme::{follow_system_color_mode, set_theme, system_color_mode, theme, ColorMode, Theme};
+pub use tooltip::{Tooltip, TooltipContent, TooltipService, TooltipServiceParams};
+pub use touch::is_touch_device;
+pub use transformed::{Transformed, TransformedParams};
+pub use visibility::{Visibility, VisibilityParams};
+pub use window_manager::{WindowManager, WindowManagerParams, WindowOpener, WindowParams};
+pub use window_supervisor::{WindowSupervisor, WindowThreadEvent};
 
-use windows::Foundation::Numerics::Vector2;
+use windows::{Foundation::Numerics::Vector2, UI::Composition::Visual};
 use winit::dpi::{PhysicalPosition, PhysicalSize};
 
 fn is_translated_point_in_box(point: Vector2, size: Vector2) -> bool {
@@ -26,10 +116,37 @@ fn is_translated_point_in_box(point: Vector2, size: Vector2) -> bool {
 fn is_point_in_box(point: Vector2, offset: Vector2, size: Vector2) -> bool {
     point.X >= offset.X
         && point.X <= offset.X + size.X
-        && point.Y >= offset.X
+        && point.Y >= offset.Y
         && point.Y <= offset.Y + size.Y
 }
 
+fn is_translated_point_in_rounded_box(point: Vector2, size: Vector2, radius: f32) -> bool {
+    if !is_translated_point_in_box(point, size) {
+        return false;
+    }
+    let radius = radius.min(size.X / 2.).min(size.Y / 2.).max(0.);
+    // Distance to the nearest point of the box shrunk by the radius
+    let dx = point.X - point.X.clamp(radius, size.X - radius);
+    let dy = point.Y - point.Y.clamp(radius, size.Y - radius);
+    dx * dx + dy * dy <= radius * radius
+}
+
+// Offset of the visual relative to the root of its visual tree
+fn visual_offset(visual: &Visual) -> crate::Result<Vector2> {
+    let mut offset = Vector2 { X: 0., Y: 0. };
+    let mut visual = visual.clone();
+    loop {
+        let v = visual.Offset()?;
+        offset.X += v.X;
+        offset.Y += v.Y;
+        match visual.Parent() {
+            Ok(parent) => visual = parent.into(),
+            Err(_) => break,
+        }
+    }
+    Ok(offset)
+}
+
 trait IntoVector2 {
     fn into_vector2(&self) -> Vector2;
 }
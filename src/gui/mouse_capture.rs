@@ -0,0 +1,146 @@
+use std::sync::Mutex;
+
+use windows::UI::Composition::Visual;
+use winit::event::MouseButton;
+
+use super::Panel;
+
+struct Capture {
+    // Root visual of the window's panel tree, so the windows don't share the captures
+    root: Visual,
+    button: MouseButton,
+    panel_id: usize,
+}
+
+static MOUSE_CAPTURES: Mutex<Vec<Capture>> = Mutex::new(Vec::new());
+
+// Topmost ancestor of the panel's visual, i.e. the root visual of its window
+pub(crate) fn root_visual<T: Panel + ?Sized>(panel: &T) -> Visual {
+    let mut visual = panel.outer_frame();
+    while let Ok(parent) = visual.Parent() {
+        visual = parent.into();
+    }
+    visual
+}
+
+///
+/// Route the mouse input in the panel's window to the panel until the mouse button
+/// is released, e.g. to keep dragging the slider thumb when the cursor leaves the slider.
+/// Called by the panel when it handles the button press.
+///
+pub fn capture_mouse<T: Panel + ?Sized>(panel: &T, button: MouseButton) {
+    let root = root_visual(panel);
+    let mut captures = MOUSE_CAPTURES.lock().unwrap();
+    captures.retain(|capture| !(capture.root == root && capture.button == button));
+    captures.push(Capture {
+        root,
+        button,
+        panel_id: panel.id(),
+    });
+}
+
+///
+/// Release the panel's capture of the button before the button is released.
+/// The captures of the other buttons are kept.
+///
+pub fn release_mouse_capture<T: Panel + ?Sized>(panel: &T, button: MouseButton) {
+    let id = panel.id();
+    MOUSE_CAPTURES
+        .lock()
+        .unwrap()
+        .retain(|capture| !(capture.panel_id == id && capture.button == button));
+}
+
+// The button is released in the window with the root visual
+pub(crate) fn release_window_mouse_capture(root: &Visual, button: MouseButton) {
+    MOUSE_CAPTURES
+        .lock()
+        .unwrap()
+        .retain(|capture| !(capture.root == *root && capture.button == button));
+}
+
+///
+/// `Panel::id` of the panel which captured the button in the window of the given panel
+///
+pub fn mouse_capture<T: Panel + ?Sized>(panel: &T, button: MouseButton) -> Option<usize> {
+    let root = root_visual(panel);
+    MOUSE_CAPTURES
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|capture| capture.root == root && capture.button == button)
+        .map(|capture| capture.panel_id)
+}
+
+pub fn has_mouse_capture<T: Panel + ?Sized>(panel: &T, button: MouseButton) -> bool {
+    mouse_capture(panel, button) == Some(panel.id())
+}
+
+///
+/// Captured buttons and the ids of the capturing panels in the window of the given panel
+///
+pub(crate) fn window_mouse_captures<T: Panel + ?Sized>(panel: &T) -> Vec<(MouseButton, usize)> {
+    let root = root_visual(panel);
+    MOUSE_CAPTURES
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|capture| capture.root == root)
+        .map(|capture| (capture.button, capture.panel_id))
+        .collect()
+}
+
+///
+/// Children of the container on the way to the panels which captured the mouse buttons.
+/// The container remembers the child which took the capture while handling
+/// the button press and sends the mouse events only to it until the capture ends.
+///
+#[derive(Default)]
+pub(crate) struct CaptureRoute(Mutex<Vec<Route>>);
+
+#[derive(Clone, Copy)]
+struct Route {
+    button: MouseButton,
+    captured_id: usize,
+    child_id: usize,
+}
+
+impl CaptureRoute {
+    // Routes to the captures which are still held
+    fn routes<T: Panel + ?Sized>(&self, owner: &T) -> Vec<Route> {
+        let captures = window_mouse_captures(owner);
+        let mut routes = self.0.lock().unwrap();
+        routes.retain(|route| captures.contains(&(route.button, route.captured_id)));
+        routes.clone()
+    }
+    ///
+    /// Check if the child took the capture while handling the event,
+    /// `had_captures` is the `window_mouse_captures` of the `owner` before the event was sent
+    ///
+    pub(crate) fn update<T: Panel + ?Sized>(
+        &self,
+        owner: &T,
+        had_captures: &[(MouseButton, usize)],
+        child_id: usize,
+    ) {
+        let captures = window_mouse_captures(owner);
+        let mut routes = self.0.lock().unwrap();
+        for (button, captured_id) in captures {
+            if !had_captures.contains(&(button, captured_id)) {
+                routes.retain(|route| route.button != button);
+                routes.push(Route {
+                    button,
+                    captured_id,
+                    child_id,
+                });
+            }
+        }
+    }
+    ///
+    /// Whether the mouse event should be sent to the child
+    ///
+    pub(crate) fn accepts<T: Panel + ?Sized>(&self, owner: &T, child_id: usize) -> bool {
+        let routes = self.routes(owner);
+        routes.is_empty() || routes.iter().any(|route| route.child_id == child_id)
+    }
+}
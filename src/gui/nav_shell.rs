@@ -0,0 +1,410 @@
+use std::borrow::Cow;
+
+use async_event_streams::{
+    EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
+};
+use async_event_streams_derive::EventSink;
+use async_std::sync::{Arc, RwLock};
+use async_trait::async_trait;
+use futures::{
+    task::{Spawn, SpawnExt},
+    StreamExt,
+};
+use typed_builder::TypedBuilder;
+use windows::UI::Composition::{Compositor, Visual};
+
+use crate::handle_err;
+
+use super::{
+    detach, Button, ButtonEvent, ButtonParams, CellLimit, Panel, PanelEvent, ResponsivePanel,
+    ResponsivePanelParams, Ribbon, RibbonMutation, RibbonOrientation, RibbonParams,
+    SimpleButtonSkin, SimpleButtonSkinParams, SizeClass,
+};
+
+const MENU_ICON: &str = "☰";
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum NavShellEvent {
+    /// Item with this index is selected and its panel is shown in the content frame
+    SelectionChanged(usize),
+    /// Sidebar is expanded (`true`) or collapsed to icons (`false`)
+    PaneToggled(bool),
+}
+
+struct Item {
+    label: String,
+    icon: String,
+    panel: Arc<dyn Panel>,
+    skin: Arc<SimpleButtonSkin>,
+}
+
+struct Core {
+    selected: Option<usize>,
+    pane_open: bool,
+    compact: Option<bool>,
+}
+
+// State shared with the tasks listening to the sidebar buttons
+struct Shared {
+    body: Arc<Ribbon>,
+    sidebar_id: usize,
+    content: Arc<Ribbon>,
+    items: Vec<Item>,
+    sidebar_width: f32,
+    compact_sidebar_width: f32,
+    core: RwLock<Core>,
+    nav_shell_events: EventStreams<NavShellEvent>,
+}
+
+impl Shared {
+    async fn select(&self, index: usize) -> crate::Result<()> {
+        let item = self.items.get(index).ok_or(crate::Error::BadIndex)?;
+        {
+            // The lock is held until the page is replaced, so the concurrent selections
+            // don't leave several pages in the content frame
+            let mut core = self.core.write().await;
+            if core.selected == Some(index) {
+                return Ok(());
+            }
+            // The panel may be still attached to the cell it was removed from
+            detach(&*item.panel)?;
+            self.content
+                .mutate(vec![
+                    RibbonMutation::Clear,
+                    RibbonMutation::Insert {
+                        index: 0,
+                        panel: item.panel.clone(),
+                        limit: CellLimit::default(),
+                    },
+                ])
+                .await?;
+            core.selected = Some(index);
+        }
+        self.nav_shell_events
+            .send_event(NavShellEvent::SelectionChanged(index), None)
+            .await;
+        Ok(())
+    }
+
+    async fn set_pane_open(&self, open: bool) -> crate::Result<()> {
+        {
+            let mut core = self.core.write().await;
+            if core.pane_open == open {
+                return Ok(());
+            }
+            core.pane_open = open;
+        }
+        let width = if open {
+            self.sidebar_width
+        } else {
+            self.compact_sidebar_width
+        };
+        for item in &self.items {
+            if open {
+                item.skin
+                    .set_text(format!("{}  {}", item.icon, item.label))
+                    .await;
+            } else {
+                item.skin.set_text(item.icon.clone()).await;
+            }
+        }
+        self.body
+            .set_cell_limit(
+                self.sidebar_id,
+                CellLimit::new(1., width, Some(width), None),
+            )
+            .await?;
+        self.nav_shell_events
+            .send_event(NavShellEvent::PaneToggled(open), None)
+            .await;
+        Ok(())
+    }
+}
+
+///
+/// Application frame with the optional header on top, the sidebar with navigation items
+/// on the left and the content frame showing the panel of the selected item.
+/// The sidebar collapses to icons when the shell has the compact `SizeClass`
+/// and can be toggled with its menu button.
+///
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+pub struct NavShell {
+    root: Arc<ResponsivePanel>,
+    shared: Arc<Shared>,
+    panel_events: EventStreams<PanelEvent>,
+    id: Arc<()>,
+}
+
+#[derive(TypedBuilder)]
+pub struct NavShellParams<T: Spawn> {
+    compositor: Compositor,
+    spawner: T,
+    #[builder(default, setter(transform = |panel: impl Panel + 'static| Some(Arc::new(panel) as Arc<dyn Panel>)))]
+    header: Option<Arc<dyn Panel>>,
+    #[builder(default = 48.)]
+    header_height: f32,
+    #[builder(default = 40.)]
+    item_height: f32,
+    /// Width of the expanded sidebar
+    #[builder(default = 240.)]
+    sidebar_width: f32,
+    /// Width of the sidebar collapsed to icons
+    #[builder(default = 48.)]
+    compact_sidebar_width: f32,
+    /// The sidebar is collapsed automatically when the shell is narrower than this,
+    /// i.e. this is the smallest width of the medium `SizeClass`
+    #[builder(default = 641.)]
+    compact_breakpoint: f32,
+    #[builder(default)]
+    items: Vec<(String, String, Arc<dyn Panel>)>,
+}
+
+impl<T: Spawn> NavShellParams<T> {
+    ///
+    /// Add the navigation item, the `icon` is the short text shown in the collapsed sidebar
+    ///
+    pub fn add_item(
+        mut self,
+        label: impl Into<String>,
+        icon: impl Into<String>,
+        panel: impl Panel + 'static,
+    ) -> Self {
+        self.items
+            .push((label.into(), icon.into(), Arc::new(panel)));
+        self
+    }
+}
+
+impl<T: Spawn + Clone> TryFrom<NavShellParams<T>> for NavShell {
+    type Error = crate::Error;
+
+    fn try_from(value: NavShellParams<T>) -> crate::Result<Self> {
+        let button = |text: String| -> crate::Result<(Arc<SimpleButtonSkin>, Arc<Button>)> {
+            let skin: Arc<SimpleButtonSkin> = SimpleButtonSkinParams::builder()
+                .compositor(value.compositor.clone())
+                .text(text)
+                .spawner(value.spawner.clone())
+                .build()
+                .try_into()?;
+            let button = ButtonParams::builder()
+                .compositor(value.compositor.clone())
+                .skin(skin.clone())
+                .build()
+                .try_into()?;
+            Ok((skin, button))
+        };
+        let row = CellLimit::new(1., value.item_height, Some(value.item_height), None);
+
+        let (_, toggle) = button(MENU_ICON.to_owned())?;
+        let mut sidebar_params = RibbonParams::builder()
+            .compositor(value.compositor.clone())
+            .orientation(RibbonOrientation::Vertical)
+            .spacing(4.)
+            .build()
+            .add_panel(toggle.clone(), row)?;
+        let mut items = Vec::new();
+        let mut buttons = Vec::new();
+        for (label, icon, panel) in value.items {
+            let (skin, button) = button(format!("{icon}  {label}"))?;
+            sidebar_params = sidebar_params.add_panel(button.clone(), row)?;
+            buttons.push(button);
+            items.push(Item {
+                label,
+                icon,
+                panel,
+                skin,
+            });
+        }
+        let sidebar: Arc<Ribbon> = sidebar_params.try_into()?;
+
+        let mut content_params = RibbonParams::builder()
+            .compositor(value.compositor.clone())
+            .orientation(RibbonOrientation::Stack)
+            .build();
+        if let Some(item) = items.first() {
+            content_params = content_params.add_panel(item.panel.clone(), CellLimit::default())?;
+        }
+        let content: Arc<Ribbon> = content_params.try_into()?;
+
+        let sidebar_limit =
+            CellLimit::new(1., value.sidebar_width, Some(value.sidebar_width), None);
+        let body: Arc<Ribbon> = RibbonParams::builder()
+            .compositor(value.compositor.clone())
+            .orientation(RibbonOrientation::Horizontal)
+            .build()
+            .add_panel(sidebar.clone(), sidebar_limit)?
+            .add_panel(content.clone(), CellLimit::default())?
+            .try_into()?;
+
+        let mut root_params = RibbonParams::builder()
+            .compositor(value.compositor.clone())
+            .orientation(RibbonOrientation::Vertical)
+            .build();
+        if let Some(header) = value.header {
+            root_params = root_params.add_panel(
+                header,
+                CellLimit::new(1., value.header_height, Some(value.header_height), None),
+            )?;
+        }
+        let frame: Arc<Ribbon> = root_params
+            .add_panel(body.clone(), CellLimit::default())?
+            .try_into()?;
+        let root: Arc<ResponsivePanel> = ResponsivePanelParams::builder()
+            .compositor(value.compositor.clone())
+            .compact(frame)
+            .medium_breakpoint(value.compact_breakpoint)
+            .expanded_breakpoint(f32::MAX)
+            .build()
+            .try_into()?;
+
+        let shared = Arc::new(Shared {
+            body,
+            sidebar_id: sidebar.id(),
+            content,
+            items,
+            sidebar_width: value.sidebar_width,
+            compact_sidebar_width: value.compact_sidebar_width,
+            core: RwLock::new(Core {
+                selected: (!buttons.is_empty()).then_some(0),
+                pane_open: true,
+                compact: None,
+            }),
+            nav_shell_events: EventStreams::new(),
+        });
+
+        for (index, button) in buttons.iter().enumerate() {
+            let mut stream = EventSource::<ButtonEvent>::event_stream(&**button);
+            let shared = Arc::downgrade(&shared);
+            value.spawner.spawn(handle_err(async move {
+                while let Some(event) = stream.next().await {
+                    if *event == ButtonEvent::Release(true) {
+                        if let Some(shared) = shared.upgrade() {
+                            shared.select(index).await?;
+                        }
+                    }
+                }
+                Ok(())
+            }))?;
+        }
+        let mut stream = EventSource::<ButtonEvent>::event_stream(&*toggle);
+        let weak_shared = Arc::downgrade(&shared);
+        value.spawner.spawn(handle_err(async move {
+            while let Some(event) = stream.next().await {
+                if *event == ButtonEvent::Release(true) {
+                    if let Some(shared) = weak_shared.upgrade() {
+                        let open = shared.core.read().await.pane_open;
+                        shared.set_pane_open(!open).await?;
+                    }
+                }
+            }
+            Ok(())
+        }))?;
+
+        Ok(NavShell {
+            root,
+            shared,
+            panel_events: EventStreams::new(),
+            id: Arc::new(()),
+        })
+    }
+}
+
+impl<T: Spawn + Clone> TryFrom<NavShellParams<T>> for Arc<NavShell> {
+    type Error = crate::Error;
+
+    fn try_from(value: NavShellParams<T>) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
+
+impl NavShell {
+    ///
+    /// Index of the item shown in the content frame, `None` if there are no items
+    ///
+    pub async fn selected(&self) -> Option<usize> {
+        self.shared.core.read().await.selected
+    }
+
+    pub async fn select(&self, index: usize) -> crate::Result<()> {
+        self.shared.select(index).await
+    }
+
+    pub async fn is_pane_open(&self) -> bool {
+        self.shared.core.read().await.pane_open
+    }
+
+    ///
+    /// Expand or collapse the sidebar. The state is reset when the shell
+    /// crosses the compact breakpoint.
+    ///
+    pub async fn set_pane_open(&self, open: bool) -> crate::Result<()> {
+        self.shared.set_pane_open(open).await
+    }
+
+    ///
+    /// Whether the shell is narrower than the compact breakpoint,
+    /// `None` before it is resized for the first time
+    ///
+    pub async fn is_compact(&self) -> Option<bool> {
+        self.shared.core.read().await.compact
+    }
+
+    // Collapse or expand the sidebar when the root panel changes its size class
+    async fn update_compact(&self) -> crate::Result<()> {
+        let compact = match self.root.size_class().await {
+            Some(size_class) => size_class == SizeClass::Compact,
+            None => return Ok(()),
+        };
+        let changed = {
+            let mut core = self.shared.core.write().await;
+            core.compact.replace(compact) != Some(compact)
+        };
+        if changed {
+            self.shared.set_pane_open(!compact).await?;
+        }
+        Ok(())
+    }
+}
+
+impl Panel for NavShell {
+    fn outer_frame(&self) -> Visual {
+        self.root.outer_frame()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+}
+
+impl EventSource<PanelEvent> for NavShell {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+impl EventSource<NavShellEvent> for NavShell {
+    fn event_stream(&self) -> EventStream<NavShellEvent> {
+        self.shared.nav_shell_events.create_event_stream()
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for NavShell {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        self.root
+            .on_event_ref(event.as_ref(), source.clone())
+            .await?;
+        if let PanelEvent::Resized(_) = event.as_ref() {
+            self.update_compact().await?;
+        }
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
@@ -0,0 +1,288 @@
+use std::borrow::Cow;
+
+use async_event_streams::{
+    EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
+};
+use async_event_streams_derive::EventSink;
+use async_std::sync::{Arc, RwLock};
+use async_trait::async_trait;
+use typed_builder::TypedBuilder;
+use windows::{
+    Foundation::Numerics::{Vector2, Vector3},
+    UI::Composition::{Compositor, ContainerVisual, Visual},
+};
+
+use super::{
+    attach, is_translated_point_in_box,
+    mouse_capture::{window_mouse_captures, CaptureRoute},
+    Panel, PanelEvent,
+};
+
+#[derive(Clone)]
+struct Item {
+    panel: Arc<dyn Panel>,
+    container: ContainerVisual,
+}
+
+impl Item {
+    fn translate_point(&self, mut point: Vector2) -> crate::Result<Vector2> {
+        let offset = self.container.Offset()?;
+        point.X -= offset.X;
+        point.Y -= offset.Y;
+        Ok(point)
+    }
+    fn is_translated_point_in_item(&self, point: Vector2) -> crate::Result<bool> {
+        Ok(is_translated_point_in_box(point, self.container.Size()?))
+    }
+}
+
+struct Core {
+    items: Vec<Item>,
+    mouse_pos: Option<Vector2>,
+}
+
+///
+/// Layer for panels shown at arbitrary position and size over the other content:
+/// tooltips, menus, popups. Unlike `LayerStack` it doesn't stretch its items to its own size.
+/// When the content below is hosted by the overlay itself, mouse input over shown items
+/// is delivered to the content with `in_slot` set to false.
+///
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+pub struct Overlay {
+    compositor: Compositor,
+    container: ContainerVisual,
+    content: Option<Arc<dyn Panel>>,
+    core: RwLock<Core>,
+    capture_route: CaptureRoute,
+    panel_events: EventStreams<PanelEvent>,
+    id: Arc<()>,
+}
+
+#[derive(TypedBuilder)]
+pub struct OverlayParams {
+    compositor: Compositor,
+    #[builder(default, setter(transform = |panel: impl Panel + 'static| Some(Arc::new(panel) as Arc<dyn Panel>)))]
+    content: Option<Arc<dyn Panel>>,
+}
+
+impl TryFrom<OverlayParams> for Overlay {
+    type Error = crate::Error;
+
+    fn try_from(value: OverlayParams) -> crate::Result<Self> {
+        let container = value.compositor.CreateContainerVisual()?;
+        if let Some(content) = &value.content {
+            attach(&container, &**content)?;
+        }
+        Ok(Overlay {
+            compositor: value.compositor,
+            container,
+            content: value.content,
+            core: RwLock::new(Core {
+                items: Vec::new(),
+                mouse_pos: None,
+            }),
+            capture_route: CaptureRoute::default(),
+            panel_events: EventStreams::new(),
+            id: Arc::new(()),
+        })
+    }
+}
+
+impl TryFrom<OverlayParams> for Arc<Overlay> {
+    type Error = crate::Error;
+
+    fn try_from(value: OverlayParams) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
+
+impl Overlay {
+    pub fn size(&self) -> crate::Result<Vector2> {
+        Ok(self.container.Size()?)
+    }
+
+    ///
+    /// Show the panel at given position in overlay coordinates. If the panel is already shown
+    /// it's moved and resized.
+    ///
+    pub async fn show(
+        &self,
+        panel: Arc<dyn Panel>,
+        offset: Vector2,
+        size: Vector2,
+    ) -> crate::Result<()> {
+        let item = {
+            let mut core = self.core.write().await;
+            if let Some(item) = core.items.iter().find(|v| v.panel.id() == panel.id()) {
+                item.clone()
+            } else {
+                let container = self.compositor.CreateContainerVisual()?;
+                attach(&container, &*panel)?;
+                self.container.Children()?.InsertAtTop(&container)?;
+                let item = Item { panel, container };
+                core.items.push(item.clone());
+                item
+            }
+        };
+        item.container.SetOffset(Vector3 {
+            X: offset.X,
+            Y: offset.Y,
+            Z: 0.,
+        })?;
+        item.container.SetSize(size)?;
+        item.panel
+            .on_event_owned(PanelEvent::Resized(size), None)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn hide(&self, panel_id: usize) -> crate::Result<()> {
+        let mut core = self.core.write().await;
+        if let Some(index) = core.items.iter().position(|v| v.panel.id() == panel_id) {
+            let item = core.items.remove(index);
+            self.container.Children()?.Remove(&item.container)?;
+        }
+        Ok(())
+    }
+
+    pub async fn is_shown(&self, panel_id: usize) -> bool {
+        self.core
+            .read()
+            .await
+            .items
+            .iter()
+            .any(|v| v.panel.id() == panel_id)
+    }
+
+    async fn items(&self) -> Vec<Item> {
+        self.core.read().await.items.clone()
+    }
+
+    async fn translate_cursor_moved(
+        &self,
+        mouse_pos: Vector2,
+        seq: u64,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        self.core.write().await.mouse_pos = Some(mouse_pos);
+        if let Some(content) = &self.content {
+            if self.capture_route.accepts(self, content.id()) {
+                let had_captures = window_mouse_captures(self);
+                content
+                    .on_event_owned(PanelEvent::CursorMoved(mouse_pos, seq), source.clone())
+                    .await?;
+                self.capture_route.update(self, &had_captures, content.id());
+            }
+        }
+        for item in self.items().await {
+            if !self.capture_route.accepts(self, item.panel.id()) {
+                continue;
+            }
+            let mouse_pos = item.translate_point(mouse_pos)?;
+            let had_captures = window_mouse_captures(self);
+            item.panel
+                .on_event_owned(PanelEvent::CursorMoved(mouse_pos, seq), source.clone())
+                .await?;
+            self.capture_route
+                .update(self, &had_captures, item.panel.id());
+        }
+        Ok(())
+    }
+
+    // Mouse button events go to the items under cursor and to the content if it's not covered.
+    // While some panel holds the mouse capture, they go only to the item containing it.
+    async fn translate_mouse_input(
+        &self,
+        event: &PanelEvent,
+        in_slot: bool,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        if let Some(mouse_pos) = self.core.read().await.mouse_pos {
+            let mut covered = false;
+            for item in self.items().await {
+                let mouse_pos = item.translate_point(mouse_pos)?;
+                let in_item = item.is_translated_point_in_item(mouse_pos)?;
+                covered |= in_item;
+                if !self.capture_route.accepts(self, item.panel.id()) {
+                    continue;
+                }
+                let had_captures = window_mouse_captures(self);
+                item.panel
+                    .on_event_owned(event.with_in_slot(in_slot && in_item), source.clone())
+                    .await?;
+                self.capture_route
+                    .update(self, &had_captures, item.panel.id());
+            }
+            if let Some(content) = &self.content {
+                if self.capture_route.accepts(self, content.id()) {
+                    let had_captures = window_mouse_captures(self);
+                    content
+                        .on_event_owned(event.with_in_slot(in_slot && !covered), source.clone())
+                        .await?;
+                    self.capture_route.update(self, &had_captures, content.id());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Panel for Overlay {
+    fn outer_frame(&self) -> Visual {
+        self.container.clone().into()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+}
+
+impl EventSource<PanelEvent> for Overlay {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for Overlay {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        match event.as_ref() {
+            // Items keep their own size, only the overlay area and the content follow the parent
+            PanelEvent::Resized(size) => {
+                self.container.SetSize(*size)?;
+                if let Some(content) = &self.content {
+                    content.on_event_ref(event.as_ref(), source.clone()).await?;
+                }
+            }
+            PanelEvent::CursorMoved(mouse_pos, seq) => {
+                self.translate_cursor_moved(*mouse_pos, *seq, source.clone())
+                    .await?
+            }
+            PanelEvent::MouseInput { in_slot, .. }
+            | PanelEvent::MouseDoubleClick { in_slot, .. }
+            | PanelEvent::DragDrop { in_slot, .. } => {
+                self.translate_mouse_input(event.as_ref(), *in_slot, source.clone())
+                    .await?
+            }
+            _ => {
+                if let Some(content) = &self.content {
+                    content.on_event_ref(event.as_ref(), source.clone()).await?;
+                }
+                for item in self.items().await {
+                    item.panel
+                        .on_event_ref(event.as_ref(), source.clone())
+                        .await?;
+                }
+            }
+        }
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
@@ -0,0 +1,231 @@
+use std::borrow::Cow;
+
+use async_event_streams::{
+    EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
+};
+use async_event_streams_derive::EventSink;
+use async_std::sync::{Arc, RwLock};
+use async_trait::async_trait;
+use typed_builder::TypedBuilder;
+use windows::{
+    Foundation::Numerics::{Vector2, Vector3},
+    UI::Composition::{Compositor, ContainerVisual, Visual},
+};
+
+use super::{attach, is_translated_point_in_box, Panel, PanelEvent};
+
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct Margins {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl Margins {
+    pub fn new(left: f32, top: f32, right: f32, bottom: f32) -> Self {
+        Self {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+    pub fn uniform(value: f32) -> Self {
+        Self::new(value, value, value, value)
+    }
+    pub fn symmetric(horizontal: f32, vertical: f32) -> Self {
+        Self::new(horizontal, vertical, horizontal, vertical)
+    }
+    pub fn offset(&self) -> Vector2 {
+        Vector2 {
+            X: self.left,
+            Y: self.top,
+        }
+    }
+    ///
+    /// Size of the area inside the margins, never negative
+    ///
+    pub fn inner_size(&self, size: Vector2) -> Vector2 {
+        Vector2 {
+            X: (size.X - self.left - self.right).max(0.),
+            Y: (size.Y - self.top - self.bottom).max(0.),
+        }
+    }
+}
+
+struct Core {
+    margins: Margins,
+    size: Vector2,
+    mouse_pos: Option<Vector2>,
+}
+
+///
+/// Decorator which insets the wrapped panel by the margins
+///
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+pub struct Padded {
+    container: ContainerVisual,
+    panel: Arc<dyn Panel>,
+    core: RwLock<Core>,
+    panel_events: EventStreams<PanelEvent>,
+    id: Arc<()>,
+}
+
+#[derive(TypedBuilder)]
+pub struct PaddedParams {
+    compositor: Compositor,
+    #[builder(setter(transform = |panel: impl Panel + 'static| Arc::new(panel) as Arc<dyn Panel>))]
+    panel: Arc<dyn Panel>,
+    #[builder(default)]
+    margins: Margins,
+}
+
+impl TryFrom<PaddedParams> for Padded {
+    type Error = crate::Error;
+
+    fn try_from(value: PaddedParams) -> crate::Result<Self> {
+        let container = value.compositor.CreateContainerVisual()?;
+        attach(&container, &*value.panel)?;
+        Ok(Padded {
+            container,
+            panel: value.panel,
+            core: RwLock::new(Core {
+                margins: value.margins,
+                size: Vector2::default(),
+                mouse_pos: None,
+            }),
+            panel_events: EventStreams::new(),
+            id: Arc::new(()),
+        })
+    }
+}
+
+impl TryFrom<PaddedParams> for Arc<Padded> {
+    type Error = crate::Error;
+
+    fn try_from(value: PaddedParams) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
+
+impl Padded {
+    pub async fn margins(&self) -> Margins {
+        self.core.read().await.margins
+    }
+
+    pub async fn set_margins(&self, margins: Margins) -> crate::Result<()> {
+        let size = {
+            let mut core = self.core.write().await;
+            core.margins = margins;
+            core.size
+        };
+        self.resize(size, None).await
+    }
+
+    async fn resize(&self, size: Vector2, source: Option<Arc<EventBox>>) -> crate::Result<()> {
+        let margins = {
+            let mut core = self.core.write().await;
+            core.size = size;
+            core.margins
+        };
+        self.container.SetSize(size)?;
+        let frame = self.panel.outer_frame();
+        let offset = margins.offset();
+        frame.SetOffset(Vector3 {
+            X: offset.X,
+            Y: offset.Y,
+            Z: 0.,
+        })?;
+        let inner_size = margins.inner_size(size);
+        frame.SetSize(inner_size)?;
+        self.panel
+            .on_event_owned(PanelEvent::Resized(inner_size), source)
+            .await
+    }
+
+    async fn translate_cursor_moved(
+        &self,
+        mouse_pos: Vector2,
+        seq: u64,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        let offset = {
+            let mut core = self.core.write().await;
+            core.mouse_pos = Some(mouse_pos);
+            core.margins.offset()
+        };
+        let mouse_pos = Vector2 {
+            X: mouse_pos.X - offset.X,
+            Y: mouse_pos.Y - offset.Y,
+        };
+        self.panel
+            .on_event_owned(PanelEvent::CursorMoved(mouse_pos, seq), source)
+            .await
+    }
+
+    async fn is_in_child(&self) -> bool {
+        let core = self.core.read().await;
+        core.mouse_pos.map_or(false, |mouse_pos| {
+            let offset = core.margins.offset();
+            is_translated_point_in_box(
+                Vector2 {
+                    X: mouse_pos.X - offset.X,
+                    Y: mouse_pos.Y - offset.Y,
+                },
+                core.margins.inner_size(core.size),
+            )
+        })
+    }
+}
+
+impl Panel for Padded {
+    fn outer_frame(&self) -> Visual {
+        self.container.clone().into()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+}
+
+impl EventSource<PanelEvent> for Padded {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for Padded {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        match event.as_ref() {
+            PanelEvent::Resized(size) => self.resize(*size, source.clone()).await?,
+            PanelEvent::CursorMoved(mouse_pos, seq) => {
+                self.translate_cursor_moved(*mouse_pos, *seq, source.clone())
+                    .await?
+            }
+            PanelEvent::MouseInput { in_slot, .. }
+            | PanelEvent::MouseDoubleClick { in_slot, .. }
+            | PanelEvent::DragDrop { in_slot, .. } => {
+                let in_slot = *in_slot && self.is_in_child().await;
+                self.panel
+                    .on_event_owned(event.with_in_slot(in_slot), source.clone())
+                    .await?
+            }
+            _ => {
+                self.panel
+                    .on_event_ref(event.as_ref(), source.clone())
+                    .await?
+            }
+        }
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
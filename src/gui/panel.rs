@@ -1,45 +1,299 @@
-use std::sync::Arc;
+use std::{
+    collections::VecDeque,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
 
 use async_event_streams::{EventSink, EventSource};
 use futures::{
     channel::mpsc::{channel, Sender},
+    future::{self, Either},
+    stream,
     task::{Spawn, SpawnExt},
     StreamExt,
 };
 use windows::{
-    Foundation::Numerics::Vector2,
+    Foundation::{Numerics::Vector2, Rect},
+    Win32::{
+        Foundation::{LPARAM, WPARAM},
+        System::Threading::GetCurrentThreadId,
+        UI::{
+            Input::KeyboardAndMouse::GetDoubleClickTime,
+            WindowsAndMessaging::{
+                GetSystemMetrics, PostThreadMessageW, SM_CXDOUBLECLK, SM_CYDOUBLECLK, WM_NULL,
+            },
+        },
+    },
     UI::Composition::{ContainerVisual, Visual},
 };
-use winit::event::{ElementState, MouseButton, WindowEvent};
+use winit::event::{ElementState, KeyboardInput, MouseButton, MouseScrollDelta, WindowEvent};
 
-use crate::error::handle_err;
+use crate::{
+    debug::timed,
+    error::on_err,
+    window::{
+        drop_events,
+        native::{run_message_loop_until, Window, WindowState},
+        window_state_events, DropData,
+    },
+};
 
-use super::IntoVector2;
+use super::{
+    cancellation::{remove_resize_cancellation, renew_resize_cancellation},
+    is_translated_point_in_box,
+    mouse_capture::release_window_mouse_capture,
+    theme::{on_system_color_mode_changed, theme_events, ColorMode, Theme},
+    visual_offset, InputState, IntoVector2,
+};
 
+///
+/// Events delivered to the panel tree. Input events originating from the window carry
+/// the sequence number `seq`, growing in the order the window received them, so the widgets
+/// can discard stale events arriving through the different streams out of order
+/// (see `EventOrder`).
+///
 #[derive(Clone, Debug)]
 pub enum PanelEvent {
     Resized(Vector2),
-    CursorMoved(Vector2),
+    CursorMoved(Vector2, u64),
     MouseInput {
         in_slot: bool,
         state: ElementState,
         button: MouseButton,
+        seq: u64,
+    },
+    ///
+    /// Sent after the `MouseInput` press event which completes the series of clicks
+    /// made within the system double-click time and distance. `count` is 2 for double click,
+    /// 3 for triple click and so on.
+    ///
+    MouseDoubleClick {
+        in_slot: bool,
+        button: MouseButton,
+        count: u32,
+        seq: u64,
     },
+    ///
+    /// Mouse wheel rotation in lines: positive `Y` scrolls up, positive `X` scrolls left.
+    /// The panel which scrolls marks the event with `set_handled`, so the enclosing
+    /// scrolling panels leave it alone.
+    ///
+    MouseWheel {
+        delta: Vector2,
+        seq: u64,
+        handled: EventHandled,
+    },
+    ///
+    /// Physical key press or release, for shortcuts and navigation
+    ///
+    KeyboardInput(KeyboardInput, u64),
+    ///
+    /// Character composed from the key presses by the keyboard layout, including dead keys
+    /// and input methods, for text entry
+    ///
+    ReceivedCharacter(char, u64),
+    ///
+    /// Files or text dropped from the other application. Sent after `CursorMoved`
+    /// to the drop point, so the containers route it like the mouse button event:
+    /// `in_slot` is set for the panel under the cursor.
+    ///
+    DragDrop {
+        in_slot: bool,
+        data: DropData,
+        seq: u64,
+    },
+    ///
+    /// Window is minimized (`true`) or restored (`false`). The minimized window keeps
+    /// the layout and doesn't need redrawing, e.g. animations can be paused.
+    ///
+    Occluded(bool),
+    ///
+    /// Window is minimized, maximized or restored, e.g. by the system title bar
+    /// or by `WindowStateControl`. Sent before the `Resized` with the new size.
+    ///
+    WindowStateChanged(WindowState),
+    ///
+    /// New global theme is installed, widgets using it should restyle themselves
+    ///
+    ThemeChanged(Arc<Theme>),
     Empty,
 }
 
+impl PanelEvent {
+    ///
+    /// Mouse button event with the `in_slot` flag replaced, other events are returned unchanged
+    ///
+    pub fn with_in_slot(&self, in_slot: bool) -> PanelEvent {
+        match self {
+            PanelEvent::MouseInput {
+                state, button, seq, ..
+            } => PanelEvent::MouseInput {
+                in_slot,
+                state: *state,
+                button: *button,
+                seq: *seq,
+            },
+            PanelEvent::MouseDoubleClick {
+                button, count, seq, ..
+            } => PanelEvent::MouseDoubleClick {
+                in_slot,
+                button: *button,
+                count: *count,
+                seq: *seq,
+            },
+            PanelEvent::DragDrop { data, seq, .. } => PanelEvent::DragDrop {
+                in_slot,
+                data: data.clone(),
+                seq: *seq,
+            },
+            event => event.clone(),
+        }
+    }
+    ///
+    /// Paths of the files dropped onto the panel under the cursor. The drop position
+    /// is the one of the preceding `CursorMoved`.
+    ///
+    pub fn dropped_files(&self) -> Option<&[PathBuf]> {
+        match self {
+            PanelEvent::DragDrop {
+                in_slot: true,
+                data,
+                ..
+            } => data.files(),
+            _ => None,
+        }
+    }
+    ///
+    /// Sequence number of the window input event, `None` for other events
+    ///
+    pub fn seq(&self) -> Option<u64> {
+        match self {
+            PanelEvent::CursorMoved(_, seq)
+            | PanelEvent::MouseInput { seq, .. }
+            | PanelEvent::MouseDoubleClick { seq, .. }
+            | PanelEvent::MouseWheel { seq, .. }
+            | PanelEvent::DragDrop { seq, .. }
+            | PanelEvent::KeyboardInput(_, seq)
+            | PanelEvent::ReceivedCharacter(_, seq) => Some(*seq),
+            _ => None,
+        }
+    }
+    ///
+    /// The `in_slot` flag of the mouse button event, true for other events
+    ///
+    pub fn in_slot(&self) -> bool {
+        match self {
+            PanelEvent::MouseInput { in_slot, .. }
+            | PanelEvent::MouseDoubleClick { in_slot, .. }
+            | PanelEvent::DragDrop { in_slot, .. } => *in_slot,
+            _ => true,
+        }
+    }
+    ///
+    /// Mark the input event as consumed, e.g. by the scrolling panel, so its ancestors
+    /// receiving the same event afterwards leave it alone. Only the events carrying
+    /// the `EventHandled` flag can be marked.
+    ///
+    pub fn set_handled(&self) {
+        if let PanelEvent::MouseWheel { handled, .. } = self {
+            handled.set()
+        }
+    }
+    pub fn is_handled(&self) -> bool {
+        match self {
+            PanelEvent::MouseWheel { handled, .. } => handled.get(),
+            _ => false,
+        }
+    }
+}
+
+///
+/// Flag of the event consumed by one of the panels. It's shared by the copies
+/// of the event passed down the panel tree.
+///
+#[derive(Clone, Default, Debug)]
+pub struct EventHandled(Arc<AtomicBool>);
+
+impl EventHandled {
+    pub fn get(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+    pub fn set(&self) {
+        self.0.store(true, Ordering::Release)
+    }
+}
+
+// Conversion of the touchpad scroll distance to the wheel lines
+const PIXELS_PER_LINE: f32 = 48.;
+
+static EVENT_SEQ: AtomicU64 = AtomicU64::new(1);
+
+pub(crate) fn next_event_seq() -> u64 {
+    EVENT_SEQ.fetch_add(1, Ordering::Relaxed)
+}
+
+///
+/// Tracks the last seen input event sequence number to detect the stale events
+///
+#[derive(Clone, Copy, Default, Debug)]
+pub struct EventOrder {
+    last_seq: u64,
+}
+
+impl EventOrder {
+    ///
+    /// Returns false for the input event older than the one already accepted.
+    /// Events derived from the same input (like `MouseDoubleClick` after its `MouseInput`)
+    /// share the sequence number and are accepted.
+    ///
+    pub fn accept(&mut self, event: &PanelEvent) -> bool {
+        match event.seq() {
+            Some(seq) if seq < self.last_seq => false,
+            Some(seq) => {
+                self.last_seq = seq;
+                true
+            }
+            None => true,
+        }
+    }
+}
+
 impl From<WindowEvent<'static>> for PanelEvent {
     fn from(source: WindowEvent<'static>) -> Self {
         match source {
             WindowEvent::Resized(size) => PanelEvent::Resized(size.into_vector2()),
             WindowEvent::CursorMoved { position, .. } => {
-                PanelEvent::CursorMoved(position.into_vector2())
+                PanelEvent::CursorMoved(position.into_vector2(), next_event_seq())
             }
             WindowEvent::MouseInput { state, button, .. } => PanelEvent::MouseInput {
                 in_slot: true,
                 state: state,
                 button: button,
+                seq: next_event_seq(),
+            },
+            WindowEvent::MouseWheel { delta, .. } => PanelEvent::MouseWheel {
+                delta: match delta {
+                    MouseScrollDelta::LineDelta(x, y) => Vector2 { X: x, Y: y },
+                    MouseScrollDelta::PixelDelta(position) => {
+                        let position = position.into_vector2();
+                        Vector2 {
+                            X: position.X / PIXELS_PER_LINE,
+                            Y: position.Y / PIXELS_PER_LINE,
+                        }
+                    }
+                },
+                seq: next_event_seq(),
+                handled: EventHandled::default(),
             },
+            WindowEvent::KeyboardInput { input, .. } => {
+                PanelEvent::KeyboardInput(input, next_event_seq())
+            }
+            WindowEvent::ReceivedCharacter(c) => PanelEvent::ReceivedCharacter(c, next_event_seq()),
+            WindowEvent::Occluded(occluded) => PanelEvent::Occluded(occluded),
             _ => PanelEvent::Empty,
         }
     }
@@ -56,6 +310,17 @@ pub trait Panel:
     ///
     fn outer_frame(&self) -> Visual;
     fn id(&self) -> usize;
+    ///
+    /// Whether the point in coordinates of the outer frame hits the panel's content.
+    /// The parents use it to decide which panel gets the mouse input, so the panels
+    /// of irregular shape can override it. By default any point inside the outer frame hits.
+    ///
+    fn hit_test(&self, point: Vector2) -> bool {
+        self.outer_frame()
+            .Size()
+            .map(|size| is_translated_point_in_box(point, size))
+            .unwrap_or(false)
+    }
 }
 
 impl<T: Panel> Panel for Arc<T> {
@@ -65,13 +330,34 @@ impl<T: Panel> Panel for Arc<T> {
     fn id(&self) -> usize {
         (**self).id()
     }
+    fn hit_test(&self, point: Vector2) -> bool {
+        (**self).hit_test(point)
+    }
 }
 
 pub fn attach<T: Panel + ?Sized>(container: &ContainerVisual, panel: &T) -> crate::Result<()> {
     container.Children()?.InsertAtTop(&panel.outer_frame())?;
     Ok(())
 }
-pub fn detach(panel: &impl Panel) -> crate::Result<()> {
+///
+/// Bounds of the panel in screen coordinates, e.g. to position popup windows near it.
+/// The window must host the visual tree containing the panel. Composition coordinates of
+/// the root visual match the client area pixels, so the result follows DPI scaling and
+/// the current window position.
+///
+pub fn screen_bounds<T: Panel + ?Sized>(panel: &T, window: &Window) -> crate::Result<Rect> {
+    let frame = panel.outer_frame();
+    let origin = window.client_to_screen(visual_offset(&frame)?)?;
+    let size = frame.Size()?;
+    Ok(Rect {
+        X: origin.X,
+        Y: origin.Y,
+        Width: size.X,
+        Height: size.Y,
+    })
+}
+
+pub fn detach<T: Panel + ?Sized>(panel: &T) -> crate::Result<()> {
     // TODO: implement owner notification that panel is detached
     let visual = panel.outer_frame();
     if let Ok(parent) = visual.Parent() {
@@ -80,25 +366,279 @@ pub fn detach(panel: &impl Panel) -> crate::Result<()> {
     Ok(())
 }
 
+// Counts the successive clicks of the same button close in time and position
+#[derive(Default)]
+struct ClickCounter {
+    mouse_pos: Vector2,
+    last_click: Option<(Instant, Vector2, MouseButton)>,
+    count: u32,
+}
+
+impl ClickCounter {
+    // Returns the number of clicks in the series ended by this event
+    fn update(&mut self, event: &PanelEvent) -> u32 {
+        match event {
+            PanelEvent::CursorMoved(mouse_pos, _) => self.mouse_pos = *mouse_pos,
+            PanelEvent::MouseInput {
+                state: ElementState::Pressed,
+                button,
+                ..
+            } => {
+                let now = Instant::now();
+                let time = Duration::from_millis(unsafe { GetDoubleClickTime() } as u64);
+                // Size of the rectangle centered on the first click
+                let (width, height) = unsafe {
+                    (
+                        GetSystemMetrics(SM_CXDOUBLECLK),
+                        GetSystemMetrics(SM_CYDOUBLECLK),
+                    )
+                };
+                let repeated = match self.last_click {
+                    Some((last_time, last_pos, last_button)) => {
+                        last_button == *button
+                            && now - last_time <= time
+                            && (self.mouse_pos.X - last_pos.X).abs() <= width as f32 / 2.
+                            && (self.mouse_pos.Y - last_pos.Y).abs() <= height as f32 / 2.
+                    }
+                    None => false,
+                };
+                self.count = if repeated { self.count + 1 } else { 1 };
+                self.last_click = Some((now, self.mouse_pos, *button));
+                return self.count;
+            }
+            _ => (),
+        }
+        0
+    }
+}
+
+static WINDOW_EVENT_BUFFER_SIZE: AtomicUsize = AtomicUsize::new(1024 * 64);
+const WINDOW_EVENTS: &str = "window events";
+
+// Events merged by the window event receiver
+enum Received {
+    Window(WindowEvent<'static>),
+    Theme(Arc<Theme>),
+    Drop(Vector2, DropData),
+    WindowState(WindowState),
+}
+
+///
+/// Capacity of the window event channel for the receivers spawned after the call.
+/// Events are dropped and reported as `StreamDiagnostic::Overflow` when it's full.
+///
+pub fn set_window_event_buffer_size(size: usize) {
+    WINDOW_EVENT_BUFFER_SIZE.store(size, Ordering::Relaxed);
+}
+
 pub fn spawn_window_event_receiver(
     pool: impl Spawn,
     panel: impl Panel + 'static,
     container: ContainerVisual,
 ) -> crate::Result<Sender<WindowEvent<'static>>> {
-    let (tx_event_channel, mut rx_event_channel) = channel::<WindowEvent<'static>>(1024 * 64);
-    let panel = panel;
+    spawn_window_event_receiver_with_input_state(
+        pool,
+        panel,
+        container,
+        Arc::new(InputState::default()),
+    )
+}
+
+///
+/// Same as `spawn_window_event_receiver`, but also keeps the polled input state up to date.
+///
+/// The panel is laid out to the initial size of the container before the call returns,
+/// i.e. before the window is shown, so the first presented frame doesn't flash unlaid-out
+/// content. The layout runs on the pool while the calling thread keeps dispatching its
+/// messages, so the panels may wait for the window thread meanwhile.
+///
+pub fn spawn_window_event_receiver_with_input_state(
+    pool: impl Spawn,
+    panel: impl Panel + 'static,
+    container: ContainerVisual,
+    input_state: Arc<InputState>,
+) -> crate::Result<Sender<WindowEvent<'static>>> {
+    let (tx_event_channel, rx_event_channel) =
+        channel::<WindowEvent<'static>>(WINDOW_EVENT_BUFFER_SIZE.load(Ordering::Relaxed));
+    let panel = Arc::new(panel);
     attach(&container, &panel)?;
-    pool.spawn(handle_err(async move {
-        while let Some(event) = rx_event_channel.next().await {
-            let panel_event = event.into();
-            match &panel_event {
-                // TODO: handle quit here
-                PanelEvent::Resized(size) => container.SetSize(*size)?,
-                _ => (),
+    let size = container.Size()?;
+    if size.X > 0. && size.Y > 0. {
+        lay_out(&pool, panel.clone(), size)?;
+    }
+    // Theme changes, drops and window state changes are delivered until the window
+    // event channel is closed
+    let window_events = rx_event_channel
+        .map(|event| Some(Received::Window(event)))
+        .chain(stream::once(future::ready(None)));
+    let theme_events = theme_events().map(|theme| Some(Received::Theme(theme)));
+    let root_visual = container.clone();
+    let drop_events = drop_events()
+        .filter(move |drop| future::ready(drop.root_visual == root_visual))
+        .map(|drop| Some(Received::Drop(drop.position, drop.data)));
+    let root_visual = container.clone();
+    let state_events = window_state_events()
+        .filter(move |change| future::ready(change.root_visual == root_visual))
+        .map(|change| Some(Received::WindowState(change.state)));
+    let mut events = stream::select(
+        window_events,
+        stream::select(theme_events, stream::select(drop_events, state_events)),
+    );
+    pool.spawn(async move {
+        let mut click_counter = ClickCounter::default();
+        let root: Visual = container.clone().into();
+        // Events received while the `Resized` pass is running, handled after it
+        let mut pending = VecDeque::new();
+        let mut closed = false;
+        loop {
+            let event = match pending.pop_front() {
+                Some(event) => event,
+                None if closed => break,
+                None => match events.next().await {
+                    Some(Some(event)) => event,
+                    _ => break,
+                },
+            };
+            let token = is_resized(&event).then(|| renew_resize_cancellation(&root));
+            let receiving =
+                receive_event(event, &panel, &container, &input_state, &mut click_counter);
+            let result = match token {
+                None => receiving.await,
+                // The newer size makes the running pass stale: it's cancelled, so the panels
+                // checking `resize_cancellation_token` stop it and the newer size is laid out
+                Some(token) => {
+                    let mut receiving = Box::pin(receiving);
+                    loop {
+                        if closed {
+                            break receiving.await;
+                        }
+                        match future::select(receiving, events.next()).await {
+                            Either::Left((result, _)) => break result,
+                            Either::Right((next, r)) => {
+                                receiving = r;
+                                match next {
+                                    Some(Some(next)) => {
+                                        if is_resized(&next) {
+                                            token.cancel();
+                                        }
+                                        pending.push_back(next);
+                                    }
+                                    _ => closed = true,
+                                }
+                            }
+                        }
+                    }
+                }
             };
-            panel.on_event_owned(panel_event, None).await?;
+            // The failed event is reported and the window keeps receiving the input,
+            // e.g. to close the error dialog
+            result.unwrap_or_else(on_err);
         }
-        Ok(())
-    }))?;
+        remove_resize_cancellation(&root);
+    })?;
     Ok(tx_event_channel)
 }
+
+fn is_resized(event: &Received) -> bool {
+    matches!(event, Received::Window(WindowEvent::Resized(_)))
+}
+
+// Send the initial size to the panel on the pool and wait for it on the calling thread
+fn lay_out(
+    pool: &impl Spawn,
+    panel: Arc<impl Panel + 'static>,
+    size: Vector2,
+) -> crate::Result<()> {
+    let result = Arc::new(Mutex::new(None));
+    let thread_id = unsafe { GetCurrentThreadId() };
+    {
+        let result = result.clone();
+        pool.spawn(async move {
+            let laid_out = panel.on_event_owned(PanelEvent::Resized(size), None).await;
+            *result.lock().unwrap() = Some(laid_out);
+            // Wake the message loop below
+            unsafe { PostThreadMessageW(thread_id, WM_NULL, WPARAM::default(), LPARAM::default()) };
+        })?;
+    }
+    run_message_loop_until(|| result.lock().unwrap().is_some());
+    result.lock().unwrap().take().unwrap_or(Ok(()))
+}
+
+async fn receive_event(
+    event: Received,
+    panel: &impl Panel,
+    container: &ContainerVisual,
+    input_state: &InputState,
+    click_counter: &mut ClickCounter,
+) -> crate::Result<()> {
+    let event = match event {
+        Received::Window(event) => event,
+        Received::Theme(theme) => {
+            panel
+                .on_event_owned(PanelEvent::ThemeChanged(theme), None)
+                .await?;
+            return Ok(());
+        }
+        Received::Drop(position, data) => {
+            let seq = next_event_seq();
+            panel
+                .on_event_owned(PanelEvent::CursorMoved(position, seq), None)
+                .await?;
+            let drop = PanelEvent::DragDrop {
+                in_slot: true,
+                data,
+                seq,
+            };
+            timed(WINDOW_EVENTS, panel.on_event_owned(drop, None)).await?;
+            return Ok(());
+        }
+        Received::WindowState(state) => {
+            panel
+                .on_event_owned(PanelEvent::WindowStateChanged(state), None)
+                .await?;
+            return Ok(());
+        }
+    };
+    if let WindowEvent::ThemeChanged(theme) = &event {
+        on_system_color_mode_changed(match *theme {
+            winit::window::Theme::Light => ColorMode::Light,
+            winit::window::Theme::Dark => ColorMode::Dark,
+        });
+    }
+    input_state.update(&event).await;
+    let panel_event = event.into();
+    match &panel_event {
+        // TODO: handle quit here
+        PanelEvent::Resized(size) => container.SetSize(*size)?,
+        _ => (),
+    };
+    let count = click_counter.update(&panel_event);
+    let double_click = match &panel_event {
+        PanelEvent::MouseInput { button, seq, .. } if count > 1 => {
+            Some(PanelEvent::MouseDoubleClick {
+                in_slot: true,
+                button: *button,
+                count,
+                seq: *seq,
+            })
+        }
+        _ => None,
+    };
+    let released = match panel_event {
+        PanelEvent::MouseInput {
+            state: ElementState::Released,
+            button,
+            ..
+        } => Some(button),
+        _ => None,
+    };
+    timed(WINDOW_EVENTS, panel.on_event_owned(panel_event, None)).await?;
+    if let Some(double_click) = double_click {
+        timed(WINDOW_EVENTS, panel.on_event_owned(double_click, None)).await?;
+    }
+    // The capturing panel has received the release, the next press starts anew
+    if let Some(button) = released {
+        release_window_mouse_capture(&container.clone().into(), button);
+    }
+    Ok(())
+}
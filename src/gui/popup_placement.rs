@@ -0,0 +1,207 @@
+use windows::Foundation::{Numerics::Vector2, Rect};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PopupDirection {
+    Below,
+    Above,
+    Right,
+    Left,
+}
+
+impl PopupDirection {
+    pub fn opposite(&self) -> Self {
+        match self {
+            PopupDirection::Below => PopupDirection::Above,
+            PopupDirection::Above => PopupDirection::Below,
+            PopupDirection::Right => PopupDirection::Left,
+            PopupDirection::Left => PopupDirection::Right,
+        }
+    }
+    fn is_vertical(&self) -> bool {
+        matches!(self, PopupDirection::Below | PopupDirection::Above)
+    }
+}
+
+///
+/// Computes the popup position near the anchor rectangle so that the popup stays inside
+/// the available area. Popup is placed in the preferred direction and aligned to the start
+/// of the anchor; if it doesn't fit it's flipped to the opposite side and then shifted
+/// to the area bounds.
+///
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct PopupPlacement {
+    pub direction: PopupDirection,
+    /// Distance between the anchor and the popup
+    pub gap: f32,
+    pub flip: bool,
+    pub shift: bool,
+}
+
+impl Default for PopupPlacement {
+    fn default() -> Self {
+        Self {
+            direction: PopupDirection::Below,
+            gap: 0.,
+            flip: true,
+            shift: true,
+        }
+    }
+}
+
+impl PopupPlacement {
+    pub fn new(direction: PopupDirection, gap: f32) -> Self {
+        Self {
+            direction,
+            gap,
+            ..Default::default()
+        }
+    }
+
+    fn position_in_direction(
+        &self,
+        direction: PopupDirection,
+        anchor: Rect,
+        popup: Vector2,
+    ) -> Vector2 {
+        match direction {
+            PopupDirection::Below => Vector2 {
+                X: anchor.X,
+                Y: anchor.Y + anchor.Height + self.gap,
+            },
+            PopupDirection::Above => Vector2 {
+                X: anchor.X,
+                Y: anchor.Y - self.gap - popup.Y,
+            },
+            PopupDirection::Right => Vector2 {
+                X: anchor.X + anchor.Width + self.gap,
+                Y: anchor.Y,
+            },
+            PopupDirection::Left => Vector2 {
+                X: anchor.X - self.gap - popup.X,
+                Y: anchor.Y,
+            },
+        }
+    }
+
+    // Length of the popup part which is out of the area along the direction axis
+    fn overflow(direction: PopupDirection, position: Vector2, popup: Vector2, area: Rect) -> f32 {
+        let (start, length, area_start, area_length) = if direction.is_vertical() {
+            (position.Y, popup.Y, area.Y, area.Height)
+        } else {
+            (position.X, popup.X, area.X, area.Width)
+        };
+        (area_start - start).max(0.) + (start + length - area_start - area_length).max(0.)
+    }
+
+    ///
+    /// Position of the popup top-left corner. All rectangles are in the same coordinates.
+    ///
+    pub fn place(&self, anchor: Rect, popup: Vector2, area: Rect) -> Vector2 {
+        let direction = self.direction;
+        let mut position = self.position_in_direction(direction, anchor, popup);
+        let overflow = Self::overflow(direction, position, popup, area);
+        if self.flip && overflow > 0. {
+            let opposite = direction.opposite();
+            let flipped = self.position_in_direction(opposite, anchor, popup);
+            if Self::overflow(opposite, flipped, popup, area) < overflow {
+                position = flipped;
+            }
+        }
+        if self.shift {
+            position.X = position.X.min(area.X + area.Width - popup.X).max(area.X);
+            position.Y = position.Y.min(area.Y + area.Height - popup.Y).max(area.Y);
+        }
+        position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> Rect {
+        Rect {
+            X: x,
+            Y: y,
+            Width: width,
+            Height: height,
+        }
+    }
+
+    const AREA: Rect = Rect {
+        X: 0.,
+        Y: 0.,
+        Width: 400.,
+        Height: 300.,
+    };
+    const POPUP: Vector2 = Vector2 { X: 100., Y: 50. };
+
+    #[test]
+    fn placed_in_preferred_direction() {
+        let anchor = rect(150., 100., 80., 20.);
+        let place = |direction| PopupPlacement::new(direction, 4.).place(anchor, POPUP, AREA);
+        assert_eq!(place(PopupDirection::Below), Vector2 { X: 150., Y: 124. });
+        assert_eq!(place(PopupDirection::Above), Vector2 { X: 150., Y: 46. });
+        assert_eq!(place(PopupDirection::Right), Vector2 { X: 234., Y: 100. });
+        assert_eq!(place(PopupDirection::Left), Vector2 { X: 46., Y: 100. });
+    }
+
+    #[test]
+    fn flipped_when_not_fitting() {
+        let anchor = rect(50., 260., 80., 20.);
+        let position = PopupPlacement::new(PopupDirection::Below, 4.).place(anchor, POPUP, AREA);
+        assert_eq!(position, Vector2 { X: 50., Y: 206. });
+
+        let anchor = rect(350., 100., 40., 20.);
+        let position = PopupPlacement::new(PopupDirection::Right, 0.).place(anchor, POPUP, AREA);
+        assert_eq!(position, Vector2 { X: 250., Y: 100. });
+    }
+
+    #[test]
+    fn not_flipped_when_opposite_is_worse() {
+        let anchor = rect(50., 20., 80., 220.);
+        let placement = PopupPlacement {
+            shift: false,
+            ..PopupPlacement::new(PopupDirection::Below, 0.)
+        };
+        assert_eq!(
+            placement.place(anchor, POPUP, AREA),
+            Vector2 { X: 50., Y: 240. }
+        );
+        // Below overflows by 10, above would overflow by 30
+        let anchor = rect(50., 20., 80., 240.);
+        assert_eq!(
+            placement.place(anchor, POPUP, AREA),
+            Vector2 { X: 50., Y: 260. }
+        );
+    }
+
+    #[test]
+    fn shifted_into_area() {
+        let anchor = rect(350., 100., 40., 20.);
+        let position = PopupPlacement::new(PopupDirection::Below, 0.).place(anchor, POPUP, AREA);
+        assert_eq!(position, Vector2 { X: 300., Y: 120. });
+    }
+
+    #[test]
+    fn flip_and_shift_can_be_disabled() {
+        let anchor = rect(350., 260., 40., 20.);
+        let placement = PopupPlacement {
+            flip: false,
+            shift: false,
+            ..PopupPlacement::new(PopupDirection::Below, 0.)
+        };
+        assert_eq!(
+            placement.place(anchor, POPUP, AREA),
+            Vector2 { X: 350., Y: 280. }
+        );
+    }
+
+    #[test]
+    fn shifted_to_area_start_when_popup_is_larger() {
+        let anchor = rect(10., 10., 10., 10.);
+        let popup = Vector2 { X: 500., Y: 400. };
+        let position = PopupPlacement::default().place(anchor, popup, AREA);
+        assert_eq!(position, Vector2 { X: 0., Y: 0. });
+    }
+}
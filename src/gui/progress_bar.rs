@@ -0,0 +1,208 @@
+use std::{borrow::Cow, time::Duration};
+
+use async_event_streams::{
+    EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
+};
+use async_event_streams_derive::EventSink;
+use async_std::sync::{Arc, RwLock};
+use async_trait::async_trait;
+use typed_builder::TypedBuilder;
+use windows::{
+    h,
+    Foundation::Numerics::{Vector2, Vector3},
+    UI::{
+        Color,
+        Composition::{
+            AnimationIterationBehavior, Compositor, ContainerVisual, SpriteVisual, Visual,
+        },
+    },
+};
+
+use super::{Panel, PanelEvent};
+
+// Part of the bar width occupied by the running indicator in indeterminate mode
+const INDETERMINATE_RATIO: f32 = 0.3;
+const INDETERMINATE_PERIOD: Duration = Duration::from_millis(1500);
+
+struct Core {
+    compositor: Compositor,
+    indicator: SpriteVisual,
+    size: Vector2,
+    value: f32,
+    indeterminate: bool,
+}
+
+impl Core {
+    fn redraw(&self) -> crate::Result<()> {
+        if self.indeterminate {
+            let width = self.size.X * INDETERMINATE_RATIO;
+            self.indicator.SetSize(Vector2 {
+                X: width,
+                Y: self.size.Y,
+            })?;
+            let animation = self.compositor.CreateVector3KeyFrameAnimation()?;
+            animation.InsertKeyFrame(
+                0.,
+                Vector3 {
+                    X: -width,
+                    Y: 0.,
+                    Z: 0.,
+                },
+            )?;
+            animation.InsertKeyFrame(
+                1.,
+                Vector3 {
+                    X: self.size.X,
+                    Y: 0.,
+                    Z: 0.,
+                },
+            )?;
+            animation.SetDuration(INDETERMINATE_PERIOD.into())?;
+            animation.SetIterationBehavior(AnimationIterationBehavior::Forever)?;
+            self.indicator.StartAnimation(h!("Offset"), &animation)?;
+        } else {
+            self.indicator.StopAnimation(h!("Offset"))?;
+            self.indicator.SetOffset(Vector3 {
+                X: 0.,
+                Y: 0.,
+                Z: 0.,
+            })?;
+            self.indicator.SetSize(Vector2 {
+                X: self.size.X * self.value,
+                Y: self.size.Y,
+            })?;
+        }
+        Ok(())
+    }
+    fn resize(&mut self, size: Vector2) -> crate::Result<()> {
+        self.size = size;
+        self.redraw()
+    }
+    fn set_value(&mut self, value: f32) -> crate::Result<()> {
+        self.value = value.clamp(0., 1.);
+        self.indeterminate = false;
+        self.redraw()
+    }
+    fn set_indeterminate(&mut self, indeterminate: bool) -> crate::Result<()> {
+        if self.indeterminate != indeterminate {
+            self.indeterminate = indeterminate;
+            self.redraw()?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+pub struct ProgressBar {
+    container: ContainerVisual,
+    track: SpriteVisual,
+    core: RwLock<Core>,
+    panel_events: EventStreams<PanelEvent>,
+    id: Arc<()>,
+}
+
+#[derive(TypedBuilder)]
+pub struct ProgressBarParams {
+    compositor: Compositor,
+    color: Color,
+    track_color: Color,
+    #[builder(default)]
+    value: f32,
+    #[builder(default)]
+    indeterminate: bool,
+}
+
+impl TryFrom<ProgressBarParams> for ProgressBar {
+    type Error = crate::Error;
+
+    fn try_from(value: ProgressBarParams) -> crate::Result<Self> {
+        let compositor = value.compositor;
+        let container = compositor.CreateContainerVisual()?;
+        // Keep the running indicator of indeterminate mode inside the bar
+        container.SetClip(&compositor.CreateInsetClip()?)?;
+        let track = compositor.CreateSpriteVisual()?;
+        track.SetBrush(&compositor.CreateColorBrushWithColor(value.track_color)?)?;
+        let indicator = compositor.CreateSpriteVisual()?;
+        indicator.SetBrush(&compositor.CreateColorBrushWithColor(value.color)?)?;
+        container.Children()?.InsertAtTop(&track)?;
+        container.Children()?.InsertAtTop(&indicator)?;
+        let core = Core {
+            compositor,
+            indicator,
+            size: Vector2 { X: 0., Y: 0. },
+            value: value.value.clamp(0., 1.),
+            indeterminate: value.indeterminate,
+        };
+        core.redraw()?;
+        Ok(ProgressBar {
+            container,
+            track,
+            core: RwLock::new(core),
+            panel_events: EventStreams::new(),
+            id: Arc::new(()),
+        })
+    }
+}
+
+impl TryFrom<ProgressBarParams> for Arc<ProgressBar> {
+    type Error = crate::Error;
+
+    fn try_from(value: ProgressBarParams) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
+
+impl ProgressBar {
+    pub async fn value(&self) -> f32 {
+        self.core.read().await.value
+    }
+    ///
+    /// Set progress in range 0..1. Values outside of the range are clamped.
+    /// Switches the bar to determinate mode.
+    ///
+    pub async fn set_value(&self, value: f32) -> crate::Result<()> {
+        self.core.write().await.set_value(value)
+    }
+    pub async fn is_indeterminate(&self) -> bool {
+        self.core.read().await.indeterminate
+    }
+    pub async fn set_indeterminate(&self, indeterminate: bool) -> crate::Result<()> {
+        self.core.write().await.set_indeterminate(indeterminate)
+    }
+}
+
+impl Panel for ProgressBar {
+    fn outer_frame(&self) -> Visual {
+        self.container.clone().into()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+}
+
+impl EventSource<PanelEvent> for ProgressBar {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for ProgressBar {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        if let PanelEvent::Resized(size) = event.as_ref() {
+            self.container.SetSize(*size)?;
+            self.track.SetSize(*size)?;
+            self.core.write().await.resize(*size)?;
+        }
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
@@ -0,0 +1,160 @@
+use std::{borrow::Cow, time::Duration};
+
+use async_event_streams::{
+    EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
+};
+use async_event_streams_derive::EventSink;
+use async_std::{
+    sync::{Arc, RwLock},
+    task::sleep,
+};
+use async_trait::async_trait;
+use futures::task::{Spawn, SpawnExt};
+use typed_builder::TypedBuilder;
+use windows::UI::Composition::Visual;
+
+use crate::handle_err;
+
+use super::{Button, ButtonEvent, Panel, PanelEvent};
+
+struct Core {
+    pressed: bool,
+    // Incremented on each press to stop the repeats of the previous one
+    generation: u64,
+}
+
+///
+/// Decorator which makes the button send repeated `Press` events while it's held
+/// with the cursor over it, e.g. for scrollbar arrows and numeric steppers
+///
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+pub struct RepeatButton {
+    button: Arc<Button>,
+    spawner: Arc<dyn Spawn + Send + Sync>,
+    delay: Duration,
+    interval: Duration,
+    core: Arc<RwLock<Core>>,
+    panel_events: EventStreams<PanelEvent>,
+    id: Arc<()>,
+}
+
+#[derive(TypedBuilder)]
+pub struct RepeatButtonParams<T: Spawn + Send + Sync + 'static> {
+    spawner: T,
+    #[builder(setter(transform = |button: impl Into<Arc<Button>>| button.into()))]
+    button: Arc<Button>,
+    /// Time from the press to the first repeat
+    #[builder(default = Duration::from_millis(500))]
+    delay: Duration,
+    /// Time between the repeats
+    #[builder(default = Duration::from_millis(50))]
+    interval: Duration,
+}
+
+impl<T: Spawn + Send + Sync + 'static> From<RepeatButtonParams<T>> for RepeatButton {
+    fn from(value: RepeatButtonParams<T>) -> Self {
+        RepeatButton {
+            button: value.button,
+            spawner: Arc::new(value.spawner),
+            delay: value.delay,
+            interval: value.interval,
+            core: Arc::new(RwLock::new(Core {
+                pressed: false,
+                generation: 0,
+            })),
+            panel_events: EventStreams::new(),
+            id: Arc::new(()),
+        }
+    }
+}
+
+impl<T: Spawn + Send + Sync + 'static> From<RepeatButtonParams<T>> for Arc<RepeatButton> {
+    fn from(value: RepeatButtonParams<T>) -> Self {
+        Arc::new(value.into())
+    }
+}
+
+impl RepeatButton {
+    pub fn button(&self) -> Arc<Button> {
+        self.button.clone()
+    }
+
+    async fn update_pressed(&self) -> crate::Result<()> {
+        let pressed = self.button.is_pressed().await;
+        let generation = {
+            let mut core = self.core.write().await;
+            if core.pressed == pressed {
+                return Ok(());
+            }
+            core.pressed = pressed;
+            core.generation += 1;
+            core.generation
+        };
+        if !pressed {
+            return Ok(());
+        }
+        // The task holds the weak references and stops when the repeat button is dropped
+        let button = Arc::downgrade(&self.button);
+        let core = Arc::downgrade(&self.core);
+        let delay = self.delay;
+        let interval = self.interval;
+        self.spawner.spawn(handle_err(async move {
+            sleep(delay).await;
+            loop {
+                let (core, button) = match (core.upgrade(), button.upgrade()) {
+                    (Some(core), Some(button)) => (core, button),
+                    _ => return Ok(()),
+                };
+                if core.read().await.generation != generation || !button.repeat_press(None).await {
+                    return Ok(());
+                }
+                drop((core, button));
+                sleep(interval).await;
+            }
+        }))?;
+        Ok(())
+    }
+}
+
+impl Panel for RepeatButton {
+    fn outer_frame(&self) -> Visual {
+        self.button.outer_frame()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+}
+
+impl EventSource<PanelEvent> for RepeatButton {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+impl EventSource<ButtonEvent> for RepeatButton {
+    fn event_stream(&self) -> EventStream<ButtonEvent> {
+        EventSource::<ButtonEvent>::event_stream(&*self.button)
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for RepeatButton {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        self.button
+            .on_event_ref(event.as_ref(), source.clone())
+            .await?;
+        if let PanelEvent::MouseInput { .. } = event.as_ref() {
+            self.update_pressed().await?;
+        }
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
@@ -0,0 +1,257 @@
+use std::{
+    borrow::Cow,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use async_event_streams::{
+    EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
+};
+use async_event_streams_derive::EventSink;
+use async_std::sync::{Arc, RwLock};
+use async_trait::async_trait;
+use typed_builder::TypedBuilder;
+use windows::{
+    Foundation::Numerics::Vector2,
+    UI::Composition::{Compositor, ContainerVisual, Visual},
+};
+
+use super::{attach, detach, ImplicitAnimation, Panel, PanelEvent};
+
+///
+/// Range of the panel size selecting the layout, with the same meaning as the window size
+/// classes of the Windows design guidelines
+///
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Default)]
+pub enum SizeClass {
+    #[default]
+    Compact,
+    Medium,
+    Expanded,
+}
+
+///
+/// Dimension of the panel compared with the breakpoints
+///
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum BreakpointAxis {
+    #[default]
+    Width,
+    Height,
+}
+
+struct Core {
+    size_class: Option<SizeClass>,
+    current: Option<Arc<dyn Panel>>,
+}
+
+///
+/// Panel which shows one of the alternative layouts depending on its size,
+/// e.g. to collapse the navigation sidebar in the narrow window.
+/// Sends `SizeClass` event each time the layout is switched.
+///
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+pub struct ResponsivePanel {
+    container: ContainerVisual,
+    compact: Arc<dyn Panel>,
+    medium: Option<Arc<dyn Panel>>,
+    expanded: Option<Arc<dyn Panel>>,
+    medium_breakpoint: f32,
+    expanded_breakpoint: f32,
+    axis: BreakpointAxis,
+    transition: ImplicitAnimation,
+    // Id of the shown layout, the hidden one is removed from the tree only if it wasn't shown again
+    shown_id: Arc<AtomicUsize>,
+    core: RwLock<Core>,
+    panel_events: EventStreams<PanelEvent>,
+    size_class_events: EventStreams<SizeClass>,
+    id: Arc<()>,
+}
+
+#[derive(TypedBuilder)]
+pub struct ResponsivePanelParams {
+    compositor: Compositor,
+    #[builder(setter(transform = |panel: impl Panel + 'static| Arc::new(panel) as Arc<dyn Panel>))]
+    compact: Arc<dyn Panel>,
+    /// Layout for the medium size, the compact one is used if not set
+    #[builder(default, setter(transform = |panel: impl Panel + 'static| Some(Arc::new(panel) as Arc<dyn Panel>)))]
+    medium: Option<Arc<dyn Panel>>,
+    /// Layout for the expanded size, the medium one is used if not set
+    #[builder(default, setter(transform = |panel: impl Panel + 'static| Some(Arc::new(panel) as Arc<dyn Panel>)))]
+    expanded: Option<Arc<dyn Panel>>,
+    /// Smallest size of the medium class
+    #[builder(default = 641.)]
+    medium_breakpoint: f32,
+    /// Smallest size of the expanded class
+    #[builder(default = 1008.)]
+    expanded_breakpoint: f32,
+    #[builder(default)]
+    axis: BreakpointAxis,
+    /// Animation of the layouts replacing each other
+    #[builder(default)]
+    transition: ImplicitAnimation,
+}
+
+impl TryFrom<ResponsivePanelParams> for ResponsivePanel {
+    type Error = crate::Error;
+
+    fn try_from(value: ResponsivePanelParams) -> crate::Result<Self> {
+        let container = value.compositor.CreateContainerVisual()?;
+        Ok(ResponsivePanel {
+            container,
+            compact: value.compact,
+            medium: value.medium,
+            expanded: value.expanded,
+            medium_breakpoint: value.medium_breakpoint,
+            expanded_breakpoint: value.expanded_breakpoint,
+            axis: value.axis,
+            transition: value.transition,
+            shown_id: Arc::new(AtomicUsize::new(0)),
+            core: RwLock::new(Core {
+                size_class: None,
+                current: None,
+            }),
+            panel_events: EventStreams::new(),
+            size_class_events: EventStreams::new(),
+            id: Arc::new(()),
+        })
+    }
+}
+
+impl TryFrom<ResponsivePanelParams> for Arc<ResponsivePanel> {
+    type Error = crate::Error;
+
+    fn try_from(value: ResponsivePanelParams) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
+
+impl ResponsivePanel {
+    ///
+    /// Size class of the current size, `None` before the panel is resized for the first time
+    ///
+    pub async fn size_class(&self) -> Option<SizeClass> {
+        self.core.read().await.size_class
+    }
+
+    fn classify(&self, size: Vector2) -> SizeClass {
+        let value = match self.axis {
+            BreakpointAxis::Width => size.X,
+            BreakpointAxis::Height => size.Y,
+        };
+        if value >= self.expanded_breakpoint {
+            SizeClass::Expanded
+        } else if value >= self.medium_breakpoint {
+            SizeClass::Medium
+        } else {
+            SizeClass::Compact
+        }
+    }
+
+    fn layout(&self, size_class: SizeClass) -> Arc<dyn Panel> {
+        let medium = self.medium.as_ref().unwrap_or(&self.compact);
+        match size_class {
+            SizeClass::Compact => self.compact.clone(),
+            SizeClass::Medium => medium.clone(),
+            SizeClass::Expanded => self.expanded.as_ref().unwrap_or(medium).clone(),
+        }
+    }
+
+    async fn resize(&self, size: Vector2, source: Option<Arc<EventBox>>) -> crate::Result<()> {
+        self.container.SetSize(size)?;
+        let size_class = self.classify(size);
+        let (current, previous, changed) = {
+            let mut core = self.core.write().await;
+            let changed = core.size_class != Some(size_class);
+            core.size_class = Some(size_class);
+            let current = self.layout(size_class);
+            let previous = match &core.current {
+                Some(previous) if previous.id() == current.id() => None,
+                _ => core.current.replace(current.clone()),
+            };
+            (current, previous, changed)
+        };
+        // The same layout may serve several size classes
+        if previous.is_some() || self.shown_id.load(Ordering::Acquire) != current.id() {
+            self.show(&current, previous)?;
+        }
+        current
+            .on_event_owned(PanelEvent::Resized(size), source.clone())
+            .await?;
+        if changed {
+            self.size_class_events.send_event(size_class, source).await;
+        }
+        Ok(())
+    }
+
+    fn show(
+        &self,
+        current: &Arc<dyn Panel>,
+        previous: Option<Arc<dyn Panel>>,
+    ) -> crate::Result<()> {
+        self.shown_id.store(current.id(), Ordering::Release);
+        if let Some(previous) = previous {
+            let frame = previous.outer_frame();
+            let container = self.container.clone();
+            let shown_id = self.shown_id.clone();
+            let previous_id = previous.id();
+            self.transition.hide(&frame.clone(), move || {
+                if shown_id.load(Ordering::Acquire) != previous_id {
+                    container.Children()?.Remove(&frame)?;
+                    Ok(true)
+                } else {
+                    Ok(false)
+                }
+            })?;
+        }
+        // The layout may be still in the tree if its hiding isn't finished
+        detach(&**current)?;
+        attach(&self.container, &**current)?;
+        self.transition.show(&current.outer_frame())
+    }
+}
+
+impl Panel for ResponsivePanel {
+    fn outer_frame(&self) -> Visual {
+        self.container.clone().into()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+}
+
+impl EventSource<PanelEvent> for ResponsivePanel {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+impl EventSource<SizeClass> for ResponsivePanel {
+    fn event_stream(&self) -> EventStream<SizeClass> {
+        self.size_class_events.create_event_stream()
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for ResponsivePanel {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        match event.as_ref() {
+            PanelEvent::Resized(size) => self.resize(*size, source.clone()).await?,
+            _ => {
+                let current = self.core.read().await.current.clone();
+                if let Some(current) = current {
+                    current.on_event_ref(event.as_ref(), source.clone()).await?;
+                }
+            }
+        }
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
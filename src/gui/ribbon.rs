@@ -1,6 +1,19 @@
-use std::borrow::Cow;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
 
-use super::{attach, is_translated_point_in_box, Panel, PanelEvent};
+use super::{
+    attach, capture_mouse,
+    easing::{Easing, EasingMode},
+    expression::Expression,
+    is_translated_point_in_box, mouse_capture,
+    mouse_capture::{window_mouse_captures, CaptureRoute},
+    release_mouse_capture, resize_cancellation_token,
+    theme::Theme,
+    CancellationSource, ImplicitAnimation, Margins, Panel, PanelEvent,
+};
 use async_event_streams::{
     EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
 };
@@ -9,11 +22,21 @@ use async_std::sync::{Arc, RwLock};
 use async_trait::async_trait;
 use typed_builder::TypedBuilder;
 use windows::{
+    core::HSTRING,
+    h,
     Foundation::Numerics::{Vector2, Vector3},
-    UI::Composition::{Compositor, ContainerVisual, Visual},
+    UI::Composition::{
+        CompositionClip, CompositionPropertySet, Compositor, ContainerVisual, Visual,
+    },
 };
 use winit::event::{ElementState, MouseButton};
 
+pub use crate::layout::CellAlignment;
+use crate::{
+    debug::CellOverlay,
+    layout::{arrange, SizeLimit, Span},
+};
+
 #[derive(PartialEq, Clone, Copy, Debug)]
 pub enum RibbonOrientation {
     Stack,
@@ -21,12 +44,126 @@ pub enum RibbonOrientation {
     Vertical,
 }
 
+///
+/// Layout of the cells which don't fit into the ribbon with their minimal sizes
+///
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub enum RibbonOverflow {
+    /// Cells continue past the ribbon edge
+    #[default]
+    Visible,
+    /// Cells are clipped by the ribbon and scrolled along its orientation
+    /// with the mouse wheel or by dragging, the fast drag keeps them sliding after the release
+    /// until the next press. Cells scrolled entirely out of view
+    /// don't receive `Resized` and `ThemeChanged` until they are scrolled back.
+    Scroll,
+}
+
+// Properties of `Ribbon::scroll_properties`
+const SCROLL_OFFSET_PROPERTY: &str = "ScrollOffset";
+const MAX_SCROLL_OFFSET_PROPERTY: &str = "MaxScrollOffset";
+// Duration of the animated `Ribbon::scroll_to`
+const SCROLL_ANIMATION_DURATION: Duration = Duration::from_millis(250);
+// Scroll distance of one mouse wheel notch
+const SCROLL_LINE: f32 = 48.;
+// Pointer movement after the press which starts the drag scrolling
+const DRAG_THRESHOLD: f32 = 8.;
+// Pointer positions within this time before the release give the fling velocity
+const VELOCITY_WINDOW: Duration = Duration::from_millis(100);
+// Slower drag stops where it's released, pixels per second
+const MIN_FLING_VELOCITY: f32 = 50.;
+// Time constant of the exponential decay of the fling velocity, in seconds
+const FLING_TIME_CONSTANT: f32 = 0.325;
+
+#[derive(Clone)]
+struct ScrollDrag {
+    start_pos: f32,
+    start_offset: f32,
+    active: bool,
+    // Recent pointer positions along the ribbon
+    samples: VecDeque<(Instant, f32)>,
+}
+
+impl ScrollDrag {
+    fn new(start_pos: f32, start_offset: f32) -> Self {
+        Self {
+            start_pos,
+            start_offset,
+            active: false,
+            samples: VecDeque::from([(Instant::now(), start_pos)]),
+        }
+    }
+    fn track(&mut self, pos: f32) {
+        let now = Instant::now();
+        self.samples.push_back((now, pos));
+        while let Some(&(time, _)) = self.samples.front() {
+            if now - time <= VELOCITY_WINDOW {
+                break;
+            }
+            self.samples.pop_front();
+        }
+    }
+    // Velocity of the scroll offset, opposite to the pointer movement. The pointer
+    // which stopped before the release gives zero.
+    fn velocity(&self, now: Instant) -> f32 {
+        let mut samples = self
+            .samples
+            .iter()
+            .filter(|(time, _)| now - *time <= VELOCITY_WINDOW);
+        match (samples.next(), samples.last()) {
+            (Some(&(t0, p0)), Some(&(t1, p1))) if t1 > t0 => (p0 - p1) / (t1 - t0).as_secs_f32(),
+            _ => 0.,
+        }
+    }
+}
+
+// Inertial scrolling after the fling, the cells slide with the cubic ease out
+// which starts with the release velocity
+#[derive(Copy, Clone)]
+struct Glide {
+    from: f32,
+    to: f32,
+    started: Instant,
+    duration: Duration,
+}
+
+impl Glide {
+    fn new(from: f32, velocity: f32, max_offset: f32) -> Option<Self> {
+        if velocity.abs() < MIN_FLING_VELOCITY {
+            return None;
+        }
+        // The exponentially decaying velocity travels `velocity * time constant`,
+        // the overscroll is clamped at the ends
+        let to = (from + velocity * FLING_TIME_CONSTANT).clamp(0., max_offset);
+        if to == from {
+            return None;
+        }
+        Some(Glide {
+            from,
+            to,
+            started: Instant::now(),
+            duration: Duration::from_secs_f32(3. * (to - from).abs() / velocity.abs()),
+        })
+    }
+    fn is_running(&self, now: Instant) -> bool {
+        now - self.started < self.duration
+    }
+    // Scroll offset the sliding cells have reached
+    fn offset(&self, now: Instant) -> f32 {
+        let t = (now - self.started).as_secs_f32() / self.duration.as_secs_f32();
+        Easing::Cubic(EasingMode::Out).interpolate(self.from, self.to, t)
+    }
+}
+
 #[derive(Copy, Clone)]
 pub struct CellLimit {
     pub ratio: f32,
     pub min_size: f32,
     pub max_size: Option<f32>,
+    /// Size of the content relative to the cell
     pub content_ratio: Vector2,
+    pub horizontal_alignment: CellAlignment,
+    pub vertical_alignment: CellAlignment,
 }
 
 impl CellLimit {
@@ -42,9 +179,57 @@ impl CellLimit {
             min_size,
             max_size,
             content_ratio,
+            horizontal_alignment: CellAlignment::default(),
+            vertical_alignment: CellAlignment::default(),
         }
     }
 
+    ///
+    /// Same limit with the content aligned in the cell, e.g. `(End, Center)`
+    /// pins the narrow content to the right edge of the wide cell
+    ///
+    pub fn with_alignment(self, horizontal: CellAlignment, vertical: CellAlignment) -> Self {
+        Self {
+            horizontal_alignment: horizontal,
+            vertical_alignment: vertical,
+            ..self
+        }
+    }
+
+    pub fn size_limit(&self) -> SizeLimit {
+        SizeLimit {
+            ratio: self.ratio,
+            min_size: self.min_size,
+            max_size: self.max_size,
+        }
+    }
+
+    // Position and size of the content inside the cell's slot
+    fn place_content(&self, offset: Vector2, size: Vector2) -> (Vector2, Vector2) {
+        let content_size = size * self.content_ratio;
+        let x = self.horizontal_alignment.align(
+            Span {
+                offset: offset.X,
+                size: size.X,
+            },
+            content_size.X,
+        );
+        let y = self.vertical_alignment.align(
+            Span {
+                offset: offset.Y,
+                size: size.Y,
+            },
+            content_size.Y,
+        );
+        (
+            Vector2 {
+                X: x.offset,
+                Y: y.offset,
+            },
+            content_size,
+        )
+    }
+
     pub fn set_size(&mut self, size: f32) {
         self.min_size = size;
         self.max_size = Some(size);
@@ -58,15 +243,52 @@ impl Default for CellLimit {
             min_size: 0.,
             max_size: None,
             content_ratio: Vector2::new(1., 1.),
+            horizontal_alignment: CellAlignment::default(),
+            vertical_alignment: CellAlignment::default(),
         }
     }
 }
 
+///
+/// Change of the ribbon's cell list. Mutations passed to `Ribbon::mutate` together
+/// are applied with a single relayout, so cells move to their new positions at once.
+///
+#[derive(Clone)]
+pub enum RibbonMutation {
+    Insert {
+        index: usize,
+        panel: Arc<dyn Panel>,
+        limit: CellLimit,
+    },
+    Remove(usize),
+    Move {
+        from: usize,
+        to: usize,
+    },
+    /// Remove all the cells
+    Clear,
+}
+
+// Check the indices of the mutations applied in order to the ribbon with `len` cells
+fn check_mutations(mut len: usize, mutations: &[RibbonMutation]) -> crate::Result<()> {
+    for mutation in mutations {
+        len = match *mutation {
+            RibbonMutation::Insert { index, .. } if index <= len => len + 1,
+            RibbonMutation::Remove(index) if index < len => len - 1,
+            RibbonMutation::Move { from, to } if from < len && to < len => len,
+            RibbonMutation::Clear => 0,
+            _ => return Err(crate::Error::BadIndex),
+        };
+    }
+    Ok(())
+}
+
 #[derive(Clone)]
 pub struct Cell {
     panel: Arc<dyn Panel>,
     container: ContainerVisual,
     limit: CellLimit,
+    debug_overlay: Option<CellOverlay>,
 }
 
 impl Cell {
@@ -82,6 +304,7 @@ impl Cell {
             panel: panel.into(),
             container,
             limit,
+            debug_overlay: None,
         })
     }
     fn translate_point(&self, mut point: Vector2) -> crate::Result<Vector2> {
@@ -94,13 +317,71 @@ impl Cell {
         let size = self.container.Size()?;
         Ok(is_translated_point_in_box(point, size))
     }
-    fn resize(&mut self, offset: Vector2, size: Vector2) -> crate::Result<()> {
-        self.container.SetOffset(Vector3 {
+    // Whether any part of the cell is inside the ribbon of the given size
+    fn is_in_viewport(&self, viewport: Vector2) -> crate::Result<bool> {
+        let offset = self.container.Offset()?;
+        let size = self.container.Size()?;
+        Ok(offset.X <= viewport.X
+            && offset.X + size.X >= 0.
+            && offset.Y <= viewport.Y
+            && offset.Y + size.Y >= 0.)
+    }
+    // Hit test of the panel with the point in the cell's coordinates
+    fn hit_test(&self, point: Vector2) -> crate::Result<bool> {
+        let offset = self.panel.outer_frame().Offset()?;
+        Ok(self.panel.hit_test(Vector2 {
+            X: point.X - offset.X,
+            Y: point.Y - offset.Y,
+        }))
+    }
+    fn resize(
+        &mut self,
+        offset: Vector2,
+        size: Vector2,
+        duration: Option<Duration>,
+    ) -> crate::Result<()> {
+        let old_offset = self.container.Offset()?;
+        let old_size = self.container.Size()?;
+        let new_offset = Vector3 {
             X: offset.X,
             Y: offset.Y,
             Z: 0.,
-        })?;
+        };
+        // The cells sliding after the fling stop where they are placed
+        self.container.StopAnimation(h!("Offset"))?;
+        self.container.SetOffset(new_offset)?;
         self.container.SetSize(size)?;
+        // Only the cells which were already placed are moved, new ones appear in place
+        if let Some(duration) = duration {
+            if old_size != Vector2::default() && old_offset != new_offset {
+                let compositor = self.container.Compositor()?;
+                let easing = Easing::Cubic(EasingMode::Out).composition_function(&compositor)?;
+                let animation = compositor.CreateVector3KeyFrameAnimation()?;
+                animation.InsertKeyFrame(0., old_offset)?;
+                animation.InsertKeyFrameWithEasingFunction(1., new_offset, &easing)?;
+                animation.SetDuration(duration.into())?;
+                self.container.StartAnimation(h!("Offset"), &animation)?;
+            }
+        }
+        if let Some(debug_overlay) = &self.debug_overlay {
+            debug_overlay.update(offset, size, &self.limit)?;
+        }
+        Ok(())
+    }
+    fn set_debug_overlay(&mut self, compositor: &Compositor, index: usize) -> crate::Result<()> {
+        if self.debug_overlay.is_none() {
+            let debug_overlay = CellOverlay::new(compositor, index)?;
+            self.container
+                .Children()?
+                .InsertAtTop(debug_overlay.visual())?;
+            self.debug_overlay = Some(debug_overlay);
+        }
+        Ok(())
+    }
+    fn clear_debug_overlay(&mut self) -> crate::Result<()> {
+        if let Some(debug_overlay) = self.debug_overlay.take() {
+            self.container.Children()?.Remove(debug_overlay.visual())?;
+        }
         Ok(())
     }
 }
@@ -111,10 +392,30 @@ impl PartialEq for Cell {
     }
 }
 
+// Events held back from the cell scrolled out of view, only the latest ones matter
+#[derive(Default)]
+struct Deferred {
+    resized: bool,
+    theme: Option<Arc<Theme>>,
+}
+
 struct Core {
     orientation: RibbonOrientation,
     cells: Vec<Cell>,
+    spacing: f32,
+    padding: Margins,
+    overflow: RibbonOverflow,
+    scroll_offset: f32,
+    max_scroll_offset: f32,
+    drag: Option<ScrollDrag>,
+    glide: Option<Glide>,
     mouse_pos: Option<Vector2>,
+    debug_overlay: bool,
+    reorder_duration: Option<Duration>,
+    show_animation: ImplicitAnimation,
+    hide_animation: ImplicitAnimation,
+    // Deferred events by `Panel::id` of the cell
+    deferred: HashMap<usize, Deferred>,
 }
 
 impl Core {
@@ -130,6 +431,19 @@ impl Core {
     fn get_mouse_pos(&self) -> Option<Vector2> {
         self.mouse_pos
     }
+    fn is_scrollable(&self) -> bool {
+        self.overflow == RibbonOverflow::Scroll
+            && self.orientation != RibbonOrientation::Stack
+            && self.max_scroll_offset > 0.
+    }
+    // Coordinate of the point along the ribbon orientation
+    fn along(&self, point: Vector2) -> f32 {
+        if self.orientation == RibbonOrientation::Vertical {
+            point.Y
+        } else {
+            point.X
+        }
+    }
 }
 
 #[derive(EventSink)]
@@ -137,7 +451,11 @@ impl Core {
 pub struct Ribbon {
     compositor: Compositor,
     ribbon_container: ContainerVisual,
+    scroll_properties: CompositionPropertySet,
     core: RwLock<Core>,
+    // Newer resize propagation makes the older one obsolete
+    resize_cancellation: CancellationSource,
+    capture_route: CaptureRoute,
     panel_events: EventStreams<PanelEvent>,
     id: Arc<()>,
 }
@@ -148,6 +466,23 @@ pub struct RibbonParams {
     orientation: RibbonOrientation,
     #[builder(default)]
     cells: Vec<Cell>,
+    /// Gap between the neighbouring cells
+    #[builder(default)]
+    spacing: f32,
+    /// Inset of the cells from the ribbon edges
+    #[builder(default)]
+    padding: Margins,
+    #[builder(default)]
+    overflow: RibbonOverflow,
+    /// Duration of the cells movement after mutations, no animation if not set
+    #[builder(default, setter(strip_option))]
+    reorder_duration: Option<Duration>,
+    /// Animation of the cells added after the ribbon is created
+    #[builder(default)]
+    show_animation: ImplicitAnimation,
+    /// Animation of the removed cells
+    #[builder(default)]
+    hide_animation: ImplicitAnimation,
 }
 
 impl RibbonParams {
@@ -167,15 +502,36 @@ impl TryFrom<RibbonParams> for Ribbon {
             ribbon_container.Children()?.InsertAtTop(&cell.container)?;
         }
         // ribbon_container.SetComment(HSTRING::from("RIBBON_CONTAINER"))?;
+        if value.overflow == RibbonOverflow::Scroll {
+            ribbon_container.SetClip(&value.compositor.CreateInsetClip()?)?;
+        }
+        let scroll_properties = value.compositor.CreatePropertySet()?;
+        scroll_properties.InsertScalar(&HSTRING::from(SCROLL_OFFSET_PROPERTY), 0.)?;
+        scroll_properties.InsertScalar(&HSTRING::from(MAX_SCROLL_OFFSET_PROPERTY), 0.)?;
         let core = RwLock::new(Core {
             orientation: value.orientation,
             cells: value.cells,
+            spacing: value.spacing,
+            padding: value.padding,
+            overflow: value.overflow,
+            scroll_offset: 0.,
+            max_scroll_offset: 0.,
+            drag: None,
+            glide: None,
             mouse_pos: None,
+            debug_overlay: false,
+            reorder_duration: value.reorder_duration,
+            show_animation: value.show_animation,
+            hide_animation: value.hide_animation,
+            deferred: HashMap::new(),
         });
         Ok(Ribbon {
             compositor: value.compositor,
             ribbon_container,
+            scroll_properties,
             core,
+            resize_cancellation: CancellationSource::default(),
+            capture_route: CaptureRoute::default(),
             panel_events: EventStreams::new(),
             id: Arc::new(()),
         })
@@ -192,55 +548,423 @@ impl TryFrom<RibbonParams> for Arc<Ribbon> {
 
 impl Ribbon {
     pub async fn add_panel(&self, panel: Arc<dyn Panel>, limit: CellLimit) -> crate::Result<()> {
-        let cell = Cell::new(panel, &self.compositor, limit)?;
+        let mut cell = Cell::new(panel, &self.compositor, limit)?;
         self.ribbon_container
             .Children()?
             .InsertAtTop(&cell.container)?;
-        self.core.write().await.cells.push(cell);
-        self.resize_cells(self.ribbon_container.Size()?).await?;
+        let container = cell.container.clone();
+        let show_animation = {
+            let mut core = self.core.write().await;
+            if core.debug_overlay {
+                cell.set_debug_overlay(&self.compositor, core.cells.len())?;
+            }
+            core.cells.push(cell);
+            core.show_animation
+        };
+        self.resize_cells(self.ribbon_container.Size()?, false)
+            .await?;
+        show_animation.show(&container.into())
+    }
+    pub async fn insert_panel(
+        &self,
+        index: usize,
+        panel: Arc<dyn Panel>,
+        limit: CellLimit,
+    ) -> crate::Result<()> {
+        self.mutate(vec![RibbonMutation::Insert {
+            index,
+            panel,
+            limit,
+        }])
+        .await
+    }
+    pub async fn remove_panel(&self, index: usize) -> crate::Result<()> {
+        self.mutate(vec![RibbonMutation::Remove(index)]).await
+    }
+    ///
+    /// Remove the cell with the panel of given `Panel::id`
+    ///
+    pub async fn remove_panel_by_id(&self, id: usize) -> crate::Result<()> {
+        self.mutate_with(|cells| {
+            let index = cells
+                .iter()
+                .position(|cell| cell.panel.id() == id)
+                .ok_or(crate::Error::BadIndex)?;
+            Ok(vec![RibbonMutation::Remove(index)])
+        })
+        .await
+    }
+    ///
+    /// Limit of the cell with the panel of given `Panel::id`
+    ///
+    pub async fn cell_limit(&self, panel_id: usize) -> crate::Result<CellLimit> {
+        self.core
+            .read()
+            .await
+            .cells
+            .iter()
+            .find(|cell| cell.panel.id() == panel_id)
+            .map(|cell| cell.limit)
+            .ok_or(crate::Error::BadIndex)
+    }
+    ///
+    /// Replace the limit of the cell with the panel of given `Panel::id` and relayout
+    /// the ribbon, e.g. set `max_size` to 0 to collapse a side pane
+    ///
+    pub async fn set_cell_limit(&self, panel_id: usize, limit: CellLimit) -> crate::Result<()> {
+        {
+            let mut core = self.core.write().await;
+            let cell = core
+                .cells
+                .iter_mut()
+                .find(|cell| cell.panel.id() == panel_id)
+                .ok_or(crate::Error::BadIndex)?;
+            cell.limit = limit;
+        }
+        self.resize_cells(self.ribbon_container.Size()?, true)
+            .await?;
+        self.send_resized_to_cells(None).await
+    }
+    ///
+    /// Remove all the cells
+    ///
+    pub async fn clear(&self) -> crate::Result<()> {
+        self.mutate(vec![RibbonMutation::Clear]).await
+    }
+    pub async fn move_panel(&self, from: usize, to: usize) -> crate::Result<()> {
+        self.mutate(vec![RibbonMutation::Move { from, to }]).await
+    }
+    ///
+    /// Apply the mutations in order and relayout the ribbon once. Remaining cells are animated
+    /// to their new positions if the reorder duration is set. If any of the mutations
+    /// has the bad index, none of them is applied.
+    ///
+    pub async fn mutate(&self, mutations: Vec<RibbonMutation>) -> crate::Result<()> {
+        self.mutate_with(|_| Ok(mutations)).await
+    }
+    // Apply the mutations made from the cells under the same lock, so the indices
+    // found in the cell list are still valid
+    async fn mutate_with(
+        &self,
+        f: impl FnOnce(&[Cell]) -> crate::Result<Vec<RibbonMutation>> + Send,
+    ) -> crate::Result<()> {
+        let mut inserted = Vec::new();
+        let show_animation = {
+            let mut core = self.core.write().await;
+            let mutations = f(&core.cells)?;
+            check_mutations(core.cells.len(), &mutations)?;
+            for mutation in mutations {
+                match mutation {
+                    RibbonMutation::Insert {
+                        index,
+                        panel,
+                        limit,
+                    } => {
+                        let cell = Cell::new(panel, &self.compositor, limit)?;
+                        inserted.push(cell.container.clone());
+                        core.cells.insert(index, cell);
+                        self.place_cell_container(&core.cells, index)?;
+                    }
+                    RibbonMutation::Remove(index) => {
+                        self.remove_cell(&mut core, index, &mut inserted)?;
+                    }
+                    RibbonMutation::Clear => {
+                        while !core.cells.is_empty() {
+                            self.remove_cell(&mut core, 0, &mut inserted)?;
+                        }
+                    }
+                    RibbonMutation::Move { from, to } => {
+                        let cell = core.cells.remove(from);
+                        self.ribbon_container.Children()?.Remove(&cell.container)?;
+                        core.cells.insert(to, cell);
+                        self.place_cell_container(&core.cells, to)?;
+                    }
+                }
+            }
+            if core.debug_overlay {
+                for (index, cell) in core.cells.iter_mut().enumerate() {
+                    cell.clear_debug_overlay()?;
+                    cell.set_debug_overlay(&self.compositor, index)?;
+                }
+            }
+            core.show_animation
+        };
+        self.resize_cells(self.ribbon_container.Size()?, true)
+            .await?;
+        // New cells get their positions only after the layout
+        for container in inserted {
+            show_animation.show(&container.into())?;
+        }
+        self.send_resized_to_cells(None).await
+    }
+    // Remove the cell from the list, its container is removed from the tree after the hide animation
+    fn remove_cell(
+        &self,
+        core: &mut Core,
+        index: usize,
+        inserted: &mut Vec<ContainerVisual>,
+    ) -> crate::Result<()> {
+        let cell = core.cells.remove(index);
+        inserted.retain(|container| *container != cell.container);
+        let ribbon_container = self.ribbon_container.clone();
+        let container = cell.container.clone();
+        core.hide_animation.hide(&cell.container.into(), move || {
+            ribbon_container.Children()?.Remove(&container)?;
+            Ok(true)
+        })
+    }
+    ///
+    /// Insert the cell's container into the visual tree keeping the children order equal to
+    /// the cells order, which defines the z-order of the overlapping cells
+    ///
+    fn place_cell_container(&self, cells: &[Cell], index: usize) -> crate::Result<()> {
+        let children = self.ribbon_container.Children()?;
+        match cells.get(index + 1) {
+            Some(next) => children.InsertBelow(&cells[index].container, &next.container)?,
+            None => children.InsertAtTop(&cells[index].container)?,
+        }
         Ok(())
     }
-    async fn resize_cells(&self, size: Vector2) -> crate::Result<()> {
+    pub async fn spacing(&self) -> f32 {
+        self.core.read().await.spacing
+    }
+    pub async fn set_spacing(&self, spacing: f32) -> crate::Result<()> {
+        self.core.write().await.spacing = spacing;
+        self.resize_cells(self.ribbon_container.Size()?, false)
+            .await?;
+        self.send_resized_to_cells(None).await
+    }
+    pub async fn padding(&self) -> Margins {
+        self.core.read().await.padding
+    }
+    pub async fn set_padding(&self, padding: Margins) -> crate::Result<()> {
+        self.core.write().await.padding = padding;
+        self.resize_cells(self.ribbon_container.Size()?, false)
+            .await?;
+        self.send_resized_to_cells(None).await
+    }
+    pub async fn overflow(&self) -> RibbonOverflow {
+        self.core.read().await.overflow
+    }
+    pub async fn set_overflow(&self, overflow: RibbonOverflow) -> crate::Result<()> {
+        {
+            let mut core = self.core.write().await;
+            core.overflow = overflow;
+            core.drag = None;
+            core.glide = None;
+        }
+        if overflow == RibbonOverflow::Scroll {
+            self.ribbon_container
+                .SetClip(&self.compositor.CreateInsetClip()?)?;
+        } else {
+            self.ribbon_container.SetClip(None::<&CompositionClip>)?;
+        }
+        self.resize_cells(self.ribbon_container.Size()?, false)
+            .await?;
+        self.send_deferred_events().await
+    }
+    ///
+    /// Distance the cells are scrolled by from the start of the ribbon
+    ///
+    pub async fn scroll_offset(&self) -> f32 {
+        self.core.read().await.scroll_offset
+    }
+    ///
+    /// Scroll the cells, the offset is limited by the size of the cells exceeding the ribbon.
+    /// Has no effect unless the overflow mode is `RibbonOverflow::Scroll`.
+    ///
+    pub async fn set_scroll_offset(&self, offset: f32) -> crate::Result<()> {
+        self.scroll_to(offset, false).await
+    }
+    ///
+    /// Same as `set_scroll_offset`, but the cells can slide to the new position
+    ///
+    pub async fn scroll_to(&self, offset: f32, animated: bool) -> crate::Result<()> {
+        self.core.write().await.glide = None;
+        self.slide_to(offset, animated.then_some(SCROLL_ANIMATION_DURATION))
+            .await
+    }
+    async fn slide_to(&self, offset: f32, duration: Option<Duration>) -> crate::Result<()> {
+        self.core.write().await.scroll_offset = offset;
+        self.place_cells(self.ribbon_container.Size()?, duration)
+            .await?;
+        self.send_deferred_events().await
+    }
+    ///
+    /// Scroll by the least distance which makes the cell with the panel entirely visible.
+    /// The cell larger than the ribbon is aligned to its start.
+    ///
+    pub async fn bring_into_view(&self, panel_id: usize, animated: bool) -> crate::Result<()> {
+        let offset = {
+            let core = self.core.read().await;
+            let cell = core
+                .cells
+                .iter()
+                .find(|cell| cell.panel.id() == panel_id)
+                .ok_or(crate::Error::BadIndex)?;
+            let cell_offset = cell.container.Offset()?;
+            let cell_start = core.along(Vector2 {
+                X: cell_offset.X,
+                Y: cell_offset.Y,
+            });
+            let cell_end = cell_start + core.along(cell.container.Size()?);
+            let view_start = core.along(core.padding.offset());
+            let view_end =
+                view_start + core.along(core.padding.inner_size(self.ribbon_container.Size()?));
+            let delta = if cell_start < view_start {
+                cell_start - view_start
+            } else if cell_end > view_end {
+                (cell_end - view_end).min(cell_start - view_start)
+            } else {
+                0.
+            };
+            core.scroll_offset + delta
+        };
+        self.scroll_to(offset, animated).await
+    }
+    ///
+    /// Property set with the scalars "ScrollOffset" and "MaxScrollOffset" updated
+    /// on scrolling, for the expression animations linked to the scroll position
+    ///
+    pub fn scroll_properties(&self) -> CompositionPropertySet {
+        self.scroll_properties.clone()
+    }
+    ///
+    /// Scroll offset for the `Expression` animating other panels, e.g. the parallax
+    /// or the shrinking header
+    ///
+    pub fn scroll_offset_expression(&self) -> Expression {
+        Expression::property(&self.scroll_properties, SCROLL_OFFSET_PROPERTY)
+    }
+    pub async fn set_reorder_duration(&self, duration: Option<Duration>) {
+        self.core.write().await.reorder_duration = duration;
+    }
+    pub async fn set_show_animation(&self, animation: ImplicitAnimation) {
+        self.core.write().await.show_animation = animation;
+    }
+    pub async fn set_hide_animation(&self, animation: ImplicitAnimation) {
+        self.core.write().await.hide_animation = animation;
+    }
+    ///
+    /// Layout inspection mode: tint each cell and show its computed rectangle and limits
+    ///
+    pub async fn set_debug_overlay(&self, enabled: bool) -> crate::Result<()> {
+        {
+            let mut core = self.core.write().await;
+            core.debug_overlay = enabled;
+            for (index, cell) in core.cells.iter_mut().enumerate() {
+                if enabled {
+                    cell.set_debug_overlay(&self.compositor, index)?;
+                } else {
+                    cell.clear_debug_overlay()?;
+                }
+            }
+        }
+        self.resize_cells(self.ribbon_container.Size()?, false)
+            .await
+    }
+    pub async fn debug_overlay(&self) -> bool {
+        self.core.read().await.debug_overlay
+    }
+    async fn resize_cells(&self, size: Vector2, animate: bool) -> crate::Result<()> {
+        let duration = if animate {
+            self.core.read().await.reorder_duration
+        } else {
+            None
+        };
+        self.place_cells(size, duration).await
+    }
+    // The property follows the sliding cells, so the expressions bound to it don't jump
+    fn set_scroll_offset_property(
+        &self,
+        scroll_offset: f32,
+        duration: Option<Duration>,
+    ) -> crate::Result<()> {
+        let name = HSTRING::from(SCROLL_OFFSET_PROPERTY);
+        let mut old_scroll_offset = 0.;
+        self.scroll_properties
+            .TryGetScalar(&name, &mut old_scroll_offset)?;
+        self.scroll_properties.StopAnimation(&name)?;
+        self.scroll_properties.InsertScalar(&name, scroll_offset)?;
+        if let Some(duration) = duration {
+            if old_scroll_offset != scroll_offset {
+                let easing =
+                    Easing::Cubic(EasingMode::Out).composition_function(&self.compositor)?;
+                let animation = self.compositor.CreateScalarKeyFrameAnimation()?;
+                animation.InsertKeyFrame(0., old_scroll_offset)?;
+                animation.InsertKeyFrameWithEasingFunction(1., scroll_offset, &easing)?;
+                animation.SetDuration(duration.into())?;
+                self.scroll_properties.StartAnimation(&name, &animation)?;
+            }
+        }
+        Ok(())
+    }
+    // Moving cells are animated if the duration is set
+    async fn place_cells(&self, size: Vector2, duration: Option<Duration>) -> crate::Result<()> {
         self.ribbon_container.SetSize(size)?;
-        let (orientation, mut cells) = {
+        let (orientation, mut cells, spacing, padding, overflow) = {
             let v = self.core.read().await;
-            (v.orientation(), v.cells())
+            (v.orientation(), v.cells(), v.spacing, v.padding, v.overflow)
         };
+        let origin = padding.offset();
+        let size = padding.inner_size(size);
         if orientation == RibbonOrientation::Stack {
             for cell in &mut cells {
-                let content_size = size.clone() * cell.limit.content_ratio.clone();
-                let content_offset = Vector2 {
-                    X: (size.X - content_size.X) / 2.,
-                    Y: (size.Y - content_size.Y) / 2.,
-                };
-                cell.resize(content_offset, content_size)?;
+                let (content_offset, content_size) = cell.limit.place_content(origin, size);
+                cell.resize(content_offset, content_size, duration)?;
             }
         } else {
-            let limits = cells.iter().map(|c| c.limit).collect::<Vec<_>>();
+            let limits = cells
+                .iter()
+                .map(|c| c.limit.size_limit())
+                .collect::<Vec<_>>();
             let hor = orientation == RibbonOrientation::Horizontal;
             let target = if hor { size.X } else { size.Y };
-            let sizes = adjust_cells(limits, target);
-            let mut pos: f32 = 0.;
-            for i in 0..cells.len() {
-                let size = if hor {
-                    Vector2 {
-                        X: sizes[i],
-                        Y: size.Y,
-                    }
-                } else {
-                    Vector2 {
-                        X: size.X,
-                        Y: sizes[i],
-                    }
-                };
-                let cell = &mut cells[i];
-                let offset = if hor {
-                    Vector2 { X: pos, Y: 0. }
+            let spans = arrange(&limits, 0., target, spacing);
+            let extent = spans.last().map(|span| span.end()).unwrap_or(0.);
+            let max_scroll_offset = if overflow == RibbonOverflow::Scroll {
+                (extent - target).max(0.)
+            } else {
+                0.
+            };
+            let scroll_offset = {
+                let mut core = self.core.write().await;
+                core.max_scroll_offset = max_scroll_offset;
+                core.scroll_offset = core.scroll_offset.clamp(0., max_scroll_offset);
+                core.scroll_offset
+            };
+            self.set_scroll_offset_property(scroll_offset, duration)?;
+            self.scroll_properties.InsertScalar(
+                &HSTRING::from(MAX_SCROLL_OFFSET_PROPERTY),
+                max_scroll_offset,
+            )?;
+            let start = (if hor { origin.X } else { origin.Y }) - scroll_offset;
+            for (cell, span) in cells.iter_mut().zip(spans) {
+                let (offset, size) = if hor {
+                    (
+                        Vector2 {
+                            X: start + span.offset,
+                            Y: origin.Y,
+                        },
+                        Vector2 {
+                            X: span.size,
+                            Y: size.Y,
+                        },
+                    )
                 } else {
-                    Vector2 { X: 0., Y: pos }
+                    (
+                        Vector2 {
+                            X: origin.X,
+                            Y: start + span.offset,
+                        },
+                        Vector2 {
+                            X: size.X,
+                            Y: span.size,
+                        },
+                    )
                 };
-                cell.resize(offset, size)?;
-                pos += sizes[i];
+                let (content_offset, content_size) = cell.limit.place_content(offset, size);
+                cell.resize(content_offset, content_size, duration)?;
             }
         }
         Ok(())
@@ -275,12 +999,47 @@ impl EventSinkExt<PanelEvent> for Ribbon {
                 self.translate_panel_event_resized(*size, source.clone())
                     .await
             }
-            PanelEvent::MouseInput { state, button, .. } => {
-                self.translate_slot_event_mouse_input(*state, *button, source.clone())
+            PanelEvent::MouseInput {
+                in_slot,
+                state,
+                button,
+                ..
+            } => {
+                let dragged = *button == MouseButton::Left
+                    && self
+                        .start_or_end_drag(*in_slot && *state == ElementState::Pressed)
+                        .await?;
+                // The release ending the drag scrolling doesn't click the cell under the cursor,
+                // nor the press stopping the inertial scrolling
+                let event = if dragged {
+                    event.with_in_slot(false)
+                } else {
+                    event.as_ref().clone()
+                };
+                self.translate_slot_event_mouse_input(&event, source.clone())
                     .await
             }
-            PanelEvent::CursorMoved(mouse_pos) => {
-                self.translate_slot_event_cursor_moved(*mouse_pos, source.clone())
+            PanelEvent::MouseDoubleClick { .. } | PanelEvent::DragDrop { .. } => {
+                self.translate_slot_event_mouse_input(event.as_ref(), source.clone())
+                    .await
+            }
+            PanelEvent::CursorMoved(mouse_pos, seq) => {
+                self.translate_slot_event_cursor_moved(*mouse_pos, *seq, source.clone())
+                    .await?;
+                self.drag_scroll(*mouse_pos, *seq, source.clone()).await
+            }
+            PanelEvent::MouseWheel { delta, .. } => {
+                self.translate_panel_event_default(event.as_ref(), source.clone())
+                    .await?;
+                // The nested scrolling ribbon has already scrolled
+                if event.is_handled() {
+                    Ok(())
+                } else {
+                    self.wheel_scroll(event.as_ref(), *delta).await
+                }
+            }
+            PanelEvent::ThemeChanged(theme) => {
+                self.translate_panel_event_theme_changed(theme, source.clone())
                     .await
             }
             _ => {
@@ -314,10 +1073,29 @@ impl Ribbon {
         size: Vector2,
         source: Option<Arc<EventBox>>,
     ) -> crate::Result<()> {
-        self.resize_cells(size).await?;
+        self.resize_cells(size, false).await?;
+        self.send_resized_to_cells(source).await?;
+        // Cells could come into view with the new size
+        self.send_deferred_events().await
+    }
+
+    async fn send_resized_to_cells(&self, source: Option<Arc<EventBox>>) -> crate::Result<()> {
         // TODO: run simultaneosuly
+        let token = self.resize_cancellation.renew();
+        let window_token = resize_cancellation_token(self);
         let cells = self.core.read().await.cells();
         for cell in cells {
+            if token.is_cancelled() || window_token.is_cancelled() {
+                break;
+            }
+            if self.is_occluded(&cell).await? {
+                let mut core = self.core.write().await;
+                core.deferred.entry(cell.panel.id()).or_default().resized = true;
+                continue;
+            }
+            if let Some(deferred) = self.core.write().await.deferred.get_mut(&cell.panel.id()) {
+                deferred.resized = false;
+            }
             let size = cell.container.Size()?;
             cell.panel
                 .on_event_owned(PanelEvent::Resized(size), source.clone())
@@ -326,94 +1104,240 @@ impl Ribbon {
         Ok(())
     }
 
+    async fn translate_panel_event_theme_changed(
+        &self,
+        theme: &Arc<Theme>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        let cells = self.core.read().await.cells();
+        for cell in cells {
+            if self.is_occluded(&cell).await? {
+                let mut core = self.core.write().await;
+                core.deferred.entry(cell.panel.id()).or_default().theme = Some(theme.clone());
+                continue;
+            }
+            if let Some(deferred) = self.core.write().await.deferred.get_mut(&cell.panel.id()) {
+                deferred.theme = None;
+            }
+            cell.panel
+                .on_event_owned(PanelEvent::ThemeChanged(theme.clone()), source.clone())
+                .await?;
+        }
+        Ok(())
+    }
+
+    // The cell of the scrolling ribbon is out of view, so its layout and redraw can wait
+    async fn is_occluded(&self, cell: &Cell) -> crate::Result<bool> {
+        Ok(self.core.read().await.is_scrollable()
+            && !cell.is_in_viewport(self.ribbon_container.Size()?)?)
+    }
+
+    // Send the events held back from the cells which came into view
+    async fn send_deferred_events(&self) -> crate::Result<()> {
+        let cells = {
+            let mut core = self.core.write().await;
+            if core.deferred.is_empty() {
+                return Ok(());
+            }
+            let cells = core.cells();
+            // Forget the removed cells
+            core.deferred
+                .retain(|id, _| cells.iter().any(|cell| cell.panel.id() == *id));
+            cells
+        };
+        for cell in cells {
+            if self.is_occluded(&cell).await? {
+                continue;
+            }
+            let deferred = self.core.write().await.deferred.remove(&cell.panel.id());
+            if let Some(deferred) = deferred {
+                if let Some(theme) = deferred.theme {
+                    cell.panel
+                        .on_event_owned(PanelEvent::ThemeChanged(theme), None)
+                        .await?;
+                }
+                if deferred.resized {
+                    let size = cell.container.Size()?;
+                    cell.panel
+                        .on_event_owned(PanelEvent::Resized(size), None)
+                        .await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
     async fn translate_slot_event_cursor_moved(
         &self,
         mouse_pos: Vector2,
+        seq: u64,
         source: Option<Arc<EventBox>>,
     ) -> crate::Result<()> {
         self.core.write().await.set_mouse_pos(mouse_pos);
         // TODO: run simultaneosuly
         let cells = self.core.read().await.cells();
         for cell in cells {
+            if !self.capture_route.accepts(self, cell.panel.id()) {
+                continue;
+            }
             let mouse_pos = cell.translate_point(mouse_pos)?;
+            // The cell may capture the mouse on move, e.g. the nested ribbon's scrolling drag
+            let had_captures = window_mouse_captures(self);
             cell.panel
-                .on_event_owned(PanelEvent::CursorMoved(mouse_pos), source.clone())
+                .on_event_owned(PanelEvent::CursorMoved(mouse_pos, seq), source.clone())
                 .await?;
+            self.capture_route
+                .update(self, &had_captures, cell.panel.id());
         }
         Ok(())
     }
 
+    // Returns true if the mouse input belongs to the scrolling: the release ending
+    // the drag, which may start the inertial scrolling, or the press stopping it
+    async fn start_or_end_drag(&self, pressed: bool) -> crate::Result<bool> {
+        let now = Instant::now();
+        let (dragged, stopped, glide) = {
+            let mut core = self.core.write().await;
+            let drag = core.drag.take();
+            let dragged = drag.as_ref().map_or(false, |drag| drag.active);
+            let stopped = match core.glide.take() {
+                Some(glide) if pressed && glide.is_running(now) => Some(glide.offset(now)),
+                _ => None,
+            };
+            if let Some(offset) = stopped {
+                core.scroll_offset = offset;
+            }
+            core.drag = match (pressed && core.is_scrollable(), core.mouse_pos) {
+                (true, Some(mouse_pos)) => {
+                    Some(ScrollDrag::new(core.along(mouse_pos), core.scroll_offset))
+                }
+                _ => None,
+            };
+            let glide = match drag {
+                Some(drag) if dragged && !pressed => Glide::new(
+                    core.scroll_offset,
+                    drag.velocity(now),
+                    core.max_scroll_offset,
+                ),
+                _ => None,
+            };
+            core.glide = glide;
+            (dragged, stopped, glide)
+        };
+        if dragged {
+            release_mouse_capture(self, MouseButton::Left);
+        }
+        if let Some(offset) = stopped {
+            self.slide_to(offset, None).await?;
+        }
+        if let Some(glide) = glide {
+            self.slide_to(glide.to, Some(glide.duration)).await?;
+        }
+        Ok(dragged || stopped.is_some())
+    }
+
+    async fn drag_scroll(
+        &self,
+        mouse_pos: Vector2,
+        seq: u64,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        let (offset, started) = {
+            let mut core = self.core.write().await;
+            // The cell's panel or the nested ribbon drags something itself
+            if matches!(mouse_capture(self, MouseButton::Left), Some(id) if id != self.id()) {
+                core.drag = None;
+                return Ok(());
+            }
+            let pos = core.along(mouse_pos);
+            match &mut core.drag {
+                Some(drag) => {
+                    drag.track(pos);
+                    let moved = pos - drag.start_pos;
+                    let started = !drag.active && moved.abs() > DRAG_THRESHOLD;
+                    drag.active |= started;
+                    if !drag.active {
+                        return Ok(());
+                    }
+                    (drag.start_offset - moved, started)
+                }
+                None => return Ok(()),
+            }
+        };
+        if started {
+            // The press turned into the drag: release the pressed cell without the click
+            let release = PanelEvent::MouseInput {
+                in_slot: false,
+                state: ElementState::Released,
+                button: MouseButton::Left,
+                seq,
+            };
+            self.translate_slot_event_mouse_input(&release, source)
+                .await?;
+            // The enclosing containers send the mouse input only to this ribbon until
+            // the button is released, so the outer ribbons don't drag too
+            capture_mouse(self, MouseButton::Left);
+        }
+        self.set_scroll_offset(offset).await
+    }
+
+    async fn wheel_scroll(&self, event: &PanelEvent, delta: Vector2) -> crate::Result<()> {
+        let offset = {
+            let core = self.core.read().await;
+            let in_ribbon = match core.mouse_pos {
+                Some(mouse_pos) => {
+                    is_translated_point_in_box(mouse_pos, self.ribbon_container.Size()?)
+                }
+                None => false,
+            };
+            if !core.is_scrollable() || !in_ribbon {
+                return Ok(());
+            }
+            // Vertical wheel scrolls the horizontal ribbon too
+            let lines = match core.orientation {
+                RibbonOrientation::Horizontal if delta.X != 0. => delta.X,
+                _ => delta.Y,
+            };
+            let offset =
+                (core.scroll_offset - lines * SCROLL_LINE).clamp(0., core.max_scroll_offset);
+            // At the end of the range the wheel scrolls the enclosing ribbon
+            if offset == core.scroll_offset {
+                return Ok(());
+            }
+            offset
+        };
+        event.set_handled();
+        self.set_scroll_offset(offset).await
+    }
+
     async fn translate_slot_event_mouse_input(
         &self,
-        state: ElementState,
-        button: MouseButton,
+        event: &PanelEvent,
         source: Option<Arc<EventBox>>,
     ) -> crate::Result<()> {
         if let Some(mouse_pos) = self.core.read().await.get_mouse_pos() {
+            // Parts of the cells scrolled out of the ribbon are clipped and can't be clicked
+            let in_ribbon = self.core.read().await.overflow != RibbonOverflow::Scroll
+                || is_translated_point_in_box(mouse_pos, self.ribbon_container.Size()?);
             // TODO: run simultaneosuly
             let cells = self.core.read().await.cells();
             for cell in cells {
+                if !self.capture_route.accepts(self, cell.panel.id()) {
+                    continue;
+                }
                 let mouse_pos = cell.translate_point(mouse_pos)?;
-                let in_slot = cell.is_translated_point_in_cell(mouse_pos)?;
+                let in_slot = event.in_slot()
+                    && in_ribbon
+                    && cell.is_translated_point_in_cell(mouse_pos)?
+                    && cell.hit_test(mouse_pos)?;
+                let had_captures = window_mouse_captures(self);
                 cell.panel
-                    .on_event_owned(
-                        PanelEvent::MouseInput {
-                            in_slot,
-                            state,
-                            button,
-                        },
-                        source.clone(),
-                    )
+                    .on_event_owned(event.with_in_slot(in_slot), source.clone())
                     .await?;
+                self.capture_route
+                    .update(self, &had_captures, cell.panel.id());
             }
         }
         Ok(())
     }
 }
-
-fn adjust_cells(limits: Vec<CellLimit>, mut target: f32) -> Vec<f32> {
-    let mut lock = Vec::with_capacity(limits.len());
-    let mut result = Vec::with_capacity(limits.len());
-    lock.resize(limits.len(), false);
-    result.resize(limits.len(), 0.);
-
-    let mut sum_ratio = limits
-        .iter()
-        .map(|c| {
-            assert!(c.ratio > 0.);
-            c.ratio
-        })
-        .sum::<f32>();
-    loop {
-        let mut new_target = target;
-        let mut all_lock = true;
-        for i in 0..limits.len() {
-            if !lock[i] {
-                let mut share = target * limits[i].ratio / sum_ratio;
-                if share <= limits[i].min_size {
-                    share = limits[i].min_size;
-                    lock[i] = true;
-                }
-                if let Some(max_size) = limits[i].max_size {
-                    if share > max_size {
-                        share = max_size;
-                        lock[i] = true;
-                    }
-                }
-                if lock[i] {
-                    new_target -= share;
-                    sum_ratio -= limits[i].ratio;
-                    lock[i] = true;
-                } else {
-                    all_lock = false;
-                }
-                result[i] = share;
-            }
-        }
-        if all_lock || new_target == target {
-            break;
-        }
-        target = if new_target > 0. { new_target } else { 0. };
-    }
-    result
-}
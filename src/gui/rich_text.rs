@@ -0,0 +1,306 @@
+use std::{borrow::Cow, sync::Arc};
+
+use async_event_streams::{
+    spawn_event_pipe, EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
+};
+use async_event_streams_derive::EventSink;
+use async_std::sync::RwLock;
+use async_trait::async_trait;
+use futures::task::Spawn;
+use typed_builder::TypedBuilder;
+use windows::{
+    core::InParam,
+    Foundation::Numerics::{Matrix3x2, Vector2},
+    Graphics::SizeInt32,
+    Win32::{
+        Foundation::POINT,
+        Graphics::{
+            Direct2D::{
+                Common::{D2D1_COLOR_F, D2D_POINT_2F},
+                ID2D1DeviceContext, D2D1_BRUSH_PROPERTIES, D2D1_DRAW_TEXT_OPTIONS_NONE,
+            },
+            DirectWrite::{
+                IDWriteTextLayout, DWRITE_FONT_STRETCH_NORMAL, DWRITE_FONT_STYLE_ITALIC,
+                DWRITE_FONT_STYLE_NORMAL, DWRITE_FONT_WEIGHT_BOLD, DWRITE_FONT_WEIGHT_NORMAL,
+                DWRITE_TEXT_RANGE,
+            },
+        },
+    },
+    UI::{
+        Color, Colors,
+        Composition::{Compositor, Visual},
+    },
+};
+
+use crate::{
+    on_err,
+    window::{dwrite_factory, ToWide},
+};
+
+use super::{surface::SurfaceEvent, Panel, PanelEvent, Surface, SurfaceFormat, SurfaceParams};
+
+///
+/// Fragment of the `RichText` with its own style
+///
+#[derive(Clone, Debug, TypedBuilder)]
+pub struct TextRun {
+    #[builder(setter(into))]
+    text: String,
+    #[builder(default = Color { A: 0xFF, R: 0, G: 0, B: 0 })]
+    color: Color,
+    #[builder(default)]
+    bold: bool,
+    #[builder(default)]
+    italic: bool,
+    #[builder(default)]
+    underline: bool,
+}
+
+impl TextRun {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+fn to_d2d_color(color: Color) -> D2D1_COLOR_F {
+    D2D1_COLOR_F {
+        r: color.R as f32 / 255.,
+        g: color.G as f32 / 255.,
+        b: color.B as f32 / 255.,
+        a: color.A as f32 / 255.,
+    }
+}
+
+#[derive(EventSink)]
+#[event_sink(event=SurfaceEvent)]
+struct Core {
+    surface: Arc<Surface>,
+    runs: Vec<TextRun>,
+    font_family: String,
+    font_size: f32,
+}
+
+async fn redraw(size: Vector2, core: &Core) -> crate::Result<()> {
+    let new_surface_size = SizeInt32 {
+        Width: size.X as i32,
+        Height: size.Y as i32,
+    };
+    core.surface.resize_surface(new_surface_size).await?;
+    core.surface
+        .draw(|context, point| draw_runs(context, point, size, core))
+        .await
+}
+
+fn create_text_layout(core: &Core, size: Vector2) -> crate::Result<IDWriteTextLayout> {
+    let font_family = core.font_family.to_wide();
+    let locale = "en-US".to_wide();
+    let text_format = unsafe {
+        dwrite_factory()?.CreateTextFormat(
+            font_family.as_pcwstr(),
+            InParam::null(),
+            DWRITE_FONT_WEIGHT_NORMAL,
+            DWRITE_FONT_STYLE_NORMAL,
+            DWRITE_FONT_STRETCH_NORMAL,
+            core.font_size,
+            locale.as_pcwstr(),
+        )
+    }?;
+    let text: Vec<u16> = core
+        .runs
+        .iter()
+        .flat_map(|run| run.text.encode_utf16())
+        .collect();
+    Ok(unsafe {
+        dwrite_factory()?.CreateTextLayout(text.as_slice(), &text_format, size.X, size.Y)
+    }?)
+}
+
+fn draw_runs(
+    context: ID2D1DeviceContext,
+    point: POINT,
+    size: Vector2,
+    core: &Core,
+) -> crate::Result<()> {
+    let text_layout = create_text_layout(core, size)?;
+    let brush_properties = D2D1_BRUSH_PROPERTIES {
+        opacity: 1.,
+        transform: Matrix3x2::identity(),
+    };
+    // Ranges of the text layout are measured in UTF-16 code units
+    let mut start = 0;
+    for run in &core.runs {
+        let length = run.text.encode_utf16().count() as u32;
+        let range = DWRITE_TEXT_RANGE {
+            startPosition: start,
+            length,
+        };
+        start += length;
+        let weight = if run.bold {
+            DWRITE_FONT_WEIGHT_BOLD
+        } else {
+            DWRITE_FONT_WEIGHT_NORMAL
+        };
+        let style = if run.italic {
+            DWRITE_FONT_STYLE_ITALIC
+        } else {
+            DWRITE_FONT_STYLE_NORMAL
+        };
+        let brush = unsafe {
+            context.CreateSolidColorBrush(&to_d2d_color(run.color), Some(&brush_properties))
+        }?;
+        unsafe {
+            text_layout.SetFontWeight(weight, range)?;
+            text_layout.SetFontStyle(style, range)?;
+            text_layout.SetUnderline(run.underline, range)?;
+            text_layout.SetDrawingEffect(&brush, range)?;
+        }
+    }
+
+    let clearcolor = D2D1_COLOR_F {
+        r: 0.,
+        g: 0.,
+        b: 0.,
+        a: 0.,
+    };
+    let default_brush = unsafe {
+        context.CreateSolidColorBrush(&to_d2d_color(Colors::Black()?), Some(&brush_properties))
+    }?;
+    unsafe {
+        context.Clear(Some(&clearcolor));
+        context.DrawTextLayout(
+            D2D_POINT_2F {
+                x: point.x as f32,
+                y: point.y as f32,
+            },
+            &text_layout,
+            &default_brush,
+            D2D1_DRAW_TEXT_OPTIONS_NONE,
+        );
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl EventSinkExt<SurfaceEvent> for Core {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, SurfaceEvent>,
+        _: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        match event.as_ref() {
+            SurfaceEvent::Redraw(size) => redraw(*size, self).await?,
+        }
+        Ok(())
+    }
+}
+
+///
+/// Text composed of the runs with different color, weight, style and underline
+///
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+pub struct RichText {
+    surface: Arc<Surface>,
+    core: Arc<RwLock<Core>>,
+    panel_events: EventStreams<PanelEvent>,
+    id: Arc<()>,
+}
+
+impl RichText {
+    pub async fn runs(&self) -> Vec<TextRun> {
+        self.core.read().await.runs.clone()
+    }
+
+    pub async fn set_runs(&self, runs: Vec<TextRun>) {
+        self.core.write().await.runs = runs;
+        self.surface.request_redraw().await;
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for RichText {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        self.surface
+            .on_event_ref(event.as_ref(), source.clone())
+            .await?;
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
+
+impl EventSource<PanelEvent> for RichText {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+impl Panel for RichText {
+    fn outer_frame(&self) -> Visual {
+        self.surface.outer_frame()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+}
+
+#[derive(TypedBuilder)]
+pub struct RichTextParams<T: Spawn> {
+    compositor: Compositor,
+    spawner: T,
+    #[builder(default)]
+    runs: Vec<TextRun>,
+    #[builder(default = "Segoe UI".into(), setter(into))]
+    font_family: String,
+    #[builder(default = 30.)]
+    font_size: f32,
+    #[builder(default)]
+    format: SurfaceFormat,
+}
+
+impl<T: Spawn> RichTextParams<T> {
+    pub fn add_run(mut self, run: TextRun) -> Self {
+        self.runs.push(run);
+        self
+    }
+}
+
+impl<T: Spawn> TryFrom<RichTextParams<T>> for RichText {
+    type Error = crate::Error;
+
+    fn try_from(value: RichTextParams<T>) -> crate::Result<Self> {
+        let surface: Arc<Surface> = SurfaceParams::builder()
+            .compositor(value.compositor)
+            .format(value.format)
+            .build()
+            .try_into()?;
+        let core = Arc::new(RwLock::new(Core {
+            surface: surface.clone(),
+            runs: value.runs,
+            font_family: value.font_family,
+            font_size: value.font_size,
+        }));
+        spawn_event_pipe(&value.spawner, &surface, core.clone(), on_err)?;
+        Ok(RichText {
+            surface,
+            core,
+            panel_events: EventStreams::new(),
+            id: Arc::new(()),
+        })
+    }
+}
+
+impl<T: Spawn> TryFrom<RichTextParams<T>> for Arc<RichText> {
+    type Error = crate::Error;
+
+    fn try_from(value: RichTextParams<T>) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
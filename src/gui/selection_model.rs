@@ -0,0 +1,325 @@
+use std::collections::BTreeSet;
+
+use async_event_streams::{EventSource, EventStream, EventStreams};
+use async_std::sync::{Mutex, RwLock};
+use winit::event::ModifiersState;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum SelectionMode {
+    #[default]
+    Single,
+    Multiple,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum SelectionEvent {
+    Changed {
+        added: Vec<usize>,
+        removed: Vec<usize>,
+    },
+}
+
+#[derive(Default)]
+struct Core {
+    mode: SelectionMode,
+    selected: BTreeSet<usize>,
+    anchor: Option<usize>,
+}
+
+impl Core {
+    fn update(&mut self, selected: BTreeSet<usize>) -> Option<SelectionEvent> {
+        let added: Vec<usize> = selected.difference(&self.selected).copied().collect();
+        let removed: Vec<usize> = self.selected.difference(&selected).copied().collect();
+        self.selected = selected;
+        if added.is_empty() && removed.is_empty() {
+            None
+        } else {
+            Some(SelectionEvent::Changed { added, removed })
+        }
+    }
+}
+
+///
+/// Selected item indices of the list-like widget. Kept apart from the rendering
+/// so that all such widgets share the same Ctrl/Shift behavior.
+///
+pub struct SelectionModel {
+    core: RwLock<Core>,
+    // Held from the change to its delivery, so the changes are reported in order.
+    // The core is unlocked before the delivery: the subscribers may read the selection.
+    changing: Mutex<()>,
+    selection_events: EventStreams<SelectionEvent>,
+}
+
+impl SelectionModel {
+    pub fn new(mode: SelectionMode) -> Self {
+        Self {
+            core: RwLock::new(Core {
+                mode,
+                ..Default::default()
+            }),
+            changing: Mutex::new(()),
+            selection_events: EventStreams::new(),
+        }
+    }
+
+    pub async fn mode(&self) -> SelectionMode {
+        self.core.read().await.mode
+    }
+
+    pub async fn selected(&self) -> Vec<usize> {
+        self.core.read().await.selected.iter().copied().collect()
+    }
+
+    pub async fn is_selected(&self, index: usize) -> bool {
+        self.core.read().await.selected.contains(&index)
+    }
+
+    ///
+    /// Item from which the Shift-extended range starts
+    ///
+    pub async fn anchor(&self) -> Option<usize> {
+        self.core.read().await.anchor
+    }
+
+    ///
+    /// Update the selection on item click. Without modifiers the item becomes the only
+    /// selected one. In the multiple mode Ctrl toggles the item, Shift selects the range
+    /// from the anchor, Ctrl+Shift adds this range to the current selection.
+    ///
+    pub async fn click(&self, index: usize, modifiers: ModifiersState) {
+        let _changing = self.changing.lock().await;
+        let mut core = self.core.write().await;
+        let mut selected = core.selected.clone();
+        let ctrl = modifiers.ctrl() && core.mode == SelectionMode::Multiple;
+        let shift = modifiers.shift() && core.mode == SelectionMode::Multiple;
+        match (core.anchor, shift) {
+            (Some(anchor), true) => {
+                if !ctrl {
+                    selected.clear();
+                }
+                selected.extend(anchor.min(index)..=anchor.max(index));
+            }
+            _ => {
+                if ctrl {
+                    if !selected.remove(&index) {
+                        selected.insert(index);
+                    }
+                } else {
+                    selected.clear();
+                    selected.insert(index);
+                }
+                core.anchor = Some(index);
+            }
+        }
+        let change = core.update(selected);
+        drop(core);
+        self.send_change(change).await;
+    }
+
+    pub async fn select(&self, index: usize) {
+        self.click(index, ModifiersState::empty()).await
+    }
+
+    ///
+    /// Select items in the inclusive range. In the single mode only the last item is selected
+    /// and becomes the anchor, as if it was clicked.
+    ///
+    pub async fn select_range(&self, from: usize, to: usize) {
+        let _changing = self.changing.lock().await;
+        let mut core = self.core.write().await;
+        let selected = if core.mode == SelectionMode::Single {
+            core.anchor = Some(to);
+            BTreeSet::from([to])
+        } else {
+            core.anchor = Some(from);
+            (from.min(to)..=from.max(to)).collect()
+        };
+        let change = core.update(selected);
+        drop(core);
+        self.send_change(change).await;
+    }
+
+    pub async fn clear(&self) {
+        let _changing = self.changing.lock().await;
+        let mut core = self.core.write().await;
+        core.anchor = None;
+        let change = core.update(BTreeSet::new());
+        drop(core);
+        self.send_change(change).await;
+    }
+
+    ///
+    /// Shift indices after inserting `count` items at `index`
+    ///
+    pub async fn items_inserted(&self, index: usize, count: usize) {
+        let _changing = self.changing.lock().await;
+        let mut core = self.core.write().await;
+        let shift = |i: usize| if i >= index { i + count } else { i };
+        core.selected = core.selected.iter().map(|i| shift(*i)).collect();
+        core.anchor = core.anchor.map(shift);
+    }
+
+    ///
+    /// Unselect removed items and shift indices after removing `count` items at `index`
+    ///
+    pub async fn items_removed(&self, index: usize, count: usize) {
+        let _changing = self.changing.lock().await;
+        let mut core = self.core.write().await;
+        let removed = index..index + count;
+        let selected = core
+            .selected
+            .iter()
+            .filter(|i| !removed.contains(*i))
+            .copied()
+            .collect();
+        let change = core.update(selected);
+        let shift = |i: usize| if i >= removed.end { i - count } else { i };
+        core.selected = core.selected.iter().map(|i| shift(*i)).collect();
+        core.anchor = core.anchor.filter(|i| !removed.contains(i)).map(shift);
+        drop(core);
+        self.send_change(change).await;
+    }
+
+    async fn send_change(&self, change: Option<SelectionEvent>) {
+        if let Some(change) = change {
+            self.selection_events.send_event(change, None).await;
+        }
+    }
+}
+
+impl EventSource<SelectionEvent> for SelectionModel {
+    fn event_stream(&self) -> EventStream<SelectionEvent> {
+        self.selection_events.create_event_stream()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, future::join, StreamExt};
+
+    use super::*;
+
+    #[test]
+    fn click_without_modifiers_selects_one() {
+        block_on(async {
+            let model = SelectionModel::new(SelectionMode::Multiple);
+            model.select(2).await;
+            model.select(4).await;
+            assert_eq!(model.selected().await, vec![4]);
+            assert_eq!(model.anchor().await, Some(4));
+        })
+    }
+
+    #[test]
+    fn ctrl_toggles_and_shift_extends() {
+        block_on(async {
+            let model = SelectionModel::new(SelectionMode::Multiple);
+            model.select(2).await;
+            model.click(5, ModifiersState::CTRL).await;
+            assert_eq!(model.selected().await, vec![2, 5]);
+            model.click(2, ModifiersState::CTRL).await;
+            assert_eq!(model.selected().await, vec![5]);
+            // Ctrl click moves the anchor even when it unselects the item
+            model.click(4, ModifiersState::SHIFT).await;
+            assert_eq!(model.selected().await, vec![2, 3, 4]);
+            assert_eq!(model.anchor().await, Some(2));
+            model.select(0).await;
+            model
+                .click(1, ModifiersState::CTRL | ModifiersState::SHIFT)
+                .await;
+            assert_eq!(model.selected().await, vec![0, 1]);
+        })
+    }
+
+    #[test]
+    fn single_mode_ignores_modifiers() {
+        block_on(async {
+            let model = SelectionModel::new(SelectionMode::Single);
+            model.select(1).await;
+            model.click(3, ModifiersState::CTRL).await;
+            assert_eq!(model.selected().await, vec![3]);
+            model.click(5, ModifiersState::SHIFT).await;
+            assert_eq!(model.selected().await, vec![5]);
+        })
+    }
+
+    #[test]
+    fn select_range_sets_anchor() {
+        block_on(async {
+            let model = SelectionModel::new(SelectionMode::Multiple);
+            model.select_range(6, 3).await;
+            assert_eq!(model.selected().await, vec![3, 4, 5, 6]);
+            assert_eq!(model.anchor().await, Some(6));
+
+            let model = SelectionModel::new(SelectionMode::Single);
+            model.select_range(2, 7).await;
+            assert_eq!(model.selected().await, vec![7]);
+            // The anchor is the selected item, not the start of the range
+            assert_eq!(model.anchor().await, Some(7));
+        })
+    }
+
+    #[test]
+    fn indices_follow_inserted_and_removed_items() {
+        block_on(async {
+            let model = SelectionModel::new(SelectionMode::Multiple);
+            model.select_range(2, 4).await;
+            model.items_inserted(3, 2).await;
+            assert_eq!(model.selected().await, vec![2, 5, 6]);
+            assert_eq!(model.anchor().await, Some(2));
+            model.items_removed(1, 2).await;
+            assert_eq!(model.selected().await, vec![3, 4]);
+            assert_eq!(model.anchor().await, None);
+        })
+    }
+
+    #[test]
+    fn changes_are_reported() {
+        block_on(async {
+            let model = SelectionModel::new(SelectionMode::Multiple);
+            let mut stream = model.event_stream();
+            let change = async {
+                model.select(1).await;
+                model.select(2).await;
+            };
+            let events = async {
+                let mut events = Vec::new();
+                for _ in 0..2 {
+                    events.push((*stream.next().await.unwrap()).clone());
+                }
+                events
+            };
+            let ((), events) = join(change, events).await;
+            assert_eq!(
+                events,
+                vec![
+                    SelectionEvent::Changed {
+                        added: vec![1],
+                        removed: vec![]
+                    },
+                    SelectionEvent::Changed {
+                        added: vec![2],
+                        removed: vec![1]
+                    },
+                ]
+            );
+        })
+    }
+
+    #[test]
+    fn subscriber_reads_selection_during_change() {
+        block_on(async {
+            let model = SelectionModel::new(SelectionMode::Multiple);
+            let mut stream = model.event_stream();
+            let read = async {
+                let event = stream.next().await.unwrap();
+                let selected = model.selected().await;
+                drop(event);
+                selected
+            };
+            let ((), selected) = join(model.select(1), read).await;
+            assert_eq!(selected, vec![1]);
+        })
+    }
+}
@@ -0,0 +1,302 @@
+use std::borrow::Cow;
+
+use async_event_streams::{
+    EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
+};
+use async_event_streams_derive::EventSink;
+use async_std::sync::Arc;
+use async_trait::async_trait;
+use futures::task::Spawn;
+use typed_builder::TypedBuilder;
+use windows::{
+    Foundation::Numerics::Vector2,
+    UI::Composition::{Compositor, Visual},
+};
+
+use super::{
+    theme::Theme, Background, BackgroundParams, CellAlignment, CellLimit, LayerStack,
+    LayerStackParams, Margins, Panel, PanelEvent, Ribbon, RibbonOrientation, RibbonParams, Text,
+    TextParams,
+};
+
+// Height of the trailing control relative to the row
+const CONTROL_HEIGHT_RATIO: f32 = 0.6;
+
+///
+/// Row of the settings page: optional icon, title with the optional description below it
+/// and the trailing control, e.g. the button changing the setting
+///
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+pub struct SettingsRow {
+    layer_stack: LayerStack,
+    title: Arc<Text>,
+    description: Option<Arc<Text>>,
+    panel_events: EventStreams<PanelEvent>,
+    id: Arc<()>,
+}
+
+#[derive(TypedBuilder)]
+pub struct SettingsRowParams<T: Spawn + Clone> {
+    compositor: Compositor,
+    spawner: T,
+    #[builder(setter(into))]
+    title: String,
+    #[builder(default, setter(strip_option, into))]
+    description: Option<String>,
+    /// Short text shown before the title, e.g. the symbol of the icon font
+    #[builder(default, setter(strip_option, into))]
+    icon: Option<String>,
+    #[builder(default, setter(transform = |panel: impl Panel + 'static| Some(Arc::new(panel) as Arc<dyn Panel>)))]
+    control: Option<Arc<dyn Panel>>,
+    #[builder(default = 160.)]
+    control_width: f32,
+    /// Theme to use instead of the global one
+    #[builder(default, setter(into))]
+    theme: Option<Arc<Theme>>,
+}
+
+impl<T: Spawn + Clone> TryFrom<SettingsRowParams<T>> for SettingsRow {
+    type Error = crate::Error;
+
+    fn try_from(value: SettingsRowParams<T>) -> crate::Result<Self> {
+        let text = |text: String| -> crate::Result<Arc<Text>> {
+            TextParams::builder()
+                .compositor(value.compositor.clone())
+                .text(text)
+                .spawner(value.spawner.clone())
+                .theme(value.theme.clone())
+                .build()
+                .try_into()
+        };
+        let title = text(value.title)?;
+        let description = value.description.map(text).transpose()?;
+
+        let mut labels = RibbonParams::builder()
+            .compositor(value.compositor.clone())
+            .orientation(RibbonOrientation::Vertical)
+            .build()
+            .add_panel(title.clone(), CellLimit::new(3., 20., None, None))?;
+        if let Some(description) = &description {
+            labels = labels.add_panel(description.clone(), CellLimit::new(2., 16., None, None))?;
+        }
+        let labels: Arc<Ribbon> = labels.try_into()?;
+
+        let mut row = RibbonParams::builder()
+            .compositor(value.compositor.clone())
+            .orientation(RibbonOrientation::Horizontal)
+            .spacing(12.)
+            .padding(Margins::symmetric(16., 8.))
+            .build();
+        if let Some(icon) = value.icon {
+            row = row.add_panel(text(icon)?, CellLimit::new(1., 32., Some(32.), None))?;
+        }
+        row = row.add_panel(labels, CellLimit::new(1., 0., None, None))?;
+        if let Some(control) = value.control {
+            let limit = CellLimit::new(
+                1.,
+                value.control_width,
+                Some(value.control_width),
+                Some(Vector2::new(1., CONTROL_HEIGHT_RATIO)),
+            )
+            .with_alignment(CellAlignment::End, CellAlignment::Center);
+            row = row.add_panel(control, limit)?;
+        }
+        let row: Arc<Ribbon> = row.try_into()?;
+
+        let background: Arc<Background> = BackgroundParams::builder()
+            .compositor(value.compositor.clone())
+            .round_corners(true)
+            .theme(value.theme)
+            .build()
+            .try_into()?;
+        let layer_stack = LayerStackParams::builder()
+            .compositor(value.compositor)
+            .build()
+            .push_panel(background)
+            .push_panel(row)
+            .try_into()?;
+        Ok(SettingsRow {
+            layer_stack,
+            title,
+            description,
+            panel_events: EventStreams::new(),
+            id: Arc::new(()),
+        })
+    }
+}
+
+impl<T: Spawn + Clone> TryFrom<SettingsRowParams<T>> for Arc<SettingsRow> {
+    type Error = crate::Error;
+
+    fn try_from(value: SettingsRowParams<T>) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
+
+impl SettingsRow {
+    pub async fn set_title(&self, title: impl Into<String>) {
+        self.title.set_text(title).await
+    }
+    ///
+    /// Change the description, does nothing if the row was created without it
+    ///
+    pub async fn set_description(&self, description: impl Into<String>) {
+        if let Some(text) = &self.description {
+            text.set_text(description).await
+        }
+    }
+}
+
+impl Panel for SettingsRow {
+    fn outer_frame(&self) -> Visual {
+        self.layer_stack.outer_frame()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+}
+
+impl EventSource<PanelEvent> for SettingsRow {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for SettingsRow {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        self.layer_stack
+            .on_event_ref(event.as_ref(), source.clone())
+            .await?;
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
+
+///
+/// Titled group of the settings rows stacked vertically
+///
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+pub struct SettingsGroup {
+    ribbon: Ribbon,
+    header: Arc<Text>,
+    row_limit: CellLimit,
+    panel_events: EventStreams<PanelEvent>,
+    id: Arc<()>,
+}
+
+#[derive(TypedBuilder)]
+pub struct SettingsGroupParams<T: Spawn + Clone> {
+    compositor: Compositor,
+    spawner: T,
+    #[builder(setter(into))]
+    header: String,
+    #[builder(default = 64.)]
+    row_height: f32,
+    #[builder(default)]
+    rows: Vec<Arc<dyn Panel>>,
+    /// Theme to use instead of the global one
+    #[builder(default, setter(into))]
+    theme: Option<Arc<Theme>>,
+}
+
+impl<T: Spawn + Clone> SettingsGroupParams<T> {
+    pub fn add_row(mut self, row: impl Panel + 'static) -> Self {
+        self.rows.push(Arc::new(row));
+        self
+    }
+}
+
+impl<T: Spawn + Clone> TryFrom<SettingsGroupParams<T>> for SettingsGroup {
+    type Error = crate::Error;
+
+    fn try_from(value: SettingsGroupParams<T>) -> crate::Result<Self> {
+        let header: Arc<Text> = TextParams::builder()
+            .compositor(value.compositor.clone())
+            .text(value.header)
+            .spawner(value.spawner)
+            .theme(value.theme)
+            .build()
+            .try_into()?;
+        let row_limit = CellLimit::new(1., value.row_height, Some(value.row_height), None);
+        let mut ribbon = RibbonParams::builder()
+            .compositor(value.compositor)
+            .orientation(RibbonOrientation::Vertical)
+            .spacing(4.)
+            .build()
+            .add_panel(
+                header.clone(),
+                CellLimit::new(1., 32., Some(32.), None)
+                    .with_alignment(CellAlignment::Start, CellAlignment::Center),
+            )?;
+        for row in value.rows {
+            ribbon = ribbon.add_panel(row, row_limit)?;
+        }
+        Ok(SettingsGroup {
+            ribbon: ribbon.try_into()?,
+            header,
+            row_limit,
+            panel_events: EventStreams::new(),
+            id: Arc::new(()),
+        })
+    }
+}
+
+impl<T: Spawn + Clone> TryFrom<SettingsGroupParams<T>> for Arc<SettingsGroup> {
+    type Error = crate::Error;
+
+    fn try_from(value: SettingsGroupParams<T>) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
+
+impl SettingsGroup {
+    pub async fn add_row(&self, row: Arc<dyn Panel>) -> crate::Result<()> {
+        self.ribbon.add_panel(row, self.row_limit).await
+    }
+    pub async fn set_header(&self, header: impl Into<String>) {
+        self.header.set_text(header).await
+    }
+}
+
+impl Panel for SettingsGroup {
+    fn outer_frame(&self) -> Visual {
+        self.ribbon.outer_frame()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+}
+
+impl EventSource<PanelEvent> for SettingsGroup {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for SettingsGroup {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        self.ribbon
+            .on_event_ref(event.as_ref(), source.clone())
+            .await?;
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
@@ -0,0 +1,199 @@
+use std::borrow::Cow;
+
+use async_event_streams::{
+    EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
+};
+use async_event_streams_derive::EventSink;
+use async_std::sync::{Arc, RwLock};
+use async_trait::async_trait;
+use typed_builder::TypedBuilder;
+use windows::{
+    Foundation::Numerics::Vector2,
+    UI::Composition::{Compositor, ContainerVisual, Visual},
+};
+
+use crate::gui::{
+    theme::{Theme, ThemeRef},
+    ButtonVisualState, Panel, PanelEvent,
+};
+
+use super::{accent_color, stroke_color, Part};
+
+const BOX_SIZE: f32 = 20.;
+const STROKE: f32 = 1.;
+// Part of the box side occupied by the check mark
+const MARK_RATIO: f32 = 0.4;
+
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub struct CheckBoxState {
+    pub visual: ButtonVisualState,
+    pub checked: bool,
+}
+
+struct Core {
+    size: Vector2,
+    state: CheckBoxState,
+    theme: ThemeRef,
+}
+
+///
+/// Check box square aligned to the left, filled with accent color when checked
+///
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+#[event_sink(event=CheckBoxState)]
+pub struct FluentCheckBoxSkin {
+    container: ContainerVisual,
+    frame: Part,
+    fill: Part,
+    mark: Part,
+    core: RwLock<Core>,
+    panel_events: EventStreams<PanelEvent>,
+    id: Arc<()>,
+}
+
+#[derive(TypedBuilder)]
+pub struct FluentCheckBoxSkinParams {
+    compositor: Compositor,
+    /// Theme to use instead of the global one
+    #[builder(default, setter(into))]
+    theme: Option<Arc<Theme>>,
+}
+
+impl TryFrom<FluentCheckBoxSkinParams> for FluentCheckBoxSkin {
+    type Error = crate::Error;
+
+    fn try_from(value: FluentCheckBoxSkinParams) -> crate::Result<Self> {
+        let compositor = value.compositor;
+        let container = compositor.CreateContainerVisual()?;
+        let theme = ThemeRef::new(value.theme);
+        let fill_color = theme.get().background_color;
+        let frame = Part::new(&compositor, &container, fill_color)?;
+        let fill = Part::new(&compositor, &container, fill_color)?;
+        let mark = Part::new(&compositor, &container, fill_color)?;
+        Ok(FluentCheckBoxSkin {
+            container,
+            frame,
+            fill,
+            mark,
+            core: RwLock::new(Core {
+                size: Vector2::default(),
+                state: CheckBoxState::default(),
+                theme,
+            }),
+            panel_events: EventStreams::new(),
+            id: Arc::new(()),
+        })
+    }
+}
+
+impl TryFrom<FluentCheckBoxSkinParams> for Arc<FluentCheckBoxSkin> {
+    type Error = crate::Error;
+
+    fn try_from(value: FluentCheckBoxSkinParams) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
+
+impl FluentCheckBoxSkin {
+    fn redraw(&self, core: &Core) -> crate::Result<()> {
+        let side = core.size.X.min(core.size.Y).min(BOX_SIZE);
+        let offset = Vector2 {
+            X: 0.,
+            Y: (core.size.Y - side) / 2.,
+        };
+        let state = core.state;
+        let theme = core.theme.get();
+        let (frame_color, fill_color) = if state.checked {
+            let accent = accent_color(theme, state.visual);
+            (accent, accent)
+        } else {
+            (stroke_color(state.visual), theme.background_color)
+        };
+        self.mark.set_color(theme.background_color)?;
+        self.frame.set_color(frame_color)?;
+        self.frame.place(offset, Vector2 { X: side, Y: side })?;
+        self.fill.set_color(fill_color)?;
+        self.fill.place(
+            Vector2 {
+                X: offset.X + STROKE,
+                Y: offset.Y + STROKE,
+            },
+            Vector2 {
+                X: side - STROKE * 2.,
+                Y: side - STROKE * 2.,
+            },
+        )?;
+        let mark_side = if state.checked { side * MARK_RATIO } else { 0. };
+        self.mark.place(
+            Vector2 {
+                X: offset.X + (side - mark_side) / 2.,
+                Y: offset.Y + (side - mark_side) / 2.,
+            },
+            Vector2 {
+                X: mark_side,
+                Y: mark_side,
+            },
+        )?;
+        Ok(())
+    }
+}
+
+impl Panel for FluentCheckBoxSkin {
+    fn outer_frame(&self) -> Visual {
+        self.container.clone().into()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+}
+
+impl EventSource<PanelEvent> for FluentCheckBoxSkin {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<CheckBoxState> for FluentCheckBoxSkin {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, CheckBoxState>,
+        _: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        let mut core = self.core.write().await;
+        core.state = *event;
+        self.redraw(&core)
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for FluentCheckBoxSkin {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        match event.as_ref() {
+            PanelEvent::Resized(size) => {
+                self.container.SetSize(*size)?;
+                let mut core = self.core.write().await;
+                core.size = *size;
+                self.redraw(&core)?;
+            }
+            PanelEvent::ThemeChanged(theme) => {
+                let mut core = self.core.write().await;
+                if core.theme.update(theme) {
+                    self.redraw(&core)?;
+                }
+            }
+            _ => {}
+        }
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
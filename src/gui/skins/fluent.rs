@@ -0,0 +1,320 @@
+use std::{borrow::Cow, time::Duration};
+
+use async_event_streams::{
+    EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
+};
+use async_event_streams_derive::EventSink;
+use async_std::sync::{Arc, RwLock};
+use async_trait::async_trait;
+use futures::task::Spawn;
+use typed_builder::TypedBuilder;
+use windows::{
+    h,
+    Foundation::Numerics::Vector2,
+    UI::{
+        Color,
+        Composition::{
+            CompositionBrush, CompositionColorBrush, CompositionRoundedRectangleGeometry,
+            Compositor, ContainerVisual, ShapeVisual, Visual,
+        },
+        ViewManagement::{UIColorType, UISettings},
+    },
+};
+
+use crate::gui::{
+    attach,
+    easing::{Easing, EasingMode},
+    theme::{Theme, ThemeRef},
+    ButtonTransition, ButtonVisualState, Panel, PanelEvent, Text, TextParams,
+};
+
+use super::{rgb, CONTROL_DISABLED};
+
+pub const CORNER_RADIUS: f32 = 4.;
+pub const SPACING_SMALL: f32 = 4.;
+pub const SPACING: f32 = 8.;
+pub const SPACING_LARGE: f32 = 16.;
+pub const CONTROL_HEIGHT: f32 = 32.;
+pub const TRANSITION_DURATION: Duration = Duration::from_millis(83);
+
+const SURFACE_TINT: Color = Color {
+    A: 0xCC,
+    R: 0xF3,
+    G: 0xF3,
+    B: 0xF3,
+};
+
+///
+/// Colors of the Fluent look, taken from the theme unless set explicitly
+///
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct FluentPalette {
+    pub accent: Color,
+    pub accent_hover: Color,
+    pub accent_pressed: Color,
+    pub accent_subtle: Color,
+    pub disabled: Color,
+    pub surface: Color,
+}
+
+impl Default for FluentPalette {
+    fn default() -> Self {
+        Self {
+            accent: rgb(0x00, 0x78, 0xD4),
+            accent_hover: rgb(0x42, 0x9C, 0xE3),
+            accent_pressed: rgb(0x00, 0x5A, 0x9E),
+            accent_subtle: rgb(0x99, 0xEB, 0xFF),
+            disabled: CONTROL_DISABLED,
+            surface: SURFACE_TINT,
+        }
+    }
+}
+
+impl FluentPalette {
+    ///
+    /// Palette with the accent colors of the theme
+    ///
+    pub fn from_theme(theme: &Theme) -> Self {
+        Self {
+            accent: theme.accent_color,
+            accent_hover: theme.accent_hover_color,
+            accent_pressed: theme.accent_pressed_color,
+            accent_subtle: theme.accent_color,
+            disabled: theme.disabled_color,
+            surface: Color {
+                A: SURFACE_TINT.A,
+                ..theme.background_color
+            },
+        }
+    }
+
+    ///
+    /// Palette derived from the system accent color
+    ///
+    pub fn from_system() -> crate::Result<Self> {
+        let settings = UISettings::new()?;
+        Ok(Self {
+            accent: settings.GetColorValue(UIColorType::Accent)?,
+            accent_hover: settings.GetColorValue(UIColorType::AccentLight1)?,
+            accent_pressed: settings.GetColorValue(UIColorType::AccentDark1)?,
+            accent_subtle: settings.GetColorValue(UIColorType::AccentLight2)?,
+            ..Self::default()
+        })
+    }
+
+    pub fn control_color(&self, state: ButtonVisualState) -> Color {
+        match state {
+            ButtonVisualState::Normal | ButtonVisualState::Focused => self.accent_subtle,
+            ButtonVisualState::Hover => self.accent_hover,
+            ButtonVisualState::Pressed => self.accent_pressed,
+            ButtonVisualState::Disabled => self.disabled,
+        }
+    }
+}
+
+///
+/// Acrylic-like brush for panel surfaces: blurred desktop wallpaper where the system
+/// supports it, translucent tint otherwise
+///
+pub fn create_surface_brush(
+    compositor: &Compositor,
+    palette: &FluentPalette,
+) -> crate::Result<CompositionBrush> {
+    if let Ok(brush) = compositor.TryCreateBlurredWallpaperBackdropBrush() {
+        return Ok(brush.into());
+    }
+    Ok(compositor
+        .CreateColorBrushWithColor(palette.surface)?
+        .into())
+}
+
+///
+/// Button skin with rounded accent colored background and the text label.
+/// The color change is animated when the skin receives the button's transition events.
+///
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+#[event_sink(event=ButtonVisualState)]
+#[event_sink(event=ButtonTransition)]
+pub struct FluentButtonSkin {
+    container: ContainerVisual,
+    shape: ShapeVisual,
+    geometry: CompositionRoundedRectangleGeometry,
+    brush: CompositionColorBrush,
+    text: Arc<Text>,
+    core: RwLock<Core>,
+    panel_events: EventStreams<PanelEvent>,
+    id: Arc<()>,
+}
+
+struct Core {
+    // Palette set explicitly instead of the theme's colors
+    palette: Option<FluentPalette>,
+    theme: ThemeRef,
+    state: ButtonVisualState,
+}
+
+impl Core {
+    fn palette(&self) -> FluentPalette {
+        self.palette
+            .unwrap_or_else(|| FluentPalette::from_theme(self.theme.get()))
+    }
+    fn color(&self) -> Color {
+        self.palette().control_color(self.state)
+    }
+}
+
+#[derive(TypedBuilder)]
+pub struct FluentButtonSkinParams<T: Spawn> {
+    compositor: Compositor,
+    text: String,
+    spawner: T,
+    /// Colors to use instead of the theme's ones, e.g. `FluentPalette::from_system()`
+    #[builder(default, setter(strip_option))]
+    palette: Option<FluentPalette>,
+    /// Theme to use instead of the global one
+    #[builder(default, setter(into))]
+    theme: Option<Arc<Theme>>,
+}
+
+impl<T: Spawn> TryFrom<FluentButtonSkinParams<T>> for FluentButtonSkin {
+    type Error = crate::Error;
+
+    fn try_from(value: FluentButtonSkinParams<T>) -> crate::Result<Self> {
+        let compositor = value.compositor;
+        let container = compositor.CreateContainerVisual()?;
+        let shape = compositor.CreateShapeVisual()?;
+        let geometry = compositor.CreateRoundedRectangleGeometry()?;
+        geometry.SetCornerRadius(Vector2 {
+            X: CORNER_RADIUS,
+            Y: CORNER_RADIUS,
+        })?;
+        let core = Core {
+            palette: value.palette,
+            theme: ThemeRef::new(value.theme.clone()),
+            state: ButtonVisualState::default(),
+        };
+        let brush = compositor.CreateColorBrushWithColor(core.color())?;
+        let sprite_shape = compositor.CreateSpriteShapeWithGeometry(&geometry)?;
+        sprite_shape.SetFillBrush(&brush)?;
+        shape.Shapes()?.Append(&sprite_shape)?;
+        container.Children()?.InsertAtTop(&shape)?;
+        let text: Arc<Text> = TextParams::builder()
+            .compositor(compositor.clone())
+            .text(value.text)
+            .spawner(value.spawner)
+            .theme(value.theme)
+            .build()
+            .try_into()?;
+        attach(&container, &*text)?;
+        Ok(FluentButtonSkin {
+            container,
+            shape,
+            geometry,
+            brush,
+            text,
+            core: RwLock::new(core),
+            panel_events: EventStreams::new(),
+            id: Arc::new(()),
+        })
+    }
+}
+
+impl<T: Spawn> TryFrom<FluentButtonSkinParams<T>> for Arc<FluentButtonSkin> {
+    type Error = crate::Error;
+
+    fn try_from(value: FluentButtonSkinParams<T>) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
+
+impl Panel for FluentButtonSkin {
+    fn outer_frame(&self) -> Visual {
+        self.container.clone().into()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+}
+
+impl EventSource<PanelEvent> for FluentButtonSkin {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<ButtonVisualState> for FluentButtonSkin {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, ButtonVisualState>,
+        _: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        let mut core = self.core.write().await;
+        core.state = *event.as_ref();
+        self.brush.SetColor(core.color())?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<ButtonTransition> for FluentButtonSkin {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, ButtonTransition>,
+        _: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        let transition = event.as_ref();
+        if transition.token.is_cancelled() {
+            return Ok(());
+        }
+        let palette = self.core.read().await.palette();
+        let compositor = &transition.compositor;
+        let easing = Easing::Quad(EasingMode::Out).composition_function(compositor)?;
+        let animation = compositor.CreateColorKeyFrameAnimation()?;
+        animation.InsertKeyFrame(0., palette.control_color(transition.from))?;
+        animation.InsertKeyFrameWithEasingFunction(
+            1.,
+            palette.control_color(transition.to),
+            &easing,
+        )?;
+        animation.SetDuration(TRANSITION_DURATION.into())?;
+        // Starting the animation replaces the unfinished one of the previous transition
+        self.brush.StartAnimation(h!("Color"), &animation)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for FluentButtonSkin {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        match event.as_ref() {
+            PanelEvent::Resized(size) => {
+                self.container.SetSize(*size)?;
+                self.shape.SetSize(*size)?;
+                self.geometry.SetSize(*size)?;
+            }
+            PanelEvent::ThemeChanged(theme) => {
+                let mut core = self.core.write().await;
+                if core.theme.update(theme) {
+                    self.brush.SetColor(core.color())?;
+                }
+            }
+            _ => {}
+        }
+        self.text
+            .on_event_ref(event.as_ref(), source.clone())
+            .await?;
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
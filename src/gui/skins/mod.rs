@@ -0,0 +1,128 @@
+mod check_box;
+#[cfg(feature = "fluent")]
+pub mod fluent;
+mod scroll_bar;
+mod slider;
+
+pub use check_box::{CheckBoxState, FluentCheckBoxSkin, FluentCheckBoxSkinParams};
+pub use scroll_bar::{FluentScrollBarSkin, FluentScrollBarSkinParams, ScrollBarState};
+pub use slider::{FluentSliderSkin, FluentSliderSkinParams, SliderState};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use async_event_streams::EventSink;
+use async_std::sync::Arc;
+use windows::{
+    Foundation::Numerics::{Vector2, Vector3},
+    UI::{
+        Color,
+        Composition::{CompositionColorBrush, Compositor, ContainerVisual, SpriteVisual, Visual},
+    },
+};
+
+use super::{theme::Theme, ButtonVisualState, Panel};
+
+///
+/// Cancelled when the control changes its state again, so the skin can abandon
+/// the transition which is not actual anymore
+///
+#[derive(Clone, Default, Debug)]
+pub struct TransitionToken(Arc<AtomicBool>);
+
+impl TransitionToken {
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+    pub(crate) fn cancel(&self) {
+        self.0.store(true, Ordering::Release)
+    }
+}
+
+///
+/// State change published by the control after the new state is delivered to its skin.
+/// Skins subscribe to it to start composition animations (press ripple, hover glow, etc.)
+///
+#[derive(Clone, Debug)]
+pub struct SkinTransition<State> {
+    pub from: State,
+    pub to: State,
+    pub compositor: Compositor,
+    /// Visual of the control hosting the skin
+    pub visual: Visual,
+    pub token: TransitionToken,
+}
+
+///
+/// Panel which displays the control in the state provided by the control.
+/// Controls are parametrized by skins to separate input handling from the look.
+/// Animated skins additionally receive the control's `SkinTransition` events.
+///
+pub trait ControlSkin<State>: Panel + EventSink<State, Error = crate::Error> {}
+impl<State, T: Panel + EventSink<State, Error = crate::Error>> ControlSkin<State> for T {}
+
+const fn rgb(r: u8, g: u8, b: u8) -> Color {
+    Color {
+        A: 0xFF,
+        R: r,
+        G: g,
+        B: b,
+    }
+}
+
+const CONTROL_STROKE: Color = rgb(0x8A, 0x8A, 0x8A);
+const CONTROL_STROKE_HOVER: Color = rgb(0x60, 0x60, 0x60);
+const CONTROL_TRACK: Color = rgb(0xE5, 0xE5, 0xE5);
+const CONTROL_DISABLED: Color = rgb(0xC6, 0xC6, 0xC6);
+
+fn accent_color(theme: &Theme, state: ButtonVisualState) -> Color {
+    match state {
+        ButtonVisualState::Normal | ButtonVisualState::Focused => theme.accent_color,
+        ButtonVisualState::Hover => theme.accent_hover_color,
+        ButtonVisualState::Pressed => theme.accent_pressed_color,
+        ButtonVisualState::Disabled => theme.disabled_color,
+    }
+}
+
+fn stroke_color(state: ButtonVisualState) -> Color {
+    match state {
+        ButtonVisualState::Normal | ButtonVisualState::Focused => CONTROL_STROKE,
+        ButtonVisualState::Hover | ButtonVisualState::Pressed => CONTROL_STROKE_HOVER,
+        ButtonVisualState::Disabled => CONTROL_DISABLED,
+    }
+}
+
+// Solid colored rectangle, the building block of the skins
+struct Part {
+    visual: SpriteVisual,
+    brush: CompositionColorBrush,
+}
+
+impl Part {
+    fn new(
+        compositor: &Compositor,
+        container: &ContainerVisual,
+        color: Color,
+    ) -> crate::Result<Self> {
+        let visual = compositor.CreateSpriteVisual()?;
+        let brush = compositor.CreateColorBrushWithColor(color)?;
+        visual.SetBrush(&brush)?;
+        container.Children()?.InsertAtTop(&visual)?;
+        Ok(Self { visual, brush })
+    }
+    fn set_color(&self, color: Color) -> crate::Result<()> {
+        self.brush.SetColor(color)?;
+        Ok(())
+    }
+    fn place(&self, offset: Vector2, size: Vector2) -> crate::Result<()> {
+        self.visual.SetOffset(Vector3 {
+            X: offset.X,
+            Y: offset.Y,
+            Z: 0.,
+        })?;
+        self.visual.SetSize(Vector2 {
+            X: size.X.max(0.),
+            Y: size.Y.max(0.),
+        })?;
+        Ok(())
+    }
+}
@@ -0,0 +1,179 @@
+use std::borrow::Cow;
+
+use async_event_streams::{
+    EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
+};
+use async_event_streams_derive::EventSink;
+use async_std::sync::{Arc, RwLock};
+use async_trait::async_trait;
+use typed_builder::TypedBuilder;
+use windows::{
+    Foundation::Numerics::Vector2,
+    UI::Composition::{Compositor, ContainerVisual, Visual},
+};
+
+use crate::gui::{ButtonVisualState, Panel, PanelEvent};
+
+use super::{stroke_color, Part, CONTROL_TRACK};
+
+const MIN_THUMB_LENGTH: f32 = 16.;
+
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub struct ScrollBarState {
+    pub visual: ButtonVisualState,
+    /// Scroll position from 0 (start) to 1 (end)
+    pub position: f32,
+    /// Visible part of the content from 0 to 1
+    pub viewport: f32,
+}
+
+struct Core {
+    size: Vector2,
+    state: ScrollBarState,
+}
+
+///
+/// Track with the thumb proportional to the visible part of the content
+///
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+#[event_sink(event=ScrollBarState)]
+pub struct FluentScrollBarSkin {
+    container: ContainerVisual,
+    horizontal: bool,
+    track: Part,
+    thumb: Part,
+    core: RwLock<Core>,
+    panel_events: EventStreams<PanelEvent>,
+    id: Arc<()>,
+}
+
+#[derive(TypedBuilder)]
+pub struct FluentScrollBarSkinParams {
+    compositor: Compositor,
+    #[builder(default)]
+    horizontal: bool,
+}
+
+impl TryFrom<FluentScrollBarSkinParams> for FluentScrollBarSkin {
+    type Error = crate::Error;
+
+    fn try_from(value: FluentScrollBarSkinParams) -> crate::Result<Self> {
+        let compositor = value.compositor;
+        let container = compositor.CreateContainerVisual()?;
+        let state = ScrollBarState::default();
+        let track = Part::new(&compositor, &container, CONTROL_TRACK)?;
+        let thumb = Part::new(&compositor, &container, stroke_color(state.visual))?;
+        Ok(FluentScrollBarSkin {
+            container,
+            horizontal: value.horizontal,
+            track,
+            thumb,
+            core: RwLock::new(Core {
+                size: Vector2::default(),
+                state,
+            }),
+            panel_events: EventStreams::new(),
+            id: Arc::new(()),
+        })
+    }
+}
+
+impl TryFrom<FluentScrollBarSkinParams> for Arc<FluentScrollBarSkin> {
+    type Error = crate::Error;
+
+    fn try_from(value: FluentScrollBarSkinParams) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
+
+impl FluentScrollBarSkin {
+    fn redraw(&self, core: &Core) -> crate::Result<()> {
+        let (length, thickness) = if self.horizontal {
+            (core.size.X, core.size.Y)
+        } else {
+            (core.size.Y, core.size.X)
+        };
+        let thumb_length = (length * core.state.viewport.clamp(0., 1.))
+            .max(MIN_THUMB_LENGTH)
+            .min(length);
+        let thumb_offset = (length - thumb_length) * core.state.position.clamp(0., 1.);
+        let (offset, size) = if self.horizontal {
+            (
+                Vector2 {
+                    X: thumb_offset,
+                    Y: 0.,
+                },
+                Vector2 {
+                    X: thumb_length,
+                    Y: thickness,
+                },
+            )
+        } else {
+            (
+                Vector2 {
+                    X: 0.,
+                    Y: thumb_offset,
+                },
+                Vector2 {
+                    X: thickness,
+                    Y: thumb_length,
+                },
+            )
+        };
+        self.track.place(Vector2::default(), core.size)?;
+        self.thumb.set_color(stroke_color(core.state.visual))?;
+        self.thumb.place(offset, size)?;
+        Ok(())
+    }
+}
+
+impl Panel for FluentScrollBarSkin {
+    fn outer_frame(&self) -> Visual {
+        self.container.clone().into()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+}
+
+impl EventSource<PanelEvent> for FluentScrollBarSkin {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<ScrollBarState> for FluentScrollBarSkin {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, ScrollBarState>,
+        _: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        let mut core = self.core.write().await;
+        core.state = *event;
+        self.redraw(&core)
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for FluentScrollBarSkin {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        if let PanelEvent::Resized(size) = event.as_ref() {
+            self.container.SetSize(*size)?;
+            let mut core = self.core.write().await;
+            core.size = *size;
+            self.redraw(&core)?;
+        }
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
@@ -0,0 +1,196 @@
+use std::borrow::Cow;
+
+use async_event_streams::{
+    EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
+};
+use async_event_streams_derive::EventSink;
+use async_std::sync::{Arc, RwLock};
+use async_trait::async_trait;
+use typed_builder::TypedBuilder;
+use windows::{
+    Foundation::Numerics::Vector2,
+    UI::Composition::{Compositor, ContainerVisual, Visual},
+};
+
+use crate::gui::{
+    theme::{Theme, ThemeRef},
+    ButtonVisualState, Panel, PanelEvent,
+};
+
+use super::{accent_color, Part, CONTROL_TRACK};
+
+const TRACK_THICKNESS: f32 = 4.;
+const THUMB_SIZE: f32 = 20.;
+
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub struct SliderState {
+    pub visual: ButtonVisualState,
+    /// Position of the thumb from 0 to 1
+    pub value: f32,
+}
+
+struct Core {
+    size: Vector2,
+    state: SliderState,
+    theme: ThemeRef,
+}
+
+///
+/// Horizontal track filled with accent color up to the thumb
+///
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+#[event_sink(event=SliderState)]
+pub struct FluentSliderSkin {
+    container: ContainerVisual,
+    track: Part,
+    filled: Part,
+    thumb: Part,
+    core: RwLock<Core>,
+    panel_events: EventStreams<PanelEvent>,
+    id: Arc<()>,
+}
+
+#[derive(TypedBuilder)]
+pub struct FluentSliderSkinParams {
+    compositor: Compositor,
+    /// Theme to use instead of the global one
+    #[builder(default, setter(into))]
+    theme: Option<Arc<Theme>>,
+}
+
+impl TryFrom<FluentSliderSkinParams> for FluentSliderSkin {
+    type Error = crate::Error;
+
+    fn try_from(value: FluentSliderSkinParams) -> crate::Result<Self> {
+        let compositor = value.compositor;
+        let container = compositor.CreateContainerVisual()?;
+        let state = SliderState::default();
+        let theme = ThemeRef::new(value.theme);
+        let accent = accent_color(theme.get(), state.visual);
+        let track = Part::new(&compositor, &container, CONTROL_TRACK)?;
+        let filled = Part::new(&compositor, &container, accent)?;
+        let thumb = Part::new(&compositor, &container, accent)?;
+        Ok(FluentSliderSkin {
+            container,
+            track,
+            filled,
+            thumb,
+            core: RwLock::new(Core {
+                size: Vector2::default(),
+                state,
+                theme,
+            }),
+            panel_events: EventStreams::new(),
+            id: Arc::new(()),
+        })
+    }
+}
+
+impl TryFrom<FluentSliderSkinParams> for Arc<FluentSliderSkin> {
+    type Error = crate::Error;
+
+    fn try_from(value: FluentSliderSkinParams) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
+
+impl FluentSliderSkin {
+    fn redraw(&self, core: &Core) -> crate::Result<()> {
+        let thumb_size = core.size.Y.min(THUMB_SIZE);
+        // The thumb center moves between the half-thumb insets at both ends
+        let length = (core.size.X - thumb_size).max(0.);
+        let position = length * core.state.value.clamp(0., 1.);
+        let track_offset = Vector2 {
+            X: thumb_size / 2.,
+            Y: (core.size.Y - TRACK_THICKNESS) / 2.,
+        };
+        let color = accent_color(core.theme.get(), core.state.visual);
+        self.track.place(
+            track_offset,
+            Vector2 {
+                X: length,
+                Y: TRACK_THICKNESS,
+            },
+        )?;
+        self.filled.set_color(color)?;
+        self.filled.place(
+            track_offset,
+            Vector2 {
+                X: position,
+                Y: TRACK_THICKNESS,
+            },
+        )?;
+        self.thumb.set_color(color)?;
+        self.thumb.place(
+            Vector2 {
+                X: position,
+                Y: (core.size.Y - thumb_size) / 2.,
+            },
+            Vector2 {
+                X: thumb_size,
+                Y: thumb_size,
+            },
+        )?;
+        Ok(())
+    }
+}
+
+impl Panel for FluentSliderSkin {
+    fn outer_frame(&self) -> Visual {
+        self.container.clone().into()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+}
+
+impl EventSource<PanelEvent> for FluentSliderSkin {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<SliderState> for FluentSliderSkin {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, SliderState>,
+        _: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        let mut core = self.core.write().await;
+        core.state = *event;
+        self.redraw(&core)
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for FluentSliderSkin {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        match event.as_ref() {
+            PanelEvent::Resized(size) => {
+                self.container.SetSize(*size)?;
+                let mut core = self.core.write().await;
+                core.size = *size;
+                self.redraw(&core)?;
+            }
+            PanelEvent::ThemeChanged(theme) => {
+                let mut core = self.core.write().await;
+                if core.theme.update(theme) {
+                    self.redraw(&core)?;
+                }
+            }
+            _ => {}
+        }
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
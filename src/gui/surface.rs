@@ -4,64 +4,316 @@ use async_event_streams::{
     EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
 };
 use async_event_streams_derive::EventSink;
+use async_std::sync::RwLock;
 use async_trait::async_trait;
 use typed_builder::TypedBuilder;
 use windows::{
-    Foundation::Numerics::Vector2,
-    Graphics::DirectX::{DirectXAlphaMode, DirectXPixelFormat},
+    Foundation::Numerics::{Vector2, Vector3},
+    Graphics::{
+        DirectX::{DirectXAlphaMode, DirectXPixelFormat},
+        SizeInt32,
+    },
+    Win32::{
+        Foundation::POINT,
+        Graphics::Direct2D::{Common::D2D_RECT_F, ID2D1DeviceContext, D2D1_ANTIALIAS_MODE_ALIASED},
+    },
     UI::Composition::{
         CompositionDrawingSurface, CompositionGraphicsDevice, CompositionStretch,
-        CompositionSurfaceBrush, Compositor, SpriteVisual, Visual,
+        CompositionSurfaceBrush, Compositor, ContainerVisual, SpriteVisual, Visual,
     },
 };
 
-use crate::window::create_composition_graphics_device;
+use crate::window::{self, create_composition_graphics_device};
 
 use super::{Panel, PanelEvent};
 
+/// Largest texture dimension guaranteed by Direct3D 11 hardware
+pub const MAX_SURFACE_SIZE: i32 = 16384;
+
+///
+/// Pixel format and color space of the drawing surface
+///
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum SurfaceFormat {
+    /// 8 bit per channel BGRA in sRGB color space
+    #[default]
+    Srgb,
+    /// 16 bit float per channel RGBA in linear scRGB color space, for HDR displays with advanced color
+    ScRgb,
+}
+
+impl SurfaceFormat {
+    pub fn pixel_format(&self) -> DirectXPixelFormat {
+        match self {
+            SurfaceFormat::Srgb => DirectXPixelFormat::B8G8R8A8UIntNormalized,
+            SurfaceFormat::ScRgb => DirectXPixelFormat::R16G16B16A16Float,
+        }
+    }
+}
+
 #[derive(PartialEq, Clone)]
 pub enum SurfaceEvent {
     Redraw(Vector2),
 }
 
+struct Tile {
+    visual: SpriteVisual,
+    surface: CompositionDrawingSurface,
+    brush: CompositionSurfaceBrush,
+    offset: POINT,
+    size: SizeInt32,
+}
+
+struct Core {
+    tiles: Vec<Tile>,
+    surface_size: SizeInt32,
+    visual_size: Vector2,
+}
+
+// Scale and offset of the content of `content` size placed into the visual
+// the same way as the surface brush places its surface
+fn place_content(
+    stretch: CompositionStretch,
+    alignment: Vector2,
+    content: Vector2,
+    visual: Vector2,
+) -> (Vector2, Vector2) {
+    let ratio = |visual: f32, content: f32| if content > 0. { visual / content } else { 1. };
+    let (x, y) = (ratio(visual.X, content.X), ratio(visual.Y, content.Y));
+    let scale = match stretch {
+        CompositionStretch::Fill => Vector2 { X: x, Y: y },
+        CompositionStretch::Uniform => Vector2 {
+            X: x.min(y),
+            Y: x.min(y),
+        },
+        CompositionStretch::UniformToFill => Vector2 {
+            X: x.max(y),
+            Y: x.max(y),
+        },
+        _ => Vector2 { X: 1., Y: 1. },
+    };
+    let offset = Vector2 {
+        X: (visual.X - content.X * scale.X) * alignment.X,
+        Y: (visual.Y - content.Y * scale.Y) * alignment.Y,
+    };
+    (scale, offset)
+}
+
 #[derive(EventSink)]
 #[event_sink(event=PanelEvent)]
 pub struct Surface {
-    sprite_visual: SpriteVisual,
-    _composition_graphic_device: CompositionGraphicsDevice,
+    compositor: Compositor,
+    container: ContainerVisual,
+    composition_graphic_device: CompositionGraphicsDevice,
+    // Surface of the first tile, which is kept while the surface is resized
     surface: CompositionDrawingSurface,
-    _surface_brush: CompositionSurfaceBrush,
+    stretch: CompositionStretch,
+    alignment: Vector2,
+    format: SurfaceFormat,
+    alpha_mode: DirectXAlphaMode,
+    max_tile_size: i32,
+    fit_surface_to_visual: bool,
+    core: RwLock<Core>,
     panel_events: EventStreams<PanelEvent>,
     surface_events: EventStreams<SurfaceEvent>,
     id: Arc<()>,
 }
 
 impl Surface {
-    fn new(compositor: Compositor) -> crate::Result<Self> {
-        let sprite_visual = compositor.CreateSpriteVisual()?;
+    fn new(params: SurfaceParams) -> crate::Result<Self> {
+        let compositor = params.compositor;
+        let container = compositor.CreateContainerVisual()?;
+        // The tiles stretched to fill the panel are cut at its bounds
+        container.SetClip(&compositor.CreateInsetClip()?)?;
         let composition_graphic_device = create_composition_graphics_device(&compositor)?;
-        let surface_brush = compositor.CreateSurfaceBrush()?;
-        surface_brush.SetStretch(CompositionStretch::UniformToFill)?;
-        let surface = composition_graphic_device.CreateDrawingSurface(
-            windows::Foundation::Size::default(),
-            DirectXPixelFormat::B8G8R8A8UIntNormalized,
-            DirectXAlphaMode::Premultiplied,
+        let first_tile = create_tile(
+            &compositor,
+            &container,
+            &composition_graphic_device,
+            params.format,
+            params.alpha_mode,
         )?;
-        surface_brush.SetSurface(&surface)?;
-        sprite_visual.SetBrush(&surface_brush)?;
         Ok(Self {
-            sprite_visual,
-            _composition_graphic_device: composition_graphic_device,
-            surface,
-            _surface_brush: surface_brush,
+            compositor,
+            container,
+            composition_graphic_device,
+            surface: first_tile.surface.clone(),
+            stretch: params.stretch,
+            alignment: params.alignment,
+            format: params.format,
+            alpha_mode: params.alpha_mode,
+            max_tile_size: params.max_tile_size.clamp(1, MAX_SURFACE_SIZE),
+            fit_surface_to_visual: params.fit_surface_to_visual,
+            core: RwLock::new(Core {
+                tiles: vec![first_tile],
+                surface_size: SizeInt32::default(),
+                visual_size: Vector2 { X: 0., Y: 0. },
+            }),
             panel_events: EventStreams::new(),
             surface_events: EventStreams::new(),
             id: Arc::new(()),
         })
     }
+
+    fn layout_tiles(&self, core: &Core) -> crate::Result<()> {
+        if let [tile] = core.tiles.as_slice() {
+            // Single surface is stretched over the whole panel according to the brush settings
+            tile.brush.SetStretch(self.stretch)?;
+            tile.brush.SetHorizontalAlignmentRatio(self.alignment.X)?;
+            tile.brush.SetVerticalAlignmentRatio(self.alignment.Y)?;
+            tile.visual.SetOffset(Vector3::default())?;
+            tile.visual.SetSize(core.visual_size)?;
+        } else {
+            // Each tile fills its visual, the visuals are placed as the single brush
+            // would place the whole surface
+            let content = Vector2 {
+                X: core.surface_size.Width as f32,
+                Y: core.surface_size.Height as f32,
+            };
+            let (scale, origin) =
+                place_content(self.stretch, self.alignment, content, core.visual_size);
+            for tile in &core.tiles {
+                tile.brush.SetStretch(CompositionStretch::Fill)?;
+                tile.visual.SetOffset(Vector3 {
+                    X: origin.X + tile.offset.x as f32 * scale.X,
+                    Y: origin.Y + tile.offset.y as f32 * scale.Y,
+                    Z: 0.,
+                })?;
+                tile.visual.SetSize(Vector2 {
+                    X: tile.size.Width as f32 * scale.X,
+                    Y: tile.size.Height as f32 * scale.Y,
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn format(&self) -> SurfaceFormat {
+        self.format
+    }
+
+    ///
+    /// Backing drawing surface. When the surface is tiled it's the top left tile only,
+    /// use `draw` to draw on all of them.
+    ///
     pub fn surface(&self) -> &CompositionDrawingSurface {
         &self.surface
     }
+
+    ///
+    /// Resize the backing drawing surface. Sizes exceeding the maximal tile size
+    /// are split to grid of separate drawing surfaces.
+    ///
+    pub async fn resize_surface(&self, size: SizeInt32) -> crate::Result<()> {
+        let max = self.max_tile_size;
+        let columns = ((size.Width + max - 1) / max).max(1);
+        let rows = ((size.Height + max - 1) / max).max(1);
+        let count = (columns * rows) as usize;
+        let mut core = self.core.write().await;
+        while core.tiles.len() > count {
+            if let Some(tile) = core.tiles.pop() {
+                self.container.Children()?.Remove(&tile.visual)?;
+            }
+        }
+        while core.tiles.len() < count {
+            let tile = create_tile(
+                &self.compositor,
+                &self.container,
+                &self.composition_graphic_device,
+                self.format,
+                self.alpha_mode,
+            )?;
+            core.tiles.push(tile);
+        }
+        core.surface_size = size;
+        for row in 0..rows {
+            for column in 0..columns {
+                let tile = &mut core.tiles[(row * columns + column) as usize];
+                tile.offset = POINT {
+                    x: column * max,
+                    y: row * max,
+                };
+                tile.size = SizeInt32 {
+                    Width: (size.Width - tile.offset.x).clamp(0, max),
+                    Height: (size.Height - tile.offset.y).clamp(0, max),
+                };
+                tile.surface.Resize(tile.size)?;
+            }
+        }
+        self.layout_tiles(&core)
+    }
+
+    ///
+    /// Ask the surface owner to redraw the content at current size, e.g. when the content is changed
+    ///
+    pub async fn request_redraw(&self) {
+        let size = self.core.read().await.visual_size;
+        self.surface_events.clear();
+        self.surface_events
+            .post_event(SurfaceEvent::Redraw(size), None);
+    }
+
+    ///
+    /// Draw on the surface. The callback receives the device context and the origin point
+    /// of the whole surface in context coordinates. For tiled surfaces it's called once
+    /// for each tile with drawing clipped to the tile.
+    ///
+    pub async fn draw<F: Fn(ID2D1DeviceContext, POINT) -> crate::Result<()>>(
+        &self,
+        f: F,
+    ) -> crate::Result<()> {
+        let core = self.core.read().await;
+        if let [tile] = core.tiles.as_slice() {
+            return window::draw(&tile.surface, f);
+        }
+        for tile in &core.tiles {
+            window::draw(&tile.surface, |context, point| {
+                let clip = D2D_RECT_F {
+                    left: point.x as f32,
+                    top: point.y as f32,
+                    right: (point.x + tile.size.Width) as f32,
+                    bottom: (point.y + tile.size.Height) as f32,
+                };
+                unsafe { context.PushAxisAlignedClip(&clip, D2D1_ANTIALIAS_MODE_ALIASED) };
+                let result = f(
+                    context.clone(),
+                    POINT {
+                        x: point.x - tile.offset.x,
+                        y: point.y - tile.offset.y,
+                    },
+                );
+                unsafe { context.PopAxisAlignedClip() };
+                result
+            })?;
+        }
+        Ok(())
+    }
+}
+
+fn create_tile(
+    compositor: &Compositor,
+    container: &ContainerVisual,
+    composition_graphic_device: &CompositionGraphicsDevice,
+    format: SurfaceFormat,
+    alpha_mode: DirectXAlphaMode,
+) -> crate::Result<Tile> {
+    let visual = compositor.CreateSpriteVisual()?;
+    let brush = compositor.CreateSurfaceBrush()?;
+    let surface = composition_graphic_device.CreateDrawingSurface(
+        windows::Foundation::Size::default(),
+        format.pixel_format(),
+        alpha_mode,
+    )?;
+    brush.SetSurface(&surface)?;
+    visual.SetBrush(&brush)?;
+    container.Children()?.InsertAtTop(&visual)?;
+    Ok(Tile {
+        visual,
+        surface,
+        brush,
+        offset: POINT { x: 0, y: 0 },
+        size: SizeInt32::default(),
+    })
 }
 
 #[async_trait]
@@ -73,7 +325,17 @@ impl EventSinkExt<PanelEvent> for Surface {
         source: Option<Arc<EventBox>>,
     ) -> crate::Result<()> {
         if let PanelEvent::Resized(size) = event.as_ref() {
-            self.sprite_visual.SetSize(*size)?;
+            self.container.SetSize(*size)?;
+            self.core.write().await.visual_size = *size;
+            if self.fit_surface_to_visual {
+                self.resize_surface(SizeInt32 {
+                    Width: size.X as i32,
+                    Height: size.Y as i32,
+                })
+                .await?;
+            } else {
+                self.layout_tiles(&*self.core.read().await)?;
+            }
             self.surface_events.clear(); // No need to keep unhandled redraw events - only latest one makes sense
             self.surface_events
                 .post_event(SurfaceEvent::Redraw(*size), None);
@@ -99,7 +361,7 @@ impl EventSource<SurfaceEvent> for Surface {
 #[async_trait]
 impl Panel for Surface {
     fn outer_frame(&self) -> Visual {
-        self.sprite_visual.clone().into()
+        self.container.clone().into()
     }
     fn id(&self) -> usize {
         Arc::as_ptr(&self.id) as usize
@@ -109,13 +371,30 @@ impl Panel for Surface {
 #[derive(TypedBuilder)]
 pub struct SurfaceParams {
     compositor: Compositor,
+    /// How the drawn content is scaled when surface size differs from the panel size
+    #[builder(default = CompositionStretch::UniformToFill)]
+    stretch: CompositionStretch,
+    /// Position of the content inside the panel: 0 - left/top, 0.5 - center, 1 - right/bottom
+    #[builder(default = Vector2 { X: 0.5, Y: 0.5 })]
+    alignment: Vector2,
+    /// Pixel format and color space of the backing surface
+    #[builder(default)]
+    format: SurfaceFormat,
+    #[builder(default = DirectXAlphaMode::Premultiplied)]
+    alpha_mode: DirectXAlphaMode,
+    /// Resize the backing surface to the exact panel size on each resize
+    #[builder(default)]
+    fit_surface_to_visual: bool,
+    /// Surfaces larger than this size are composed from several drawing surfaces
+    #[builder(default = MAX_SURFACE_SIZE)]
+    max_tile_size: i32,
 }
 
 impl TryFrom<SurfaceParams> for Surface {
     type Error = crate::Error;
 
     fn try_from(value: SurfaceParams) -> crate::Result<Self> {
-        Ok(Surface::new(value.compositor)?)
+        Ok(Surface::new(value)?)
     }
 }
 
@@ -13,107 +13,160 @@ use windows::{
     w,
     Foundation::Numerics::{Matrix3x2, Vector2},
     Graphics::SizeInt32,
-    Win32::Graphics::{
-        Direct2D::{
-            Common::{D2D1_COLOR_F, D2D_RECT_F},
-            D2D1_BRUSH_PROPERTIES, D2D1_DRAW_TEXT_OPTIONS_NONE,
-        },
-        DirectWrite::{
-            DWRITE_FONT_STRETCH_NORMAL, DWRITE_FONT_STYLE_ITALIC, DWRITE_FONT_WEIGHT_BOLD,
-            DWRITE_MEASURING_MODE_NATURAL,
+    Win32::{
+        Foundation::POINT,
+        Graphics::{
+            Direct2D::{
+                Common::{D2D1_COLOR_F, D2D_RECT_F},
+                ID2D1DeviceContext, D2D1_BRUSH_PROPERTIES, D2D1_DRAW_TEXT_OPTIONS_NONE,
+            },
+            DirectWrite::{
+                IDWriteTextFormat, DWRITE_FONT_STRETCH_NORMAL, DWRITE_FONT_STYLE_ITALIC,
+                DWRITE_FONT_WEIGHT_BOLD, DWRITE_MEASURING_MODE_NATURAL,
+            },
         },
     },
-    UI::Composition::{CompositionDrawingSurface, Compositor, Visual},
+    UI::Composition::{Compositor, Visual},
 };
 
 use crate::{
     on_err,
-    window::{draw, dwrite_factory, ToWide},
+    window::{dwrite_factory, ToWide},
 };
 
-use super::{surface::SurfaceEvent, Panel, PanelEvent, Surface, SurfaceParams};
+use super::{
+    surface::SurfaceEvent,
+    theme::{theme, Theme, ThemeRef},
+    CellLimit, Panel, PanelEvent, RibbonOrientation, Surface, SurfaceFormat, SurfaceParams,
+};
 
 #[derive(EventSink)]
 #[event_sink(event=SurfaceEvent)]
 struct Core {
     surface: Arc<Surface>,
     text: String,
+    theme: ThemeRef,
 }
 
 impl Core {
-    fn new(surface: Arc<Surface>, text: String) -> crate::Result<Self> {
-        Ok(Self { surface, text })
+    fn new(surface: Arc<Surface>, text: String, theme: ThemeRef) -> crate::Result<Self> {
+        Ok(Self {
+            surface,
+            text,
+            theme,
+        })
     }
 }
 
-fn redraw(size: Vector2, surface: &CompositionDrawingSurface, text: &str) -> crate::Result<()> {
+async fn redraw(size: Vector2, surface: &Surface, text: &str, theme: &Theme) -> crate::Result<()> {
     let new_surface_size = SizeInt32 {
         Width: size.X as i32,
         Height: size.Y as i32,
     };
-    surface.Resize(new_surface_size)?;
-    draw(surface, |context, point| {
-        let fontsize = 30.;
-        let dwrite_text_format = unsafe {
-            dwrite_factory()?.CreateTextFormat(
-                w!("Segoe UI"),
-                InParam::null(),
-                DWRITE_FONT_WEIGHT_BOLD,
-                DWRITE_FONT_STYLE_ITALIC,
-                DWRITE_FONT_STRETCH_NORMAL,
-                fontsize,
-                w!("en-US"),
-            )
-        }?;
+    surface.resize_surface(new_surface_size).await?;
+    surface
+        .draw(|context, point| draw_text(context, point, size, text, theme))
+        .await
+}
 
-        let clearcolor = D2D1_COLOR_F {
-            r: 0.,
-            g: 0.,
-            b: 0.,
-            a: 0.,
-        };
-        let text_color = D2D1_COLOR_F {
-            r: 0.,
-            g: 0.,
-            b: 0.,
-            a: 255.,
-        };
-        let text_brush_properties = D2D1_BRUSH_PROPERTIES {
-            opacity: 1.,
-            transform: Matrix3x2::identity(),
-        };
-        unsafe { context.Clear(Some(&clearcolor)) };
-        let text_brush =
-            unsafe { context.CreateSolidColorBrush(&text_color, Some(&text_brush_properties)) }?;
-        unsafe {
-            context.DrawText(
-                text.to_wide().0.as_slice(),
-                &dwrite_text_format,
-                &D2D_RECT_F {
-                    left: point.x as f32,
-                    top: point.y as f32,
-                    right: point.x as f32 + size.X,
-                    bottom: point.y as f32 + size.Y,
-                },
-                &text_brush,
-                D2D1_DRAW_TEXT_OPTIONS_NONE,
-                DWRITE_MEASURING_MODE_NATURAL,
-            );
-            /*
-            context.DrawTextLayout(
-                D2D_POINT_2F {
-                    x: 0.,
-                    y: size.Y / 2.,
-                },
-                &text_layout,
-                &text_brush,
-                D2D1_DRAW_TEXT_OPTIONS_NONE,
-            )
-            */
-        };
+fn create_text_format(theme: &Theme) -> crate::Result<IDWriteTextFormat> {
+    Ok(unsafe {
+        dwrite_factory()?.CreateTextFormat(
+            theme.font_family.to_wide().as_pcwstr(),
+            InParam::null(),
+            DWRITE_FONT_WEIGHT_BOLD,
+            DWRITE_FONT_STYLE_ITALIC,
+            DWRITE_FONT_STRETCH_NORMAL,
+            theme.font_size,
+            w!("en-US"),
+        )
+    }?)
+}
+
+///
+/// Size of the text drawn by `Text` panel with the global theme, wrapped to the max width
+/// if provided
+///
+pub fn measure_text(text: &str, max_width: Option<f32>) -> crate::Result<Vector2> {
+    measure_text_with_theme(text, max_width, &theme())
+}
+
+fn measure_text_with_theme(
+    text: &str,
+    max_width: Option<f32>,
+    theme: &Theme,
+) -> crate::Result<Vector2> {
+    let text_format = create_text_format(theme)?;
+    let text_layout = unsafe {
+        dwrite_factory()?.CreateTextLayout(
+            text.to_wide().as_wide(),
+            &text_format,
+            max_width.unwrap_or(f32::MAX),
+            f32::MAX,
+        )
+    }?;
+    let metrics = unsafe { text_layout.GetMetrics() }?;
+    Ok(Vector2 {
+        X: metrics.widthIncludingTrailingWhitespace.ceil(),
+        Y: metrics.height.ceil(),
+    })
+}
+
+fn draw_text(
+    context: ID2D1DeviceContext,
+    point: POINT,
+    size: Vector2,
+    text: &str,
+    theme: &Theme,
+) -> crate::Result<()> {
+    let dwrite_text_format = create_text_format(theme)?;
+
+    let clearcolor = D2D1_COLOR_F {
+        r: 0.,
+        g: 0.,
+        b: 0.,
+        a: 0.,
+    };
+    let text_color = D2D1_COLOR_F {
+        r: theme.text_color.R as f32 / 255.,
+        g: theme.text_color.G as f32 / 255.,
+        b: theme.text_color.B as f32 / 255.,
+        a: theme.text_color.A as f32 / 255.,
+    };
+    let text_brush_properties = D2D1_BRUSH_PROPERTIES {
+        opacity: 1.,
+        transform: Matrix3x2::identity(),
+    };
+    unsafe { context.Clear(Some(&clearcolor)) };
+    let text_brush =
+        unsafe { context.CreateSolidColorBrush(&text_color, Some(&text_brush_properties)) }?;
+    unsafe {
+        context.DrawText(
+            text.to_wide().as_wide(),
+            &dwrite_text_format,
+            &D2D_RECT_F {
+                left: point.x as f32,
+                top: point.y as f32,
+                right: point.x as f32 + size.X,
+                bottom: point.y as f32 + size.Y,
+            },
+            &text_brush,
+            D2D1_DRAW_TEXT_OPTIONS_NONE,
+            DWRITE_MEASURING_MODE_NATURAL,
+        );
+        /*
+        context.DrawTextLayout(
+            D2D_POINT_2F {
+                x: 0.,
+                y: size.Y / 2.,
+            },
+            &text_layout,
+            &text_brush,
+            D2D1_DRAW_TEXT_OPTIONS_NONE,
+        )
+        */
+    };
 
-        Ok(())
-    })?;
     Ok(())
 }
 
@@ -127,7 +180,7 @@ impl EventSinkExt<SurfaceEvent> for Core {
     ) -> crate::Result<()> {
         match event.as_ref() {
             SurfaceEvent::Redraw(size) => {
-                redraw(*size, self.surface.surface(), self.text.as_str())?
+                redraw(*size, &self.surface, self.text.as_str(), self.theme.get()).await?
             }
         }
         Ok(())
@@ -138,7 +191,7 @@ impl EventSinkExt<SurfaceEvent> for Core {
 #[event_sink(event=PanelEvent)]
 pub struct Text {
     surface: Arc<Surface>,
-    _core: Arc<RwLock<Core>>,
+    core: Arc<RwLock<Core>>,
     panel_events: EventStreams<PanelEvent>,
     id: Arc<()>,
 }
@@ -174,7 +227,7 @@ impl Text {
         // unsafe { dwrite_text_format.SetTextAlignment(DWRITE_TEXT_ALIGNMENT_LEADING) }?;
         let text_layout = unsafe {
             dwrite_factory()?.CreateTextLayout(
-                self.text.as_str().to_wide().0.as_slice(),
+                self.text.as_str().to_wide().as_wide(),
                 &dwrite_text_format,
                 size.X,
                 size.Y / 2.,
@@ -208,7 +261,7 @@ impl Text {
                 unsafe { context.CreateSolidColorBrush(&text_color, &text_brush_properties) }?;
             unsafe {
                 context.DrawText(
-                    self.text.as_str().to_wide().0.as_slice(),
+                    self.text.as_str().to_wide().as_wide(),
                     &dwrite_text_format,
                     &D2D_RECT_F {
                         left: updateoffset.x as f32,
@@ -240,6 +293,40 @@ impl Text {
 
 */
 
+impl Text {
+    pub async fn text(&self) -> String {
+        self.core.read().await.text.clone()
+    }
+
+    ///
+    /// Size required to show the whole text, wrapped to the max width if provided
+    ///
+    pub async fn preferred_size(&self, max_width: Option<f32>) -> crate::Result<Vector2> {
+        let core = self.core.read().await;
+        measure_text_with_theme(&core.text, max_width, core.theme.get())
+    }
+
+    ///
+    /// Cell limit for placing the text into the ribbon with given orientation
+    /// which doesn't allow to shrink the cell below the text size
+    ///
+    pub async fn cell_limit(&self, orientation: RibbonOrientation) -> crate::Result<CellLimit> {
+        let size = self.preferred_size(None).await?;
+        let mut limit = CellLimit::default();
+        match orientation {
+            RibbonOrientation::Horizontal => limit.min_size = size.X,
+            RibbonOrientation::Vertical => limit.min_size = size.Y,
+            RibbonOrientation::Stack => (),
+        }
+        Ok(limit)
+    }
+
+    pub async fn set_text(&self, text: impl Into<String>) {
+        self.core.write().await.text = text.into();
+        self.surface.request_redraw().await;
+    }
+}
+
 #[async_trait]
 impl EventSinkExt<PanelEvent> for Text {
     type Error = crate::Error;
@@ -248,6 +335,11 @@ impl EventSinkExt<PanelEvent> for Text {
         event: Cow<'a, PanelEvent>,
         source: Option<Arc<EventBox>>,
     ) -> crate::Result<()> {
+        if let PanelEvent::ThemeChanged(theme) = event.as_ref() {
+            if self.core.write().await.theme.update(theme) {
+                self.surface.request_redraw().await;
+            }
+        }
         self.surface
             .on_event_ref(event.as_ref(), source.clone())
             .await?;
@@ -279,6 +371,11 @@ pub struct TextParams<T: Spawn> {
     compositor: Compositor,
     text: String,
     spawner: T,
+    #[builder(default)]
+    format: SurfaceFormat,
+    /// Theme to use instead of the global one
+    #[builder(default, setter(into))]
+    theme: Option<Arc<Theme>>,
 }
 
 impl<T: Spawn> TryFrom<TextParams<T>> for Text {
@@ -287,13 +384,18 @@ impl<T: Spawn> TryFrom<TextParams<T>> for Text {
     fn try_from(value: TextParams<T>) -> crate::Result<Self> {
         let surface: Arc<Surface> = SurfaceParams::builder()
             .compositor(value.compositor)
+            .format(value.format)
             .build()
             .try_into()?;
-        let core = Arc::new(RwLock::new(Core::new(surface.clone(), value.text)?));
+        let core = Arc::new(RwLock::new(Core::new(
+            surface.clone(),
+            value.text,
+            ThemeRef::new(value.theme),
+        )?));
         spawn_event_pipe(&value.spawner, &surface, core.clone(), on_err)?;
         Ok(Text {
             surface,
-            _core: core,
+            core,
             panel_events: EventStreams::new(),
             id: Arc::new(()),
         })
@@ -0,0 +1,219 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex, RwLock,
+};
+
+use async_std::sync::Arc;
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use windows::UI::Color;
+
+use crate::window::is_system_dark_mode;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum ColorMode {
+    #[default]
+    Light,
+    Dark,
+}
+
+///
+/// Light or dark mode selected for the applications in the system settings
+///
+pub fn system_color_mode() -> ColorMode {
+    if is_system_dark_mode() {
+        ColorMode::Dark
+    } else {
+        ColorMode::Light
+    }
+}
+
+///
+/// Shared style resources. Widgets take the defaults from the theme passed to their builders
+/// or from the globally installed one. Widgets using the global theme are restyled
+/// by `PanelEvent::ThemeChanged` when a new theme is installed.
+///
+#[derive(Clone, PartialEq, Debug)]
+pub struct Theme {
+    pub color_mode: ColorMode,
+    pub background_color: Color,
+    pub text_color: Color,
+    pub accent_color: Color,
+    pub accent_hover_color: Color,
+    pub accent_pressed_color: Color,
+    pub disabled_color: Color,
+    /// Opacity of the disabled panel faded by the `Enabled` decorator and of the disabled button
+    pub disabled_opacity: f32,
+    /// Radius of the rounded corners, `None` makes it proportional to the panel size
+    pub corner_radius: Option<f32>,
+    pub font_family: String,
+    pub font_size: f32,
+    /// Gap between the neighbouring elements in layouts
+    pub spacing: f32,
+    /// Smallest hit area of the controls when touch input is available. Smaller controls
+    /// are hit tested as if they were extended to this size around their center.
+    pub min_touch_target: f32,
+}
+
+const fn rgb(r: u8, g: u8, b: u8) -> Color {
+    Color {
+        A: 0xFF,
+        R: r,
+        G: g,
+        B: b,
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::light()
+    }
+}
+
+impl Theme {
+    pub fn light() -> Self {
+        Self {
+            color_mode: ColorMode::Light,
+            background_color: rgb(0xFF, 0xFF, 0xFF),
+            text_color: rgb(0, 0, 0),
+            accent_color: rgb(0xFF, 0, 0xFF),
+            accent_hover_color: rgb(0xDA, 0x70, 0xD6),
+            accent_pressed_color: rgb(0x8B, 0, 0x8B),
+            disabled_color: rgb(0x80, 0x80, 0x80),
+            disabled_opacity: 0.4,
+            corner_radius: None,
+            font_family: "Segoe UI".into(),
+            font_size: 30.,
+            spacing: 8.,
+            min_touch_target: 32.,
+        }
+    }
+
+    pub fn dark() -> Self {
+        Self {
+            color_mode: ColorMode::Dark,
+            background_color: rgb(0x20, 0x20, 0x20),
+            text_color: rgb(0xFF, 0xFF, 0xFF),
+            accent_color: rgb(0xC0, 0x40, 0xC0),
+            accent_hover_color: rgb(0xDA, 0x70, 0xD6),
+            accent_pressed_color: rgb(0x8B, 0, 0x8B),
+            disabled_color: rgb(0x50, 0x50, 0x50),
+            ..Self::light()
+        }
+    }
+
+    pub fn for_mode(mode: ColorMode) -> Self {
+        match mode {
+            ColorMode::Light => Self::light(),
+            ColorMode::Dark => Self::dark(),
+        }
+    }
+
+    ///
+    /// Same theme with the colors of the palette for the mode
+    ///
+    pub fn with_color_mode(&self, mode: ColorMode) -> Self {
+        Self {
+            disabled_opacity: self.disabled_opacity,
+            corner_radius: self.corner_radius,
+            font_family: self.font_family.clone(),
+            font_size: self.font_size,
+            spacing: self.spacing,
+            min_touch_target: self.min_touch_target,
+            ..Self::for_mode(mode)
+        }
+    }
+}
+
+static THEME: RwLock<Option<Arc<Theme>>> = RwLock::new(None);
+static FOLLOW_SYSTEM_COLOR_MODE: AtomicBool = AtomicBool::new(false);
+static THEME_LISTENERS: Mutex<Vec<UnboundedSender<Arc<Theme>>>> = Mutex::new(Vec::new());
+
+///
+/// The globally installed theme, `Theme::default()` if none was installed
+///
+pub fn theme() -> Arc<Theme> {
+    THEME
+        .read()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(|| Arc::new(Theme::default()))
+}
+
+///
+/// Install the global theme. Window event receivers deliver it to their panel trees
+/// as `PanelEvent::ThemeChanged`.
+///
+pub fn set_theme(theme: Theme) {
+    let theme = Arc::new(theme);
+    *THEME.write().unwrap() = Some(theme.clone());
+    THEME_LISTENERS
+        .lock()
+        .unwrap()
+        .retain(|tx| tx.unbounded_send(theme.clone()).is_ok());
+}
+
+///
+/// Switch the palette of the global theme when the system light or dark mode changes.
+/// When enabled, the palette is updated to the current system mode immediately.
+///
+pub fn follow_system_color_mode(follow: bool) {
+    FOLLOW_SYSTEM_COLOR_MODE.store(follow, Ordering::Relaxed);
+    if follow {
+        on_system_color_mode_changed(system_color_mode());
+    }
+}
+
+pub(crate) fn on_system_color_mode_changed(mode: ColorMode) {
+    if !FOLLOW_SYSTEM_COLOR_MODE.load(Ordering::Relaxed) {
+        return;
+    }
+    // Each window reports the change, only the first one replaces the theme
+    let current = theme();
+    if current.color_mode != mode {
+        set_theme(current.with_color_mode(mode));
+    }
+}
+
+pub(crate) fn theme_events() -> UnboundedReceiver<Arc<Theme>> {
+    let (tx, rx) = unbounded();
+    THEME_LISTENERS.lock().unwrap().push(tx);
+    rx
+}
+
+///
+/// Theme of the widget: either passed explicitly to its builder and kept, or the global one,
+/// replaced on theme change
+///
+#[derive(Clone, Debug)]
+pub(crate) struct ThemeRef {
+    theme: Arc<Theme>,
+    pinned: bool,
+}
+
+impl ThemeRef {
+    pub(crate) fn new(explicit: Option<Arc<Theme>>) -> Self {
+        match explicit {
+            Some(theme) => Self {
+                theme,
+                pinned: true,
+            },
+            None => Self {
+                theme: theme(),
+                pinned: false,
+            },
+        }
+    }
+    pub(crate) fn get(&self) -> &Theme {
+        &self.theme
+    }
+    ///
+    /// Accept the changed global theme, returns true if the widget should be restyled
+    ///
+    pub(crate) fn update(&mut self, theme: &Arc<Theme>) -> bool {
+        if self.pinned || Arc::ptr_eq(&self.theme, theme) {
+            return false;
+        }
+        self.theme = theme.clone();
+        true
+    }
+}
@@ -0,0 +1,284 @@
+use std::{borrow::Cow, time::Duration};
+
+use async_event_streams::{
+    EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
+};
+use async_event_streams_derive::EventSink;
+use async_std::{
+    sync::{Arc, RwLock},
+    task::sleep,
+};
+use async_trait::async_trait;
+use futures::task::{Spawn, SpawnExt};
+use typed_builder::TypedBuilder;
+use windows::{
+    Foundation::{Numerics::Vector2, Rect},
+    UI::{
+        Color,
+        Composition::{Compositor, Visual},
+    },
+};
+
+use crate::handle_err;
+
+use super::{
+    is_translated_point_in_box, visual_offset, Background, BackgroundParams, LayerStack,
+    LayerStackParams, Overlay, Panel, PanelEvent, PopupDirection, PopupPlacement, Text, TextParams,
+};
+
+// Vertical distance between the cursor and the tooltip
+const CURSOR_GAP: f32 = 20.;
+
+pub enum TooltipContent {
+    Text(String),
+    Panel(Arc<dyn Panel>),
+}
+
+impl From<String> for TooltipContent {
+    fn from(text: String) -> Self {
+        TooltipContent::Text(text)
+    }
+}
+
+impl From<&str> for TooltipContent {
+    fn from(text: &str) -> Self {
+        TooltipContent::Text(text.to_owned())
+    }
+}
+
+impl From<Arc<dyn Panel>> for TooltipContent {
+    fn from(panel: Arc<dyn Panel>) -> Self {
+        TooltipContent::Panel(panel)
+    }
+}
+
+///
+/// Shows tooltips for attached panels in the shared overlay layer
+///
+#[derive(Clone)]
+pub struct TooltipService {
+    compositor: Compositor,
+    overlay: Arc<Overlay>,
+    spawner: Arc<dyn Spawn + Send + Sync>,
+    delay: Duration,
+    size: Vector2,
+    color: Color,
+}
+
+#[derive(TypedBuilder)]
+pub struct TooltipServiceParams<T: Spawn + Send + Sync + 'static> {
+    compositor: Compositor,
+    overlay: Arc<Overlay>,
+    spawner: T,
+    color: Color,
+    #[builder(default = Duration::from_millis(500))]
+    delay: Duration,
+    #[builder(default = Vector2 { X: 200., Y: 40. })]
+    size: Vector2,
+}
+
+impl<T: Spawn + Send + Sync + 'static> From<TooltipServiceParams<T>> for TooltipService {
+    fn from(value: TooltipServiceParams<T>) -> Self {
+        TooltipService {
+            compositor: value.compositor,
+            overlay: value.overlay,
+            spawner: Arc::new(value.spawner),
+            delay: value.delay,
+            size: value.size,
+            color: value.color,
+        }
+    }
+}
+
+impl TooltipService {
+    ///
+    /// Wrap the panel into decorator which shows the tooltip when cursor stays over it
+    ///
+    pub fn attach(
+        &self,
+        panel: Arc<dyn Panel>,
+        content: impl Into<TooltipContent>,
+    ) -> crate::Result<Arc<Tooltip>> {
+        let content = match content.into() {
+            TooltipContent::Text(text) => self.create_text_content(text)?,
+            TooltipContent::Panel(panel) => panel,
+        };
+        Ok(Arc::new(Tooltip {
+            service: self.clone(),
+            panel,
+            content,
+            core: Arc::new(RwLock::new(Core {
+                size: Vector2 { X: 0., Y: 0. },
+                generation: 0,
+                shown: false,
+            })),
+            panel_events: EventStreams::new(),
+            id: Arc::new(()),
+        }))
+    }
+
+    fn create_text_content(&self, text: String) -> crate::Result<Arc<dyn Panel>> {
+        let background: Arc<Background> = BackgroundParams::builder()
+            .compositor(self.compositor.clone())
+            .color(self.color)
+            .round_corners(true)
+            .build()
+            .try_into()?;
+        let text: Arc<Text> = TextParams::builder()
+            .compositor(self.compositor.clone())
+            .text(text)
+            .spawner(self.spawner.clone())
+            .build()
+            .try_into()?;
+        let layer_stack: Arc<LayerStack> = LayerStackParams::builder()
+            .compositor(self.compositor.clone())
+            .build()
+            .push_panel(background)
+            .push_panel(text)
+            .try_into()?;
+        Ok(layer_stack)
+    }
+
+    fn position(&self, anchor: Vector2) -> crate::Result<Vector2> {
+        let area = self.overlay.size()?;
+        Ok(
+            PopupPlacement::new(PopupDirection::Below, CURSOR_GAP).place(
+                Rect {
+                    X: anchor.X,
+                    Y: anchor.Y,
+                    Width: 0.,
+                    Height: 0.,
+                },
+                self.size,
+                Rect {
+                    X: 0.,
+                    Y: 0.,
+                    Width: area.X,
+                    Height: area.Y,
+                },
+            ),
+        )
+    }
+}
+
+struct Core {
+    size: Vector2,
+    // Incremented on each cursor movement to invalidate pending delayed show
+    generation: u64,
+    shown: bool,
+}
+
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+pub struct Tooltip {
+    service: TooltipService,
+    panel: Arc<dyn Panel>,
+    content: Arc<dyn Panel>,
+    core: Arc<RwLock<Core>>,
+    panel_events: EventStreams<PanelEvent>,
+    id: Arc<()>,
+}
+
+impl Tooltip {
+    async fn hide(&self) -> crate::Result<()> {
+        let shown = {
+            let mut core = self.core.write().await;
+            core.generation += 1;
+            std::mem::replace(&mut core.shown, false)
+        };
+        if shown {
+            self.service.overlay.hide(self.content.id()).await?;
+        }
+        Ok(())
+    }
+
+    async fn on_cursor_moved(&self, mouse_pos: Vector2) -> crate::Result<()> {
+        self.hide().await?;
+        let (generation, inside) = {
+            let core = self.core.read().await;
+            (
+                core.generation,
+                is_translated_point_in_box(mouse_pos, core.size),
+            )
+        };
+        if !inside {
+            return Ok(());
+        }
+        let service = self.service.clone();
+        let content = self.content.clone();
+        let core = self.core.clone();
+        let target = self.panel.outer_frame();
+        self.service.spawner.spawn(handle_err(async move {
+            sleep(service.delay).await;
+            {
+                let mut core = core.write().await;
+                if core.generation != generation {
+                    return Ok(());
+                }
+                core.shown = true;
+            }
+            // The overlay is not awaited under the lock, so the cursor movements
+            // are not blocked while the tooltip is being shown
+            let origin = visual_offset(&target)?;
+            let overlay_origin = visual_offset(&service.overlay.outer_frame())?;
+            let position = service.position(Vector2 {
+                X: origin.X - overlay_origin.X + mouse_pos.X,
+                Y: origin.Y - overlay_origin.Y + mouse_pos.Y,
+            })?;
+            let content_id = content.id();
+            service
+                .overlay
+                .show(content, position, service.size)
+                .await?;
+            // The cursor has left while showing and the tooltip was hidden before it appeared
+            let hidden = {
+                let core = core.read().await;
+                core.generation != generation && !core.shown
+            };
+            if hidden {
+                service.overlay.hide(content_id).await?;
+            }
+            Ok(())
+        }))?;
+        Ok(())
+    }
+}
+
+impl Panel for Tooltip {
+    fn outer_frame(&self) -> Visual {
+        self.panel.outer_frame()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+}
+
+impl EventSource<PanelEvent> for Tooltip {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for Tooltip {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        match event.as_ref() {
+            PanelEvent::Resized(size) => self.core.write().await.size = *size,
+            PanelEvent::CursorMoved(mouse_pos, _) => self.on_cursor_moved(*mouse_pos).await?,
+            PanelEvent::MouseInput { .. } => self.hide().await?,
+            _ => (),
+        }
+        self.panel
+            .on_event_ref(event.as_ref(), source.clone())
+            .await?;
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+use windows::{
+    Foundation::Numerics::Vector2,
+    Win32::UI::WindowsAndMessaging::{GetSystemMetrics, SM_MAXIMUMTOUCHES},
+};
+
+use super::{is_point_in_box, Margins};
+
+///
+/// True if a touch screen or other touch digitizer is connected
+///
+pub fn is_touch_device() -> bool {
+    unsafe { GetSystemMetrics(SM_MAXIMUMTOUCHES) > 0 }
+}
+
+///
+/// Hit test of the control of given size, with the area extended by `hit_slop` and
+/// to `min_touch_target` (usually `Theme::min_touch_target`) on touch devices
+///
+pub(crate) fn is_point_in_hit_area(
+    point: Vector2,
+    size: Vector2,
+    hit_slop: &Margins,
+    min_touch_target: f32,
+) -> bool {
+    let mut offset = Vector2 {
+        X: -hit_slop.left,
+        Y: -hit_slop.top,
+    };
+    let mut area = Vector2 {
+        X: size.X + hit_slop.left + hit_slop.right,
+        Y: size.Y + hit_slop.top + hit_slop.bottom,
+    };
+    if is_touch_device() {
+        let min = min_touch_target.max(0.);
+        if area.X < min {
+            offset.X -= (min - area.X) / 2.;
+            area.X = min;
+        }
+        if area.Y < min {
+            offset.Y -= (min - area.Y) / 2.;
+            area.Y = min;
+        }
+    }
+    is_point_in_box(point, offset, area)
+}
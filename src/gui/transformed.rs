@@ -0,0 +1,257 @@
+use std::borrow::Cow;
+
+use async_event_streams::{
+    EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
+};
+use async_event_streams_derive::EventSink;
+use async_std::sync::{Arc, RwLock};
+use async_trait::async_trait;
+use typed_builder::TypedBuilder;
+use windows::{
+    Foundation::Numerics::{Vector2, Vector3},
+    UI::Composition::{Compositor, ContainerVisual, Visual},
+};
+
+use super::{attach, is_translated_point_in_box, Panel, PanelEvent};
+
+#[derive(Copy, Clone)]
+struct Transform {
+    // Radians, clockwise
+    rotation: f32,
+    scale: Vector2,
+    translation: Vector2,
+}
+
+impl Transform {
+    fn apply(&self, frame: &Visual, size: Vector2) -> crate::Result<()> {
+        frame.SetCenterPoint(Vector3 {
+            X: size.X / 2.,
+            Y: size.Y / 2.,
+            Z: 0.,
+        })?;
+        frame.SetRotationAngle(self.rotation)?;
+        frame.SetScale(Vector3 {
+            X: self.scale.X,
+            Y: self.scale.Y,
+            Z: 1.,
+        })?;
+        frame.SetOffset(Vector3 {
+            X: self.translation.X,
+            Y: self.translation.Y,
+            Z: 0.,
+        })?;
+        Ok(())
+    }
+
+    // Map the point from the container coordinates to the untransformed child coordinates
+    fn inverse(&self, point: Vector2, size: Vector2) -> Vector2 {
+        let cx = size.X / 2.;
+        let cy = size.Y / 2.;
+        let x = point.X - self.translation.X - cx;
+        let y = point.Y - self.translation.Y - cy;
+        let (sin, cos) = (-self.rotation).sin_cos();
+        let rx = x * cos - y * sin;
+        let ry = x * sin + y * cos;
+        let sx = if self.scale.X != 0. {
+            self.scale.X
+        } else {
+            f32::EPSILON
+        };
+        let sy = if self.scale.Y != 0. {
+            self.scale.Y
+        } else {
+            f32::EPSILON
+        };
+        Vector2 {
+            X: rx / sx + cx,
+            Y: ry / sy + cy,
+        }
+    }
+}
+
+struct Core {
+    transform: Transform,
+    size: Vector2,
+    mouse_pos: Option<Vector2>,
+}
+
+///
+/// Decorator which rotates, scales and translates the wrapped panel. Cursor positions
+/// passed to the panel are transformed back to its own coordinates.
+///
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+pub struct Transformed {
+    container: ContainerVisual,
+    panel: Arc<dyn Panel>,
+    core: RwLock<Core>,
+    panel_events: EventStreams<PanelEvent>,
+    id: Arc<()>,
+}
+
+#[derive(TypedBuilder)]
+pub struct TransformedParams {
+    compositor: Compositor,
+    #[builder(setter(transform = |panel: impl Panel + 'static| Arc::new(panel) as Arc<dyn Panel>))]
+    panel: Arc<dyn Panel>,
+    /// Clockwise rotation around the panel center in radians
+    #[builder(default)]
+    rotation: f32,
+    #[builder(default = Vector2 { X: 1., Y: 1. })]
+    scale: Vector2,
+    #[builder(default)]
+    translation: Vector2,
+}
+
+impl TryFrom<TransformedParams> for Transformed {
+    type Error = crate::Error;
+
+    fn try_from(value: TransformedParams) -> crate::Result<Self> {
+        let container = value.compositor.CreateContainerVisual()?;
+        attach(&container, &*value.panel)?;
+        let transform = Transform {
+            rotation: value.rotation,
+            scale: value.scale,
+            translation: value.translation,
+        };
+        transform.apply(&value.panel.outer_frame(), Vector2::default())?;
+        Ok(Transformed {
+            container,
+            panel: value.panel,
+            core: RwLock::new(Core {
+                transform,
+                size: Vector2::default(),
+                mouse_pos: None,
+            }),
+            panel_events: EventStreams::new(),
+            id: Arc::new(()),
+        })
+    }
+}
+
+impl TryFrom<TransformedParams> for Arc<Transformed> {
+    type Error = crate::Error;
+
+    fn try_from(value: TransformedParams) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
+
+impl Transformed {
+    async fn update_transform(&self, f: impl FnOnce(&mut Transform)) -> crate::Result<()> {
+        let mut core = self.core.write().await;
+        f(&mut core.transform);
+        core.transform.apply(&self.panel.outer_frame(), core.size)
+    }
+
+    pub async fn set_rotation(&self, rotation: f32) -> crate::Result<()> {
+        self.update_transform(|t| t.rotation = rotation).await
+    }
+
+    pub async fn set_scale(&self, scale: Vector2) -> crate::Result<()> {
+        self.update_transform(|t| t.scale = scale).await
+    }
+
+    pub async fn set_translation(&self, translation: Vector2) -> crate::Result<()> {
+        self.update_transform(|t| t.translation = translation).await
+    }
+
+    pub async fn rotation(&self) -> f32 {
+        self.core.read().await.transform.rotation
+    }
+
+    pub async fn scale(&self) -> Vector2 {
+        self.core.read().await.transform.scale
+    }
+
+    pub async fn translation(&self) -> Vector2 {
+        self.core.read().await.transform.translation
+    }
+
+    async fn resize(&self, size: Vector2, source: Option<Arc<EventBox>>) -> crate::Result<()> {
+        self.container.SetSize(size)?;
+        let frame = self.panel.outer_frame();
+        frame.SetSize(size)?;
+        {
+            let mut core = self.core.write().await;
+            core.size = size;
+            core.transform.apply(&frame, size)?;
+        }
+        self.panel
+            .on_event_owned(PanelEvent::Resized(size), source)
+            .await
+    }
+
+    async fn translate_cursor_moved(
+        &self,
+        mouse_pos: Vector2,
+        seq: u64,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        let mouse_pos = {
+            let mut core = self.core.write().await;
+            core.mouse_pos = Some(mouse_pos);
+            core.transform.inverse(mouse_pos, core.size)
+        };
+        self.panel
+            .on_event_owned(PanelEvent::CursorMoved(mouse_pos, seq), source)
+            .await
+    }
+
+    async fn is_in_child(&self) -> bool {
+        let core = self.core.read().await;
+        core.mouse_pos.map_or(false, |mouse_pos| {
+            is_translated_point_in_box(core.transform.inverse(mouse_pos, core.size), core.size)
+        })
+    }
+}
+
+impl Panel for Transformed {
+    fn outer_frame(&self) -> Visual {
+        self.container.clone().into()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+}
+
+impl EventSource<PanelEvent> for Transformed {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for Transformed {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        match event.as_ref() {
+            PanelEvent::Resized(size) => self.resize(*size, source.clone()).await?,
+            PanelEvent::CursorMoved(mouse_pos, seq) => {
+                self.translate_cursor_moved(*mouse_pos, *seq, source.clone())
+                    .await?
+            }
+            PanelEvent::MouseInput { in_slot, .. }
+            | PanelEvent::MouseDoubleClick { in_slot, .. }
+            | PanelEvent::DragDrop { in_slot, .. } => {
+                let in_slot = *in_slot && self.is_in_child().await;
+                self.panel
+                    .on_event_owned(event.with_in_slot(in_slot), source.clone())
+                    .await?
+            }
+            _ => {
+                self.panel
+                    .on_event_ref(event.as_ref(), source.clone())
+                    .await?
+            }
+        }
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+use std::borrow::Cow;
+
+use async_event_streams::{
+    EventBox, EventSink, EventSinkExt, EventSource, EventStream, EventStreams,
+};
+use async_event_streams_derive::EventSink;
+use async_std::sync::{Arc, RwLock};
+use async_trait::async_trait;
+use typed_builder::TypedBuilder;
+use windows::UI::Composition::Visual;
+use winit::event::{ElementState, MouseButton};
+
+use super::{panel::next_event_seq, Panel, PanelEvent};
+
+struct Core {
+    visible: bool,
+    opacity: f32,
+    // Buttons pressed over the panel, released when the panel is hidden
+    pressed: Vec<MouseButton>,
+}
+
+///
+/// Decorator which hides the wrapped panel or makes it translucent without removing
+/// it from the tree. Hidden panel doesn't receive mouse input, the buttons pressed
+/// at the moment of hiding are released with `in_slot` set to false.
+///
+#[derive(EventSink)]
+#[event_sink(event=PanelEvent)]
+pub struct Visibility {
+    panel: Arc<dyn Panel>,
+    core: RwLock<Core>,
+    panel_events: EventStreams<PanelEvent>,
+    id: Arc<()>,
+}
+
+#[derive(TypedBuilder)]
+pub struct VisibilityParams {
+    #[builder(setter(transform = |panel: impl Panel + 'static| Arc::new(panel) as Arc<dyn Panel>))]
+    panel: Arc<dyn Panel>,
+    #[builder(default = true)]
+    visible: bool,
+    #[builder(default = 1.)]
+    opacity: f32,
+}
+
+impl TryFrom<VisibilityParams> for Visibility {
+    type Error = crate::Error;
+
+    fn try_from(value: VisibilityParams) -> crate::Result<Self> {
+        let frame = value.panel.outer_frame();
+        frame.SetIsVisible(value.visible)?;
+        frame.SetOpacity(value.opacity)?;
+        Ok(Visibility {
+            panel: value.panel,
+            core: RwLock::new(Core {
+                visible: value.visible,
+                opacity: value.opacity,
+                pressed: Vec::new(),
+            }),
+            panel_events: EventStreams::new(),
+            id: Arc::new(()),
+        })
+    }
+}
+
+impl TryFrom<VisibilityParams> for Arc<Visibility> {
+    type Error = crate::Error;
+
+    fn try_from(value: VisibilityParams) -> crate::Result<Self> {
+        Ok(Arc::new(value.try_into()?))
+    }
+}
+
+impl Visibility {
+    pub async fn is_visible(&self) -> bool {
+        self.core.read().await.visible
+    }
+
+    pub async fn set_visible(&self, visible: bool) -> crate::Result<()> {
+        let pressed = {
+            let mut core = self.core.write().await;
+            core.visible = visible;
+            if visible {
+                Vec::new()
+            } else {
+                std::mem::take(&mut core.pressed)
+            }
+        };
+        self.panel.outer_frame().SetIsVisible(visible)?;
+        for button in pressed {
+            self.panel
+                .on_event_owned(
+                    PanelEvent::MouseInput {
+                        in_slot: false,
+                        state: ElementState::Released,
+                        button,
+                        seq: next_event_seq(),
+                    },
+                    None,
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn opacity(&self) -> f32 {
+        self.core.read().await.opacity
+    }
+
+    pub async fn set_opacity(&self, opacity: f32) -> crate::Result<()> {
+        let opacity = opacity.clamp(0., 1.);
+        self.core.write().await.opacity = opacity;
+        self.panel.outer_frame().SetOpacity(opacity)?;
+        Ok(())
+    }
+}
+
+impl Panel for Visibility {
+    fn outer_frame(&self) -> Visual {
+        self.panel.outer_frame()
+    }
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.id) as usize
+    }
+}
+
+impl EventSource<PanelEvent> for Visibility {
+    fn event_stream(&self) -> EventStream<PanelEvent> {
+        self.panel_events.create_event_stream()
+    }
+}
+
+#[async_trait]
+impl EventSinkExt<PanelEvent> for Visibility {
+    type Error = crate::Error;
+    async fn on_event<'a>(
+        &'a self,
+        event: Cow<'a, PanelEvent>,
+        source: Option<Arc<EventBox>>,
+    ) -> crate::Result<()> {
+        let is_input = matches!(
+            event.as_ref(),
+            PanelEvent::CursorMoved(..)
+                | PanelEvent::MouseInput { .. }
+                | PanelEvent::MouseDoubleClick { .. }
+                | PanelEvent::DragDrop { .. }
+                | PanelEvent::MouseWheel { .. }
+        );
+        let deliver = {
+            let mut core = self.core.write().await;
+            if let PanelEvent::MouseInput { state, button, .. } = event.as_ref() {
+                if core.visible {
+                    core.pressed.retain(|b| b != button);
+                    if *state == ElementState::Pressed {
+                        core.pressed.push(*button);
+                    }
+                }
+            }
+            !is_input || core.visible
+        };
+        if deliver {
+            self.panel
+                .on_event_ref(event.as_ref(), source.clone())
+                .await?;
+        }
+        self.panel_events
+            .send_event(event.into_owned(), source)
+            .await;
+        Ok(())
+    }
+}
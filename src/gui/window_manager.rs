@@ -0,0 +1,207 @@
+use std::{cell::RefCell, pin::Pin, rc::Rc};
+
+use futures::{
+    channel::{
+        mpsc::{unbounded, UnboundedReceiver, UnboundedSender},
+        oneshot,
+    },
+    task::Spawn,
+};
+use typed_builder::TypedBuilder;
+use windows::{
+    core,
+    Foundation::Numerics::Vector2,
+    Win32::{
+        Foundation::{E_ABORT, HWND, LPARAM, WPARAM},
+        System::Threading::GetCurrentThreadId,
+        UI::WindowsAndMessaging::{PostThreadMessageW, WM_APP},
+    },
+    UI::Composition::Compositor,
+};
+
+use crate::window::native::{run_message_loop_until, Window, WindowClass};
+
+use super::{spawn_window_event_receiver, Panel};
+
+// Posted to the thread of the window manager by `WindowOpener`, wakes its message loop
+const WM_OPEN_WINDOW: u32 = WM_APP + 16;
+
+type OpenRequest = Box<dyn FnOnce(&WindowManager) + Send>;
+
+#[derive(TypedBuilder)]
+pub struct WindowParams {
+    #[builder(setter(into))]
+    title: String,
+    /// Initial size of the client area
+    size: Vector2,
+    /// Class of the window, the manager's default class if not set
+    #[builder(default, setter(strip_option))]
+    class: Option<WindowClass>,
+    /// See `Window::with_custom_chrome`
+    #[builder(default)]
+    custom_chrome: bool,
+}
+
+///
+/// Opens the windows sharing the compositor and the thread pool. Each window has its own
+/// root visual and event receiver task, which ends when the window is closed.
+/// Lives on the thread running the message loop, the other threads open the windows
+/// with the `WindowOpener`.
+///
+pub struct WindowManager {
+    compositor: Compositor,
+    pool: Box<dyn Spawn>,
+    default_class: WindowClass,
+    windows: RefCell<Vec<Rc<Pin<Box<Window>>>>>,
+    requests: RefCell<UnboundedReceiver<OpenRequest>>,
+    opener: WindowOpener,
+}
+
+#[derive(TypedBuilder)]
+pub struct WindowManagerParams {
+    compositor: Compositor,
+    #[builder(setter(transform = |pool: impl Spawn + 'static| Box::new(pool) as Box<dyn Spawn>))]
+    pool: Box<dyn Spawn>,
+    /// Class of the windows which don't set their own one, e.g. with the unique name
+    /// when several libraries in the process embed wag
+    #[builder(default)]
+    default_class: WindowClass,
+}
+
+impl From<WindowManagerParams> for WindowManager {
+    fn from(value: WindowManagerParams) -> Self {
+        let (sender, receiver) = unbounded();
+        WindowManager {
+            compositor: value.compositor,
+            pool: value.pool,
+            default_class: value.default_class,
+            windows: RefCell::new(Vec::new()),
+            requests: RefCell::new(receiver),
+            opener: WindowOpener {
+                thread_id: unsafe { GetCurrentThreadId() },
+                requests: sender,
+            },
+        }
+    }
+}
+
+///
+/// Open the windows of the `WindowManager` from any thread, e.g. from the async code
+/// handling the "New window" button. The window is opened by the message loop
+/// of the manager.
+///
+#[derive(Clone)]
+pub struct WindowOpener {
+    thread_id: u32,
+    requests: UnboundedSender<OpenRequest>,
+}
+
+impl WindowOpener {
+    ///
+    /// Open the window showing the panel, returns the window handle identifying it
+    ///
+    pub async fn open_window(
+        &self,
+        params: WindowParams,
+        panel: impl Panel + 'static,
+    ) -> crate::Result<HWND> {
+        let (sender, receiver) = oneshot::channel();
+        let request: OpenRequest = Box::new(move |manager| {
+            let _ = sender.send(manager.open_window(params, panel));
+        });
+        // The manager is dropped if the request can't be sent, the receiver reports it
+        let _ = self.requests.unbounded_send(request);
+        unsafe {
+            PostThreadMessageW(
+                self.thread_id,
+                WM_OPEN_WINDOW,
+                WPARAM::default(),
+                LPARAM::default(),
+            )
+        }
+        .ok()?;
+        receiver
+            .await
+            .unwrap_or_else(|_| Err(core::Error::from(E_ABORT).into()))
+    }
+}
+
+impl WindowManager {
+    ///
+    /// Open the window showing the panel, returns the window handle identifying it
+    ///
+    pub fn open_window(
+        &self,
+        params: WindowParams,
+        panel: impl Panel + 'static,
+    ) -> crate::Result<HWND> {
+        let root_visual = self.compositor.CreateContainerVisual()?;
+        root_visual.SetSize(params.size)?;
+        let channel = spawn_window_event_receiver(&*self.pool, panel, root_visual.clone())?;
+        let mut window = Window::new(self.compositor.clone(), params.title, root_visual, channel)
+            .with_class(params.class.unwrap_or_else(|| self.default_class.clone()))
+            .with_quit_on_destroy(false);
+        if params.custom_chrome {
+            window = window.with_custom_chrome();
+        }
+        let window = window.open()?;
+        let handle = window.handle();
+        self.windows.borrow_mut().push(Rc::new(window));
+        Ok(handle)
+    }
+
+    pub fn opener(&self) -> WindowOpener {
+        self.opener.clone()
+    }
+
+    ///
+    /// Access the open window, e.g. to change its title. The closure may open
+    /// or close the windows of the manager.
+    ///
+    pub fn with_window<R>(&self, handle: HWND, f: impl FnOnce(&Window) -> R) -> Option<R> {
+        let window = self
+            .windows
+            .borrow()
+            .iter()
+            .find(|window| window.handle() == handle)
+            .cloned()?;
+        Some(f(&window))
+    }
+
+    ///
+    /// Close the window without asking its close handler
+    ///
+    pub fn close_window(&self, handle: HWND) -> crate::Result<()> {
+        match self.with_window(handle, |window| window.closer()) {
+            Some(closer) => closer.close(),
+            None => Ok(()),
+        }
+    }
+
+    pub fn window_count(&self) -> usize {
+        self.windows.borrow().len()
+    }
+
+    // Open the windows requested by the `WindowOpener`s
+    fn handle_requests(&self) {
+        loop {
+            let request = match self.requests.borrow_mut().try_next() {
+                Ok(Some(request)) => request,
+                _ => return,
+            };
+            request(self);
+        }
+    }
+
+    ///
+    /// Run the message loop until all the windows are closed. The closed window is
+    /// dropped, which stops its event receiver.
+    ///
+    pub fn run_message_loop(&self) {
+        run_message_loop_until(|| {
+            self.handle_requests();
+            self.windows.borrow_mut().retain(|window| window.is_open());
+            self.windows.borrow().is_empty()
+        })
+    }
+}
@@ -0,0 +1,148 @@
+use std::{
+    sync::{mpsc, Mutex},
+    thread::{self, JoinHandle},
+};
+
+use async_event_streams::{EventSource, EventStream, EventStreams};
+use async_std::sync::Arc;
+use futures::task::Spawn;
+use windows::{
+    core,
+    Win32::{
+        Foundation::{E_ABORT, LPARAM, WPARAM},
+        System::Threading::GetCurrentThreadId,
+        UI::WindowsAndMessaging::{PostThreadMessageW, WM_QUIT},
+    },
+    UI::Composition::Compositor,
+};
+
+use crate::window::initialize_window_thread;
+
+use super::{WindowManager, WindowManagerParams, WindowOpener};
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum WindowThreadEvent {
+    /// The thread with this id has opened its windows and runs the message loop
+    Started(u32),
+    /// All the windows of the thread are closed or the thread is shut down
+    Finished(u32),
+}
+
+struct WindowThreadHandle {
+    thread_id: u32,
+    join_handle: JoinHandle<()>,
+}
+
+///
+/// Runs the windows on the separate threads, each with its own dispatcher queue, compositor
+/// and message loop, so e.g. the tool palette isn't blocked by the busy main window.
+/// Reports when the threads start and finish and stops them all on `shutdown`
+/// or when dropped.
+///
+pub struct WindowSupervisor {
+    threads: Mutex<Vec<WindowThreadHandle>>,
+    window_thread_events: Arc<EventStreams<WindowThreadEvent>>,
+}
+
+impl Default for WindowSupervisor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WindowSupervisor {
+    pub fn new() -> Self {
+        Self {
+            threads: Mutex::new(Vec::new()),
+            window_thread_events: Arc::new(EventStreams::new()),
+        }
+    }
+
+    ///
+    /// Start the thread with its own `WindowManager` and open its first windows with `init`.
+    /// The thread ends when all its windows are closed. Returns the opener of more windows
+    /// on this thread.
+    ///
+    pub fn spawn_window_thread<T, F>(&self, pool: T, init: F) -> crate::Result<WindowOpener>
+    where
+        T: Spawn + Send + 'static,
+        F: FnOnce(&WindowManager) -> crate::Result<()> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        let events = self.window_thread_events.clone();
+        let join_handle = thread::Builder::new()
+            .name("wag windows".to_owned())
+            .spawn(move || {
+                let started = initialize_window_thread().and_then(|window_thread| {
+                    let manager: WindowManager = WindowManagerParams::builder()
+                        .compositor(Compositor::new()?)
+                        .pool(pool)
+                        .build()
+                        .into();
+                    init(&manager)?;
+                    Ok((window_thread, manager))
+                });
+                let (_window_thread, manager) = match started {
+                    Ok(started) => started,
+                    Err(e) => {
+                        let _ = sender.send(Err(e));
+                        return;
+                    }
+                };
+                let thread_id = unsafe { GetCurrentThreadId() };
+                let _ = sender.send(Ok((thread_id, manager.opener())));
+                // Posted: the window thread must not wait for the subscribers, which
+                // may be waiting for this thread's windows themselves
+                events.post_event(WindowThreadEvent::Started(thread_id), None);
+                manager.run_message_loop();
+                // The windows are destroyed before the thread's apartment is uninitialized
+                drop(manager);
+                events.post_event(WindowThreadEvent::Finished(thread_id), None);
+            })?;
+        // The thread has panicked if the sender is dropped without the result
+        let (thread_id, opener) = receiver
+            .recv()
+            .unwrap_or_else(|_| Err(core::Error::from(E_ABORT).into()))?;
+        self.threads.lock().unwrap().push(WindowThreadHandle {
+            thread_id,
+            join_handle,
+        });
+        Ok(opener)
+    }
+
+    ///
+    /// Quit the message loops of all the threads, closing their windows,
+    /// and wait until the threads end
+    ///
+    pub fn shutdown(&self) {
+        let threads = std::mem::take(&mut *self.threads.lock().unwrap());
+        for thread in &threads {
+            // The id of the ended thread may be reused by the other one
+            if !thread.join_handle.is_finished() {
+                unsafe {
+                    PostThreadMessageW(
+                        thread.thread_id,
+                        WM_QUIT,
+                        WPARAM::default(),
+                        LPARAM::default(),
+                    )
+                };
+            }
+        }
+        for thread in threads {
+            let _ = thread.join_handle.join();
+        }
+    }
+}
+
+impl Drop for WindowSupervisor {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+impl EventSource<WindowThreadEvent> for WindowSupervisor {
+    fn event_stream(&self) -> EventStream<WindowThreadEvent> {
+        self.window_thread_events.create_event_stream()
+    }
+}
@@ -0,0 +1,243 @@
+//!
+//! Sizing algorithms used by the layout panels, working on plain numbers without
+//! the composition objects, so they can be used by headless tools
+//!
+
+///
+/// Constraint of one item's size along the layout axis
+///
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct SizeLimit {
+    /// Share of the free space relative to the other items
+    pub ratio: f32,
+    pub min_size: f32,
+    pub max_size: Option<f32>,
+}
+
+impl Default for SizeLimit {
+    fn default() -> Self {
+        Self {
+            ratio: 1.,
+            min_size: 0.,
+            max_size: None,
+        }
+    }
+}
+
+///
+/// Interval on the layout axis
+///
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct Span {
+    pub offset: f32,
+    pub size: f32,
+}
+
+impl Span {
+    pub fn end(&self) -> f32 {
+        self.offset + self.size
+    }
+}
+
+///
+/// Position of the content smaller than its cell along one axis
+///
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub enum CellAlignment {
+    Start,
+    #[default]
+    Center,
+    End,
+}
+
+impl CellAlignment {
+    ///
+    /// Span of the content of `size` aligned inside the `cell`
+    ///
+    pub fn align(&self, cell: Span, size: f32) -> Span {
+        let free_space = cell.size - size;
+        let offset = match self {
+            CellAlignment::Start => 0.,
+            CellAlignment::Center => free_space / 2.,
+            CellAlignment::End => free_space,
+        };
+        Span {
+            offset: cell.offset + offset,
+            size,
+        }
+    }
+}
+
+///
+/// Place the items one after another starting from `start`, with `spacing` between them,
+/// sharing the `extent`. Items exceed the extent if their minimal sizes don't fit.
+///
+pub fn arrange(limits: &[SizeLimit], start: f32, extent: f32, spacing: f32) -> Vec<Span> {
+    let gaps = spacing * limits.len().saturating_sub(1) as f32;
+    let mut offset = start;
+    distribute(limits, (extent - gaps).max(0.))
+        .into_iter()
+        .map(|size| {
+            let span = Span { offset, size };
+            offset += size + spacing;
+            span
+        })
+        .collect()
+}
+
+///
+/// Split the `target` size between the items proportionally to their ratios,
+/// respecting their minimal and maximal sizes. The items with the zero, negative
+/// or not finite ratio get their minimal sizes, the negative or NaN target is
+/// treated as zero.
+///
+pub fn distribute(limits: &[SizeLimit], target: f32) -> Vec<f32> {
+    let mut target = if target > 0. { target } else { 0. };
+    let mut lock = Vec::with_capacity(limits.len());
+    let mut result = Vec::with_capacity(limits.len());
+    for limit in limits {
+        let min_size = limit.min_size.max(0.);
+        let shared = limit.ratio > 0. && limit.ratio.is_finite();
+        if !shared {
+            target -= min_size;
+        }
+        lock.push(!shared);
+        result.push(min_size);
+    }
+    target = target.max(0.);
+
+    loop {
+        let sum_ratio = limits
+            .iter()
+            .zip(&lock)
+            .filter(|(_, lock)| !**lock)
+            .map(|(limit, _)| limit.ratio)
+            .sum::<f32>();
+        let mut new_target = target;
+        let mut all_lock = true;
+        for i in 0..limits.len() {
+            if !lock[i] {
+                let min_size = limits[i].min_size.max(0.);
+                let mut share = target * limits[i].ratio / sum_ratio;
+                if share <= min_size {
+                    share = min_size;
+                    lock[i] = true;
+                }
+                if let Some(max_size) = limits[i].max_size {
+                    // The minimal size wins if the limits contradict each other
+                    let max_size = max_size.max(min_size);
+                    if share > max_size {
+                        share = max_size;
+                        lock[i] = true;
+                    }
+                }
+                if lock[i] {
+                    new_target -= share;
+                } else {
+                    all_lock = false;
+                }
+                result[i] = share;
+            }
+        }
+        if all_lock || new_target == target {
+            break;
+        }
+        target = if new_target > 0. { new_target } else { 0. };
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit(ratio: f32, min_size: f32, max_size: Option<f32>) -> SizeLimit {
+        SizeLimit {
+            ratio,
+            min_size,
+            max_size,
+        }
+    }
+
+    #[test]
+    fn distribute_proportionally() {
+        let sizes = distribute(&[limit(1., 0., None), limit(3., 0., None)], 100.);
+        assert_eq!(sizes, vec![25., 75.]);
+    }
+
+    #[test]
+    fn distribute_respects_min_and_max() {
+        let sizes = distribute(
+            &[
+                limit(1., 40., None),
+                limit(1., 0., Some(10.)),
+                limit(1., 0., None),
+            ],
+            100.,
+        );
+        assert_eq!(sizes, vec![40., 10., 50.]);
+    }
+
+    #[test]
+    fn distribute_min_sizes_exceed_target() {
+        let sizes = distribute(&[limit(1., 60., None), limit(1., 60., None)], 100.);
+        assert_eq!(sizes, vec![60., 60.]);
+    }
+
+    #[test]
+    fn distribute_bad_ratio_gets_min_size() {
+        for ratio in [0., -1., f32::NAN, f32::INFINITY] {
+            let sizes = distribute(&[limit(ratio, 20., None), limit(1., 0., None)], 100.);
+            assert_eq!(sizes, vec![20., 80.]);
+        }
+        assert_eq!(distribute(&[limit(0., 0., None)], 100.), vec![0.]);
+    }
+
+    #[test]
+    fn distribute_bad_target_is_zero() {
+        for target in [-10., f32::NAN] {
+            let sizes = distribute(&[limit(1., 5., None), limit(1., 0., None)], target);
+            assert_eq!(sizes, vec![5., 0.]);
+        }
+    }
+
+    #[test]
+    fn distribute_min_wins_over_smaller_max() {
+        let sizes = distribute(&[limit(1., 30., Some(10.)), limit(1., 0., None)], 100.);
+        assert_eq!(sizes, vec![30., 70.]);
+    }
+
+    #[test]
+    fn distribute_empty() {
+        assert!(distribute(&[], 100.).is_empty());
+    }
+
+    #[test]
+    fn arrange_with_spacing() {
+        let spans = arrange(&[limit(1., 0., None), limit(1., 0., None)], 10., 110., 10.);
+        assert_eq!(
+            spans,
+            vec![
+                Span {
+                    offset: 10.,
+                    size: 50.
+                },
+                Span {
+                    offset: 70.,
+                    size: 50.
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn align_in_cell() {
+        let cell = Span {
+            offset: 10.,
+            size: 100.,
+        };
+        assert_eq!(CellAlignment::Start.align(cell, 20.).offset, 10.);
+        assert_eq!(CellAlignment::Center.align(cell, 20.).offset, 50.);
+        assert_eq!(CellAlignment::End.align(cell, 20.).offset, 90.);
+    }
+}
@@ -1,7 +1,13 @@
 //! # WAG - Windows Asynchronous GUI
+pub mod capabilities;
+pub mod debug;
 mod error;
+pub mod fs;
 pub mod gui;
+pub mod layout;
+pub mod process;
+pub mod shell;
 pub mod window;
 
-pub use error::{handle_err, on_err, Error, Result};
+pub use error::{clear_error_handler, handle_err, on_err, set_error_handler, Error, Result};
 pub use winit::event::WindowEvent;
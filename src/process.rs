@@ -0,0 +1,120 @@
+//! Child processes with the output delivered as event streams
+
+use std::{
+    io::{BufRead, BufReader, Read},
+    os::windows::io::AsRawHandle,
+    process::{Command, ExitStatus, Stdio},
+    sync::{Arc, Mutex},
+    thread::JoinHandle,
+};
+
+use async_event_streams::{EventStream, EventStreams};
+use windows::Win32::{Foundation::HANDLE, System::Threading::WaitForSingleObject};
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum OutputLine {
+    Stdout(String),
+    Stderr(String),
+    ///
+    /// Last event of the stream, sent after all the output is delivered
+    ///
+    Exited(ExitStatus),
+}
+
+///
+/// Handle of the process started by `spawn_streamed`. Dropping it doesn't stop the process.
+///
+#[derive(Clone)]
+pub struct Child {
+    child: Arc<Mutex<std::process::Child>>,
+    id: u32,
+}
+
+impl Child {
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+    ///
+    /// Terminate the process. Doesn't block, so it's safe to call from event handlers.
+    /// The `Exited` event is still sent when the process ends.
+    ///
+    pub fn kill(&self) -> crate::Result<()> {
+        let mut child = self.child.lock().unwrap();
+        if child.try_wait()?.is_none() {
+            child.kill()?;
+        }
+        Ok(())
+    }
+    ///
+    /// Exit status if the process has already finished
+    ///
+    pub fn exit_status(&self) -> crate::Result<Option<ExitStatus>> {
+        Ok(self.child.lock().unwrap().try_wait()?)
+    }
+}
+
+///
+/// Start the command with stdout and stderr captured. Output is read on background threads
+/// and sent line by line to the returned stream, so slow consumers never block the process.
+///
+pub fn spawn_streamed(mut cmd: Command) -> crate::Result<(Child, EventStream<OutputLine>)> {
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let output_events = Arc::new(EventStreams::new());
+    // Stream is created before the reading starts, so that no lines are lost
+    let stream = output_events.create_event_stream();
+    let readers = [
+        child
+            .stdout
+            .take()
+            .map(|stdout| read_lines(stdout, output_events.clone(), OutputLine::Stdout)),
+        child
+            .stderr
+            .take()
+            .map(|stderr| read_lines(stderr, output_events.clone(), OutputLine::Stderr)),
+    ];
+    let id = child.id();
+    let process = HANDLE(child.as_raw_handle() as isize);
+    let child = Arc::new(Mutex::new(child));
+    {
+        let child = child.clone();
+        std::thread::spawn(move || {
+            // Waiting without the lock keeps `kill` available. The handle stays valid
+            // while `child` is alive.
+            unsafe { WaitForSingleObject(process, u32::MAX) };
+            readers
+                .into_iter()
+                .flatten()
+                .for_each(|reader| reader.join().unwrap());
+            let status = child.lock().unwrap().wait();
+            status
+                .map(|status| output_events.post_event(OutputLine::Exited(status), None))
+                .unwrap_or_else(|e| crate::on_err(e.into()));
+        });
+    }
+    Ok((Child { child, id }, stream))
+}
+
+fn read_lines(
+    pipe: impl Read + Send + 'static,
+    output_events: Arc<EventStreams<OutputLine>>,
+    make_event: fn(String) -> OutputLine,
+) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(pipe);
+        let mut line = Vec::new();
+        // Console programs don't necessarily write UTF-8, so lines are not read as strings
+        while let Ok(size) = reader.read_until(b'\n', &mut line) {
+            if size == 0 {
+                break;
+            }
+            let text = String::from_utf8_lossy(&line);
+            let text = text.trim_end_matches(&['\r', '\n'][..]).to_owned();
+            output_events.post_event(make_event(text), None);
+            line.clear();
+        }
+    })
+}
@@ -0,0 +1,153 @@
+//! Access to the file icons and thumbnails provided by the Windows shell
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use windows::{
+    core::InParam,
+    Win32::{
+        Foundation::{HWND, SIZE},
+        Graphics::Gdi::{
+            DeleteObject, GetDC, GetDIBits, GetObjectW, ReleaseDC, BITMAP, BITMAPINFO,
+            BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS, HBITMAP,
+        },
+        UI::Shell::{
+            IShellItemImageFactory, SHCreateItemFromParsingName, SIIGBF, SIIGBF_BIGGERSIZEOK,
+            SIIGBF_ICONONLY, SIIGBF_RESIZETOFIT, SIIGBF_THUMBNAILONLY,
+        },
+    },
+};
+
+use crate::{
+    gui::{pixels_len, Bitmap},
+    window::ToWide,
+};
+
+#[derive(PartialEq, Eq, Hash)]
+struct CacheKey {
+    path: PathBuf,
+    size: u32,
+    icon: bool,
+    // Thumbnails of changed files are requested again
+    modified: Option<SystemTime>,
+}
+
+thread_local! {
+    static CACHE: RefCell<HashMap<CacheKey, Bitmap>> = RefCell::new(HashMap::new());
+}
+
+///
+/// Icon of the file or folder as shown by Explorer, scaled to fit the square of given size
+///
+pub fn icon_for_path(path: impl AsRef<Path>, size: u32) -> crate::Result<Bitmap> {
+    cached_image(path.as_ref(), size, true)
+}
+
+///
+/// Content preview of the file, e.g. downscaled picture or first page of the document.
+/// Fails if the shell has no thumbnail handler for the file type.
+///
+pub fn thumbnail_for_path(path: impl AsRef<Path>, size: u32) -> crate::Result<Bitmap> {
+    cached_image(path.as_ref(), size, false)
+}
+
+///
+/// Drop the icons and thumbnails cached for the current thread
+///
+pub fn clear_cache() {
+    CACHE.with(|cache| cache.borrow_mut().clear());
+}
+
+fn cached_image(path: &Path, size: u32, icon: bool) -> crate::Result<Bitmap> {
+    let key = CacheKey {
+        path: path.to_path_buf(),
+        size,
+        icon,
+        modified: std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok(),
+    };
+    if let Some(bitmap) = CACHE.with(|cache| cache.borrow().get(&key).cloned()) {
+        return Ok(bitmap);
+    }
+    let flags = if icon {
+        SIIGBF_ICONONLY
+    } else {
+        SIIGBF_THUMBNAILONLY
+    };
+    let bitmap = shell_image(path, size, SIIGBF(flags.0 | SIIGBF_RESIZETOFIT.0))?;
+    CACHE.with(|cache| cache.borrow_mut().insert(key, bitmap.clone()));
+    Ok(bitmap)
+}
+
+fn shell_image(path: &Path, size: u32, flags: SIIGBF) -> crate::Result<Bitmap> {
+    let path = path.to_wide();
+    let factory: IShellItemImageFactory =
+        unsafe { SHCreateItemFromParsingName(path.as_pcwstr(), InParam::null()) }?;
+    let size = SIZE {
+        cx: size as i32,
+        cy: size as i32,
+    };
+    let hbitmap = unsafe { factory.GetImage(size, flags) }
+        .or_else(|_| unsafe { factory.GetImage(size, SIIGBF(flags.0 | SIIGBF_BIGGERSIZEOK.0)) })?;
+    let bitmap = bitmap_from_hbitmap(hbitmap);
+    unsafe { DeleteObject(hbitmap) };
+    bitmap
+}
+
+fn bitmap_from_hbitmap(hbitmap: HBITMAP) -> crate::Result<Bitmap> {
+    let mut info = BITMAP::default();
+    if unsafe {
+        GetObjectW(
+            hbitmap,
+            std::mem::size_of::<BITMAP>() as i32,
+            Some(&mut info as *mut _ as _),
+        )
+    } == 0
+    {
+        return Err(windows::core::Error::from_win32().into());
+    }
+    let width = info.bmWidth as u32;
+    let height = info.bmHeight.unsigned_abs();
+    let mut bitmap_info = BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width as i32,
+            // Negative height requests rows from top to bottom
+            biHeight: -(height as i32),
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let len = pixels_len(width, height).ok_or(crate::Error::BadBitmapSize)?;
+    let mut pixels = vec![0u8; len];
+    let hdc = unsafe { GetDC(HWND::default()) };
+    let lines = unsafe {
+        GetDIBits(
+            hdc,
+            hbitmap,
+            0,
+            height,
+            Some(pixels.as_mut_ptr() as _),
+            &mut bitmap_info,
+            DIB_RGB_COLORS,
+        )
+    };
+    unsafe { ReleaseDC(HWND::default(), hdc) };
+    if lines == 0 {
+        return Err(windows::core::Error::from_win32().into());
+    }
+    // Shell bitmaps with alpha channel are premultiplied, as required by `Bitmap`,
+    // but opaque ones (e.g. photo thumbnails) come with zero alpha
+    if pixels.chunks(4).all(|pixel| pixel[3] == 0) {
+        pixels.chunks_mut(4).for_each(|pixel| pixel[3] = 0xFF);
+    }
+    Bitmap::new(width, height, pixels)
+}
@@ -0,0 +1,158 @@
+use std::sync::Mutex;
+
+use windows::{
+    core::{Interface, Weak},
+    Foundation::Numerics::Vector2,
+    Win32::{
+        Foundation::RECT,
+        UI::WindowsAndMessaging::{
+            GetSystemMetrics, HTBOTTOM, HTBOTTOMLEFT, HTBOTTOMRIGHT, HTCAPTION, HTCLIENT, HTLEFT,
+            HTRIGHT, HTTOP, HTTOPLEFT, HTTOPRIGHT, SM_CXPADDEDBORDER, SM_CXSIZEFRAME,
+            SM_CYSIZEFRAME,
+        },
+    },
+    UI::Composition::{ContainerVisual, Visual},
+};
+
+static REGIONS: Mutex<Vec<(RegionVisual, WindowRegion)>> = Mutex::new(Vec::new());
+
+// The marked visual is referenced weakly, so the regions of the dropped panels
+// and of the closed windows are removed instead of keeping their visuals alive
+struct RegionVisual(Weak<Visual>);
+
+// Safety: the composition objects are agile, their weak references can be resolved
+// on any thread
+unsafe impl Send for RegionVisual {}
+
+impl RegionVisual {
+    fn get(&self) -> Option<Visual> {
+        self.0.upgrade()
+    }
+}
+
+///
+/// Role of the area of the window with the custom chrome, i.e. without the system
+/// title bar and borders (see `Window::with_custom_chrome`)
+///
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum WindowRegion {
+    /// Dragging moves the window, double click maximizes it, like the system title bar
+    Caption,
+    /// Usual client area inside the caption, e.g. the buttons on the title bar
+    Client,
+}
+
+///
+/// Mark the visual's area as the caption or the client region, `None` removes the mark.
+/// The client regions take precedence over the caption ones. The area follows
+/// the visual's offset and size and is ignored while the visual is hidden.
+///
+pub fn set_window_region(visual: &Visual, region: Option<WindowRegion>) -> crate::Result<()> {
+    let mut regions = REGIONS.lock().unwrap();
+    regions.retain(|(v, _)| v.get().map_or(false, |v| v != *visual));
+    if let Some(region) = region {
+        regions.push((RegionVisual(visual.downgrade()?), region));
+    }
+    Ok(())
+}
+
+// Offset of the visual in the root visual coordinates, `None` if the visual is hidden
+// or is not attached to the root
+fn offset_in_root(visual: &Visual, root: &ContainerVisual) -> Option<Vector2> {
+    let mut offset = Vector2 { X: 0., Y: 0. };
+    let mut visual = visual.clone();
+    loop {
+        if !visual.IsVisible().ok()? {
+            return None;
+        }
+        let v = visual.Offset().ok()?;
+        offset.X += v.X;
+        offset.Y += v.Y;
+        let parent = visual.Parent().ok()?;
+        if &parent == root {
+            return Some(offset);
+        }
+        visual = parent.into();
+    }
+}
+
+fn region_at(root: &ContainerVisual, point: Vector2) -> Option<WindowRegion> {
+    let contains = |visual: &Visual| {
+        let (offset, size) = match (offset_in_root(visual, root), visual.Size()) {
+            (Some(offset), Ok(size)) => (offset, size),
+            _ => return false,
+        };
+        point.X >= offset.X
+            && point.Y >= offset.Y
+            && point.X < offset.X + size.X
+            && point.Y < offset.Y + size.Y
+    };
+    let mut regions = REGIONS.lock().unwrap();
+    let mut visuals = Vec::new();
+    regions.retain(|(visual, region)| match visual.get() {
+        Some(visual) => {
+            visuals.push((visual, *region));
+            true
+        }
+        None => false,
+    });
+    drop(regions);
+    let hit = |region| {
+        visuals
+            .iter()
+            .any(|(visual, r)| *r == region && contains(visual))
+    };
+    if hit(WindowRegion::Client) {
+        Some(WindowRegion::Client)
+    } else if hit(WindowRegion::Caption) {
+        Some(WindowRegion::Caption)
+    } else {
+        None
+    }
+}
+
+///
+/// Width of the invisible resize border of the window without the system frame
+///
+pub(crate) fn resize_border() -> (i32, i32) {
+    unsafe {
+        let padding = GetSystemMetrics(SM_CXPADDEDBORDER);
+        (
+            GetSystemMetrics(SM_CXSIZEFRAME) + padding,
+            GetSystemMetrics(SM_CYSIZEFRAME) + padding,
+        )
+    }
+}
+
+///
+/// WM_NCHITTEST result for the point in the client area of the window with the custom chrome
+///
+pub(crate) fn hit_test(
+    root: &ContainerVisual,
+    client_rect: RECT,
+    point: Vector2,
+    resizable: bool,
+) -> u32 {
+    if resizable {
+        let (border_x, border_y) = resize_border();
+        let left = point.X < (client_rect.left + border_x) as f32;
+        let right = point.X >= (client_rect.right - border_x) as f32;
+        let top = point.Y < (client_rect.top + border_y) as f32;
+        let bottom = point.Y >= (client_rect.bottom - border_y) as f32;
+        match (left, right, top, bottom) {
+            (true, _, true, _) => return HTTOPLEFT,
+            (_, true, true, _) => return HTTOPRIGHT,
+            (true, _, _, true) => return HTBOTTOMLEFT,
+            (_, true, _, true) => return HTBOTTOMRIGHT,
+            (true, ..) => return HTLEFT,
+            (_, true, ..) => return HTRIGHT,
+            (_, _, true, _) => return HTTOP,
+            (.., true) => return HTBOTTOM,
+            _ => (),
+        }
+    }
+    match region_at(root, point) {
+        Some(WindowRegion::Caption) => HTCAPTION,
+        _ => HTCLIENT,
+    }
+}
@@ -0,0 +1,206 @@
+use std::path::PathBuf;
+
+use windows::{
+    w,
+    Win32::{
+        Foundation::{HANDLE, HWND, POINT},
+        System::{
+            DataExchange::{
+                CloseClipboard, EmptyClipboard, EnumClipboardFormats, GetClipboardData,
+                IsClipboardFormatAvailable, OpenClipboard, RegisterClipboardFormatW,
+                SetClipboardData,
+            },
+            Memory::{
+                GlobalAlloc, GlobalFree, GlobalLock, GlobalSize, GlobalUnlock, GMEM_MOVEABLE,
+            },
+            SystemServices::{CF_DIB, CF_HDROP, CF_UNICODETEXT},
+        },
+        UI::Shell::{DROPFILES, HDROP},
+    },
+};
+
+use super::{drop_target::query_files, wide_string::ToWide};
+
+///
+/// Clipboard content formats which wag reads and writes
+///
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ClipboardFormat {
+    Text,
+    /// Device independent bitmap (`CF_DIB`)
+    Dib,
+    /// PNG image registered by the browsers and the image editors, keeps the transparency
+    Png,
+    /// List of the files (`CF_HDROP`), e.g. copied in the Explorer
+    Files,
+}
+
+impl ClipboardFormat {
+    fn id(self) -> u32 {
+        match self {
+            ClipboardFormat::Text => CF_UNICODETEXT.0,
+            ClipboardFormat::Dib => CF_DIB.0,
+            // Registering the existing format returns its id
+            ClipboardFormat::Png => unsafe { RegisterClipboardFormatW(w!("PNG")) },
+            ClipboardFormat::Files => CF_HDROP.0,
+        }
+    }
+
+    fn from_id(id: u32) -> Option<Self> {
+        [
+            ClipboardFormat::Text,
+            ClipboardFormat::Dib,
+            ClipboardFormat::Png,
+            ClipboardFormat::Files,
+        ]
+        .into_iter()
+        .find(|format| format.id() == id)
+    }
+}
+
+// Run the function with the clipboard opened by the current thread
+fn with_clipboard<T>(f: impl FnOnce() -> crate::Result<T>) -> crate::Result<T> {
+    unsafe { OpenClipboard(HWND::default()) }.ok()?;
+    let result = f();
+    unsafe { CloseClipboard() };
+    result
+}
+
+pub fn is_clipboard_format_available(format: ClipboardFormat) -> bool {
+    unsafe { IsClipboardFormatAvailable(format.id()) }.as_bool()
+}
+
+///
+/// Formats of the clipboard content known to wag, in the order of preference
+/// of the application which has put it
+///
+pub fn clipboard_formats() -> crate::Result<Vec<ClipboardFormat>> {
+    with_clipboard(|| {
+        let mut formats = Vec::new();
+        let mut id = 0;
+        loop {
+            id = unsafe { EnumClipboardFormats(id) };
+            if id == 0 {
+                return Ok(formats);
+            }
+            formats.extend(ClipboardFormat::from_id(id));
+        }
+    })
+}
+
+///
+/// Replace the clipboard content with the data in several formats at once,
+/// e.g. the image both as PNG and as DIB for the applications not supporting PNG
+///
+pub fn set_clipboard_data(data: &[(ClipboardFormat, &[u8])]) -> crate::Result<()> {
+    with_clipboard(|| unsafe {
+        EmptyClipboard().ok()?;
+        for (format, data) in data {
+            put_data(format.id(), data)?;
+        }
+        Ok(())
+    })
+}
+
+///
+/// Raw clipboard content in the format, `None` if the clipboard has no such format
+///
+pub fn clipboard_data(format: ClipboardFormat) -> crate::Result<Option<Vec<u8>>> {
+    with_clipboard(|| unsafe {
+        let memory = match GetClipboardData(format.id()) {
+            Ok(handle) => handle.0,
+            Err(_) => return Ok(None),
+        };
+        let size = GlobalSize(memory);
+        let source = GlobalLock(memory) as *const u8;
+        if source.is_null() {
+            return Err(windows::core::Error::from_win32().into());
+        }
+        let data = std::slice::from_raw_parts(source, size).to_vec();
+        GlobalUnlock(memory);
+        Ok(Some(data))
+    })
+}
+
+///
+/// Replace the clipboard content with the text
+///
+pub fn set_clipboard_text(text: &str) -> crate::Result<()> {
+    let text = text
+        .encode_utf16()
+        .chain(Some(0))
+        .flat_map(u16::to_ne_bytes)
+        .collect::<Vec<_>>();
+    set_clipboard_data(&[(ClipboardFormat::Text, &text)])
+}
+
+pub fn clipboard_text() -> crate::Result<Option<String>> {
+    Ok(clipboard_data(ClipboardFormat::Text)?.map(|data| {
+        let text = data
+            .chunks_exact(2)
+            .map(|c| u16::from_ne_bytes([c[0], c[1]]))
+            .take_while(|c| *c != 0)
+            .collect::<Vec<_>>();
+        String::from_utf16_lossy(&text)
+    }))
+}
+
+///
+/// Replace the clipboard content with the list of the files, the Explorer pastes them
+///
+pub fn set_clipboard_files(files: &[PathBuf]) -> crate::Result<()> {
+    let header = DROPFILES {
+        pFiles: std::mem::size_of::<DROPFILES>() as u32,
+        pt: POINT::default(),
+        fNC: false.into(),
+        fWide: true.into(),
+    };
+    // Safety: DROPFILES is the plain C structure
+    let mut data = unsafe {
+        std::slice::from_raw_parts(
+            &header as *const DROPFILES as *const u8,
+            std::mem::size_of::<DROPFILES>(),
+        )
+    }
+    .to_vec();
+    // The null terminated paths followed by the empty one
+    for file in files {
+        data.extend(
+            file.to_wide()
+                .as_wide_with_nul()
+                .iter()
+                .flat_map(|c| c.to_ne_bytes()),
+        );
+    }
+    data.extend(0u16.to_ne_bytes());
+    set_clipboard_data(&[(ClipboardFormat::Files, &data)])
+}
+
+pub fn clipboard_files() -> crate::Result<Option<Vec<PathBuf>>> {
+    with_clipboard(|| unsafe {
+        Ok(match GetClipboardData(ClipboardFormat::Files.id()) {
+            Ok(handle) => Some(query_files(HDROP(handle.0))),
+            Err(_) => None,
+        })
+    })
+}
+
+unsafe fn put_data(format: u32, data: &[u8]) -> crate::Result<()> {
+    let memory = GlobalAlloc(GMEM_MOVEABLE, data.len());
+    if memory == 0 {
+        return Err(windows::core::Error::from_win32().into());
+    }
+    let target = GlobalLock(memory) as *mut u8;
+    if target.is_null() {
+        GlobalFree(memory);
+        return Err(windows::core::Error::from_win32().into());
+    }
+    std::ptr::copy_nonoverlapping(data.as_ptr(), target, data.len());
+    GlobalUnlock(memory);
+    // On success the clipboard owns the memory
+    if let Err(e) = SetClipboardData(format, HANDLE(memory)) {
+        GlobalFree(memory);
+        return Err(e.into());
+    }
+    Ok(())
+}
@@ -0,0 +1,25 @@
+use windows::{
+    w,
+    Win32::System::Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD},
+};
+
+///
+/// True if the user selected the dark mode for the applications in the system settings
+///
+pub fn is_system_dark_mode() -> bool {
+    let mut value: u32 = 1;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    let result = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            w!("Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize"),
+            w!("AppsUseLightTheme"),
+            RRF_RT_REG_DWORD,
+            None,
+            Some(&mut value as *mut _ as _),
+            Some(&mut size as *mut _),
+        )
+    };
+    // The value is absent before Windows 10 1809, which has no dark mode for applications
+    result.is_ok() && value == 0
+}
@@ -0,0 +1,60 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use windows::{
+    core::PCWSTR,
+    Win32::{
+        Foundation::HINSTANCE,
+        UI::WindowsAndMessaging::{
+            LoadCursorW, SetCursor, IDC_APPSTARTING, IDC_ARROW, IDC_CROSS, IDC_HAND, IDC_HELP,
+            IDC_IBEAM, IDC_NO, IDC_SIZEALL, IDC_SIZENESW, IDC_SIZENS, IDC_SIZENWSE, IDC_SIZEWE,
+            IDC_WAIT,
+        },
+    },
+};
+use winit::window::CursorIcon;
+
+// Resource id of the system cursor shown over the client area of the windows
+static CURSOR_ID: AtomicUsize = AtomicUsize::new(0);
+
+fn cursor_id(icon: CursorIcon) -> PCWSTR {
+    match icon {
+        CursorIcon::Hand | CursorIcon::Grab | CursorIcon::Grabbing => IDC_HAND,
+        CursorIcon::Crosshair => IDC_CROSS,
+        CursorIcon::Text | CursorIcon::VerticalText => IDC_IBEAM,
+        CursorIcon::Wait => IDC_WAIT,
+        CursorIcon::Progress => IDC_APPSTARTING,
+        CursorIcon::Help => IDC_HELP,
+        CursorIcon::NotAllowed | CursorIcon::NoDrop => IDC_NO,
+        CursorIcon::Move | CursorIcon::AllScroll => IDC_SIZEALL,
+        CursorIcon::EResize
+        | CursorIcon::WResize
+        | CursorIcon::EwResize
+        | CursorIcon::ColResize => IDC_SIZEWE,
+        CursorIcon::NResize
+        | CursorIcon::SResize
+        | CursorIcon::NsResize
+        | CursorIcon::RowResize => IDC_SIZENS,
+        CursorIcon::NeResize | CursorIcon::SwResize | CursorIcon::NeswResize => IDC_SIZENESW,
+        CursorIcon::NwResize | CursorIcon::SeResize | CursorIcon::NwseResize => IDC_SIZENWSE,
+        _ => IDC_ARROW,
+    }
+}
+
+///
+/// Change the cursor shown over the client area. The window applies it on each
+/// WM_SETCURSOR, so the cursor set by a panel survives the mouse movements.
+///
+pub fn set_cursor(icon: CursorIcon) -> crate::Result<()> {
+    CURSOR_ID.store(cursor_id(icon).0 as usize, Ordering::Relaxed);
+    apply_cursor()
+}
+
+pub(crate) fn apply_cursor() -> crate::Result<()> {
+    let id = match CURSOR_ID.load(Ordering::Relaxed) {
+        0 => IDC_ARROW,
+        id => PCWSTR(id as *const u16),
+    };
+    let cursor = unsafe { LoadCursorW(HINSTANCE::default(), id) }?;
+    unsafe { SetCursor(cursor) };
+    Ok(())
+}
@@ -0,0 +1,234 @@
+use std::{path::PathBuf, sync::Mutex};
+
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use windows::{
+    core::implement,
+    Foundation::Numerics::Vector2,
+    Win32::{
+        Foundation::{HWND, POINT, POINTL},
+        Graphics::Gdi::ScreenToClient,
+        System::{
+            Com::{IDataObject, DVASPECT_CONTENT, FORMATETC, TYMED_HGLOBAL},
+            Memory::{GlobalLock, GlobalSize, GlobalUnlock},
+            Ole::{
+                IDropTarget, IDropTarget_Impl, ReleaseStgMedium, DROPEFFECT, DROPEFFECT_COPY,
+                DROPEFFECT_NONE,
+            },
+            SystemServices::{CF_HDROP, CF_UNICODETEXT, CLIPBOARD_FORMATS, MODIFIERKEYS_FLAGS},
+        },
+        UI::Shell::{DragFinish, DragQueryFileW, DragQueryPoint, HDROP},
+    },
+    UI::Composition::ContainerVisual,
+};
+
+static DROP_LISTENERS: Mutex<Vec<UnboundedSender<WindowDrop>>> = Mutex::new(Vec::new());
+
+///
+/// Content dropped onto the window from the other application
+///
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum DropData {
+    Files(Vec<PathBuf>),
+    Text(String),
+}
+
+impl DropData {
+    pub fn files(&self) -> Option<&[PathBuf]> {
+        match self {
+            DropData::Files(files) => Some(files),
+            DropData::Text(_) => None,
+        }
+    }
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            DropData::Files(_) => None,
+            DropData::Text(text) => Some(text),
+        }
+    }
+}
+
+///
+/// Drop onto the window, delivered to the event receiver hosting the window's root visual
+///
+pub(crate) struct WindowDrop {
+    pub root_visual: ContainerVisual,
+    /// Position in the client area, i.e. in the root visual coordinates
+    pub position: Vector2,
+    pub data: DropData,
+}
+
+pub(crate) fn drop_events() -> UnboundedReceiver<WindowDrop> {
+    let (tx, rx) = unbounded();
+    DROP_LISTENERS.lock().unwrap().push(tx);
+    rx
+}
+
+fn send_drop(root_visual: &ContainerVisual, position: Vector2, data: DropData) {
+    DROP_LISTENERS.lock().unwrap().retain(|tx| {
+        tx.unbounded_send(WindowDrop {
+            root_visual: root_visual.clone(),
+            position,
+            data: data.clone(),
+        })
+        .is_ok()
+    });
+}
+
+///
+/// Handle WM_DROPFILES of the window accepting files with `DragAcceptFiles`,
+/// used when OLE drag and drop is not available on the window thread
+///
+pub(crate) fn send_dropped_files(root_visual: &ContainerVisual, hdrop: HDROP) {
+    let mut point = POINT::default();
+    let files = unsafe {
+        DragQueryPoint(hdrop, &mut point);
+        let files = query_files(hdrop);
+        DragFinish(hdrop);
+        files
+    };
+    if !files.is_empty() {
+        let position = Vector2::new(point.x as f32, point.y as f32);
+        send_drop(root_visual, position, DropData::Files(files));
+    }
+}
+
+pub(crate) unsafe fn query_files(hdrop: HDROP) -> Vec<PathBuf> {
+    let count = DragQueryFileW(hdrop, u32::MAX, None);
+    (0..count)
+        .filter_map(|index| {
+            let len = DragQueryFileW(hdrop, index, None) as usize;
+            let mut buffer = vec![0u16; len + 1];
+            let copied = DragQueryFileW(hdrop, index, Some(&mut buffer)) as usize;
+            (copied > 0).then(|| String::from_utf16_lossy(&buffer[..copied]).into())
+        })
+        .collect()
+}
+
+fn format(format: CLIPBOARD_FORMATS) -> FORMATETC {
+    FORMATETC {
+        cfFormat: format.0 as u16,
+        ptd: std::ptr::null_mut(),
+        dwAspect: DVASPECT_CONTENT.0,
+        lindex: -1,
+        tymed: TYMED_HGLOBAL.0 as u32,
+    }
+}
+
+fn has_format(data: &IDataObject, clipboard_format: CLIPBOARD_FORMATS) -> bool {
+    unsafe { data.QueryGetData(&format(clipboard_format)) }.is_ok()
+}
+
+// Files take precedence, e.g. Explorer provides both the files and their names as text
+fn read_data(data: &IDataObject) -> Option<DropData> {
+    if let Ok(mut medium) = unsafe { data.GetData(&format(CF_HDROP)) } {
+        let files = unsafe { query_files(HDROP(medium.Anonymous.hGlobal)) };
+        unsafe { ReleaseStgMedium(&mut medium) };
+        return Some(DropData::Files(files));
+    }
+    if let Ok(mut medium) = unsafe { data.GetData(&format(CF_UNICODETEXT)) } {
+        let text = unsafe {
+            let memory = medium.Anonymous.hGlobal;
+            // The text is not guaranteed to be nul terminated within the block
+            let capacity = GlobalSize(memory) / std::mem::size_of::<u16>();
+            let text = GlobalLock(memory) as *const u16;
+            let result = (!text.is_null()).then(|| {
+                let len = (0..capacity).take_while(|&i| *text.add(i) != 0).count();
+                String::from_utf16_lossy(std::slice::from_raw_parts(text, len))
+            });
+            GlobalUnlock(memory);
+            ReleaseStgMedium(&mut medium);
+            result
+        };
+        return text.map(DropData::Text);
+    }
+    None
+}
+
+///
+/// OLE drop target registered for the window with `RegisterDragDrop`. The drag source
+/// captures the mouse, so the window receives no mouse messages until the drop.
+///
+#[implement(IDropTarget)]
+pub(crate) struct WindowDropTarget {
+    hwnd: HWND,
+    root_visual: ContainerVisual,
+    // Whether the data being dragged over the window can be dropped
+    accepted: Mutex<bool>,
+}
+
+impl WindowDropTarget {
+    pub(crate) fn new(hwnd: HWND, root_visual: ContainerVisual) -> Self {
+        Self {
+            hwnd,
+            root_visual,
+            accepted: Mutex::new(false),
+        }
+    }
+
+    fn client_position(&self, pt: &POINTL) -> Vector2 {
+        let mut point = POINT { x: pt.x, y: pt.y };
+        unsafe { ScreenToClient(self.hwnd, &mut point) };
+        Vector2::new(point.x as f32, point.y as f32)
+    }
+
+    fn effect(&self) -> DROPEFFECT {
+        if *self.accepted.lock().unwrap() {
+            DROPEFFECT_COPY
+        } else {
+            DROPEFFECT_NONE
+        }
+    }
+}
+
+impl IDropTarget_Impl for WindowDropTarget {
+    fn DragEnter(
+        &self,
+        pdataobj: &Option<IDataObject>,
+        _: MODIFIERKEYS_FLAGS,
+        _: &POINTL,
+        pdweffect: *mut DROPEFFECT,
+    ) -> windows::core::Result<()> {
+        *self.accepted.lock().unwrap() = pdataobj.as_ref().map_or(false, |data| {
+            has_format(data, CF_HDROP) || has_format(data, CF_UNICODETEXT)
+        });
+        unsafe { *pdweffect = self.effect() };
+        Ok(())
+    }
+
+    fn DragOver(
+        &self,
+        _: MODIFIERKEYS_FLAGS,
+        _: &POINTL,
+        pdweffect: *mut DROPEFFECT,
+    ) -> windows::core::Result<()> {
+        unsafe { *pdweffect = self.effect() };
+        Ok(())
+    }
+
+    fn DragLeave(&self) -> windows::core::Result<()> {
+        *self.accepted.lock().unwrap() = false;
+        Ok(())
+    }
+
+    fn Drop(
+        &self,
+        pdataobj: &Option<IDataObject>,
+        _: MODIFIERKEYS_FLAGS,
+        pt: &POINTL,
+        pdweffect: *mut DROPEFFECT,
+    ) -> windows::core::Result<()> {
+        let data = pdataobj.as_ref().and_then(read_data);
+        *self.accepted.lock().unwrap() = false;
+        unsafe {
+            *pdweffect = if data.is_some() {
+                DROPEFFECT_COPY
+            } else {
+                DROPEFFECT_NONE
+            }
+        };
+        if let Some(data) = data {
+            send_drop(&self.root_visual, self.client_position(pt), data);
+        }
+        Ok(())
+    }
+}
@@ -1,3 +1,5 @@
+use std::cell::RefCell;
+
 use windows::{
     core::{InParam, Interface},
     Win32::Graphics::Dxgi::{DXGI_ERROR_DEVICE_REMOVED, DXGI_ERROR_DEVICE_RESET},
@@ -25,6 +27,8 @@ thread_local! {
     static DWRITE_FACTORY: windows::core::Result<IDWriteFactory> = create_dwrite_factory();
     static D3D11_DEVICE: windows::core::Result<ID3D11Device> = create_d3d11_device();
     static D2D1_DEVICE: windows::core::Result<ID2D1Device> = create_d2d1_device();
+    static COMPOSITION_GRAPHICS_DEVICES: RefCell<Vec<(Compositor, CompositionGraphicsDevice)>> =
+        RefCell::new(Vec::new());
 }
 
 fn create_dwrite_factory() -> windows::core::Result<IDWriteFactory> {
@@ -91,6 +95,23 @@ pub fn create_composition_graphics_device(
     Ok(graphic_device)
 }
 
+///
+/// Graphics device shared by the drawing surfaces of the compositor, created on first use
+///
+pub fn composition_graphics_device(
+    compositor: &Compositor,
+) -> crate::Result<CompositionGraphicsDevice> {
+    COMPOSITION_GRAPHICS_DEVICES.with(|devices| {
+        let mut devices = devices.borrow_mut();
+        if let Some((_, device)) = devices.iter().find(|(c, _)| c == compositor) {
+            return Ok(device.clone());
+        }
+        let device = create_composition_graphics_device(compositor)?;
+        devices.push((compositor.clone(), device.clone()));
+        Ok(device)
+    })
+}
+
 //
 // TODO: Actually handle the device reset situation
 //
@@ -0,0 +1,85 @@
+use std::path::Path;
+
+use windows::Win32::{
+    Foundation::{HINSTANCE, HWND, LPARAM, WPARAM},
+    Graphics::Gdi::{CreateBitmap, DeleteObject},
+    UI::WindowsAndMessaging::{
+        CreateIconIndirect, DestroyIcon, LoadImageW, SendMessageW, HICON, ICONINFO, ICON_BIG,
+        ICON_SMALL, IMAGE_ICON, LR_DEFAULTSIZE, LR_LOADFROMFILE, WM_SETICON,
+    },
+};
+
+use crate::window::wide_string::ToWide;
+
+///
+/// Icon of the window shown in its title bar, in the taskbar and in the Alt+Tab list.
+/// The icon is destroyed when dropped, so the window keeps it while it's shown.
+///
+#[derive(Debug)]
+pub struct WindowIcon(HICON);
+
+impl WindowIcon {
+    ///
+    /// Create the icon from the RGBA pixels, rows from top to bottom, alpha not premultiplied
+    ///
+    pub fn from_rgba(rgba: &[u8], width: u32, height: u32) -> crate::Result<Self> {
+        if rgba.len() != width as usize * height as usize * 4 {
+            return Err(crate::Error::BadBitmapSize);
+        }
+        // The color bitmap is BGRA, the mask is ignored where the alpha channel is present
+        let bgra = rgba
+            .chunks_exact(4)
+            .flat_map(|p| [p[2], p[1], p[0], p[3]])
+            .collect::<Vec<_>>();
+        // Rows of the monochrome bitmap are aligned to 16 bits
+        let mask = vec![0u8; ((width as usize + 15) / 16) * 2 * height as usize];
+        unsafe {
+            let color = CreateBitmap(width as i32, height as i32, 1, 32, Some(bgra.as_ptr() as _));
+            let mask = CreateBitmap(width as i32, height as i32, 1, 1, Some(mask.as_ptr() as _));
+            let icon = CreateIconIndirect(&ICONINFO {
+                fIcon: true.into(),
+                xHotspot: 0,
+                yHotspot: 0,
+                hbmMask: mask,
+                hbmColor: color,
+            });
+            // The icon keeps its own copies of the bitmaps
+            DeleteObject(color);
+            DeleteObject(mask);
+            Ok(Self(icon?))
+        }
+    }
+
+    ///
+    /// Load the icon from the .ico file
+    ///
+    pub fn from_path(path: impl AsRef<Path>) -> crate::Result<Self> {
+        let path = path.as_ref().to_wide();
+        let handle = unsafe {
+            LoadImageW(
+                HINSTANCE::default(),
+                path.as_pcwstr(),
+                IMAGE_ICON,
+                0,
+                0,
+                LR_LOADFROMFILE | LR_DEFAULTSIZE,
+            )?
+        };
+        Ok(Self(HICON(handle.0)))
+    }
+
+    // Use the icon for both the title bar and the taskbar of the window
+    pub(crate) fn apply(&self, window: HWND) {
+        for size in [ICON_SMALL, ICON_BIG] {
+            unsafe {
+                SendMessageW(window, WM_SETICON, WPARAM(size as usize), LPARAM(self.0 .0));
+            }
+        }
+    }
+}
+
+impl Drop for WindowIcon {
+    fn drop(&mut self) {
+        unsafe { DestroyIcon(self.0) };
+    }
+}
@@ -0,0 +1,143 @@
+use windows::Win32::UI::Input::KeyboardAndMouse::*;
+use winit::event::VirtualKeyCode;
+
+const LETTERS: [VirtualKeyCode; 26] = [
+    VirtualKeyCode::A,
+    VirtualKeyCode::B,
+    VirtualKeyCode::C,
+    VirtualKeyCode::D,
+    VirtualKeyCode::E,
+    VirtualKeyCode::F,
+    VirtualKeyCode::G,
+    VirtualKeyCode::H,
+    VirtualKeyCode::I,
+    VirtualKeyCode::J,
+    VirtualKeyCode::K,
+    VirtualKeyCode::L,
+    VirtualKeyCode::M,
+    VirtualKeyCode::N,
+    VirtualKeyCode::O,
+    VirtualKeyCode::P,
+    VirtualKeyCode::Q,
+    VirtualKeyCode::R,
+    VirtualKeyCode::S,
+    VirtualKeyCode::T,
+    VirtualKeyCode::U,
+    VirtualKeyCode::V,
+    VirtualKeyCode::W,
+    VirtualKeyCode::X,
+    VirtualKeyCode::Y,
+    VirtualKeyCode::Z,
+];
+
+const DIGITS: [VirtualKeyCode; 10] = [
+    VirtualKeyCode::Key0,
+    VirtualKeyCode::Key1,
+    VirtualKeyCode::Key2,
+    VirtualKeyCode::Key3,
+    VirtualKeyCode::Key4,
+    VirtualKeyCode::Key5,
+    VirtualKeyCode::Key6,
+    VirtualKeyCode::Key7,
+    VirtualKeyCode::Key8,
+    VirtualKeyCode::Key9,
+];
+
+const FUNCTION_KEYS: [VirtualKeyCode; 12] = [
+    VirtualKeyCode::F1,
+    VirtualKeyCode::F2,
+    VirtualKeyCode::F3,
+    VirtualKeyCode::F4,
+    VirtualKeyCode::F5,
+    VirtualKeyCode::F6,
+    VirtualKeyCode::F7,
+    VirtualKeyCode::F8,
+    VirtualKeyCode::F9,
+    VirtualKeyCode::F10,
+    VirtualKeyCode::F11,
+    VirtualKeyCode::F12,
+];
+
+// Scan code of the right shift key, the left one is 0x2A
+const RSHIFT_SCANCODE: u32 = 0x36;
+
+///
+/// Map the Win32 virtual key code from WM_KEYDOWN/WM_KEYUP to winit key code.
+/// The messages report the generic modifier keys, the side is taken from the scan code
+/// for shift and from the extended key flag for control and alt.
+///
+pub(crate) fn virtual_keycode(
+    vkey: VIRTUAL_KEY,
+    scancode: u32,
+    extended: bool,
+) -> Option<VirtualKeyCode> {
+    let code = vkey.0;
+    if (VK_A.0..=VK_Z.0).contains(&code) {
+        return Some(LETTERS[(code - VK_A.0) as usize]);
+    }
+    if (VK_0.0..=VK_9.0).contains(&code) {
+        return Some(DIGITS[(code - VK_0.0) as usize]);
+    }
+    if (VK_F1.0..=VK_F12.0).contains(&code) {
+        return Some(FUNCTION_KEYS[(code - VK_F1.0) as usize]);
+    }
+    match vkey {
+        VK_BACK => Some(VirtualKeyCode::Back),
+        VK_TAB => Some(VirtualKeyCode::Tab),
+        VK_RETURN => Some(VirtualKeyCode::Return),
+        VK_ESCAPE => Some(VirtualKeyCode::Escape),
+        VK_SPACE => Some(VirtualKeyCode::Space),
+        VK_PRIOR => Some(VirtualKeyCode::PageUp),
+        VK_NEXT => Some(VirtualKeyCode::PageDown),
+        VK_END => Some(VirtualKeyCode::End),
+        VK_HOME => Some(VirtualKeyCode::Home),
+        VK_LEFT => Some(VirtualKeyCode::Left),
+        VK_UP => Some(VirtualKeyCode::Up),
+        VK_RIGHT => Some(VirtualKeyCode::Right),
+        VK_DOWN => Some(VirtualKeyCode::Down),
+        VK_INSERT => Some(VirtualKeyCode::Insert),
+        VK_DELETE => Some(VirtualKeyCode::Delete),
+        VK_PAUSE => Some(VirtualKeyCode::Pause),
+        VK_SNAPSHOT => Some(VirtualKeyCode::Snapshot),
+        VK_CAPITAL => Some(VirtualKeyCode::Capital),
+        VK_SHIFT if scancode == RSHIFT_SCANCODE => Some(VirtualKeyCode::RShift),
+        VK_SHIFT | VK_LSHIFT => Some(VirtualKeyCode::LShift),
+        VK_RSHIFT => Some(VirtualKeyCode::RShift),
+        VK_CONTROL if extended => Some(VirtualKeyCode::RControl),
+        VK_CONTROL | VK_LCONTROL => Some(VirtualKeyCode::LControl),
+        VK_RCONTROL => Some(VirtualKeyCode::RControl),
+        VK_MENU if extended => Some(VirtualKeyCode::RAlt),
+        VK_MENU | VK_LMENU => Some(VirtualKeyCode::LAlt),
+        VK_RMENU => Some(VirtualKeyCode::RAlt),
+        VK_LWIN => Some(VirtualKeyCode::LWin),
+        VK_RWIN => Some(VirtualKeyCode::RWin),
+        VK_APPS => Some(VirtualKeyCode::Apps),
+        VK_NUMPAD0 => Some(VirtualKeyCode::Numpad0),
+        VK_NUMPAD1 => Some(VirtualKeyCode::Numpad1),
+        VK_NUMPAD2 => Some(VirtualKeyCode::Numpad2),
+        VK_NUMPAD3 => Some(VirtualKeyCode::Numpad3),
+        VK_NUMPAD4 => Some(VirtualKeyCode::Numpad4),
+        VK_NUMPAD5 => Some(VirtualKeyCode::Numpad5),
+        VK_NUMPAD6 => Some(VirtualKeyCode::Numpad6),
+        VK_NUMPAD7 => Some(VirtualKeyCode::Numpad7),
+        VK_NUMPAD8 => Some(VirtualKeyCode::Numpad8),
+        VK_NUMPAD9 => Some(VirtualKeyCode::Numpad9),
+        VK_MULTIPLY => Some(VirtualKeyCode::NumpadMultiply),
+        VK_ADD => Some(VirtualKeyCode::NumpadAdd),
+        VK_SUBTRACT => Some(VirtualKeyCode::NumpadSubtract),
+        VK_DECIMAL => Some(VirtualKeyCode::NumpadDecimal),
+        VK_DIVIDE => Some(VirtualKeyCode::NumpadDivide),
+        VK_OEM_PLUS => Some(VirtualKeyCode::Equals),
+        VK_OEM_COMMA => Some(VirtualKeyCode::Comma),
+        VK_OEM_MINUS => Some(VirtualKeyCode::Minus),
+        VK_OEM_PERIOD => Some(VirtualKeyCode::Period),
+        VK_OEM_1 => Some(VirtualKeyCode::Semicolon),
+        VK_OEM_2 => Some(VirtualKeyCode::Slash),
+        VK_OEM_3 => Some(VirtualKeyCode::Grave),
+        VK_OEM_4 => Some(VirtualKeyCode::LBracket),
+        VK_OEM_5 => Some(VirtualKeyCode::Backslash),
+        VK_OEM_6 => Some(VirtualKeyCode::RBracket),
+        VK_OEM_7 => Some(VirtualKeyCode::Apostrophe),
+        _ => None,
+    }
+}
@@ -1,24 +1,51 @@
+mod chrome;
+mod clipboard;
+mod color_mode;
+mod cursor;
+mod drop_target;
 mod graphics;
+mod icon;
 mod interop;
+mod keyboard;
 mod native_window;
 mod wide_string;
 
 pub mod native {
+    pub use super::chrome::set_window_region;
+    pub use super::chrome::WindowRegion;
+    pub use super::icon::WindowIcon;
     pub use super::native_window::run_message_loop;
+    pub use super::native_window::run_message_loop_until;
+    pub use super::native_window::SystemBackdrop;
     pub use super::native_window::Window;
+    pub use super::native_window::WindowClass;
+    pub use super::native_window::WindowCloser;
+    pub use super::native_window::WindowState;
+    pub use super::native_window::WindowStateControl;
+    pub use super::native_window::WindowTitle;
 }
 
 pub use graphics::{
-    check_for_device_removed, create_composition_graphics_device, d2d1_device, d3d11_device,
+    check_for_device_removed, composition_graphics_device, create_composition_graphics_device,
+    d2d1_device, d3d11_device,
     dwrite_factory, draw
 };
+pub use clipboard::{
+    clipboard_data, clipboard_files, clipboard_formats, clipboard_text,
+    is_clipboard_format_available, set_clipboard_data, set_clipboard_files, set_clipboard_text,
+    ClipboardFormat,
+};
+pub use color_mode::is_system_dark_mode;
+pub use cursor::set_cursor;
+pub(crate) use drop_target::drop_events;
+pub use drop_target::DropData;
+pub(crate) use native_window::window_state_events;
 pub use interop::create_dispatcher_queue_controller;
 pub use interop::create_dispatcher_queue_controller_for_current_thread;
-pub use wide_string::{ToWide, WideString};
+pub use wide_string::{intern_wide, ToWide, WideStr, WideString};
 use windows::System::DispatcherQueueController;
-use windows::Win32::System::WinRT::RoInitialize;
-use windows::Win32::System::WinRT::RoUninitialize;
-use windows::Win32::System::WinRT::RO_INIT_MULTITHREADED;
+use windows::Win32::System::Ole::OleInitialize;
+use windows::Win32::System::Ole::OleUninitialize;
 
 pub struct WindowThread {
     pub controller: DispatcherQueueController,
@@ -26,12 +53,16 @@ pub struct WindowThread {
 
 impl Drop for WindowThread {
     fn drop(&mut self) {
-        unsafe { RoUninitialize() }
+        unsafe { OleUninitialize() }
     }
 }
 
+///
+/// Initialize the single-threaded apartment required by OLE drag and drop
+/// and the dispatcher queue of the thread running the windows
+///
 pub fn initialize_window_thread() -> crate::Result<WindowThread> {
-    unsafe { RoInitialize(RO_INIT_MULTITHREADED)? }
+    unsafe { OleInitialize(std::ptr::null())? }
     Ok(WindowThread {
         controller: create_dispatcher_queue_controller_for_current_thread()?,
     })
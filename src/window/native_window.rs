@@ -1,73 +1,450 @@
-use std::sync::Once;
+use std::{
+    cell::{Cell, RefCell},
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
 
-use futures::channel::mpsc::Sender;
+use futures::{
+    channel::mpsc::{unbounded, Sender, UnboundedReceiver, UnboundedSender},
+    future::BoxFuture,
+    task::{Spawn, SpawnExt},
+    FutureExt,
+};
 use windows::{
     core::{self, Interface, PCWSTR},
+    Foundation::Numerics::Vector2,
     Graphics::SizeInt32,
     Win32::{
-        Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, RECT, WPARAM},
-        System::{LibraryLoader::GetModuleHandleW, WinRT::Composition::ICompositorDesktopInterop},
-        UI::WindowsAndMessaging::{
-            AdjustWindowRectEx, CreateWindowExW, DefWindowProcW, DispatchMessageW, GetClientRect,
-            GetMessageW, LoadCursorW, PostQuitMessage, RegisterClassW, ShowWindow,
-            TranslateMessage, CREATESTRUCTW, CW_USEDEFAULT, GWLP_USERDATA, HMENU, IDC_ARROW, MSG,
-            SW_SHOW, WINDOW_LONG_PTR_INDEX, WM_DESTROY, WM_LBUTTONDOWN, WM_LBUTTONUP, WM_MOUSEMOVE,
-            WM_NCCREATE, WM_RBUTTONDOWN, WM_SIZE, WM_SIZING, WM_TIMER, WNDCLASSW,
-            WS_EX_NOREDIRECTIONBITMAP, WS_OVERLAPPEDWINDOW,
+        Foundation::{BOOL, HINSTANCE, HWND, LPARAM, LRESULT, POINT, RECT, WPARAM},
+        Graphics::{
+            Dwm::{
+                DwmExtendFrameIntoClientArea, DwmSetWindowAttribute, DWMWA_USE_HOSTBACKDROPBRUSH,
+                DWMWINDOWATTRIBUTE,
+            },
+            Gdi::{
+                ClientToScreen, GetMonitorInfoW, MonitorFromWindow, ScreenToClient, MONITORINFO,
+                MONITOR_DEFAULTTONEAREST,
+            },
+        },
+        System::{
+            LibraryLoader::GetModuleHandleW,
+            Ole::{IDropTarget, RegisterDragDrop, RevokeDragDrop},
+            WinRT::Composition::ICompositorDesktopInterop,
+        },
+        UI::{
+            Controls::MARGINS,
+            Input::KeyboardAndMouse::{
+                GetKeyState, ReleaseCapture, SetCapture, VIRTUAL_KEY, VK_CONTROL, VK_F10, VK_LWIN,
+                VK_MENU, VK_RWIN, VK_SHIFT,
+            },
+            Shell::{DragAcceptFiles, HDROP},
+            WindowsAndMessaging::{
+                AdjustWindowRectEx, CreateWindowExW, DefWindowProcW, DestroyWindow,
+                DispatchMessageW, GetClientRect, GetCursorPos, GetMessageW, GetWindowPlacement,
+                GetWindowRect, IsIconic, IsZoomed, LoadCursorW, LoadIconW, PostMessageW,
+                PostQuitMessage, RegisterClassW, SetWindowPlacement, SetWindowPos, SetWindowTextW,
+                ShowWindow, ShowWindowAsync, TranslateMessage, CREATESTRUCTW, CS_DBLCLKS,
+                CS_DROPSHADOW, CW_USEDEFAULT, GWLP_USERDATA, GWL_EXSTYLE, GWL_STYLE, HICON, HMENU,
+                HTCLIENT, HWND_TOP, IDC_ARROW, MSG, NCCALCSIZE_PARAMS, SIZE_MAXIMIZED,
+                SIZE_MINIMIZED, SWP_ASYNCWINDOWPOS, SWP_FRAMECHANGED, SWP_NOACTIVATE, SWP_NOMOVE,
+                SWP_NOOWNERZORDER, SWP_NOSIZE, SWP_NOZORDER, SW_MAXIMIZE, SW_MINIMIZE, SW_RESTORE,
+                SW_SHOW, UNICODE_NOCHAR, WHEEL_DELTA, WINDOWPLACEMENT, WINDOW_EX_STYLE,
+                WINDOW_LONG_PTR_INDEX, WINDOW_STYLE, WM_APP, WM_CHAR, WM_CLOSE, WM_DESTROY,
+                WM_DROPFILES, WM_KEYDOWN, WM_KEYUP, WM_KILLFOCUS, WM_LBUTTONDBLCLK, WM_LBUTTONDOWN,
+                WM_LBUTTONUP, WM_MOUSEHWHEEL, WM_MOUSEMOVE, WM_MOUSEWHEEL, WM_NCCALCSIZE,
+                WM_NCCREATE, WM_NCDESTROY, WM_NCHITTEST, WM_RBUTTONDBLCLK, WM_RBUTTONDOWN,
+                WM_RBUTTONUP, WM_SETCURSOR, WM_SETFOCUS, WM_SETTINGCHANGE, WM_SIZE, WM_SIZING,
+                WM_SYSCHAR, WM_SYSKEYDOWN, WM_SYSKEYUP, WM_TIMER, WM_UNICHAR, WNDCLASSW,
+                WNDCLASS_STYLES, WS_EX_NOREDIRECTIONBITMAP, WS_OVERLAPPEDWINDOW,
+            },
         },
     },
     UI::Composition::{Compositor, ContainerVisual, Desktop::DesktopWindowTarget},
 };
 use winit::{
     dpi::PhysicalPosition,
-    event::{DeviceId, ElementState, ModifiersState, MouseButton, WindowEvent},
+    event::{
+        DeviceId, ElementState, KeyboardInput, ModifiersState, MouseButton, MouseScrollDelta,
+        TouchPhase, WindowEvent,
+    },
+    window::Theme,
 };
 
-use crate::window::wide_string::ToWide;
+use crate::debug::{report, StreamDiagnostic};
+use crate::on_err;
+use crate::window::{
+    chrome::{hit_test, resize_border},
+    color_mode::is_system_dark_mode,
+    cursor::apply_cursor,
+    drop_target::{send_dropped_files, WindowDropTarget},
+    icon::WindowIcon,
+    keyboard::virtual_keycode,
+    wide_string::{intern_wide, ToWide, WideString},
+};
 
-static REGISTER_WINDOW_CLASS: Once = Once::new();
-static WINDOW_CLASS_NAME: &str = "wag.Window";
+// Names of the window classes registered by the process
+static REGISTERED_CLASSES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static STATE_LISTENERS: Mutex<Vec<UnboundedSender<WindowStateChange>>> = Mutex::new(Vec::new());
+// Available since Windows 11 build 22621, not yet in the windows crate metadata
+const DWMWA_SYSTEMBACKDROP_TYPE: DWMWINDOWATTRIBUTE = DWMWINDOWATTRIBUTE(38);
+const DEFAULT_WINDOW_CLASS_NAME: &str = "wag.Window";
+// Area of WM_SETTINGCHANGE sent when the system light or dark mode is switched
+const COLOR_SET_AREA: &str = "ImmersiveColorSet";
+// Posted by `Window::request_redraw`
+const WM_REQUEST_REDRAW: u32 = WM_APP + 1;
+// Posted by `WindowCloser::close`
+const WM_CLOSE_CONFIRMED: u32 = WM_APP + 2;
+// Posted by `WindowTitle::set`
+const WM_SET_TITLE: u32 = WM_APP + 3;
 
-pub struct Window {
+///
+/// Material drawn by the system behind the transparent parts of the window
+///
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum SystemBackdrop {
+    #[default]
+    Auto,
+    None,
+    Mica,
+    Acrylic,
+    Tabbed,
+}
+
+///
+/// Win32 window class. The class is registered when the first window of it is opened;
+/// the name must be unique in the process, e.g. when several libraries embed wag.
+///
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct WindowClass {
+    pub name: String,
+    /// Receive the double clicks as separate messages (`CS_DBLCLKS`), they are
+    /// reported as the usual presses
+    pub double_clicks: bool,
+    /// Shadow under the window (`CS_DROPSHADOW`)
+    pub drop_shadow: bool,
+    /// Id of the icon resource in the executable
+    pub icon: Option<u16>,
+}
+
+impl Default for WindowClass {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW_CLASS_NAME)
+    }
+}
+
+impl WindowClass {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            double_clicks: false,
+            drop_shadow: false,
+            icon: None,
+        }
+    }
+
+    ///
+    /// Register the class unless it's already registered by the process
+    /// with the same name. The options of the later registrations are ignored.
+    ///
+    pub fn register(&self) -> crate::Result<()> {
+        let mut registered = REGISTERED_CLASSES.lock().unwrap();
+        if registered.contains(&self.name) {
+            return Ok(());
+        }
+        let h_instance = unsafe { GetModuleHandleW(PCWSTR::null())? };
+        let h_cursor = unsafe { LoadCursorW(HINSTANCE::default(), IDC_ARROW)? };
+        let h_icon = match self.icon {
+            // MAKEINTRESOURCE: the resource id is passed in place of the name
+            Some(id) => unsafe { LoadIconW(h_instance, PCWSTR(id as usize as *const u16))? },
+            None => HICON::default(),
+        };
+        let mut style = WNDCLASS_STYLES::default();
+        if self.double_clicks {
+            style |= CS_DBLCLKS;
+        }
+        if self.drop_shadow {
+            style |= CS_DROPSHADOW;
+        }
+        let class = WNDCLASSW {
+            style,
+            hCursor: h_cursor,
+            hIcon: h_icon,
+            hInstance: h_instance,
+            lpszClassName: intern_wide(&self.name).as_pcwstr().into(),
+            lpfnWndProc: Some(WindowProc::wnd_proc),
+            ..Default::default()
+        };
+        if unsafe { RegisterClassW(&class) } == 0 {
+            return Err(core::Error::from_win32().into());
+        }
+        registered.push(self.name.clone());
+        Ok(())
+    }
+}
+
+///
+/// Handle closing the window from any thread, e.g. by the "Exit" menu item
+/// or by the close handler after the confirmation
+///
+#[derive(Clone, Copy, Debug)]
+pub struct WindowCloser {
+    handle: HWND,
+}
+
+impl WindowCloser {
+    pub fn close(&self) -> crate::Result<()> {
+        unsafe {
+            PostMessageW(
+                self.handle,
+                WM_CLOSE_CONFIRMED,
+                WPARAM::default(),
+                LPARAM::default(),
+            )
+        }
+        .ok()?;
+        Ok(())
+    }
+}
+
+///
+/// Change the window title from any thread, e.g. to show the document name
+/// from the async code. The last title set before the window thread handles
+/// the request wins.
+///
+#[derive(Clone, Debug)]
+pub struct WindowTitle {
+    handle: HWND,
+    pending: Arc<Mutex<Option<WideString>>>,
+}
+
+impl WindowTitle {
+    pub fn set(&self, title: &str) -> crate::Result<()> {
+        *self.pending.lock().unwrap() = Some(title.to_wide());
+        unsafe {
+            PostMessageW(
+                self.handle,
+                WM_SET_TITLE,
+                WPARAM::default(),
+                LPARAM::default(),
+            )
+        }
+        .ok()?;
+        Ok(())
+    }
+}
+
+struct CloseHandler {
+    spawner: Box<dyn Spawn>,
+    handler: Arc<dyn Fn() -> BoxFuture<'static, bool> + Send + Sync>,
+    // The handler is running, repeated close requests are ignored
+    pending: Arc<AtomicBool>,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum WindowState {
+    #[default]
+    Normal,
+    Minimized,
+    Maximized,
+}
+
+///
+/// Minimize, maximize or restore the window from any thread, e.g. by the buttons
+/// of the custom title bar
+///
+#[derive(Clone, Copy, Debug)]
+pub struct WindowStateControl {
     handle: HWND,
-    title: &'static str,
+}
+
+impl WindowStateControl {
+    pub fn state(&self) -> WindowState {
+        unsafe {
+            if IsIconic(self.handle).as_bool() {
+                WindowState::Minimized
+            } else if IsZoomed(self.handle).as_bool() {
+                WindowState::Maximized
+            } else {
+                WindowState::Normal
+            }
+        }
+    }
+
+    pub fn minimize(&self) {
+        unsafe { ShowWindowAsync(self.handle, SW_MINIMIZE) };
+    }
+
+    pub fn maximize(&self) {
+        unsafe { ShowWindowAsync(self.handle, SW_MAXIMIZE) };
+    }
+
+    ///
+    /// Restore the minimized or maximized window to its normal size and position
+    ///
+    pub fn restore(&self) {
+        unsafe { ShowWindowAsync(self.handle, SW_RESTORE) };
+    }
+}
+
+// The window with the root visual is minimized, maximized or restored
+pub(crate) struct WindowStateChange {
+    pub root_visual: ContainerVisual,
+    pub state: WindowState,
+}
+
+pub(crate) fn window_state_events() -> UnboundedReceiver<WindowStateChange> {
+    let (tx, rx) = unbounded();
+    STATE_LISTENERS.lock().unwrap().push(tx);
+    rx
+}
+
+fn send_window_state(root_visual: &ContainerVisual, state: WindowState) {
+    STATE_LISTENERS.lock().unwrap().retain(|tx| {
+        tx.unbounded_send(WindowStateChange {
+            root_visual: root_visual.clone(),
+            state,
+        })
+        .is_ok()
+    });
+}
+
+// Restored when the window leaves the fullscreen mode
+struct Windowed {
+    placement: WINDOWPLACEMENT,
+    style: WINDOW_STYLE,
+}
+
+// State of the window shared with the window procedure. The procedure is called
+// synchronously by the Win32 functions while the `Window` is borrowed, e.g. by
+// `DestroyWindow` in `Drop` or by `SetWindowPos` in `set_fullscreen`, so it holds
+// its own reference to this state, never borrows it mutably and the fields changed
+// by the messages are cells.
+struct WindowProc {
+    handle: Cell<HWND>,
+    // Title passed by `WindowTitle::set`, not yet applied by the window thread
+    pending_title: Arc<Mutex<Option<WideString>>>,
+    root_visual: ContainerVisual,
+    event_channel: RefCell<Sender<WindowEvent<'static>>>,
+    close_handler: RefCell<Option<CloseHandler>>,
+    // No system title bar and borders, the panels draw them
+    custom_chrome: Cell<bool>,
+    // Destroying the window ends the message loop
+    quit_on_destroy: Cell<bool>,
+    // State reported by the last WM_SIZE
+    state: Cell<WindowState>,
+    // Placement and style of the window before entering the fullscreen mode
+    windowed: Mutex<Option<Windowed>>,
+    // First half of the surrogate pair received in WM_CHAR
+    high_surrogate: Cell<Option<u16>>,
+    // Events dropped because the receiver doesn't keep up
+    dropped_events: Cell<u64>,
+}
+
+pub struct Window {
+    class: WindowClass,
+    // Title the window is opened with
+    title: WideString,
     target: Option<DesktopWindowTarget>,
     compositor: Compositor,
     root_visual: ContainerVisual,
-    event_channel: Sender<WindowEvent<'static>>,
+    // Icon set by the application, replaces the window class icon
+    icon: Mutex<Option<WindowIcon>>,
+    proc: Rc<WindowProc>,
 }
 
 impl Window {
     pub fn new(
         compositor: Compositor,
-        title: &'static str,
+        title: impl Into<String>,
         root_visual: ContainerVisual,
         event_channel: Sender<WindowEvent<'static>>,
     ) -> Self {
+        let title: String = title.into();
         Self {
-            handle: HWND::default(),
-            title,
+            class: WindowClass::default(),
+            title: title.to_wide(),
             target: None,
             compositor,
-            root_visual,
-            event_channel,
+            root_visual: root_visual.clone(),
+            icon: Mutex::new(None),
+            proc: Rc::new(WindowProc {
+                handle: Cell::new(HWND::default()),
+                pending_title: Arc::new(Mutex::new(None)),
+                root_visual,
+                event_channel: RefCell::new(event_channel),
+                close_handler: RefCell::new(None),
+                custom_chrome: Cell::new(false),
+                quit_on_destroy: Cell::new(true),
+                state: Cell::new(WindowState::Normal),
+                windowed: Mutex::new(None),
+                high_surrogate: Cell::new(None),
+                dropped_events: Cell::new(0),
+            }),
         }
     }
 
-    pub fn open(self) -> crate::Result<Box<Self>> {
-        let class_name = WINDOW_CLASS_NAME.to_wide();
-        let h_instance = unsafe { GetModuleHandleW(PCWSTR::null())? };
-        let h_cursor = unsafe { LoadCursorW(HINSTANCE::default(), IDC_ARROW)? };
-        REGISTER_WINDOW_CLASS.call_once(|| {
-            let class = WNDCLASSW {
-                hCursor: h_cursor,
-                hInstance: h_instance,
-                lpszClassName: class_name.as_pcwstr(),
-                lpfnWndProc: Some(Self::wnd_proc),
-                ..Default::default()
-            };
-            assert_ne!(unsafe { RegisterClassW(&class) }, 0);
+    ///
+    /// Open the window with the class other than the default "wag.Window" one
+    ///
+    pub fn with_class(mut self, class: WindowClass) -> Self {
+        self.class = class;
+        self
+    }
+
+    ///
+    /// Open the window without the system title bar and borders, the client area takes
+    /// the whole window. The window keeps the shadow and the invisible resize borders;
+    /// the panels drawing the title bar mark it with `set_window_region`.
+    ///
+    pub fn with_custom_chrome(self) -> Self {
+        self.proc.custom_chrome.set(true);
+        self
+    }
+
+    ///
+    /// Whether `run_message_loop` returns when the window is destroyed, `true` by default.
+    /// The applications with several windows usually quit after the last one is closed.
+    ///
+    pub fn with_quit_on_destroy(self, quit: bool) -> Self {
+        self.proc.quit_on_destroy.set(quit);
+        self
+    }
+
+    ///
+    /// Open the window with the icon other than the window class one
+    ///
+    pub fn with_icon(self, icon: WindowIcon) -> Self {
+        *self.icon.lock().unwrap() = Some(icon);
+        self
+    }
+
+    ///
+    /// Ask the handler when the user closes the window, e.g. to confirm discarding
+    /// unsaved changes. The window is closed if the handler's future resolves to `true`.
+    /// `WindowEvent::CloseRequested` is sent to the event channel in any case.
+    ///
+    pub fn with_close_handler<F, R>(self, spawner: impl Spawn + 'static, handler: F) -> Self
+    where
+        F: Fn() -> R + Send + Sync + 'static,
+        R: Future<Output = bool> + Send + 'static,
+    {
+        *self.proc.close_handler.borrow_mut() = Some(CloseHandler {
+            spawner: Box::new(spawner),
+            handler: Arc::new(move || handler().boxed()),
+            pending: Arc::new(AtomicBool::new(false)),
         });
+        self
+    }
+
+    pub fn open(mut self) -> crate::Result<Pin<Box<Self>>> {
+        self.class.register()?;
+        let class_name = intern_wide(&self.class.name);
+        let h_instance = unsafe { GetModuleHandleW(PCWSTR::null())? };
 
         let size = self.root_visual.Size()?;
         let width = size.X as i32;
@@ -88,13 +465,13 @@ impl Window {
             (rect.right - rect.left, rect.bottom - rect.top)
         };
 
-        let title = self.title.to_wide();
-        let mut result = Box::new(self); // TODO: use pin?
+        // The window procedure owns this reference until WM_NCDESTROY
+        let proc = Rc::into_raw(self.proc.clone());
         let window = unsafe {
             CreateWindowExW(
                 window_ex_style,
                 class_name.as_pcwstr(),
-                title.as_pcwstr(),
+                self.title.as_pcwstr(),
                 window_style,
                 CW_USEDEFAULT,
                 CW_USEDEFAULT,
@@ -103,37 +480,486 @@ impl Window {
                 HWND::default(),
                 HMENU::default(),
                 h_instance,
-                Some(result.as_mut() as *mut _ as _),
+                Some(proc as _),
             )
         };
 
-        let compositor_desktop: ICompositorDesktopInterop = result.compositor.cast()?;
-        let target =
-            unsafe { compositor_desktop.CreateDesktopWindowTarget(result.handle(), true)? };
-        target.SetRoot(&result.root_visual)?;
-        result.target = Some(target);
+        let compositor_desktop: ICompositorDesktopInterop = self.compositor.cast()?;
+        let target = unsafe { compositor_desktop.CreateDesktopWindowTarget(self.handle(), true)? };
+        target.SetRoot(&self.root_visual)?;
+        self.target = Some(target);
+        self.register_drop_target();
+        if self.proc.custom_chrome.get() {
+            // The thin frame keeps the system shadow around the borderless window
+            let margins = MARGINS {
+                cyTopHeight: 1,
+                ..Default::default()
+            };
+            unsafe { DwmExtendFrameIntoClientArea(window, &margins) }?;
+        }
+        if let Some(icon) = &*self.icon.lock().unwrap() {
+            icon.apply(window);
+        }
 
         unsafe { ShowWindow(window, SW_SHOW) };
-        Ok(result)
+        Ok(Box::pin(self))
+    }
+
+    // Accept files and text dragged from the other applications. OLE drag and drop
+    // requires the window thread to be initialized as single-threaded apartment,
+    // otherwise only the files are accepted, without the drop effect feedback.
+    fn register_drop_target(&self) {
+        let drop_target: IDropTarget =
+            WindowDropTarget::new(self.handle(), self.root_visual.clone()).into();
+        if let Err(e) = unsafe { RegisterDragDrop(self.handle(), &drop_target) } {
+            tracing::debug!("OLE drop target is not registered: {e}");
+            unsafe { DragAcceptFiles(self.handle(), true) };
+        }
     }
 
     pub fn size(&self) -> crate::Result<SizeInt32> {
-        Ok(get_window_size(self.handle)?)
+        Ok(get_window_size(self.handle())?)
+    }
+
+    ///
+    /// Size of the client area, same as `size`
+    ///
+    pub fn inner_size(&self) -> crate::Result<SizeInt32> {
+        self.size()
+    }
+
+    ///
+    /// Size of the window including the frame and the title bar
+    ///
+    pub fn outer_size(&self) -> crate::Result<SizeInt32> {
+        let mut rect = RECT::default();
+        unsafe { GetWindowRect(self.handle(), &mut rect) }.ok()?;
+        Ok(SizeInt32 {
+            Width: rect.right - rect.left,
+            Height: rect.bottom - rect.top,
+        })
+    }
+
+    ///
+    /// Resize the window to get the client area of the given size. Can be called
+    /// from any thread, the window is resized by its own thread later.
+    ///
+    pub fn set_inner_size(&self, size: SizeInt32) -> crate::Result<()> {
+        let mut rect = RECT {
+            left: 0,
+            top: 0,
+            right: size.Width,
+            bottom: size.Height,
+        };
+        unsafe {
+            let style = WINDOW_STYLE(GetWindowLong(self.handle(), GWL_STYLE) as u32);
+            let ex_style = WINDOW_EX_STYLE(GetWindowLong(self.handle(), GWL_EXSTYLE) as u32);
+            AdjustWindowRectEx(&mut rect, style, false, ex_style).ok()?;
+            SetWindowPos(
+                self.handle(),
+                HWND::default(),
+                0,
+                0,
+                rect.right - rect.left,
+                rect.bottom - rect.top,
+                SWP_ASYNCWINDOWPOS | SWP_NOACTIVATE | SWP_NOMOVE | SWP_NOZORDER,
+            )
+            .ok()?;
+        }
+        Ok(())
+    }
+
+    pub fn state(&self) -> WindowState {
+        self.state_control().state()
+    }
+
+    pub fn minimize(&self) {
+        self.state_control().minimize()
+    }
+
+    pub fn maximize(&self) {
+        self.state_control().maximize()
+    }
+
+    ///
+    /// Restore the minimized or maximized window to its normal size and position
+    ///
+    pub fn restore(&self) {
+        self.state_control().restore()
+    }
+
+    pub fn state_control(&self) -> WindowStateControl {
+        WindowStateControl {
+            handle: self.handle(),
+        }
+    }
+
+    ///
+    /// Change the icon in the title bar and in the taskbar, the previous icon is destroyed
+    ///
+    pub fn set_icon(&self, icon: WindowIcon) {
+        icon.apply(self.handle());
+        *self.icon.lock().unwrap() = Some(icon);
+    }
+
+    pub fn is_fullscreen(&self) -> bool {
+        self.proc.is_fullscreen()
+    }
+
+    ///
+    /// Switch to the borderless window covering the whole monitor and back to the previous
+    /// placement. The panels receive `Resized` with the new client area size.
+    ///
+    pub fn set_fullscreen(&self, fullscreen: bool) -> crate::Result<()> {
+        if fullscreen == self.is_fullscreen() {
+            return Ok(());
+        }
+        if fullscreen {
+            self.enter_fullscreen()?;
+        } else {
+            self.leave_fullscreen()?;
+        }
+        // WM_SIZE is not sent if the client area keeps its size, e.g. when the window
+        // is moved to the other monitor of the same resolution
+        self.request_redraw()
+    }
+
+    // The window procedure is called synchronously by the Win32 calls below with WM_SIZE,
+    // WM_STYLECHANGED, etc. It only borrows the shared `WindowProc` state and checks
+    // `is_fullscreen`, so the lock is never held while calling them
+    fn enter_fullscreen(&self) -> crate::Result<()> {
+        unsafe {
+            let style = WINDOW_STYLE(GetWindowLong(self.handle(), GWL_STYLE) as u32);
+            let mut placement = WINDOWPLACEMENT {
+                length: std::mem::size_of::<WINDOWPLACEMENT>() as u32,
+                ..Default::default()
+            };
+            GetWindowPlacement(self.handle(), &mut placement).ok()?;
+            let mut monitor_info = MONITORINFO {
+                cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+                ..Default::default()
+            };
+            let monitor = MonitorFromWindow(self.handle(), MONITOR_DEFAULTTONEAREST);
+            GetMonitorInfoW(monitor, &mut monitor_info).ok()?;
+            let rect = monitor_info.rcMonitor;
+            *self.proc.windowed.lock().unwrap() = Some(Windowed { placement, style });
+            SetWindowLong(
+                self.handle(),
+                GWL_STYLE,
+                (style & !WS_OVERLAPPEDWINDOW).0 as _,
+            );
+            SetWindowPos(
+                self.handle(),
+                HWND_TOP,
+                rect.left,
+                rect.top,
+                rect.right - rect.left,
+                rect.bottom - rect.top,
+                SWP_NOOWNERZORDER | SWP_FRAMECHANGED,
+            )
+            .ok()?;
+        }
+        Ok(())
+    }
+
+    fn leave_fullscreen(&self) -> crate::Result<()> {
+        let windowed = match self.proc.windowed.lock().unwrap().take() {
+            Some(windowed) => windowed,
+            None => return Ok(()),
+        };
+        unsafe {
+            // The saved style, e.g. the window without the maximize box keeps it off
+            SetWindowLong(self.handle(), GWL_STYLE, windowed.style.0 as _);
+            SetWindowPlacement(self.handle(), &windowed.placement).ok()?;
+            SetWindowPos(
+                self.handle(),
+                HWND::default(),
+                0,
+                0,
+                0,
+                0,
+                SWP_NOMOVE | SWP_NOSIZE | SWP_NOZORDER | SWP_NOOWNERZORDER | SWP_FRAMECHANGED,
+            )
+            .ok()?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Cursor position in the client area coordinates, may be outside of the client area
+    ///
+    pub fn cursor_position(&self) -> crate::Result<Vector2> {
+        let mut point = POINT::default();
+        unsafe {
+            GetCursorPos(&mut point).ok()?;
+            ScreenToClient(self.handle(), &mut point).ok()?;
+        }
+        Ok(Vector2 {
+            X: point.x as f32,
+            Y: point.y as f32,
+        })
+    }
+
+    ///
+    /// Ask the window thread to send the `Resized` event with the current size again,
+    /// e.g. to relayout the panels after changing them outside of the event handling
+    ///
+    pub fn request_redraw(&self) -> crate::Result<()> {
+        unsafe {
+            PostMessageW(
+                self.handle(),
+                WM_REQUEST_REDRAW,
+                WPARAM::default(),
+                LPARAM::default(),
+            )
+        }
+        .ok()?;
+        Ok(())
+    }
+
+    ///
+    /// Change the window title, can be called from any thread
+    ///
+    pub fn set_title(&self, title: &str) -> crate::Result<()> {
+        self.title_setter().set(title)
+    }
+
+    pub fn title_setter(&self) -> WindowTitle {
+        WindowTitle {
+            handle: self.handle(),
+            pending: self.proc.pending_title.clone(),
+        }
+    }
+
+    pub fn closer(&self) -> WindowCloser {
+        WindowCloser {
+            handle: self.handle(),
+        }
     }
 
     pub fn handle(&self) -> HWND {
-        self.handle
+        self.proc.handle.get()
+    }
+
+    ///
+    /// The window is opened and not yet destroyed
+    ///
+    pub fn is_open(&self) -> bool {
+        self.handle() != HWND::default()
+    }
+
+    ///
+    /// Convert the point in the client area (i.e. in the root visual coordinates)
+    /// to the screen coordinates at the current window position
+    ///
+    pub fn client_to_screen(&self, point: Vector2) -> crate::Result<Vector2> {
+        let mut point = POINT {
+            x: point.X.round() as i32,
+            y: point.Y.round() as i32,
+        };
+        unsafe { ClientToScreen(self.handle(), &mut point) }.ok()?;
+        Ok(Vector2 {
+            X: point.x as f32,
+            Y: point.y as f32,
+        })
+    }
+
+    ///
+    /// Allow `Compositor::CreateHostBackdropBrush` brushes to show the content behind the window
+    ///
+    pub fn set_host_backdrop(&self, enabled: bool) -> crate::Result<()> {
+        let value = BOOL::from(enabled);
+        unsafe {
+            DwmSetWindowAttribute(
+                self.handle(),
+                DWMWA_USE_HOSTBACKDROPBRUSH,
+                &value as *const _ as _,
+                std::mem::size_of::<BOOL>() as u32,
+            )
+        }?;
+        Ok(())
+    }
+
+    ///
+    /// Apply the Windows 11 system backdrop to the whole window. Fails on older systems,
+    /// see `capabilities::system_backdrop_or_fallback`.
+    ///
+    pub fn set_system_backdrop(&self, backdrop: SystemBackdrop) -> crate::Result<()> {
+        let value: i32 = match backdrop {
+            SystemBackdrop::Auto => 0,
+            SystemBackdrop::None => 1,
+            SystemBackdrop::Mica => 2,
+            SystemBackdrop::Acrylic => 3,
+            SystemBackdrop::Tabbed => 4,
+        };
+        // The backdrop is visible only under the frame, so extend it to the whole client area
+        let margins = MARGINS {
+            cxLeftWidth: -1,
+            cxRightWidth: -1,
+            cyTopHeight: -1,
+            cyBottomHeight: -1,
+        };
+        unsafe { DwmExtendFrameIntoClientArea(self.handle(), &margins) }?;
+        unsafe {
+            DwmSetWindowAttribute(
+                self.handle(),
+                DWMWA_SYSTEMBACKDROP_TYPE,
+                &value as *const _ as _,
+                std::mem::size_of::<i32>() as u32,
+            )
+        }?;
+        Ok(())
+    }
+
+    /// Get a reference to the window's compositor.
+    pub fn compositor(&self) -> &Compositor {
+        &self.compositor
+    }
+}
+
+impl WindowProc {
+    fn apply_pending_title(&self) {
+        let title = self.pending_title.lock().unwrap().take();
+        if let Some(title) = title {
+            unsafe { SetWindowTextW(self.handle.get(), title.as_pcwstr()) };
+        }
+    }
+
+    // Returns false if the handler decides whether to close the window
+    fn request_close(&self) -> bool {
+        self.send_event(WindowEvent::CloseRequested);
+        let close_handler = self.close_handler.borrow();
+        let close_handler = match &*close_handler {
+            Some(close_handler) => close_handler,
+            None => return true,
+        };
+        if close_handler.pending.swap(true, Ordering::AcqRel) {
+            return false;
+        }
+        let closer = WindowCloser {
+            handle: self.handle.get(),
+        };
+        let handler = close_handler.handler.clone();
+        let pending = close_handler.pending.clone();
+        let spawned = close_handler.spawner.spawn(async move {
+            if handler().await {
+                closer.close().unwrap_or_else(on_err);
+            }
+            pending.store(false, Ordering::Release);
+        });
+        if let Err(e) = spawned {
+            close_handler.pending.store(false, Ordering::Release);
+            on_err(e.into());
+        }
+        false
     }
 
-    fn message_handler(&mut self, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    fn send_event(&self, event: WindowEvent<'static>) {
+        let result = self.event_channel.borrow_mut().try_send(event);
+        if let Err(e) = result {
+            if e.is_full() {
+                self.dropped_events.set(self.dropped_events.get() + 1);
+                report(StreamDiagnostic::Overflow {
+                    stream: "window events".into(),
+                    dropped: self.dropped_events.get(),
+                });
+            }
+        }
+    }
+
+    fn is_fullscreen(&self) -> bool {
+        self.windowed.lock().unwrap().is_some()
+    }
+
+    fn send_resized(&self) {
+        let size = get_window_size(self.handle.get()).unwrap();
+        self.send_event(WindowEvent::Resized((size.Width, size.Height).into()));
+    }
+
+    fn send_mouse_input(&self, state: ElementState, button: MouseButton) {
+        // Keep receiving the mouse messages while the button is held outside of the window,
+        // so the dragging panels don't lose the cursor
+        match state {
+            ElementState::Pressed => unsafe {
+                SetCapture(self.handle.get());
+            },
+            ElementState::Released => unsafe {
+                ReleaseCapture();
+            },
+        }
+        self.send_event(WindowEvent::MouseInput {
+            device_id: unsafe { DeviceId::dummy() },
+            state,
+            button,
+            modifiers: ModifiersState::default(),
+        });
+    }
+
+    // Positive `y` scrolls up, positive `x` scrolls left, as in winit
+    #[allow(deprecated)]
+    fn send_mouse_wheel(&self, x: f32, y: f32) {
+        self.send_event(WindowEvent::MouseWheel {
+            device_id: unsafe { DeviceId::dummy() },
+            delta: MouseScrollDelta::LineDelta(x, y),
+            phase: TouchPhase::Moved,
+            modifiers: ModifiersState::default(),
+        });
+    }
+
+    #[allow(deprecated)]
+    fn send_keyboard_input(&self, state: ElementState, wparam: WPARAM, lparam: LPARAM) {
+        let scancode = ((lparam.0 >> 16) & 0xFF) as u32;
+        let extended = lparam.0 & (1 << 24) != 0;
+        self.send_event(WindowEvent::KeyboardInput {
+            device_id: unsafe { DeviceId::dummy() },
+            input: KeyboardInput {
+                scancode,
+                state,
+                virtual_keycode: virtual_keycode(VIRTUAL_KEY(wparam.0 as u16), scancode, extended),
+                modifiers: keyboard_modifiers(),
+            },
+            is_synthetic: false,
+        });
+    }
+
+    fn send_utf16_char(&self, unit: u16) {
+        let units = match self.high_surrogate.take() {
+            Some(high) => vec![high, unit],
+            None if (0xD800..0xDC00).contains(&unit) => {
+                self.high_surrogate.set(Some(unit));
+                return;
+            }
+            None => vec![unit],
+        };
+        for c in char::decode_utf16(units).filter_map(|c| c.ok()) {
+            self.send_event(WindowEvent::ReceivedCharacter(c));
+        }
+    }
+
+    fn message_handler(&self, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
         match message {
+            WM_CLOSE => {
+                if !self.request_close() {
+                    return LRESULT::default();
+                }
+            }
+            WM_CLOSE_CONFIRMED => {
+                unsafe { DestroyWindow(self.handle.get()) };
+                return LRESULT::default();
+            }
+            WM_SET_TITLE => {
+                self.apply_pending_title();
+                return LRESULT::default();
+            }
             WM_DESTROY => {
-                unsafe { PostQuitMessage(0) };
+                if self.quit_on_destroy.get() {
+                    unsafe { PostQuitMessage(0) };
+                }
                 return LRESULT::default();
             }
             WM_MOUSEMOVE => {
                 let (x, y) = get_mouse_position(lparam);
-                let _ = self.event_channel.try_send(WindowEvent::CursorMoved {
+                self.send_event(WindowEvent::CursorMoved {
                     device_id: unsafe { DeviceId::dummy() },
                     position: PhysicalPosition {
                         x: x as f64,
@@ -142,30 +968,139 @@ impl Window {
                     modifiers: ModifiersState::default(),
                 });
             }
-            WM_SIZE | WM_SIZING => {
-                let size = self.size().unwrap();
-                let _ = self
-                    .event_channel
-                    .try_send(WindowEvent::Resized((size.Width, size.Height).into()));
+            WM_SIZE => {
+                let state = match wparam.0 as u32 {
+                    SIZE_MINIMIZED => WindowState::Minimized,
+                    SIZE_MAXIMIZED => WindowState::Maximized,
+                    _ => WindowState::Normal,
+                };
+                let was_minimized = self.state.get() == WindowState::Minimized;
+                if self.state.get() != state {
+                    send_window_state(&self.root_visual, state);
+                }
+                self.state.set(state);
+                // The client area of the minimized window is empty: keep the layout,
+                // the panels may pause rendering until the window is restored
+                if state == WindowState::Minimized {
+                    if !was_minimized {
+                        self.send_event(WindowEvent::Occluded(true));
+                    }
+                } else {
+                    if was_minimized {
+                        self.send_event(WindowEvent::Occluded(false));
+                    }
+                    self.send_resized();
+                }
             }
-            WM_LBUTTONDOWN => {
-                let _ = self.event_channel.try_send(WindowEvent::MouseInput {
-                    device_id: unsafe { DeviceId::dummy() },
-                    state: ElementState::Pressed,
-                    button: MouseButton::Left,
-                    modifiers: ModifiersState::default(),
-                });
+            WM_SIZING | WM_REQUEST_REDRAW => {
+                if self.state.get() != WindowState::Minimized {
+                    self.send_resized();
+                }
             }
-            WM_LBUTTONUP => {
-                let _ = self.event_channel.try_send(WindowEvent::MouseInput {
-                    device_id: unsafe { DeviceId::dummy() },
-                    state: ElementState::Released,
-                    button: MouseButton::Left,
-                    modifiers: ModifiersState::default(),
-                });
+            // Double clicks are counted by the receiver, the second press is a usual press
+            WM_LBUTTONDOWN | WM_LBUTTONDBLCLK => {
+                self.send_mouse_input(ElementState::Pressed, MouseButton::Left)
+            }
+            WM_LBUTTONUP => self.send_mouse_input(ElementState::Released, MouseButton::Left),
+            WM_RBUTTONDOWN | WM_RBUTTONDBLCLK => {
+                self.send_mouse_input(ElementState::Pressed, MouseButton::Right)
+            }
+            WM_RBUTTONUP => self.send_mouse_input(ElementState::Released, MouseButton::Right),
+            WM_MOUSEWHEEL => self.send_mouse_wheel(0., get_wheel_lines(wparam)),
+            WM_MOUSEHWHEEL => self.send_mouse_wheel(-get_wheel_lines(wparam), 0.),
+            WM_KEYDOWN => self.send_keyboard_input(ElementState::Pressed, wparam, lparam),
+            WM_KEYUP => self.send_keyboard_input(ElementState::Released, wparam, lparam),
+            // Alt and F10 alone make DefWindowProc enter the menu mode of the window, which
+            // has no native menu: it would swallow the following keys. The application
+            // handles these keys itself, e.g. to focus its menu bar. The other system keys
+            // still go to DefWindowProc, so Alt+F4 closes the window.
+            WM_SYSKEYDOWN => {
+                self.send_keyboard_input(ElementState::Pressed, wparam, lparam);
+                if is_menu_key(wparam) {
+                    return LRESULT::default();
+                }
+            }
+            WM_SYSKEYUP => {
+                self.send_keyboard_input(ElementState::Released, wparam, lparam);
+                if is_menu_key(wparam) {
+                    return LRESULT::default();
+                }
+            }
+            // Alt+letter is delivered as WM_SYSKEYDOWN with Alt in the modifiers, the system
+            // beeps if the character is left to DefWindowProc. Alt+Space still opens
+            // the system menu.
+            WM_SYSCHAR if wparam.0 != ' ' as usize => return LRESULT::default(),
+            // Characters composed by TranslateMessage with the current keyboard layout.
+            // Dead keys produce WM_DEADCHAR, which is ignored: the composed character
+            // comes with the next WM_CHAR.
+            WM_CHAR => self.send_utf16_char(wparam.0 as u16),
+            WM_UNICHAR => {
+                if wparam.0 as u32 == UNICODE_NOCHAR {
+                    // Tell the sender that UTF-32 characters are accepted
+                    return LRESULT(1);
+                }
+                if let Some(c) = char::from_u32(wparam.0 as u32) {
+                    self.send_event(WindowEvent::ReceivedCharacter(c));
+                }
+                return LRESULT::default();
+            }
+            WM_DROPFILES => {
+                send_dropped_files(&self.root_visual, HDROP(wparam.0 as isize));
+                return LRESULT::default();
+            }
+            WM_SETFOCUS => {
+                self.send_event(WindowEvent::Focused(true));
+            }
+            WM_KILLFOCUS => {
+                self.send_event(WindowEvent::Focused(false));
+            }
+            WM_NCCALCSIZE if self.custom_chrome.get() && wparam.0 != 0 => {
+                // The maximized window is larger than the monitor by the frame size
+                if unsafe { IsZoomed(self.handle.get()) }.as_bool() {
+                    let params = unsafe { &mut *(lparam.0 as *mut NCCALCSIZE_PARAMS) };
+                    let (border_x, border_y) = resize_border();
+                    let rect = &mut params.rgrc[0];
+                    rect.left += border_x;
+                    rect.right -= border_x;
+                    rect.top += border_y;
+                    rect.bottom -= border_y;
+                }
+                return LRESULT::default();
+            }
+            WM_NCHITTEST if self.custom_chrome.get() => {
+                let mut point = POINT {
+                    x: (lparam.0 & 0xffff) as i16 as i32,
+                    y: ((lparam.0 >> 16) & 0xffff) as i16 as i32,
+                };
+                let mut client_rect = RECT::default();
+                unsafe {
+                    ScreenToClient(self.handle.get(), &mut point);
+                    GetClientRect(self.handle.get(), &mut client_rect);
+                }
+                let point = Vector2::new(point.x as f32, point.y as f32);
+                // The maximized and the fullscreen windows have no resize borders
+                let resizable =
+                    !unsafe { IsZoomed(self.handle.get()) }.as_bool() && !self.is_fullscreen();
+                let hit = hit_test(&self.root_visual, client_rect, point, resizable);
+                return LRESULT(hit as isize);
+            }
+            WM_SETCURSOR => {
+                if (lparam.0 & 0xFFFF) as u32 == HTCLIENT && apply_cursor().is_ok() {
+                    return LRESULT(1);
+                }
             }
-            WM_RBUTTONDOWN => {
-                // self.game.on_pointer_pressed(true, false).unwrap();
+            WM_SETTINGCHANGE => {
+                let area = PCWSTR(lparam.0 as *const u16);
+                if !area.is_null()
+                    && unsafe { area.to_string() }.map_or(false, |a| a == COLOR_SET_AREA)
+                {
+                    let theme = if is_system_dark_mode() {
+                        Theme::Dark
+                    } else {
+                        Theme::Light
+                    };
+                    self.send_event(WindowEvent::ThemeChanged(theme));
+                }
             }
             WM_TIMER => {
                 // dbg!("timer");
@@ -173,7 +1108,7 @@ impl Window {
             _ => {}
         }
         // self.pool.run_until_stalled();
-        unsafe { DefWindowProcW(self.handle, message, wparam, lparam) }
+        unsafe { DefWindowProcW(self.handle.get(), message, wparam, lparam) }
     }
 
     unsafe extern "system" fn wnd_proc(
@@ -183,33 +1118,60 @@ impl Window {
         lparam: LPARAM,
     ) -> LRESULT {
         if message == WM_NCCREATE {
+            // The reference passed by `Window::open`
             let cs = lparam.0 as *const CREATESTRUCTW;
-            let this = (*cs).lpCreateParams as *mut Self;
-            (*this).handle = window;
+            let this = (*cs).lpCreateParams as *const Self;
+            (*this).handle.set(window);
 
             SetWindowLong(window, GWLP_USERDATA, this as _);
         } else {
-            let this = GetWindowLong(window, GWLP_USERDATA) as *mut Self;
+            let this = GetWindowLong(window, GWLP_USERDATA) as *const Self;
 
-            if let Some(this) = this.as_mut() {
-                return this.message_handler(message, wparam, lparam);
+            if !this.is_null() {
+                if message == WM_NCDESTROY {
+                    // Last message for this window: release the reference owned by it
+                    SetWindowLong(window, GWLP_USERDATA, 0);
+                    let this = Rc::from_raw(this);
+                    this.handle.set(HWND::default());
+                } else {
+                    // The handler may destroy the window and release the owned reference
+                    Rc::increment_strong_count(this);
+                    let this = Rc::from_raw(this);
+                    return this.message_handler(message, wparam, lparam);
+                }
             }
         }
         DefWindowProcW(window, message, wparam, lparam)
     }
+}
 
-    /// Get a reference to the window's compositor.
-    pub fn compositor(&self) -> &Compositor {
-        &self.compositor
+impl Drop for Window {
+    fn drop(&mut self) {
+        let handle = self.handle();
+        if handle != HWND::default() {
+            // The window procedure owns its reference to the shared state, so WM_DESTROY
+            // is handled as usual. The reference is released on WM_NCDESTROY.
+            unsafe {
+                let _ = RevokeDragDrop(handle);
+                DestroyWindow(handle);
+            }
+        }
     }
 }
 
 pub fn run_message_loop() {
+    run_message_loop_until(|| false)
+}
+
+///
+/// Run the message loop of the current thread until `done` returns true. It's checked
+/// before waiting for each message, so it can also handle the requests which
+/// the other threads wake the loop for, e.g. with `PostThreadMessageW`.
+///
+pub fn run_message_loop_until(mut done: impl FnMut() -> bool) {
     let mut message = MSG::default();
     unsafe {
-        // const IDT_TIMER1: usize = 1;
-        // SetTimer(window.handle(), IDT_TIMER1, 10, None);
-        while GetMessageW(&mut message, HWND::default(), 0, 0).into() {
+        while !done() && GetMessageW(&mut message, HWND::default(), 0, 0).as_bool() {
             TranslateMessage(&mut message);
             DispatchMessageW(&mut message);
         }
@@ -235,6 +1197,26 @@ fn get_mouse_position(lparam: LPARAM) -> (isize, isize) {
     (x, y)
 }
 
+// Wheel rotation in notches, the high word of wparam is the signed distance in WHEEL_DELTA units
+fn get_wheel_lines(wparam: WPARAM) -> f32 {
+    ((wparam.0 >> 16) as i16) as f32 / WHEEL_DELTA as f32
+}
+
+fn is_menu_key(wparam: WPARAM) -> bool {
+    matches!(VIRTUAL_KEY(wparam.0 as u16), VK_MENU | VK_F10)
+}
+
+// Modifiers at the moment the message was posted
+fn keyboard_modifiers() -> ModifiersState {
+    let is_down = |key: VIRTUAL_KEY| unsafe { GetKeyState(key.0 as i32) } < 0;
+    let mut modifiers = ModifiersState::empty();
+    modifiers.set(ModifiersState::SHIFT, is_down(VK_SHIFT));
+    modifiers.set(ModifiersState::CTRL, is_down(VK_CONTROL));
+    modifiers.set(ModifiersState::ALT, is_down(VK_MENU));
+    modifiers.set(ModifiersState::LOGO, is_down(VK_LWIN) || is_down(VK_RWIN));
+    modifiers
+}
+
 #[allow(non_snake_case)]
 #[cfg(target_pointer_width = "32")]
 unsafe fn SetWindowLong(window: HWND, index: WINDOW_LONG_PTR_INDEX, value: isize) -> isize {
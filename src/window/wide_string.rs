@@ -1,21 +1,165 @@
-use windows::core::PCWSTR;
+use std::{collections::HashMap, ffi::OsStr, marker::PhantomData, path::Path, sync::Mutex};
 
-pub struct WideString(pub Vec<u16>);
+use windows::core::{HSTRING, PCWSTR};
+
+///
+/// Nul terminated UTF-16 string for passing to the Windows API
+///
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct WideString(Vec<u16>);
+
+impl Default for WideString {
+    fn default() -> Self {
+        WideString(vec![0])
+    }
+}
+
+///
+/// Pointer to the `WideString` content, valid while the string is borrowed.
+/// Converts to `PCWSTR` at the API call site.
+///
+#[derive(Clone, Copy)]
+pub struct WideStr<'a> {
+    ptr: PCWSTR,
+    _marker: PhantomData<&'a WideString>,
+}
+
+impl<'a> From<WideStr<'a>> for PCWSTR {
+    fn from(value: WideStr<'a>) -> Self {
+        value.ptr
+    }
+}
 
 pub trait ToWide {
     fn to_wide(&self) -> WideString;
 }
 
-impl ToWide for &str {
+impl ToWide for str {
+    fn to_wide(&self) -> WideString {
+        WideString::from_wide(&self.encode_utf16().collect::<Vec<_>>())
+    }
+}
+
+impl ToWide for OsStr {
+    fn to_wide(&self) -> WideString {
+        use std::os::windows::ffi::OsStrExt;
+        WideString::from_wide(&self.encode_wide().collect::<Vec<_>>())
+    }
+}
+
+impl ToWide for Path {
+    fn to_wide(&self) -> WideString {
+        self.as_os_str().to_wide()
+    }
+}
+
+impl ToWide for HSTRING {
     fn to_wide(&self) -> WideString {
-        let mut result: Vec<u16> = self.encode_utf16().collect();
+        WideString::from_wide(self.as_wide())
+    }
+}
+
+impl WideString {
+    ///
+    /// String from the UTF-16 characters, cut at the first nul if there is one
+    ///
+    pub fn from_wide(wide: &[u16]) -> Self {
+        let len = wide.iter().position(|c| *c == 0).unwrap_or(wide.len());
+        let mut result = Vec::with_capacity(len + 1);
+        result.extend_from_slice(&wide[..len]);
         result.push(0);
         WideString(result)
     }
+    pub fn as_pcwstr(&self) -> WideStr<'_> {
+        WideStr {
+            ptr: PCWSTR(self.0.as_ptr()),
+            _marker: PhantomData,
+        }
+    }
+    ///
+    /// Characters without the terminating nul, e.g. for DirectWrite text
+    ///
+    pub fn as_wide(&self) -> &[u16] {
+        &self.0[..self.0.len() - 1]
+    }
+    pub fn as_wide_with_nul(&self) -> &[u16] {
+        &self.0
+    }
+    pub fn to_string_lossy(&self) -> String {
+        String::from_utf16_lossy(self.as_wide())
+    }
 }
 
-impl WideString {
-    pub fn as_pcwstr(&self) -> PCWSTR {
-        PCWSTR(self.0.as_ptr() as *const _)
+impl From<&WideString> for HSTRING {
+    fn from(value: &WideString) -> Self {
+        HSTRING::from_wide(value.as_wide())
+    }
+}
+
+static INTERNED: Mutex<Option<HashMap<String, &'static WideString>>> = Mutex::new(None);
+
+///
+/// Wide string which lives until the end of the program, the same instance for the equal
+/// strings. For the repeatedly used names like window classes.
+///
+pub fn intern_wide(s: &str) -> &'static WideString {
+    let mut interned = INTERNED.lock().unwrap();
+    let interned = interned.get_or_insert_with(HashMap::new);
+    if let Some(wide) = interned.get(s) {
+        return wide;
+    }
+    let wide: &'static WideString = Box::leak(Box::new(s.to_wide()));
+    interned.insert(s.to_owned(), wide);
+    wide
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Emoji outside of the BMP take the surrogate pairs, CJK characters take one unit each
+    const TITLE: &str = "Заметки 📝 日本語 👨‍👩‍👧";
+
+    #[test]
+    fn unicode_title_round_trip() {
+        let wide = TITLE.to_wide();
+        assert_eq!(wide.as_wide(), TITLE.encode_utf16().collect::<Vec<_>>());
+        assert_eq!(wide.as_wide_with_nul().last(), Some(&0));
+        assert_eq!(wide.to_string_lossy(), TITLE);
+        let hstring = HSTRING::from(&wide);
+        assert_eq!(hstring.to_string_lossy(), TITLE);
+        assert_eq!(hstring.to_wide(), wide);
+    }
+
+    #[test]
+    fn surrogate_pairs_are_kept() {
+        let wide = "📝".to_wide();
+        assert_eq!(wide.as_wide(), [0xD83D, 0xDCDD]);
+        let wide = "日本".to_wide();
+        assert_eq!(wide.as_wide(), [0x65E5, 0x672C]);
+    }
+
+    #[test]
+    fn os_str_and_path_match_str() {
+        let wide = TITLE.to_wide();
+        assert_eq!(OsStr::new(TITLE).to_wide(), wide);
+        assert_eq!(Path::new(TITLE).to_wide(), wide);
+    }
+
+    #[test]
+    fn cut_at_first_nul() {
+        let wide = "日本\0語".to_wide();
+        assert_eq!(wide.to_string_lossy(), "日本");
+        assert_eq!(WideString::from_wide(&[]), WideString::default());
+    }
+
+    #[test]
+    fn interned_class_names() {
+        let name = "wag クラス 🪟";
+        let first = intern_wide(name);
+        let second = intern_wide(&name.to_owned());
+        assert!(std::ptr::eq(first, second));
+        assert_eq!(first.to_string_lossy(), name);
+        assert!(!std::ptr::eq(first, intern_wide("wag クラス")));
     }
 }